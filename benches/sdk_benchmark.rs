@@ -1,5 +1,11 @@
 use codspeed_criterion_compat::{Criterion, criterion_group, criterion_main};
+use goon_ai::assets::registry::AssetRegistry;
+use goon_ai::config::pack::Mood;
+use goon_ai::gui::WindowSpawner;
+use goon_ai::permissions::{PermissionChecker, PermissionSet};
+use goon_ai::runtime::runtime::{GoonRuntime, RuntimeContext};
 use goon_ai::sdk::generator::generate_definitions;
+use std::sync::Arc;
 
 fn benchmark_sdk_generation(c: &mut Criterion) {
     let allowed_modules = vec![
@@ -16,5 +22,28 @@ fn benchmark_sdk_generation(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, benchmark_sdk_generation);
+/// After the first runtime the SDK bridge comes from the cache instead of being compiled
+fn benchmark_runtime_new(c: &mut Criterion) {
+    let (window_handle, _window_spawner) = WindowSpawner::create();
+    let context = || RuntimeContext {
+        permissions: PermissionChecker::new(PermissionSet::new()),
+        window_spawner: window_handle.clone(),
+        registry: Arc::new(AssetRegistry::new()),
+        mood: Mood {
+            name: "Bench".to_string(),
+            description: String::new(),
+            tags: vec![],
+            prompt: None,
+        },
+        max_audio_concurrent: 1,
+        max_image_concurrent: usize::MAX,
+        max_video_concurrent: usize::MAX,
+        allow_fallback: false,
+        original_wallpaper: None,
+    };
+
+    c.bench_function("runtime_new", |b| b.iter(|| GoonRuntime::new(context())));
+}
+
+criterion_group!(benches, benchmark_sdk_generation, benchmark_runtime_new);
 criterion_main!(benches);
@@ -1,2 +1,4 @@
 pub mod orchestrator;
+pub mod session;
+pub mod shutdown;
 pub mod state;
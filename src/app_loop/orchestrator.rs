@@ -1,20 +1,32 @@
+use crate::app_loop::session::{Clock, SessionLimit, SystemClock};
+use crate::app_loop::shutdown::Shutdown;
 use crate::app_loop::state::{LoopState, MessageType};
+use crate::assets::library::{ActivePack, PackLibrary};
 use crate::assets::loader::AssetLoader;
 use crate::config::pack::PackConfig;
-use crate::config::settings::Settings;
-use crate::gui::WindowSpawnerHandle;
-use crate::llm::client::LLMClient;
+use crate::config::settings::{LLMSettings, Settings};
+use crate::gui::windows::types::PopupDefaults;
+use crate::gui::{PopupKind, WindowCommand, WindowSpawnerHandle};
+use crate::llm::client::{ChunkStream, LLMClient, LLMProvider};
 use crate::llm::conversation::ConversationManager;
 use crate::llm::prompt::PromptBuilder;
-use crate::permissions::PermissionChecker;
+use crate::media::wallpaper::{PlatformWallpaperSetter, WallpaperMode, WallpaperSetter};
+use crate::permissions::{Permission, PermissionChecker, PermissionResolver, PermissionSet};
 use crate::runtime::runtime::{GoonRuntime, RuntimeContext};
+use crate::sdk::methods::SdkMethods;
 use crate::typescript::compiler::TypeScriptCompiler;
 use anyhow::Result;
+use futures::StreamExt;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
+use tokio::sync::watch;
 use tokio::time::sleep;
 
+/// How often a paused loop checks whether it was resumed
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 pub struct Orchestrator {
     state: LoopState,
     settings: Arc<Settings>,
@@ -22,6 +34,78 @@ pub struct Orchestrator {
     permissions: Arc<PermissionChecker>,
     window_spawner: WindowSpawnerHandle,
     is_running: Arc<AtomicBool>,
+    clock: Arc<dyn Clock>,
+    shutdown: Shutdown,
+    wallpaper_setter: Arc<dyn WallpaperSetter + Send + Sync>,
+    original_wallpaper: Option<PathBuf>,
+    llm_client: LLMClient,
+    llm_settings_tx: watch::Sender<LLMSettings>,
+    llm_settings_rx: watch::Receiver<LLMSettings>,
+    settings_tx: watch::Sender<Arc<Settings>>,
+    settings_rx: watch::Receiver<Arc<Settings>>,
+    /// Safe mode turned on outside the settings file, kept across reloads
+    forced_safe_mode: bool,
+}
+
+/// How long windows stay up at least, the pack's setting wins over the user's
+fn min_visible(settings: &Settings, pack_config: &PackConfig) -> Duration {
+    let ms = pack_config
+        .popups
+        .as_ref()
+        .and_then(|popups| popups.min_visible_ms)
+        .or(settings.runtime.popups.min_visible_ms)
+        .unwrap_or(0);
+    Duration::from_millis(ms)
+}
+
+/// Popup cap from settings, no `max` means no cap
+fn max_popups(max: Option<u32>) -> usize {
+    max.map_or(usize::MAX, |max| max as usize)
+}
+
+/// Settings that changed between `old` and `new` but are only read when the
+/// loop starts, so they need a restart to take effect
+fn restart_required(old: &Settings, new: &Settings) -> Vec<&'static str> {
+    let (old_rt, new_rt) = (&old.runtime, &new.runtime);
+    let (old_popups, new_popups) = (&old_rt.popups, &new_rt.popups);
+    [
+        ("runtime.pack", old_rt.pack != new_rt.pack),
+        (
+            "runtime.history_size",
+            old_rt.history_size != new_rt.history_size,
+        ),
+        (
+            "runtime.strict_mood",
+            old_rt.strict_mood != new_rt.strict_mood,
+        ),
+        (
+            "runtime.session_max_mins",
+            old_rt.session_max_mins != new_rt.session_max_mins,
+        ),
+        (
+            "runtime.popups.audio.max",
+            old_popups.audio.max != new_popups.audio.max,
+        ),
+        (
+            "runtime.popups.default_opacity",
+            old_popups.default_opacity != new_popups.default_opacity,
+        ),
+        (
+            "runtime.popups.default_always_on_top",
+            old_popups.default_always_on_top != new_popups.default_always_on_top,
+        ),
+        (
+            "runtime.popups.image.timeout",
+            old_popups.image.timeout != new_popups.image.timeout,
+        ),
+        (
+            "runtime.popups.video.timeout",
+            old_popups.video.timeout != new_popups.video.timeout,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(name, changed)| changed.then_some(name))
+    .collect()
 }
 
 impl Orchestrator {
@@ -32,6 +116,20 @@ impl Orchestrator {
         window_spawner: WindowSpawnerHandle,
         is_running: Arc<AtomicBool>,
     ) -> Self {
+        let llm_client = LLMClient::new(&settings.llm_settings, &settings.llm_settings.model);
+        let popups = &settings.runtime.popups;
+        let window_spawner = window_spawner.with_popup_defaults(PopupDefaults {
+            opacity: popups.default_opacity.unwrap_or(1.0).clamp(0.0, 1.0),
+            always_on_top: popups.default_always_on_top.unwrap_or(true),
+            image_timeout: popups.image.timeout.map(Duration::from_secs),
+            video_timeout: popups.video.timeout.map(Duration::from_secs),
+        });
+        window_spawner.set_min_visible(min_visible(&settings, &pack_config));
+        window_spawner.set_mitosis(PopupKind::Image, popups.image.mitosis_factor());
+        window_spawner.set_mitosis(PopupKind::Video, popups.video.mitosis_factor());
+        let (llm_settings_tx, llm_settings_rx) = watch::channel(settings.llm_settings.clone());
+        let (settings_tx, settings_rx) = watch::channel(settings.clone());
+
         Self {
             state: LoopState::new(),
             settings,
@@ -39,6 +137,238 @@ impl Orchestrator {
             permissions,
             window_spawner,
             is_running,
+            clock: Arc::new(SystemClock),
+            shutdown: Shutdown::default(),
+            wallpaper_setter: Arc::new(PlatformWallpaperSetter),
+            original_wallpaper: None,
+            llm_client,
+            llm_settings_tx,
+            llm_settings_rx,
+            settings_tx,
+            settings_rx,
+            forced_safe_mode: false,
+        }
+    }
+
+    /// Sender for settings reloaded from disk. They are applied between
+    /// iterations; see [`Orchestrator::apply_settings`] for what applies live.
+    pub fn settings_sender(&self) -> watch::Sender<Arc<Settings>> {
+        self.settings_tx.clone()
+    }
+
+    /// Apply settings sent since the last call. The LLM connection, permissions,
+    /// iteration delay, script timeout, user details and popup caps, mitosis and
+    /// minimum visible time change live; other changes are logged as needing a
+    /// restart. Returns true when the settings changed.
+    pub fn apply_settings(&mut self, runtime: Option<&mut GoonRuntime>) -> bool {
+        if !self.settings_rx.has_changed().unwrap_or(false) {
+            return false;
+        }
+        let reloaded = self.settings_rx.borrow_and_update().clone();
+        if *reloaded == *self.settings {
+            return false;
+        }
+        println!("Settings reloaded");
+
+        let restart = restart_required(&self.settings, &reloaded);
+        if !restart.is_empty() {
+            println!("Restart to apply changed settings: {}", restart.join(", "));
+        }
+
+        // Keep naming the pack that is actually loaded
+        let mut new = (*reloaded).clone();
+        new.runtime.pack = self.settings.runtime.pack.clone();
+        let new = Arc::new(new);
+        let old = std::mem::replace(&mut self.settings, new.clone());
+
+        if old.llm_settings != new.llm_settings {
+            let _ = self.llm_settings_tx.send(new.llm_settings.clone());
+        }
+
+        let popups = &new.runtime.popups;
+        self.window_spawner
+            .set_min_visible(min_visible(&new, &self.pack_config));
+        self.window_spawner
+            .set_max_popups(PopupKind::Image, max_popups(popups.image.max));
+        self.window_spawner
+            .set_max_popups(PopupKind::Video, max_popups(popups.video.max));
+        self.window_spawner
+            .set_mitosis(PopupKind::Image, popups.image.mitosis_factor());
+        self.window_spawner
+            .set_mitosis(PopupKind::Video, popups.video.mitosis_factor());
+
+        if old.runtime.permissions != new.runtime.permissions
+            || old.runtime.safe_mode != new.runtime.safe_mode
+        {
+            let pack_permissions: PermissionSet = self.pack_config.meta.permissions.clone().into();
+            let user_permissions: PermissionSet = new.runtime.permissions.clone().into();
+            let permissions = PermissionChecker::new(PermissionResolver::resolve(
+                &pack_permissions,
+                &user_permissions,
+                self.safe_mode(&new),
+            ));
+            println!("Permissions changed: {:?}", permissions);
+            if let Some(runtime) = runtime {
+                runtime.set_permissions(permissions.clone());
+                // Packs switched to later resolve against the new grants too
+                let active = runtime.active_pack().unwrap_or_else(|| ActivePack {
+                    name: new.runtime.pack.current.clone(),
+                    config: self.pack_config.clone(),
+                });
+                runtime.set_pack_library(self.pack_library(), active);
+            }
+            self.permissions = Arc::new(permissions);
+        }
+        true
+    }
+
+    /// Sender for new LLM settings. The client is rebuilt before the next request
+    /// when the host or model changes; conversation history is kept.
+    pub fn llm_settings_sender(&self) -> watch::Sender<LLMSettings> {
+        self.llm_settings_tx.clone()
+    }
+
+    /// The client used for the next LLM request
+    pub fn llm_client(&self) -> &LLMClient {
+        &self.llm_client
+    }
+
+    /// Rebuild the LLM client if new settings were sent. Returns true when it was rebuilt.
+    pub fn refresh_llm_client(&mut self) -> bool {
+        if !self.llm_settings_rx.has_changed().unwrap_or(false) {
+            return false;
+        }
+
+        let settings = self.llm_settings_rx.borrow_and_update().clone();
+        println!(
+            "LLM settings changed, reconnecting to {} ({})",
+            settings.host, settings.model
+        );
+        self.llm_client = LLMClient::new(&settings, &settings.model);
+        true
+    }
+
+    /// Replace the clock used for session timing
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Keep safe mode on whatever the settings file says, for the environment
+    /// variable and command line flag
+    pub fn with_forced_safe_mode(mut self, forced: bool) -> Self {
+        self.forced_safe_mode = forced;
+        self
+    }
+
+    /// Whether safe mode is on under `settings`
+    fn safe_mode(&self, settings: &Settings) -> bool {
+        self.forced_safe_mode || settings.runtime.safe_mode
+    }
+
+    /// Stop the loop when `shutdown` is requested
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Replace the setter used to back up and restore the wallpaper
+    pub fn with_wallpaper_setter(mut self, setter: Arc<dyn WallpaperSetter + Send + Sync>) -> Self {
+        self.wallpaper_setter = setter;
+        self
+    }
+
+    /// Start timing a new session against the configured maximum length
+    pub fn start_session(&mut self) -> SessionLimit {
+        if self.permissions.has_permission(Permission::Wallpaper) {
+            self.original_wallpaper = self.wallpaper_setter.get_wallpaper().ok();
+        }
+        SessionLimit::from_minutes(self.clock.now(), self.settings.runtime.session_max_mins)
+    }
+
+    /// End the session if it has run too long. Returns true when the session was stopped.
+    pub fn check_session(
+        &mut self,
+        session: &SessionLimit,
+        runtime: Option<&mut GoonRuntime>,
+    ) -> bool {
+        if !session.is_exceeded(self.clock.now()) {
+            return false;
+        }
+
+        println!("Session length limit reached, stopping...");
+        let restore_wallpaper = self.settings.runtime.restore_wallpaper_on_stop;
+        self.stop_session(runtime, restore_wallpaper);
+        true
+    }
+
+    /// Clean up before the app exits: close windows, stop audio and mouse effects,
+    /// and restore the wallpaper
+    pub fn shutdown(&mut self, runtime: Option<&mut GoonRuntime>) {
+        println!("Shutting down...");
+        self.stop_session(runtime, true);
+    }
+
+    fn stop_session(&mut self, runtime: Option<&mut GoonRuntime>, restore_wallpaper: bool) {
+        let _ = self.window_spawner.send(WindowCommand::CloseAll);
+        if let Some(runtime) = runtime {
+            runtime.stop_all_audio();
+            runtime.release_mouse_effects();
+        }
+        if let Some(path) = self.original_wallpaper.take()
+            && restore_wallpaper
+            && let Err(e) = self
+                .wallpaper_setter
+                .set_wallpaper(&path, WallpaperMode::default())
+        {
+            eprintln!("Failed to restore wallpaper: {}", e);
+        }
+    }
+
+    /// The packs in `packs/`, resolved against the user's grants
+    fn pack_library(&self) -> PackLibrary {
+        PackLibrary::new(
+            "packs",
+            self.settings.runtime.permissions.clone().into(),
+            self.safe_mode(&self.settings),
+        )
+    }
+
+    /// Let scripts switch between the packs in `packs/`
+    fn enable_pack_switching(&self, runtime: &mut GoonRuntime) {
+        runtime.set_pack_library(
+            self.pack_library(),
+            ActivePack {
+                name: self.settings.runtime.pack.current.clone(),
+                config: self.pack_config.clone(),
+            },
+        );
+    }
+
+    /// Pick up a pack switch made by a script. Returns true when the pack changed.
+    fn sync_active_pack(&mut self, runtime: &GoonRuntime) -> bool {
+        let Some(active) = runtime.active_pack() else {
+            return false;
+        };
+        if Arc::ptr_eq(&active.config, &self.pack_config) {
+            return false;
+        }
+
+        println!("Switched to pack: {}", active.name);
+        self.pack_config = active.config;
+        self.permissions = Arc::new(runtime.permissions());
+        self.window_spawner
+            .set_min_visible(min_visible(&self.settings, &self.pack_config));
+        true
+    }
+
+    /// Wait until the loop is resumed or a shutdown is requested
+    async fn wait_while_paused(&self) {
+        while !self.is_running.load(Ordering::Relaxed) {
+            tokio::select! {
+                _ = sleep(PAUSE_POLL_INTERVAL) => {}
+                _ = self.shutdown.wait() => return,
+            }
         }
     }
 
@@ -51,20 +381,16 @@ impl Orchestrator {
             &self.settings.runtime.pack.current,
         )?);
 
-        let llm_client = LLMClient::new(
-            &self.settings.llm_settings,
-            &self.settings.llm_settings.model,
-        );
-
-        let mut history = ConversationManager::new(50); // TODO: Configurable history size
+        let mut history = ConversationManager::new(self.settings.runtime.history_size);
         let compiler = TypeScriptCompiler::new();
+        let sdk_methods = SdkMethods::load();
 
         // Generate SDK definitions (asset-free)
-        let sdk_defs = crate::sdk::generate_definitions_for_permissions(&self.permissions);
+        let mut sdk_defs = crate::sdk::generate_definitions_for_permissions(&self.permissions);
 
         // Initialize Runtime
         let mood_name = &self.settings.runtime.pack.mood;
-        let mood = self
+        let mut mood = self
             .pack_config
             .moods
             .iter()
@@ -77,23 +403,48 @@ impl Orchestrator {
                 prompt: None,
             });
 
+        // Back up the wallpaper before scripts get a chance to change it
+        let session = self.start_session();
         let context = RuntimeContext {
             permissions: (*self.permissions).clone(),
             window_spawner: self.window_spawner.clone(),
             registry: registry.clone(),
             mood: mood.clone(),
             max_audio_concurrent: self.settings.runtime.popups.audio.max.unwrap_or(1) as usize,
+            max_image_concurrent: max_popups(self.settings.runtime.popups.image.max),
+            max_video_concurrent: max_popups(self.settings.runtime.popups.video.max),
+            allow_fallback: !self.settings.runtime.strict_mood,
+            original_wallpaper: self.original_wallpaper.clone(),
         };
 
         let mut runtime = GoonRuntime::new(context);
+        self.enable_pack_switching(&mut runtime);
+
+        // The start of the prompt only changes with the pack or the mood's prompt
+        let mut prompt_builder =
+            PromptBuilder::new(&self.pack_config, &mood, &self.settings.user, &sdk_defs);
 
         loop {
-            // Check if paused
+            if self.shutdown.is_requested() {
+                self.shutdown(Some(&mut runtime));
+                return Ok(());
+            }
+            if self.check_session(&session, Some(&mut runtime)) {
+                return Ok(());
+            }
+
+            // Don't start a new iteration while paused, state is kept for the resume
             if !self.is_running.load(Ordering::Relaxed) {
-                sleep(Duration::from_millis(100)).await;
+                self.wait_while_paused().await;
                 continue;
             }
 
+            if self.apply_settings(Some(&mut runtime)) {
+                sdk_defs = crate::sdk::generate_definitions_for_permissions(&self.permissions);
+                prompt_builder =
+                    PromptBuilder::new(&self.pack_config, &mood, &self.settings.user, &sdk_defs);
+            }
+
             self.state.iteration_count += 1;
             println!("Iteration: {}", self.state.iteration_count);
 
@@ -110,19 +461,28 @@ impl Orchestrator {
             // Get active windows
             let active_windows = self.window_spawner.get_active_windows().unwrap_or_default();
 
-            let messages = PromptBuilder::build(
-                &self.pack_config,
-                &mood.name,
-                &self.settings.user,
-                &history,
-                &sdk_defs,
-                &active_windows,
-                execution_failed,
-            );
+            let messages =
+                prompt_builder.messages(&mood, &history, &active_windows, execution_failed);
 
             // 2. Call LLM
+            self.refresh_llm_client();
             println!("Calling LLM...");
-            match llm_client.chat(messages).await {
+            let reasoning_tags = self.llm_settings_rx.borrow().reasoning_tags.clone();
+            // Quitting doesn't wait for the reply, cleanup has to finish before the UI exits
+            let response = self
+                .shutdown
+                .or_requested(async {
+                    match self.llm_client.chat_stream(messages).await {
+                        Ok(stream) => collect_response(stream, &reasoning_tags).await,
+                        Err(e) => Err(e),
+                    }
+                })
+                .await;
+            let Some(response) = response else {
+                self.shutdown(Some(&mut runtime));
+                return Ok(());
+            };
+            match response {
                 Ok(response) => {
                     println!("LLM Response: {}", response);
                     history.add_message("assistant", &response);
@@ -134,13 +494,48 @@ impl Orchestrator {
                     // For now, let's assume the LLM returns a code block or we parse it.
                     // The PromptBuilder asks for TypeScript code.
 
-                    let code_block = extract_code_block(&response);
+                    let code_block = extract_code_block(&response, &reasoning_tags);
                     if let Some(code) = code_block {
                         println!("Compiling code...");
-                        match compiler.compile(&code) {
+                        let compiled = compiler
+                            .compile(&code)
+                            .map_err(|e| format!("Compilation Error: {}", e))
+                            .and_then(|js_code| check_sdk_calls(&sdk_methods, js_code));
+                        match compiled {
                             Ok(js_code) => {
                                 println!("Executing JS...");
-                                match runtime.execute_script(&js_code).await {
+                                let result = self
+                                    .shutdown
+                                    .or_requested(runtime.execute_script_with_timeout(
+                                        &js_code,
+                                        self.settings.runtime.script_timeout(),
+                                    ))
+                                    .await;
+                                let Some(result) = result else {
+                                    self.shutdown(Some(&mut runtime));
+                                    return Ok(());
+                                };
+                                for log in runtime.take_logs() {
+                                    println!("[console.{}] {}", log.level, log.message);
+                                }
+                                let pack_changed = self.sync_active_pack(&runtime);
+                                if pack_changed {
+                                    sdk_defs = crate::sdk::generate_definitions_for_permissions(
+                                        &self.permissions,
+                                    );
+                                }
+                                // Scripts can change the mood with `pack.setMood()`
+                                let previous_prompt =
+                                    std::mem::replace(&mut mood, runtime.current_mood()).prompt;
+                                if pack_changed || mood.prompt != previous_prompt {
+                                    prompt_builder = PromptBuilder::new(
+                                        &self.pack_config,
+                                        &mood,
+                                        &self.settings.user,
+                                        &sdk_defs,
+                                    );
+                                }
+                                match result {
                                     Ok(_) => {
                                         println!("Execution successful");
                                         self.state.reset_retry();
@@ -153,9 +548,8 @@ impl Orchestrator {
                                     }
                                 }
                             }
-                            Err(e) => {
-                                eprintln!("Compilation error: {}", e);
-                                let error_msg = format!("Compilation Error: {}", e);
+                            Err(error_msg) => {
+                                eprintln!("{}", error_msg);
                                 history.add_message("system", &error_msg);
                                 self.state.add_error(error_msg);
                             }
@@ -170,8 +564,11 @@ impl Orchestrator {
                 }
             }
 
-            // Delay
-            sleep(Duration::from_secs(5)).await;
+            // Delay, cut short by a shutdown request
+            tokio::select! {
+                _ = sleep(self.settings.runtime.iteration_delay()) => {}
+                _ = self.shutdown.wait() => {}
+            }
         }
     }
 
@@ -185,6 +582,7 @@ impl Orchestrator {
         )?);
 
         let compiler = TypeScriptCompiler::new();
+        let sdk_methods = SdkMethods::load();
 
         // Generate SDK definitions (asset-free)
         let _sdk_defs = crate::sdk::generate_definitions_for_permissions(&self.permissions);
@@ -210,15 +608,27 @@ impl Orchestrator {
             registry: registry.clone(),
             mood: mood.clone(),
             max_audio_concurrent: self.settings.runtime.popups.audio.max.unwrap_or(1) as usize,
+            max_image_concurrent: max_popups(self.settings.runtime.popups.image.max),
+            max_video_concurrent: max_popups(self.settings.runtime.popups.video.max),
+            allow_fallback: !self.settings.runtime.strict_mood,
+            original_wallpaper: self.original_wallpaper.clone(),
         };
 
         let mut runtime = GoonRuntime::new(context);
+        runtime.set_active_pack(ActivePack {
+            name: self.settings.runtime.pack.current.clone(),
+            config: self.pack_config.clone(),
+        });
 
         // Execute the provided script
         println!("Executing script...");
         match compiler.compile(script) {
             Ok(js_code) => {
                 println!("Script compiled successfully");
+                let js_code = check_sdk_calls(&sdk_methods, js_code).map_err(|e| {
+                    eprintln!("{}", e);
+                    anyhow::anyhow!(e)
+                })?;
                 match runtime.execute_script(&js_code).await {
                     Ok(_) => {
                         println!("Script execution successful");
@@ -237,92 +647,488 @@ impl Orchestrator {
 
         // Keep the event loop running to allow GUI elements to render
         println!("Script completed. Keeping GUI alive for rendering...");
-        loop {
-            sleep(Duration::from_millis(100)).await;
-        }
+        self.shutdown.wait().await;
+        self.shutdown(Some(&mut runtime));
+        Ok(())
+    }
+}
+
+/// Pass `js_code` through if every SDK method it calls exists, otherwise
+/// describe the unknown calls so the model can fix them without a V8 error
+fn check_sdk_calls(methods: &SdkMethods, js_code: String) -> Result<String, String> {
+    let unknown = methods.check(&js_code);
+    if unknown.is_empty() {
+        return Ok(js_code);
     }
+    let calls: Vec<String> = unknown.iter().map(ToString::to_string).collect();
+    Err(format!("SDK Error: {}", calls.join("\n")))
 }
 
-fn extract_code_block(response: &str) -> Option<String> {
-    // Remove <think> blocks
+/// Remove `<tag>...</tag>` reasoning blocks for each of `tags`.
+/// An unclosed tag only has its opening marker removed, so the rest of the response is kept.
+fn strip_reasoning(response: &str, tags: &[String]) -> String {
     let mut clean_response = response.to_string();
-    while let Some(start) = clean_response.find("<think>") {
-        if let Some(end) = clean_response[start..].find("</think>") {
-            clean_response.replace_range(start..start + end + 8, "");
-        } else {
-            break;
+    for tag in tags {
+        let open = format!("<{}>", tag);
+        let close = format!("</{}>", tag);
+        let mut search_from = 0;
+        while let Some(offset) = clean_response[search_from..].find(&open) {
+            let start = search_from + offset;
+            match clean_response[start..].find(&close) {
+                Some(end) => clean_response.replace_range(start..start + end + close.len(), ""),
+                None => clean_response.replace_range(start..start + open.len(), ""),
+            }
+            search_from = start;
         }
     }
+    clean_response
+}
 
-    // Extract code block
-    let code = if let Some(start) = clean_response.find("```typescript") {
-        let rest = &clean_response[start + 13..];
-        rest.find("```").map(|end| rest[..end].trim().to_string())
-    } else if let Some(start) = clean_response.find("```") {
-        let rest = &clean_response[start + 3..];
-        if let Some(end) = rest.find("```") {
-            // Strip language identifier if present (e.g. "ts\n")
-            if let Some(newline) = rest[..end].find('\n') {
-                Some(rest[newline + 1..end].trim().to_string())
-            } else {
-                Some(rest[..end].trim().to_string())
-            }
-        } else {
-            None
+/// Whether `response` has a closed ```typescript block outside reasoning
+/// blocks, at which point the rest of the reply isn't needed.
+///
+/// Cheap enough to re-run on the reply as it streams in.
+fn code_is_complete(response: &str, reasoning_tags: &[String]) -> bool {
+    let still_reasoning = reasoning_tags.iter().any(|tag| {
+        response
+            .rfind(&format!("<{}>", tag))
+            .is_some_and(|open| !response[open..].contains(&format!("</{}>", tag)))
+    });
+    if still_reasoning {
+        return false;
+    }
+
+    let clean_response = strip_reasoning(response, reasoning_tags);
+    let mut rest = clean_response.as_str();
+    while let Some(start) = rest.find("```") {
+        let after_open = &rest[start + 3..];
+        let Some(end) = after_open.find("```") else {
+            return false;
+        };
+        if after_open.starts_with("typescript") {
+            return true;
         }
-    } else if !clean_response.trim().is_empty() {
-        Some(clean_response.trim().to_string())
-    } else {
-        None
-    };
-
-    // Strip imports from the extracted code
-    if let Some(code) = code {
-        let lines: Vec<&str> = code
-            .lines()
-            .filter(|line| !line.trim().starts_with("import "))
-            .collect();
-        Some(lines.join("\n"))
-    } else {
-        None
+        rest = &after_open[end + 3..];
+    }
+    false
+}
+
+/// Read a streamed reply, stopping once its code is complete so trailing prose
+/// isn't waited for
+async fn collect_response(mut stream: ChunkStream, reasoning_tags: &[String]) -> Result<String> {
+    let mut response = String::new();
+    while let Some(chunk) = stream.next().await {
+        response.push_str(&chunk?);
+        if code_is_complete(&response, reasoning_tags) {
+            break;
+        }
+    }
+    Ok(response)
+}
+
+/// Every fenced code block in the response, in order, with reasoning blocks and
+/// import lines removed.
+///
+/// A response without any fences is taken as code as a whole.
+fn extract_all_code_blocks(response: &str, reasoning_tags: &[String]) -> Vec<String> {
+    let clean_response = strip_reasoning(response, reasoning_tags);
+
+    let mut blocks = Vec::new();
+    if !clean_response.contains("```") {
+        blocks.push(clean_response.trim().to_string());
     }
+
+    let mut rest = clean_response.as_str();
+    while let Some(start) = rest.find("```") {
+        let after_open = &rest[start + 3..];
+        let Some(end) = after_open.find("```") else {
+            break;
+        };
+        let body = &after_open[..end];
+        // Strip the language identifier if present (e.g. "typescript\n")
+        let code = match body.find('\n') {
+            Some(newline) => &body[newline + 1..],
+            None => body,
+        };
+        blocks.push(code.trim().to_string());
+        rest = &after_open[end + 3..];
+    }
+
+    blocks
+        .into_iter()
+        .map(|code| {
+            code.lines()
+                .filter(|line| !line.trim().starts_with("import "))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .filter(|code| !code.trim().is_empty())
+        .collect()
+}
+
+/// All code in the response as one script, blocks run in the order given
+fn extract_code_block(response: &str, reasoning_tags: &[String]) -> Option<String> {
+    let blocks = extract_all_code_blocks(response, reasoning_tags);
+    (!blocks.is_empty()).then(|| blocks.join("\n\n"))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::permissions::PermissionSet;
+    use std::sync::Mutex;
+    use std::sync::mpsc::{Receiver, channel};
+    use std::time::Instant;
+
+    struct ManualClock(Mutex<Instant>);
+
+    impl ManualClock {
+        fn advance(&self, by: Duration) {
+            *self.0.lock().unwrap() += by;
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    const SETTINGS: &str = r#"
+[user]
+name = "Test User"
+dob = "1990-01-01"
+gender = "male"
+
+[llmSettings]
+host = "http://localhost:11434"
+
+[runtime.popups.image]
+[runtime.popups.video]
+[runtime.popups.audio]
+
+[runtime]
+permissions = []
+session_max_mins = 30
+
+[runtime.pack]
+current = "Test Pack"
+mood = "default"
+"#;
+
+    const PACK: &str = r#"
+moods = []
+
+[meta]
+name = "Test Pack"
+version = "1.0.0"
+permissions = []
+
+[assets]
+"#;
+
+    /// A running orchestrator for `PACK` with no permissions, and the commands it sends
+    fn test_orchestrator(settings: Settings) -> (Orchestrator, Receiver<WindowCommand>) {
+        let (command_tx, command_rx) = channel();
+        let orchestrator = Orchestrator::new(
+            Arc::new(settings),
+            Arc::new(PackConfig::parse(PACK).unwrap()),
+            Arc::new(PermissionChecker::new(PermissionSet::new())),
+            WindowSpawnerHandle::new(command_tx),
+            Arc::new(AtomicBool::new(true)),
+        );
+        (orchestrator, command_rx)
+    }
+
+    #[test]
+    fn test_session_limit_triggers_stop() {
+        let clock = Arc::new(ManualClock(Mutex::new(Instant::now())));
+        let (orchestrator, command_rx) = test_orchestrator(Settings::parse(SETTINGS).unwrap());
+        let mut orchestrator = orchestrator.with_clock(clock.clone());
+
+        let session = orchestrator.start_session();
+        clock.advance(Duration::from_secs(29 * 60));
+        assert!(!orchestrator.check_session(&session, None));
+        assert!(command_rx.try_recv().is_err());
+
+        clock.advance(Duration::from_secs(2 * 60));
+        assert!(orchestrator.check_session(&session, None));
+        assert!(matches!(command_rx.try_recv(), Ok(WindowCommand::CloseAll)));
+    }
+
+    #[derive(Default)]
+    struct RecordingSetter {
+        set: Mutex<Vec<PathBuf>>,
+    }
+
+    impl WallpaperSetter for RecordingSetter {
+        fn set_wallpaper(&self, path: &std::path::Path, _mode: WallpaperMode) -> Result<()> {
+            self.set.lock().unwrap().push(path.to_path_buf());
+            Ok(())
+        }
+
+        fn get_wallpaper(&self) -> Result<PathBuf> {
+            Ok(PathBuf::from("/original.png"))
+        }
+    }
+
+    #[test]
+    fn test_shutdown_closes_windows_and_restores_wallpaper() {
+        let setter = Arc::new(RecordingSetter::default());
+        let (orchestrator, command_rx) = test_orchestrator(Settings::parse(SETTINGS).unwrap());
+        let mut orchestrator = orchestrator.with_wallpaper_setter(setter.clone());
+        orchestrator.permissions =
+            Arc::new(PermissionChecker::new(vec![Permission::Wallpaper].into()));
+
+        orchestrator.start_session();
+        orchestrator.shutdown(None);
+
+        assert!(matches!(command_rx.try_recv(), Ok(WindowCommand::CloseAll)));
+        assert_eq!(
+            *setter.set.lock().unwrap(),
+            vec![PathBuf::from("/original.png")]
+        );
+
+        // Restoring only happens once
+        orchestrator.shutdown(None);
+        assert_eq!(setter.set.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_pause_blocks_until_resumed_or_shutdown() {
+        let is_running = Arc::new(AtomicBool::new(false));
+        let shutdown = Shutdown::new();
+        let (orchestrator, _command_rx) = test_orchestrator(Settings::parse(SETTINGS).unwrap());
+        let mut orchestrator = orchestrator.with_shutdown(shutdown.clone());
+        orchestrator.is_running = is_running.clone();
+
+        let paused =
+            tokio::time::timeout(Duration::from_millis(300), orchestrator.wait_while_paused());
+        assert!(paused.await.is_err());
+
+        let resume = tokio::spawn({
+            let is_running = is_running.clone();
+            async move {
+                sleep(Duration::from_millis(50)).await;
+                is_running.store(true, Ordering::Relaxed);
+            }
+        });
+        tokio::time::timeout(Duration::from_secs(5), orchestrator.wait_while_paused())
+            .await
+            .unwrap();
+        resume.await.unwrap();
+
+        // A shutdown doesn't have to wait for a resume
+        is_running.store(false, Ordering::Relaxed);
+        shutdown.request();
+        tokio::time::timeout(Duration::from_secs(5), orchestrator.wait_while_paused())
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_llm_client_rebuilt_on_host_change() {
+        let settings = Settings::parse(SETTINGS).unwrap();
+        let (mut orchestrator, _command_rx) = test_orchestrator(settings.clone());
+        assert!(orchestrator.llm_client().host().contains("localhost"));
+        assert!(!orchestrator.refresh_llm_client());
+
+        let mut llm_settings = settings.llm_settings.clone();
+        llm_settings.host = "http://llm.example.com:8080".to_string();
+        llm_settings.model = "mistral".to_string();
+        orchestrator
+            .llm_settings_sender()
+            .send(llm_settings)
+            .unwrap();
+
+        assert!(orchestrator.refresh_llm_client());
+        assert!(
+            orchestrator
+                .llm_client()
+                .host()
+                .contains("llm.example.com:8080")
+        );
+        assert_eq!(orchestrator.llm_client().model(), "mistral");
+    }
+
+    #[test]
+    fn test_reloaded_settings_apply_live() {
+        let settings = Settings::parse(SETTINGS).unwrap();
+        let (mut orchestrator, _command_rx) = test_orchestrator(settings.clone());
+        orchestrator.pack_config = Arc::new(
+            PackConfig::parse(&PACK.replace("permissions = []", r#"permissions = ["image"]"#))
+                .unwrap(),
+        );
+        assert!(!orchestrator.apply_settings(None));
+
+        let mut reloaded = settings.clone();
+        reloaded.runtime.permissions = vec![Permission::Image];
+        reloaded.runtime.iteration_delay_secs = 1.5;
+        reloaded.llm_settings.model = "mistral".to_string();
+        reloaded.runtime.pack.current = "Other Pack".to_string();
+        orchestrator
+            .settings_sender()
+            .send(Arc::new(reloaded))
+            .unwrap();
+
+        assert!(orchestrator.apply_settings(None));
+        assert!(orchestrator.permissions.has_permission(Permission::Image));
+        assert_eq!(orchestrator.settings.runtime.iteration_delay_secs, 1.5);
+        assert!(orchestrator.refresh_llm_client());
+        assert_eq!(orchestrator.llm_client().model(), "mistral");
+        // The loaded pack only changes on restart
+        assert_eq!(orchestrator.settings.runtime.pack.current, "Test Pack");
+        assert!(!orchestrator.apply_settings(None));
+    }
+
+    #[test]
+    fn test_forced_safe_mode_outlasts_reloads() {
+        let settings = Settings::parse(SETTINGS).unwrap();
+        let (orchestrator, _command_rx) = test_orchestrator(settings.clone());
+        let mut orchestrator = orchestrator.with_forced_safe_mode(true);
+        orchestrator.pack_config = Arc::new(
+            PackConfig::parse(&PACK.replace(
+                "permissions = []",
+                r#"permissions = ["image", "wallpaper"]"#,
+            ))
+            .unwrap(),
+        );
+
+        // The settings file leaves safe mode off and grants the wallpaper
+        let mut reloaded = settings.clone();
+        reloaded.runtime.safe_mode = false;
+        reloaded.runtime.permissions = vec![Permission::Image, Permission::Wallpaper];
+        orchestrator
+            .settings_sender()
+            .send(Arc::new(reloaded))
+            .unwrap();
+
+        assert!(orchestrator.apply_settings(None));
+        assert!(orchestrator.permissions.has_permission(Permission::Image));
+        assert!(
+            !orchestrator
+                .permissions
+                .has_permission(Permission::Wallpaper)
+        );
+    }
 
     #[test]
     fn test_extract_code_block() {
+        let think = vec!["think".to_string()];
         let response = "Here is the code:\n```typescript\nconsole.log('hello');\n```";
         assert_eq!(
-            extract_code_block(response),
+            extract_code_block(response, &think),
             Some("console.log('hello');".to_string())
         );
 
         let response_with_think =
             "<think>Some thinking...</think>\n```typescript\nconsole.log('hello');\n```";
         assert_eq!(
-            extract_code_block(response_with_think),
+            extract_code_block(response_with_think, &think),
             Some("console.log('hello');".to_string())
         );
 
         let response_no_lang = "```\nconsole.log('hello');\n```";
         assert_eq!(
-            extract_code_block(response_no_lang),
+            extract_code_block(response_no_lang, &think),
             Some("console.log('hello');".to_string())
         );
 
         let response_raw = "console.log('hello');";
         assert_eq!(
-            extract_code_block(response_raw),
+            extract_code_block(response_raw, &think),
             Some("console.log('hello');".to_string())
         );
 
         let response_with_imports =
             "```typescript\nimport { image } from './sdk';\nconsole.log('hello');\n```";
         assert_eq!(
-            extract_code_block(response_with_imports),
+            extract_code_block(response_with_imports, &think),
+            Some("console.log('hello');".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_code_block_custom_reasoning_tag() {
+        let tags = vec!["think".to_string(), "scratchpad".to_string()];
+        let response = "<scratchpad>Try ```ts\nwrong()\n```</scratchpad>\n```typescript\nconsole.log('hello');\n```";
+        assert_eq!(
+            extract_code_block(response, &tags),
+            Some("console.log('hello');".to_string())
+        );
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_collect_response_stops_after_code_block() {
+        let think = vec!["think".to_string()];
+        let chunks = [
+            "<think>maybe ```typescript\nno()\n``` ",
+            "then</think>Example:\n```\nnot run\n```\n```type",
+            "script\nconst n = 1;\n`",
+            "``\nThis is why.",
+        ];
+        let stream = futures::stream::iter(chunks.map(|c| Ok(c.to_string())))
+            .chain(futures::stream::once(async {
+                Err(anyhow::anyhow!("should not be read"))
+            }))
+            .boxed();
+
+        let response = collect_response(stream, &think).await.unwrap();
+        assert!(response.ends_with("This is why."));
+        assert_eq!(
+            extract_all_code_blocks(&response, &think).last(),
+            Some(&"const n = 1;".to_string())
+        );
+
+        // Without a code block the whole reply is read
+        let prose = futures::stream::iter(["Just ", "text"].map(|c| Ok(c.to_string()))).boxed();
+        assert_eq!(collect_response(prose, &think).await.unwrap(), "Just text");
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_collect_response_stops_at_closing_fence() {
+        let think = vec!["think".to_string()];
+        let stream = futures::stream::iter(
+            ["```typescript\nconsole.log(1);\n", "```"].map(|c| Ok(c.to_string())),
+        )
+        .chain(futures::stream::pending())
+        .boxed();
+
+        let response = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            collect_response(stream, &think),
+        )
+        .await
+        .expect("stream was read past the closing fence")
+        .unwrap();
+        assert_eq!(response, "```typescript\nconsole.log(1);\n```");
+    }
+
+    #[test]
+    fn test_extract_all_code_blocks() {
+        let think = vec!["think".to_string()];
+        let response = "<think>```ts\nignored()\n```</think>First set up:\n```typescript\nimport { image } from './sdk';\nconst n = 3;\n```\nThen show them:\n```typescript\nfor (let i = 0; i < n; i++) {}\n```\nDone.";
+        assert_eq!(
+            extract_all_code_blocks(response, &think),
+            vec![
+                "const n = 3;".to_string(),
+                "for (let i = 0; i < n; i++) {}".to_string()
+            ]
+        );
+
+        assert!(extract_all_code_blocks("```typescript\nunclosed()", &think).is_empty());
+        assert!(extract_all_code_blocks("  ", &think).is_empty());
+    }
+
+    #[test]
+    fn test_extract_code_block_unclosed_reasoning_tag() {
+        let tags = vec!["think".to_string()];
+        let response = "<think>Planning the scene...\n```typescript\nconsole.log('hello');\n```";
+        assert_eq!(
+            extract_code_block(response, &tags),
             Some("console.log('hello');".to_string())
         );
     }
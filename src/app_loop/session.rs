@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+/// Source of the current time for the orchestrator, injectable for tests
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// Clock backed by `Instant::now()`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Bounds the total runtime of a session
+#[derive(Debug, Clone, Copy)]
+pub struct SessionLimit {
+    started: Instant,
+    max: Option<Duration>,
+}
+
+impl SessionLimit {
+    pub fn new(started: Instant, max: Option<Duration>) -> Self {
+        Self { started, max }
+    }
+
+    /// Build a limit from the `session_max_mins` setting
+    pub fn from_minutes(started: Instant, max_mins: Option<u64>) -> Self {
+        Self::new(started, max_mins.map(|mins| Duration::from_secs(mins * 60)))
+    }
+
+    /// Whether the session has run past its maximum length
+    pub fn is_exceeded(&self, now: Instant) -> bool {
+        self.max
+            .is_some_and(|max| now.saturating_duration_since(self.started) >= max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unbounded_session_never_exceeds() {
+        let start = Instant::now();
+        let limit = SessionLimit::from_minutes(start, None);
+        assert!(!limit.is_exceeded(start + Duration::from_secs(24 * 60 * 60)));
+    }
+
+    #[test]
+    fn test_session_exceeds_after_max() {
+        let start = Instant::now();
+        let limit = SessionLimit::from_minutes(start, Some(30));
+        assert!(!limit.is_exceeded(start + Duration::from_secs(29 * 60)));
+        assert!(limit.is_exceeded(start + Duration::from_secs(30 * 60)));
+    }
+}
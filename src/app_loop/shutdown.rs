@@ -0,0 +1,127 @@
+//! Orderly shutdown on SIGINT/SIGTERM and the tray Quit command
+//!
+//! A shutdown is requested from any thread, the loop thread cleans up
+//! (windows, audio, wallpaper) and marks it finished, then the UI thread quits
+//! the event loop.
+
+use anyhow::Result;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the app to shut down
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Wait until a shutdown is requested
+    pub async fn wait(&self) {
+        loop {
+            // Registered before checking the flag so a request in between isn't missed
+            let notified = self.notify.notified();
+            if self.is_requested() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Run `future` unless a shutdown is requested first, `None` if it was cut short.
+    ///
+    /// Used for slow steps like LLM calls, so cleanup still happens within the grace period.
+    pub async fn or_requested<F: Future>(&self, future: F) -> Option<F::Output> {
+        tokio::select! {
+            output = future => Some(output),
+            _ = self.wait() => None,
+        }
+    }
+
+    /// Mark cleanup as done, the event loop can quit
+    pub fn finish(&self) {
+        self.finished.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::SeqCst)
+    }
+
+    /// Request a shutdown on SIGINT/SIGTERM (Ctrl-C or console close on Windows).
+    /// A second signal exits immediately.
+    pub fn install_signal_handlers(&self) -> Result<()> {
+        let shutdown = self.clone();
+        ctrlc::set_handler(move || {
+            if shutdown.is_requested() {
+                std::process::exit(130);
+            }
+            shutdown.request();
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_wait_wakes_on_request() {
+        let shutdown = Shutdown::new();
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.wait().await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(!waiter.is_finished());
+
+        shutdown.request();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(shutdown.is_requested());
+        assert!(!shutdown.is_finished());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_or_requested_cuts_slow_work_short() {
+        let shutdown = Shutdown::new();
+        assert_eq!(shutdown.or_requested(async { 1 }).await, Some(1));
+
+        let slow = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move {
+                shutdown
+                    .or_requested(tokio::time::sleep(Duration::from_secs(60)))
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown.request();
+        let result = tokio::time::timeout(Duration::from_secs(5), slow)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, None);
+    }
+}
@@ -0,0 +1,143 @@
+//! Installed packs and switching between them at runtime
+
+use crate::assets::loader::AssetLoader;
+use crate::assets::registry::AssetRegistry;
+use crate::config::pack::{PackConfig, PackConfigFormat, validation_failed};
+use crate::permissions::{PermissionChecker, PermissionResolver, PermissionSet};
+use anyhow::{Result, anyhow};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The pack a runtime is currently using
+#[derive(Debug, Clone)]
+pub struct ActivePack {
+    pub name: String,
+    pub config: Arc<PackConfig>,
+}
+
+/// A pack loaded by [`PackLibrary::load`], with its permissions already resolved
+pub struct LoadedPack {
+    pub name: String,
+    pub config: Arc<PackConfig>,
+    pub registry: Arc<AssetRegistry>,
+    pub permissions: PermissionChecker,
+}
+
+/// The packs installed in a packs directory
+pub struct PackLibrary {
+    packs_dir: PathBuf,
+    user_permissions: PermissionSet,
+    safe_mode: bool,
+}
+
+impl PackLibrary {
+    pub fn new(
+        packs_dir: impl Into<PathBuf>,
+        user_permissions: PermissionSet,
+        safe_mode: bool,
+    ) -> Self {
+        Self {
+            packs_dir: packs_dir.into(),
+            user_permissions,
+            safe_mode,
+        }
+    }
+
+    /// Names of all packs with a `config.toml` or `config.json`, sorted
+    pub fn list(&self) -> Result<Vec<String>> {
+        let mut packs = Vec::new();
+        for entry in std::fs::read_dir(&self.packs_dir)? {
+            let entry = entry?;
+            if PackConfigFormat::detect(&entry.path()).is_some()
+                && let Some(name) = entry.file_name().to_str()
+            {
+                packs.push(name.to_string());
+            }
+        }
+        packs.sort();
+        Ok(packs)
+    }
+
+    /// Load a pack's config and assets and resolve its permissions against the user's grants
+    pub fn load(&self, name: &str) -> Result<LoadedPack> {
+        if !self.list()?.iter().any(|p| p == name) {
+            return Err(anyhow!("Pack '{}' not found", name));
+        }
+
+        let config = PackConfig::load_from(&self.packs_dir, name)?;
+        config
+            .validate(&self.packs_dir.join(name))
+            .map_err(|errors| validation_failed(name, &errors))?;
+        let registry = AssetLoader::load_from(&self.packs_dir, &config, name)?;
+        let pack_permissions: PermissionSet = config.meta.permissions.clone().into();
+        let permissions = PermissionChecker::new(PermissionResolver::resolve(
+            &pack_permissions,
+            &self.user_permissions,
+            self.safe_mode,
+        ));
+
+        Ok(LoadedPack {
+            name: name.to_string(),
+            config: Arc::new(config),
+            registry: Arc::new(registry),
+            permissions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permissions::Permission;
+    use uuid::Uuid;
+
+    fn write_pack(packs_dir: &Path, name: &str, permissions: &str, image: &str) {
+        let dir = packs_dir.join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("config.toml"),
+            format!(
+                r#"
+[meta]
+name = "{name}"
+version = "1.0.0"
+permissions = [{permissions}]
+
+[[moods]]
+name = "default"
+description = "Default"
+tags = []
+
+[assets]
+image = [{{ path = "{image}", tags = ["tag"] }}]
+"#
+            ),
+        )
+        .unwrap();
+        std::fs::write(dir.join(image), b"").unwrap();
+    }
+
+    #[test]
+    fn test_list_and_load() {
+        let dir = std::env::temp_dir().join(format!("goon-ai-packs-{}", Uuid::new_v4()));
+        write_pack(&dir, "beta", r#""image", "video""#, "b.png");
+        write_pack(&dir, "alpha", r#""image""#, "a.png");
+        std::fs::create_dir_all(dir.join("not-a-pack")).unwrap();
+
+        let mut user = PermissionSet::new();
+        user.add(Permission::Image);
+        let library = PackLibrary::new(&dir, user, false);
+
+        assert_eq!(library.list().unwrap(), vec!["alpha", "beta"]);
+
+        let beta = library.load("beta").unwrap();
+        assert_eq!(beta.registry.images.len(), 1);
+        assert!(beta.permissions.has_permission(Permission::Image));
+        assert!(!beta.permissions.has_permission(Permission::Video));
+
+        assert!(library.load("missing").is_err());
+        assert!(library.load("../beta").is_err());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
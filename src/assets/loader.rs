@@ -2,9 +2,13 @@ use crate::assets::registry::AssetRegistry;
 use crate::assets::types::{
     Asset, AudioAsset, HypnoAsset, ImageAsset, VideoAsset, WallpaperAsset, WebsiteAsset,
 };
-use crate::config::pack::PackConfig;
-use anyhow::Result;
-use std::path::Path;
+use crate::config::pack::{Asset as ConfigAsset, PackConfig};
+use crate::media::video::probe::{self, VideoInfo};
+use anyhow::{Context, Result};
+use globset::GlobBuilder;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+use walkdir::WalkDir;
 
 #[allow(dead_code)]
 pub struct AssetLoader;
@@ -12,62 +16,74 @@ pub struct AssetLoader;
 impl AssetLoader {
     #[allow(dead_code)]
     pub fn load(pack_config: &PackConfig, pack_name: &str) -> Result<AssetRegistry> {
+        Self::load_from(Path::new("packs"), pack_config, pack_name)
+    }
+
+    /// Load a pack's assets from a packs directory other than `packs/`
+    pub fn load_from(
+        packs_dir: &Path,
+        pack_config: &PackConfig,
+        pack_name: &str,
+    ) -> Result<AssetRegistry> {
         let mut registry = AssetRegistry::new();
-        let base_path = Path::new("packs").join(pack_name);
+        let base_path = packs_dir.join(pack_name);
+        registry.root = Some(base_path.clone());
 
         if let Some(images) = &pack_config.assets.image {
-            for img in images {
-                let path = base_path.join(&img.path);
+            for (path, img) in expand_paths(&base_path, images)? {
+                let (width, height) = image_dimensions(&path);
                 registry.add(Asset::Image(ImageAsset {
                     path,
                     tags: img.tags.clone(),
-                    width: 0,
-                    height: 0,
+                    weight: img.weight,
+                    width,
+                    height,
                 }));
             }
         }
 
         if let Some(videos) = &pack_config.assets.video {
-            for vid in videos {
-                let path = base_path.join(&vid.path);
+            for (path, vid) in expand_paths(&base_path, videos)? {
+                let info = video_info(&path);
                 registry.add(Asset::Video(VideoAsset {
                     path,
                     tags: vid.tags.clone(),
-                    duration: None,
-                    width: 0,
-                    height: 0,
+                    weight: vid.weight,
+                    duration: info.duration,
+                    width: info.width,
+                    height: info.height,
                 }));
             }
         }
 
         if let Some(audio) = &pack_config.assets.audio {
-            for aud in audio {
-                let path = base_path.join(&aud.path);
+            for (path, aud) in expand_paths(&base_path, audio)? {
                 registry.add(Asset::Audio(AudioAsset {
                     path,
                     tags: aud.tags.clone(),
+                    weight: aud.weight,
                     duration: None,
                 }));
             }
         }
 
         if let Some(hypnos) = &pack_config.assets.hypno {
-            for hyp in hypnos {
-                let path = base_path.join(&hyp.path);
+            for (path, hyp) in expand_paths(&base_path, hypnos)? {
                 registry.add(Asset::Hypno(HypnoAsset {
                     path,
                     tags: hyp.tags.clone(),
+                    weight: hyp.weight,
                     is_animated: true,
                 }));
             }
         }
 
         if let Some(wallpapers) = &pack_config.assets.wallpaper {
-            for wall in wallpapers {
-                let path = base_path.join(&wall.path);
+            for (path, wall) in expand_paths(&base_path, wallpapers)? {
                 registry.add(Asset::Wallpaper(WallpaperAsset {
                     path,
                     tags: wall.tags.clone(),
+                    weight: wall.weight,
                 }));
             }
         }
@@ -79,6 +95,7 @@ impl AssetLoader {
                     url: site.url.clone(),
                     description: site.description.clone(),
                     tags: site.tags.clone(),
+                    weight: None,
                 }));
             }
         }
@@ -87,10 +104,68 @@ impl AssetLoader {
     }
 }
 
+/// Files of each config entry, with globs expanded against the pack directory.
+///
+/// Glob matches are sorted so the registry order doesn't depend on the file system.
+fn expand_paths<'a>(
+    base_path: &Path,
+    entries: &'a [ConfigAsset],
+) -> Result<Vec<(PathBuf, &'a ConfigAsset)>> {
+    let mut paths = Vec::new();
+    for entry in entries {
+        if !entry.is_glob() {
+            paths.push((base_path.join(&entry.path), entry));
+            continue;
+        }
+
+        let matcher = GlobBuilder::new(&entry.path)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("Invalid asset glob {:?}", entry.path))?
+            .compile_matcher();
+        let mut matched: Vec<PathBuf> = WalkDir::new(base_path)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+            .map(|e| e.into_path())
+            .filter(|path| {
+                path.strip_prefix(base_path)
+                    .is_ok_and(|relative| matcher.is_match(relative))
+            })
+            .collect();
+        if matched.is_empty() {
+            warn!("Asset glob {:?} matched no files", entry.path);
+        }
+        matched.sort();
+        paths.extend(matched.into_iter().map(|path| (path, entry)));
+    }
+    Ok(paths)
+}
+
+/// Width and height from the image header, zero if the file can't be read
+fn image_dimensions(path: &Path) -> (u32, u32) {
+    ::image::image_dimensions(path).unwrap_or_else(|e| {
+        warn!("Failed to read image dimensions of {:?}: {}", path, e);
+        (0, 0)
+    })
+}
+
+/// Duration and dimensions of a video, unknown if it can't be probed
+fn video_info(path: &Path) -> VideoInfo {
+    probe::probe(path).unwrap_or_else(|e| {
+        warn!("Failed to probe video {:?}: {}", path, e);
+        VideoInfo {
+            duration: None,
+            width: 0,
+            height: 0,
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::pack::{Asset as ConfigAsset, Assets, PackMeta};
+    use crate::config::pack::{Assets, PackMeta};
 
     #[test]
     fn test_load_assets() {
@@ -105,10 +180,12 @@ mod tests {
                 image: Some(vec![ConfigAsset {
                     path: "img/1.jpg".to_string(),
                     tags: vec!["tag1".to_string()],
+                    weight: None,
                 }]),
                 video: Some(vec![ConfigAsset {
                     path: "vid/1.mp4".to_string(),
                     tags: vec!["tag2".to_string()],
+                    weight: None,
                 }]),
                 audio: None,
                 hypno: None,
@@ -116,6 +193,7 @@ mod tests {
             },
             websites: None,
             prompts: None,
+            popups: None,
         };
 
         let registry = AssetLoader::load(&pack_config, "Test Pack").unwrap();
@@ -131,4 +209,136 @@ mod tests {
             panic!("Expected ImageAsset");
         }
     }
+
+    #[test]
+    fn test_image_dimensions_read_from_header() {
+        let mut pack_config = PackConfig::new("TestPack");
+        pack_config.assets.image = Some(vec![
+            ConfigAsset {
+                path: "image/beach.jpg".to_string(),
+                tags: vec![],
+                weight: None,
+            },
+            ConfigAsset {
+                path: "image/missing.jpg".to_string(),
+                tags: vec![],
+                weight: None,
+            },
+        ]);
+
+        let registry = AssetLoader::load(&pack_config, "TestPack").unwrap();
+        let sizes: Vec<_> = registry
+            .images
+            .iter()
+            .map(|asset| match asset {
+                Asset::Image(img) => (img.width, img.height),
+                _ => panic!("Expected ImageAsset"),
+            })
+            .collect();
+        // An unreadable file is kept with zero dimensions
+        assert_eq!(sizes, vec![(2500, 1667), (0, 0)]);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_video_info_probed_at_load() {
+        let mut pack_config = PackConfig::new("TestPack");
+        pack_config.assets.video = Some(vec![
+            ConfigAsset {
+                path: "video/test-jellyfish.mp4".to_string(),
+                tags: vec![],
+                weight: None,
+            },
+            ConfigAsset {
+                path: "video/missing.mp4".to_string(),
+                tags: vec![],
+                weight: None,
+            },
+        ]);
+
+        let registry = AssetLoader::load(&pack_config, "TestPack").unwrap();
+        let Asset::Video(jellyfish) = &registry.videos[0] else {
+            panic!("Expected VideoAsset");
+        };
+        assert_eq!((jellyfish.width, jellyfish.height), (1920, 1080));
+        let seconds = jellyfish.duration.unwrap().as_secs_f64();
+        assert!((seconds - 10.0).abs() < 0.1, "{}", seconds);
+
+        // An unreadable file is kept with unknown duration and size
+        let Asset::Video(missing) = &registry.videos[1] else {
+            panic!("Expected VideoAsset");
+        };
+        assert_eq!(
+            (missing.duration, missing.width, missing.height),
+            (None, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_glob_entry_expands_to_matching_files() {
+        let packs_dir =
+            std::env::temp_dir().join(format!("goon-ai-glob-test-{}", uuid::Uuid::new_v4()));
+        let pack_dir = packs_dir.join("GlobPack");
+        for file in [
+            "image/a.jpg",
+            "image/nested/b.png",
+            "image/notes.txt",
+            "other/c.jpg",
+            "single.jpg",
+        ] {
+            let path = pack_dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+
+        let mut pack_config = PackConfig::new("GlobPack");
+        pack_config.assets.image = Some(vec![
+            ConfigAsset {
+                path: "image/**/*.{jpg,png}".to_string(),
+                tags: vec!["globbed".to_string()],
+                weight: Some(2),
+            },
+            ConfigAsset {
+                path: "single.jpg".to_string(),
+                tags: vec!["single".to_string()],
+                weight: None,
+            },
+        ]);
+
+        let registry = AssetLoader::load_from(&packs_dir, &pack_config, "GlobPack").unwrap();
+        std::fs::remove_dir_all(&packs_dir).unwrap();
+
+        let loaded: Vec<_> = registry
+            .images
+            .iter()
+            .map(|asset| match asset {
+                Asset::Image(img) => (
+                    img.path.strip_prefix(&pack_dir).unwrap().to_path_buf(),
+                    img.tags.clone(),
+                    img.weight,
+                ),
+                _ => panic!("Expected ImageAsset"),
+            })
+            .collect();
+        assert_eq!(
+            loaded,
+            vec![
+                (
+                    Path::new("image/a.jpg").to_path_buf(),
+                    vec!["globbed".to_string()],
+                    Some(2)
+                ),
+                (
+                    Path::new("image/nested/b.png").to_path_buf(),
+                    vec!["globbed".to_string()],
+                    Some(2)
+                ),
+                (
+                    Path::new("single.jpg").to_path_buf(),
+                    vec!["single".to_string()],
+                    None
+                ),
+            ]
+        );
+    }
 }
@@ -1,3 +1,4 @@
+pub mod library;
 pub mod loader;
 pub mod registry;
 pub mod selector;
@@ -1,4 +1,30 @@
 use crate::assets::types::Asset;
+use std::path::{Component, Path, PathBuf};
+
+/// Why a path into the pack was rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetPathError {
+    /// The registry wasn't loaded from a pack directory
+    NoPackRoot,
+    /// The path is absolute or leads out of the pack directory
+    OutsidePack(String),
+    /// No file exists at the path
+    NotFound(String),
+}
+
+impl std::fmt::Display for AssetPathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetPathError::NoPackRoot => write!(f, "No pack directory to load assets from"),
+            AssetPathError::OutsidePack(path) => {
+                write!(f, "Path '{}' is outside the pack directory", path)
+            }
+            AssetPathError::NotFound(path) => write!(f, "No file at '{}' in the pack", path),
+        }
+    }
+}
+
+impl std::error::Error for AssetPathError {}
 
 #[derive(Debug, Default)]
 #[allow(dead_code)]
@@ -9,6 +35,8 @@ pub struct AssetRegistry {
     pub hypnos: Vec<Asset>,
     pub wallpapers: Vec<Asset>,
     pub websites: Vec<Asset>,
+    /// Directory the assets were loaded from, `None` for registries built in code
+    pub root: Option<PathBuf>,
 }
 
 impl AssetRegistry {
@@ -28,4 +56,70 @@ impl AssetRegistry {
             Asset::Website(_) => self.websites.push(asset),
         }
     }
+
+    /// Resolve a path relative to the pack directory to a file inside it.
+    ///
+    /// Absolute paths and `..` are rejected, as are symlinks that lead out of the pack.
+    pub fn resolve_path(&self, path: &str) -> Result<PathBuf, AssetPathError> {
+        let root = self.root.as_ref().ok_or(AssetPathError::NoPackRoot)?;
+        let relative = Path::new(path);
+        if !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(AssetPathError::OutsidePack(path.to_string()));
+        }
+
+        let not_found = |_| AssetPathError::NotFound(path.to_string());
+        let resolved = root.join(relative).canonicalize().map_err(not_found)?;
+        let root = root.canonicalize().map_err(not_found)?;
+        if !resolved.starts_with(&root) {
+            return Err(AssetPathError::OutsidePack(path.to_string()));
+        }
+        if !resolved.is_file() {
+            return Err(AssetPathError::NotFound(path.to_string()));
+        }
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_resolve_path_stays_in_pack() {
+        let dir = std::env::temp_dir().join(format!("goon-ai-registry-{}", Uuid::new_v4()));
+        let pack = dir.join("pack");
+        std::fs::create_dir_all(pack.join("images")).unwrap();
+        std::fs::write(pack.join("images/a.png"), b"").unwrap();
+        std::fs::write(dir.join("secret.txt"), b"").unwrap();
+
+        let registry = AssetRegistry {
+            root: Some(pack.clone()),
+            ..Default::default()
+        };
+        let resolved = registry.resolve_path("images/a.png");
+        let escaped = registry.resolve_path("../secret.txt");
+        let nested_escape = registry.resolve_path("images/../../secret.txt");
+        let absolute = registry.resolve_path(&dir.join("secret.txt").to_string_lossy());
+        let missing = registry.resolve_path("images/b.png");
+        let is_dir = registry.resolve_path("images");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(resolved.unwrap().ends_with("images/a.png"));
+        assert_eq!(
+            escaped,
+            Err(AssetPathError::OutsidePack("../secret.txt".to_string()))
+        );
+        assert!(matches!(nested_escape, Err(AssetPathError::OutsidePack(_))));
+        assert!(matches!(absolute, Err(AssetPathError::OutsidePack(_))));
+        assert!(matches!(missing, Err(AssetPathError::NotFound(_))));
+        assert!(matches!(is_dir, Err(AssetPathError::NotFound(_))));
+        assert_eq!(
+            AssetRegistry::new().resolve_path("images/a.png"),
+            Err(AssetPathError::NoPackRoot)
+        );
+    }
 }
@@ -2,16 +2,127 @@ use crate::assets::registry::AssetRegistry;
 use crate::assets::types::Asset;
 use crate::config::pack::Mood;
 use rand::prelude::IndexedRandom;
+use std::collections::{HashMap, VecDeque};
+use std::mem::{Discriminant, discriminant};
+use std::sync::Mutex;
+
+/// How many recent picks of each media type are avoided by default
+pub const DEFAULT_REPEAT_WINDOW: usize = 3;
+
+/// The last few assets picked of each media type, so they aren't picked again right away
+#[derive(Debug)]
+pub struct RecentAssets {
+    window: usize,
+    picked: HashMap<Discriminant<Asset>, VecDeque<String>>,
+}
+
+impl Default for RecentAssets {
+    fn default() -> Self {
+        Self::new(DEFAULT_REPEAT_WINDOW)
+    }
+}
+
+impl RecentAssets {
+    /// Avoid the last `window` picks of each media type, 0 allows repeats
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            picked: HashMap::new(),
+        }
+    }
+
+    fn record(&mut self, asset: &Asset) {
+        if self.window == 0 {
+            return;
+        }
+        let picked = self.picked.entry(discriminant(asset)).or_default();
+        picked.push_back(asset_key(asset));
+        while picked.len() > self.window {
+            picked.pop_front();
+        }
+    }
+
+    /// Drop recently picked assets from `candidates`.
+    ///
+    /// When avoiding all of them would leave nothing, the oldest picks are let
+    /// back in first, so the most recent one is avoided as long as possible.
+    fn exclude<'a>(&self, candidates: Vec<&'a Asset>) -> Vec<&'a Asset> {
+        let Some(picked) = candidates
+            .first()
+            .and_then(|asset| self.picked.get(&discriminant(*asset)))
+        else {
+            return candidates;
+        };
+        for avoided in (1..=picked.len()).rev() {
+            let recent: Vec<&String> = picked.iter().rev().take(avoided).collect();
+            let remaining: Vec<&'a Asset> = candidates
+                .iter()
+                .copied()
+                .filter(|asset| !recent.contains(&&asset_key(asset)))
+                .collect();
+            if !remaining.is_empty() {
+                return remaining;
+            }
+        }
+        candidates
+    }
+}
+
+/// Whether selection may fall back to assets outside the mood, put in the op
+/// state by the runtime.
+///
+/// When it's on and nothing matches the mood, selection falls back to the
+/// requested tags alone, then to any asset of the type.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AssetFallback(pub bool);
+
+/// What makes an asset the same asset for repeat avoidance
+fn asset_key(asset: &Asset) -> String {
+    match asset {
+        Asset::Website(site) => site.url.clone(),
+        _ => asset
+            .get_path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default(),
+    }
+}
 
 #[allow(dead_code)]
 pub struct AssetSelector<'a> {
     registry: &'a AssetRegistry,
+    recent: Option<&'a Mutex<RecentAssets>>,
+    exclude_tags: &'a [String],
+    allow_fallback: bool,
 }
 
 impl<'a> AssetSelector<'a> {
     #[allow(dead_code)]
     pub fn new(registry: &'a AssetRegistry) -> Self {
-        Self { registry }
+        Self {
+            registry,
+            recent: None,
+            exclude_tags: &[],
+            allow_fallback: false,
+        }
+    }
+
+    /// When nothing matches, relax the mood and then the requested tags
+    /// instead of picking nothing
+    pub fn allow_fallback(mut self, allow: bool) -> Self {
+        self.allow_fallback = allow;
+        self
+    }
+
+    /// Never pick assets that have any of `tags`
+    pub fn excluding(mut self, tags: &'a [String]) -> Self {
+        self.exclude_tags = tags;
+        self
+    }
+
+    /// Avoid the assets picked most recently, and remember what this selector picks
+    pub fn with_recent(mut self, recent: &'a Mutex<RecentAssets>) -> Self {
+        self.recent = Some(recent);
+        self
     }
 
     #[allow(dead_code)]
@@ -45,10 +156,51 @@ impl<'a> AssetSelector<'a> {
     }
 
     fn select_from(&self, assets: &'a [Asset], mood: &Mood, tags: &[String]) -> Option<&'a Asset> {
-        let mood_tags = &mood.tags;
+        // Without a match, relax the mood first and then the requested tags
+        let no_tags: &[String] = &[];
+        let fallbacks = [(&mood.tags[..], tags), (no_tags, tags), (no_tags, no_tags)];
+        let levels = if self.allow_fallback {
+            fallbacks.len()
+        } else {
+            1
+        };
+        let mut candidates = fallbacks[..levels]
+            .iter()
+            .map(|&(mood_tags, tags)| {
+                // An asset weighted 0 is never picked
+                let mut candidates = self.matching(assets, mood_tags, tags);
+                candidates.retain(|asset| asset.weight() > 0);
+                candidates
+            })
+            .find(|candidates| !candidates.is_empty())
+            .unwrap_or_default();
 
-        // Filter assets that match mood tags AND requested tags
-        let candidates: Vec<&Asset> = assets
+        let mut recent = self.recent.map(|recent| recent.lock().unwrap());
+        if let Some(recent) = &recent {
+            candidates = recent.exclude(candidates);
+        }
+
+        // Each asset is picked with a chance proportional to its weight
+        let mut rng = rand::rng();
+        let asset = candidates
+            .choose_weighted(&mut rng, |asset| asset.weight())
+            .ok()
+            .copied()?;
+        if let Some(recent) = &mut recent {
+            recent.record(asset);
+        }
+        Some(asset)
+    }
+
+    /// Assets matching at least one mood tag (or any asset if the mood has no tags)
+    /// and all of the requested tags, without any excluded tag
+    fn matching(
+        &self,
+        assets: &'a [Asset],
+        mood_tags: &[String],
+        tags: &[String],
+    ) -> Vec<&'a Asset> {
+        assets
             .iter()
             .filter(|asset| {
                 let asset_tags = asset.get_tags();
@@ -60,18 +212,12 @@ impl<'a> AssetSelector<'a> {
                 // Check if asset has ALL requested tags
                 let matches_request = tags.iter().all(|t| asset_tags.contains(t));
 
-                matches_mood && matches_request
-            })
-            .collect();
+                // Check if asset has NONE of the excluded tags
+                let excluded = self.exclude_tags.iter().any(|t| asset_tags.contains(t));
 
-        if candidates.is_empty() {
-            // Fallback: Try matching just the requested tags if mood strictness allows (optional)
-            // For now, let's just return None if no match found with mood constraint
-            return None;
-        }
-
-        let mut rng = rand::rng();
-        candidates.choose(&mut rng).copied()
+                matches_mood && matches_request && !excluded
+            })
+            .collect()
     }
 }
 
@@ -87,6 +233,7 @@ mod tests {
         registry.add(Asset::Image(ImageAsset {
             path: PathBuf::from("img1.jpg"),
             tags: vec!["nature".to_string(), "calm".to_string()],
+            weight: None,
             width: 100,
             height: 100,
         }));
@@ -94,6 +241,7 @@ mod tests {
         registry.add(Asset::Image(ImageAsset {
             path: PathBuf::from("img2.jpg"),
             tags: vec!["city".to_string(), "busy".to_string()],
+            weight: None,
             width: 100,
             height: 100,
         }));
@@ -101,6 +249,7 @@ mod tests {
         registry.add(Asset::Image(ImageAsset {
             path: PathBuf::from("img3.jpg"),
             tags: vec!["nature".to_string(), "busy".to_string()],
+            weight: None,
             width: 100,
             height: 100,
         }));
@@ -169,6 +318,168 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_blended_moods_widen_pool() {
+        let registry = create_test_registry();
+        let selector = AssetSelector::new(&registry);
+        let mood = |name: &str, tag: &str| Mood {
+            name: name.to_string(),
+            description: "".to_string(),
+            tags: vec![tag.to_string()],
+            prompt: None,
+        };
+        let calm = mood("Calm", "calm");
+        let city = mood("City", "city");
+        let blend = Mood::blend(&[calm.clone(), city.clone()]);
+        assert_eq!(blend.name, "Calm + City");
+
+        let pool = |mood: &Mood| {
+            selector
+                .matching(&registry.images, &mood.tags, &[])
+                .iter()
+                .map(|a| a.get_path().unwrap().to_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(pool(&calm), vec!["img1.jpg"]);
+        assert_eq!(pool(&city), vec!["img2.jpg"]);
+        assert_eq!(pool(&blend), vec!["img1.jpg", "img2.jpg"]);
+
+        // A mood without tags allows everything, and so does any blend including it
+        let any = Mood {
+            tags: vec![],
+            ..mood("Any", "")
+        };
+        assert_eq!(pool(&Mood::blend(&[calm, any])).len(), 3);
+    }
+
+    #[test]
+    fn test_weighted_selection() {
+        let mut registry = AssetRegistry::new();
+        for (path, weight) in [("heavy.jpg", Some(10)), ("light.jpg", None)] {
+            registry.add(Asset::Image(ImageAsset {
+                path: PathBuf::from(path),
+                tags: vec![],
+                weight,
+                width: 100,
+                height: 100,
+            }));
+        }
+        let selector = AssetSelector::new(&registry);
+        let mood = Mood {
+            name: "Any".to_string(),
+            description: "".to_string(),
+            tags: vec![],
+            prompt: None,
+        };
+
+        let draws = 2000;
+        let heavy = (0..draws)
+            .filter(|_| {
+                selector
+                    .select_image(&mood, &[])
+                    .and_then(|a| a.get_path())
+                    .is_some_and(|p| p.ends_with("heavy.jpg"))
+            })
+            .count();
+        // Expected about 10 to 1
+        assert!(
+            heavy > (draws - heavy) * 5,
+            "heavy picked {heavy} of {draws}"
+        );
+    }
+
+    #[test]
+    fn test_recent_assets_not_repeated() {
+        let registry = create_test_registry();
+        let mood = Mood {
+            name: "Any".to_string(),
+            description: "".to_string(),
+            tags: vec![],
+            prompt: None,
+        };
+        let recent = Mutex::new(RecentAssets::new(1));
+        let selector = AssetSelector::new(&registry).with_recent(&recent);
+
+        let mut last = None;
+        for _ in 0..50 {
+            let path = selector.select_image(&mood, &[]).unwrap().get_path();
+            assert_ne!(path, last);
+            last = path;
+        }
+
+        // With a single match it is repeated rather than giving up
+        let only = selector.select_image(&mood, &["city".to_string()]);
+        assert_eq!(only, selector.select_image(&mood, &["city".to_string()]));
+        assert!(only.is_some());
+    }
+
+    #[test]
+    fn test_repeat_window_lets_oldest_back_first() {
+        let registry = create_test_registry();
+        let mood = Mood {
+            name: "Any".to_string(),
+            description: "".to_string(),
+            tags: vec![],
+            prompt: None,
+        };
+        let recent = Mutex::new(RecentAssets::new(5));
+        let selector = AssetSelector::new(&registry).with_recent(&recent);
+
+        let first: Vec<_> = (0..3)
+            .map(|_| selector.select_image(&mood, &[]).unwrap().get_path())
+            .collect();
+        // All three were picked once, the fourth pick is the oldest of them
+        assert_eq!(
+            selector.select_image(&mood, &[]).unwrap().get_path(),
+            first[0]
+        );
+    }
+
+    #[test]
+    fn test_exclude_tags() {
+        let registry = create_test_registry();
+        let any = Mood {
+            name: "Any".to_string(),
+            description: "".to_string(),
+            tags: vec![],
+            prompt: None,
+        };
+        let nature = Mood {
+            name: "Nature".to_string(),
+            tags: vec!["nature".to_string()],
+            ..any.clone()
+        };
+        let tags = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        let pool = |mood: &Mood, include: &[String], exclude: &[String]| {
+            AssetSelector::new(&registry)
+                .excluding(exclude)
+                .matching(&registry.images, &mood.tags, include)
+                .iter()
+                .map(|a| a.get_path().unwrap().to_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // Exclusion alone
+        assert_eq!(pool(&any, &[], &tags(&["busy"])), vec!["img1.jpg"]);
+        // Include "nature", exclude "busy"
+        assert_eq!(
+            pool(&any, &tags(&["nature"]), &tags(&["busy"])),
+            vec!["img1.jpg"]
+        );
+        // Mood "nature", exclude "calm"
+        assert_eq!(pool(&nature, &[], &tags(&["calm"])), vec!["img3.jpg"]);
+        // Excluding a requested tag leaves nothing
+        assert!(pool(&any, &tags(&["city"]), &tags(&["city"])).is_empty());
+        // Any one excluded tag is enough to drop an asset
+        assert_eq!(pool(&any, &[], &tags(&["calm", "city"])), vec!["img3.jpg"]);
+        assert!(
+            AssetSelector::new(&registry)
+                .excluding(&tags(&["nature", "city"]))
+                .select_image(&any, &[])
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_select_no_match() {
         let registry = create_test_registry();
@@ -186,12 +497,58 @@ mod tests {
         assert!(asset.is_none());
     }
 
+    #[test]
+    fn test_fallback_relaxes_mood_then_tags() {
+        let registry = create_test_registry();
+        let selector = AssetSelector::new(&registry).allow_fallback(true);
+        let path = |asset: Option<&Asset>| asset.and_then(|a| a.get_path()).unwrap();
+        let mood = |tag: &str| Mood {
+            name: "Mood".to_string(),
+            description: "".to_string(),
+            tags: vec![tag.to_string()],
+            prompt: None,
+        };
+
+        // Mood "calm" AND Request "city" -> the requested tag wins over the mood
+        assert_eq!(
+            path(selector.select_image(&mood("calm"), &["city".to_string()])),
+            PathBuf::from("img2.jpg")
+        );
+        // Nothing has the requested tag either -> any image
+        assert!(
+            selector
+                .select_image(&mood("calm"), &["beach".to_string()])
+                .is_some()
+        );
+        // No image has the mood tag at all
+        assert!(selector.select_image(&mood("beach"), &[]).is_some());
+
+        // Exclusions still hold while falling back
+        let excluded = ["nature".to_string(), "city".to_string()];
+        assert!(
+            AssetSelector::new(&registry)
+                .allow_fallback(true)
+                .excluding(&excluded)
+                .select_image(&mood("beach"), &[])
+                .is_none()
+        );
+
+        // Without fallback selection keeps to the mood
+        assert!(
+            selector
+                .allow_fallback(false)
+                .select_image(&mood("beach"), &[])
+                .is_none()
+        );
+    }
+
     #[test]
     fn test_select_video() {
         let mut registry = AssetRegistry::new();
         registry.add(Asset::Video(crate::assets::types::VideoAsset {
             path: PathBuf::from("vid1.mp4"),
             tags: vec!["action".to_string()],
+            weight: None,
             duration: None,
             width: 1920,
             height: 1080,
@@ -220,6 +577,7 @@ mod tests {
         registry.add(Asset::Audio(crate::assets::types::AudioAsset {
             path: PathBuf::from("audio1.mp3"),
             tags: vec!["ambient".to_string()],
+            weight: None,
             duration: None,
         }));
 
@@ -246,6 +604,7 @@ mod tests {
         registry.add(Asset::Hypno(crate::assets::types::HypnoAsset {
             path: PathBuf::from("hypno1.gif"),
             tags: vec!["spiral".to_string()],
+            weight: None,
             is_animated: true,
         }));
 
@@ -272,6 +631,7 @@ mod tests {
         registry.add(Asset::Wallpaper(crate::assets::types::WallpaperAsset {
             path: PathBuf::from("wall1.jpg"),
             tags: vec!["scenic".to_string()],
+            weight: None,
         }));
 
         let selector = AssetSelector::new(&registry);
@@ -38,6 +38,19 @@ impl Asset {
         }
     }
 
+    /// Relative chance of being picked among matching assets, 1 when unset
+    pub fn weight(&self) -> u32 {
+        let weight = match self {
+            Asset::Image(a) => a.weight,
+            Asset::Video(a) => a.weight,
+            Asset::Audio(a) => a.weight,
+            Asset::Hypno(a) => a.weight,
+            Asset::Wallpaper(a) => a.weight,
+            Asset::Website(a) => a.weight,
+        };
+        weight.unwrap_or(1)
+    }
+
     #[allow(dead_code)]
     pub fn load_data(&self) -> Result<()> {
         Ok(())
@@ -49,6 +62,7 @@ impl Asset {
 pub struct ImageAsset {
     pub path: PathBuf,
     pub tags: Vec<String>,
+    pub weight: Option<u32>,
     pub width: u32,
     pub height: u32,
 }
@@ -58,6 +72,7 @@ pub struct ImageAsset {
 pub struct VideoAsset {
     pub path: PathBuf,
     pub tags: Vec<String>,
+    pub weight: Option<u32>,
     pub duration: Option<Duration>,
     pub width: u32,
     pub height: u32,
@@ -68,6 +83,7 @@ pub struct VideoAsset {
 pub struct AudioAsset {
     pub path: PathBuf,
     pub tags: Vec<String>,
+    pub weight: Option<u32>,
     pub duration: Option<Duration>,
 }
 
@@ -76,6 +92,7 @@ pub struct AudioAsset {
 pub struct HypnoAsset {
     pub path: PathBuf,
     pub tags: Vec<String>,
+    pub weight: Option<u32>,
     pub is_animated: bool,
 }
 
@@ -84,6 +101,7 @@ pub struct HypnoAsset {
 pub struct WallpaperAsset {
     pub path: PathBuf,
     pub tags: Vec<String>,
+    pub weight: Option<u32>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -93,4 +111,5 @@ pub struct WebsiteAsset {
     pub url: String,
     pub description: String,
     pub tags: Vec<String>,
+    pub weight: Option<u32>,
 }
@@ -1,2 +1,3 @@
 pub mod pack;
 pub mod settings;
+pub mod watcher;
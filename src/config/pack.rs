@@ -1,24 +1,60 @@
 use crate::permissions::Permission;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// File format of a pack's config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackConfigFormat {
+    Toml,
+    Json,
+}
+
+impl PackConfigFormat {
+    /// Formats in the order they are looked for when a pack has more than one config
+    pub const ALL: [PackConfigFormat; 2] = [PackConfigFormat::Toml, PackConfigFormat::Json];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            PackConfigFormat::Toml => "config.toml",
+            PackConfigFormat::Json => "config.json",
+        }
+    }
+
+    /// Format of the config in a pack directory, `config.toml` winning over `config.json`
+    pub fn detect(pack_dir: &Path) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|format| pack_dir.join(format.file_name()).is_file())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct PackConfig {
     pub meta: PackMeta,
     pub moods: Vec<Mood>,
     pub assets: Assets,
     pub websites: Option<Vec<WebsiteConfig>>,
     pub prompts: Option<PromptsConfig>,
+    /// Overrides of the user's popup settings
+    #[serde(default)]
+    pub popups: Option<PackPopups>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct PackPopups {
+    /// Overrides `runtime.popups.min_visible_ms`
+    pub min_visible_ms: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct PromptsConfig {
     pub system: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct WebsiteConfig {
     pub name: String,
     pub url: String,
@@ -26,14 +62,14 @@ pub struct WebsiteConfig {
     pub tags: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct PackMeta {
     pub name: String,
     pub version: String,
     pub permissions: Vec<Permission>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Mood {
     pub name: String,
     pub description: String,
@@ -41,7 +77,43 @@ pub struct Mood {
     pub prompt: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+impl Mood {
+    /// Combine several moods into one whose asset pool is the union of theirs.
+    ///
+    /// A mood without tags allows every asset, so blending it in lifts the tag filter.
+    pub fn blend(moods: &[Mood]) -> Mood {
+        if let [mood] = moods {
+            return mood.clone();
+        }
+
+        let mut tags: Vec<String> = Vec::new();
+        if moods.iter().all(|m| !m.tags.is_empty()) {
+            for tag in moods.iter().flat_map(|m| &m.tags) {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+        }
+        let prompts: Vec<&str> = moods.iter().filter_map(|m| m.prompt.as_deref()).collect();
+
+        Mood {
+            name: moods
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>()
+                .join(" + "),
+            description: moods
+                .iter()
+                .map(|m| m.description.as_str())
+                .collect::<Vec<_>>()
+                .join("; "),
+            tags,
+            prompt: (!prompts.is_empty()).then(|| prompts.join("\n")),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Assets {
     pub image: Option<Vec<Asset>>,
     pub video: Option<Vec<Asset>>,
@@ -50,32 +122,214 @@ pub struct Assets {
     pub wallpaper: Option<Vec<Asset>>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Asset {
+    /// File relative to the pack directory, or a glob like `image/**/*.{jpg,png}`
+    /// matching several files that all share this entry's tags and weight
     pub path: String,
     pub tags: Vec<String>,
+    /// Relative chance of being picked among matching assets, defaults to 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u32>,
+}
+
+impl Assets {
+    /// Entries of every kind, with the kind's name
+    pub fn by_kind(&self) -> impl Iterator<Item = (&'static str, &Asset)> {
+        [
+            ("image", &self.image),
+            ("video", &self.video),
+            ("audio", &self.audio),
+            ("hypno", &self.hypno),
+            ("wallpaper", &self.wallpaper),
+        ]
+        .into_iter()
+        .flat_map(|(kind, assets)| assets.iter().flatten().map(move |asset| (kind, asset)))
+    }
+}
+
+/// A mistake in a pack config that parses fine but would break the pack at runtime.
+///
+/// Unknown permissions aren't listed here, they already fail to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// Two moods share a name, only the first could ever be picked
+    DuplicateMood(String),
+    /// An asset entry points at a file that doesn't exist
+    MissingAsset { kind: &'static str, path: String },
+    /// A mood filters by a tag no asset or website has
+    UnknownMoodTag { mood: String, tag: String },
+    /// The mood selected in the settings isn't in the pack
+    UnknownMood {
+        mood: String,
+        available: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::DuplicateMood(name) => {
+                write!(f, "mood '{}' is defined more than once", name)
+            }
+            ValidationError::MissingAsset { kind, path } => {
+                write!(f, "{} asset '{}' does not exist", kind, path)
+            }
+            ValidationError::UnknownMoodTag { mood, tag } => {
+                write!(f, "mood '{}' uses tag '{}' that no asset has", mood, tag)
+            }
+            ValidationError::UnknownMood { mood, available } => write!(
+                f,
+                "mood '{}' is not in the pack, available moods: {}",
+                mood,
+                available.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Combine validation errors for a pack into one error listing all of them
+pub fn validation_failed(pack_name: &str, errors: &[ValidationError]) -> anyhow::Error {
+    let list: Vec<String> = errors.iter().map(|e| format!("  - {}", e)).collect();
+    anyhow::anyhow!(
+        "Pack '{}' has {} problem(s):\n{}",
+        pack_name,
+        errors.len(),
+        list.join("\n")
+    )
+}
+
+impl Asset {
+    /// Whether the path is a glob rather than a single file
+    pub fn is_glob(&self) -> bool {
+        self.path.contains(['*', '?', '[', '{'])
+    }
 }
 
 impl PackConfig {
+    /// Check for mistakes parsing doesn't catch: duplicate mood names, asset
+    /// files missing from `pack_dir`, and mood tags no asset or website has.
+    /// Glob entries are not checked, matching nothing just adds no assets.
+    pub fn validate(&self, pack_dir: &Path) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        let mut seen = HashSet::new();
+        for mood in &self.moods {
+            if !seen.insert(mood.name.as_str()) {
+                errors.push(ValidationError::DuplicateMood(mood.name.clone()));
+            }
+        }
+
+        for (kind, asset) in self.assets.by_kind() {
+            if !asset.is_glob() && !pack_dir.join(&asset.path).exists() {
+                errors.push(ValidationError::MissingAsset {
+                    kind,
+                    path: asset.path.clone(),
+                });
+            }
+        }
+
+        let known_tags: HashSet<&str> = self
+            .assets
+            .by_kind()
+            .flat_map(|(_, asset)| &asset.tags)
+            .chain(self.websites.iter().flatten().flat_map(|site| &site.tags))
+            .map(String::as_str)
+            .collect();
+        for mood in &self.moods {
+            for tag in &mood.tags {
+                if !known_tags.contains(tag.as_str()) {
+                    errors.push(ValidationError::UnknownMoodTag {
+                        mood: mood.name.clone(),
+                        tag: tag.clone(),
+                    });
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check that the pack has the mood selected in the settings
+    pub fn validate_mood(&self, mood: &str) -> Result<(), ValidationError> {
+        if self.moods.iter().any(|m| m.name == mood) {
+            return Ok(());
+        }
+        Err(ValidationError::UnknownMood {
+            mood: mood.to_string(),
+            available: self.moods.iter().map(|m| m.name.clone()).collect(),
+        })
+    }
+
     pub fn load(pack_name: &str) -> Result<Self> {
-        let path = Path::new("packs").join(pack_name).join("config.toml");
+        Self::load_from(Path::new("packs"), pack_name)
+    }
+
+    /// Load a pack's config from a packs directory other than `packs/`.
+    ///
+    /// Reads `config.toml`, or `config.json` if the pack has no TOML config.
+    pub fn load_from(packs_dir: &Path, pack_name: &str) -> Result<Self> {
+        let pack_dir = packs_dir.join(pack_name);
+        let format = PackConfigFormat::detect(&pack_dir).unwrap_or(PackConfigFormat::Toml);
+        let path = pack_dir.join(format.file_name());
         let content = fs::read_to_string(&path)
             .with_context(|| format!("Failed to read pack config at {:?}", path))?;
 
-        let config: PackConfig = toml::from_str(&content)
-            .with_context(|| format!("Failed to parse pack config for {}", pack_name))?;
-        Ok(config)
+        Self::parse_as(&content, format)
+            .with_context(|| format!("Failed to parse pack config for {}", pack_name))
     }
 
     #[allow(dead_code)]
     pub fn parse(content: &str) -> Result<Self> {
-        let config: PackConfig = toml::from_str(content).context("Failed to parse pack config")?;
+        Self::parse_as(content, PackConfigFormat::Toml)
+    }
+
+    pub fn parse_as(content: &str, format: PackConfigFormat) -> Result<Self> {
+        let config: PackConfig = match format {
+            PackConfigFormat::Toml => {
+                toml::from_str(content).context("Failed to parse pack config")?
+            }
+            PackConfigFormat::Json => {
+                serde_json::from_str(content).context("Failed to parse pack config")?
+            }
+        };
         Ok(config)
     }
 
     pub fn save(&self, pack_name: &str) -> Result<()> {
-        let path = Path::new("packs").join(pack_name).join("config.toml");
-        let content = toml::to_string(self).context("Failed to serialize pack config")?;
+        self.save_to(Path::new("packs"), pack_name)
+    }
+
+    /// Save a pack's config in a packs directory other than `packs/`, keeping
+    /// the format the pack already uses (TOML for new packs)
+    pub fn save_to(&self, packs_dir: &Path, pack_name: &str) -> Result<()> {
+        let pack_dir = packs_dir.join(pack_name);
+        let format = PackConfigFormat::detect(&pack_dir).unwrap_or(PackConfigFormat::Toml);
+        self.save_as(packs_dir, pack_name, format)
+    }
+
+    /// Save a pack's config in the given format
+    pub fn save_as(
+        &self,
+        packs_dir: &Path,
+        pack_name: &str,
+        format: PackConfigFormat,
+    ) -> Result<()> {
+        let path = packs_dir.join(pack_name).join(format.file_name());
+        let content = match format {
+            PackConfigFormat::Toml => {
+                toml::to_string(self).context("Failed to serialize pack config")?
+            }
+            PackConfigFormat::Json => {
+                serde_json::to_string_pretty(self).context("Failed to serialize pack config")?
+            }
+        };
         fs::write(&path, content)
             .with_context(|| format!("Failed to write pack config to {:?}", path))?;
         Ok(())
@@ -108,6 +362,7 @@ impl PackConfig {
                         .to_string(),
                 ),
             }),
+            popups: None,
         }
     }
 }
@@ -139,4 +394,111 @@ mod tests {
             "image/test.jpg"
         );
     }
+
+    #[test]
+    fn test_config_round_trips_in_both_formats() {
+        let packs_dir =
+            std::env::temp_dir().join(format!("goon-ai-pack-test-{}", uuid::Uuid::new_v4()));
+        let mut config = PackConfig::new("RoundTrip");
+        config.meta.permissions = vec![Permission::Image];
+        config.assets.image = Some(vec![Asset {
+            path: "image/*.jpg".to_string(),
+            tags: vec!["test".to_string()],
+            weight: Some(2),
+        }]);
+
+        for (name, format) in [
+            ("TomlPack", PackConfigFormat::Toml),
+            ("JsonPack", PackConfigFormat::Json),
+        ] {
+            fs::create_dir_all(packs_dir.join(name)).unwrap();
+            config.save_as(&packs_dir, name, format).unwrap();
+            assert_eq!(
+                PackConfigFormat::detect(&packs_dir.join(name)),
+                Some(format)
+            );
+            assert_eq!(PackConfig::load_from(&packs_dir, name).unwrap(), config);
+        }
+
+        // Saving keeps the format the pack already uses
+        config.meta.version = "0.2.0".to_string();
+        config.save_to(&packs_dir, "JsonPack").unwrap();
+        assert!(!packs_dir.join("JsonPack/config.toml").exists());
+        assert_eq!(
+            PackConfig::load_from(&packs_dir, "JsonPack")
+                .unwrap()
+                .meta
+                .version,
+            "0.2.0"
+        );
+
+        // TOML wins when a pack has both
+        config
+            .save_as(&packs_dir, "JsonPack", PackConfigFormat::Toml)
+            .unwrap();
+        assert_eq!(
+            PackConfigFormat::detect(&packs_dir.join("JsonPack")),
+            Some(PackConfigFormat::Toml)
+        );
+
+        fs::remove_dir_all(&packs_dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let pack_dir =
+            std::env::temp_dir().join(format!("goon-ai-validate-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(pack_dir.join("image")).unwrap();
+        fs::write(pack_dir.join("image/here.jpg"), b"").unwrap();
+
+        let mut config = PackConfig::new("Broken");
+        let asset = |path: &str| Asset {
+            path: path.to_string(),
+            tags: vec!["beach".to_string()],
+            weight: None,
+        };
+        config.assets.image = Some(vec![
+            asset("image/here.jpg"),
+            asset("image/gone.jpg"),
+            asset("image/*.png"),
+        ]);
+        config.moods.push(Mood {
+            name: "default".to_string(),
+            description: "Again".to_string(),
+            tags: vec!["beach".to_string(), "city".to_string()],
+            prompt: None,
+        });
+
+        let errors = config.validate(&pack_dir).unwrap_err();
+        fs::remove_dir_all(&pack_dir).unwrap();
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::DuplicateMood("default".to_string()),
+                ValidationError::MissingAsset {
+                    kind: "image",
+                    path: "image/gone.jpg".to_string()
+                },
+                ValidationError::UnknownMoodTag {
+                    mood: "default".to_string(),
+                    tag: "city".to_string()
+                },
+            ]
+        );
+        let message = validation_failed("Broken", &errors).to_string();
+        assert!(message.contains("has 3 problem(s)"));
+        assert!(message.contains("image asset 'image/gone.jpg' does not exist"));
+
+        assert!(config.validate_mood("default").is_ok());
+        assert_eq!(
+            config.validate_mood("missing").unwrap_err().to_string(),
+            "mood 'missing' is not in the pack, available moods: default, default"
+        );
+    }
+
+    #[test]
+    fn test_new_pack_is_valid() {
+        let config = PackConfig::new("Fresh");
+        assert_eq!(config.validate(Path::new("packs/Fresh")), Ok(()));
+    }
 }
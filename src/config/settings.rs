@@ -3,8 +3,9 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Settings {
     pub user: User,
     #[serde(rename = "llmSettings")]
@@ -12,60 +13,173 @@ pub struct Settings {
     pub runtime: RuntimeSettings,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct User {
     pub name: String,
     pub dob: String,
     pub gender: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct LLMSettings {
+    /// Which kind of server `host` points at, defaults to Ollama
+    #[serde(default)]
+    pub provider: LLMProviderKind,
     pub host: String,
+    /// Bearer token for OpenAI-compatible servers that need one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
     #[serde(default = "default_model")]
     pub model: String,
+    /// Tags whose contents are stripped from responses before extracting code (e.g. "think")
+    #[serde(default = "default_reasoning_tags")]
+    pub reasoning_tags: Vec<String>,
+    /// Sampling temperature, higher gives more adventurous scripts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Nucleus sampling cutoff
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    /// Most tokens to generate per reply
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// Seed for reproducible replies
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i32>,
+}
+
+/// The API an LLM server speaks
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LLMProviderKind {
+    #[default]
+    Ollama,
+    /// `/v1/chat/completions`, as served by vLLM, LM Studio, llama.cpp and OpenRouter
+    #[serde(rename = "openai")]
+    OpenAi,
 }
 
 fn default_model() -> String {
     "llama3".to_string()
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+fn default_reasoning_tags() -> Vec<String> {
+    vec!["think".to_string()]
+}
+
+fn default_iteration_delay_secs() -> f64 {
+    5.0
+}
+
+fn default_history_size() -> usize {
+    50
+}
+
+fn default_strict_mood() -> bool {
+    true
+}
+
+fn default_script_timeout_secs() -> f64 {
+    30.0
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct RuntimeSettings {
     pub popups: Popups,
     pub permissions: Vec<Permission>,
     pub pack: PackSettings,
+    /// Maximum session length in minutes, after which the loop stops itself
+    #[serde(default)]
+    pub session_max_mins: Option<u64>,
+    /// Restore the original wallpaper when the session limit is reached
+    #[serde(default)]
+    pub restore_wallpaper_on_stop: bool,
+    /// Restrict permissions to a benign subset regardless of grants.
+    /// Can also be enabled with `GOON_SAFE_MODE=1` or `--safe-mode`.
+    #[serde(default)]
+    pub safe_mode: bool,
+    /// Seconds to wait between loop iterations, may be fractional
+    #[serde(default = "default_iteration_delay_secs")]
+    pub iteration_delay_secs: f64,
+    /// Most conversation messages sent back to the LLM, user and assistant turns alike
+    #[serde(default = "default_history_size")]
+    pub history_size: usize,
+    /// Seconds a script may run before it is stopped, may be fractional
+    #[serde(default = "default_script_timeout_secs")]
+    pub script_timeout_secs: f64,
+    /// Only pick assets matching the mood. Turn off to fall back to the
+    /// requested tags alone and then to any asset when nothing matches
+    #[serde(default = "default_strict_mood")]
+    pub strict_mood: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+impl RuntimeSettings {
+    /// Delay between loop iterations, negative or invalid values mean no delay
+    pub fn iteration_delay(&self) -> Duration {
+        Duration::try_from_secs_f64(self.iteration_delay_secs).unwrap_or(Duration::ZERO)
+    }
+
+    /// How long a script may run, invalid values fall back to the default
+    pub fn script_timeout(&self) -> Duration {
+        Duration::try_from_secs_f64(self.script_timeout_secs)
+            .ok()
+            .filter(|timeout| !timeout.is_zero())
+            .unwrap_or(Duration::from_secs_f64(default_script_timeout_secs()))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Popups {
     pub image: PopupConfig,
     pub video: PopupConfig,
     pub audio: PopupConfig,
+    /// Opacity (0.0-1.0) for image and video popups that don't set one, defaults to 1.0
+    #[serde(default)]
+    pub default_opacity: Option<f32>,
+    /// Keep image and video popups above other windows unless they say otherwise, defaults to true
+    #[serde(default)]
+    pub default_always_on_top: Option<bool>,
+    /// Milliseconds a window stays up before a close request or short timeout takes effect
+    #[serde(default)]
+    pub min_visible_ms: Option<u64>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct PopupConfig {
     pub timeout: Option<u64>,
     pub max: Option<u32>,
     pub mitosis: Option<MitosisConfig>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// Closing a popup spawns `factor` copies of it, see `gui::windows::mitosis`
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct MitosisConfig {
     pub enabled: bool,
     pub factor: u32,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+impl PopupConfig {
+    /// Copies to make of a closing popup, 0 when mitosis is off
+    pub fn mitosis_factor(&self) -> u32 {
+        self.mitosis
+            .as_ref()
+            .filter(|mitosis| mitosis.enabled)
+            .map_or(0, |mitosis| mitosis.factor)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct PackSettings {
     pub current: String,
     pub mood: String,
 }
 
+/// The user's settings file, relative to the working directory
+pub const SETTINGS_FILE: &str = "settings.toml";
+
 impl Settings {
     pub fn load() -> Result<Self> {
-        let path = Path::new("settings.toml");
+        let path = Path::new(SETTINGS_FILE);
         let content = if path.exists() {
             fs::read_to_string(path).context("Failed to read settings.toml")?
         } else {
@@ -83,6 +197,19 @@ impl Settings {
         Ok(settings)
     }
 
+    /// Read `settings.toml` again after it changed. Unlike [`Settings::load`]
+    /// this never falls back to the example settings.
+    pub fn reload() -> Result<Self> {
+        Self::load_file(Path::new(SETTINGS_FILE))
+    }
+
+    /// Read settings from a file other than `settings.toml`
+    pub fn load_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read settings at {:?}", path))?;
+        Self::parse(&content)
+    }
+
     #[allow(dead_code)]
     pub fn parse(content: &str) -> Result<Self> {
         let settings: Settings =
@@ -137,5 +264,53 @@ mood = "default"
         assert_eq!(settings.user.name, "Test User");
         assert_eq!(settings.runtime.pack.current, "Test Pack");
         assert_eq!(settings.runtime.permissions, vec![Permission::Image]);
+        assert_eq!(settings.runtime.popups.image.mitosis_factor(), 0);
+        assert_eq!(settings.runtime.popups.video.mitosis_factor(), 0);
+        assert_eq!(settings.runtime.session_max_mins, None);
+        assert!(!settings.runtime.restore_wallpaper_on_stop);
+        assert_eq!(settings.runtime.iteration_delay(), Duration::from_secs(5));
+        assert_eq!(settings.runtime.history_size, 50);
+        assert!(settings.runtime.strict_mood);
+        assert_eq!(settings.runtime.script_timeout(), Duration::from_secs(30));
+        assert_eq!(settings.llm_settings.provider, LLMProviderKind::Ollama);
+        assert_eq!(settings.llm_settings.api_key, None);
+    }
+
+    #[test]
+    fn test_fractional_iteration_delay() {
+        let settings = Settings::parse(
+            r#"
+[user]
+name = "Test User"
+dob = "1990-01-01"
+gender = "male"
+
+[llmSettings]
+provider = "openai"
+host = "http://localhost:8000"
+api_key = "secret"
+
+[runtime]
+permissions = []
+iteration_delay_secs = 0.25
+
+[runtime.popups.image]
+[runtime.popups.video]
+[runtime.popups.audio]
+
+[runtime.pack]
+current = "Test Pack"
+mood = "default"
+"#,
+        )
+        .unwrap();
+        assert_eq!(settings.llm_settings.provider, LLMProviderKind::OpenAi);
+        assert_eq!(settings.llm_settings.api_key.as_deref(), Some("secret"));
+
+        let mut runtime = settings.runtime;
+        assert_eq!(runtime.iteration_delay(), Duration::from_millis(250));
+
+        runtime.iteration_delay_secs = -1.0;
+        assert_eq!(runtime.iteration_delay(), Duration::ZERO);
     }
 }
@@ -0,0 +1,125 @@
+//! Reload `settings.toml` when it changes on disk
+//!
+//! The directory is watched rather than the file itself, since editors often
+//! save by writing a new file and renaming it over the old one.
+
+use crate::config::settings::Settings;
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// Watches a settings file until dropped
+pub struct SettingsWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl SettingsWatcher {
+    /// Call `on_change` with the new settings whenever `path` is saved with
+    /// different contents. Files that fail to parse are logged and skipped, so
+    /// a half-written save doesn't replace working settings.
+    pub fn watch<F>(path: impl Into<PathBuf>, current: Settings, on_change: F) -> Result<Self>
+    where
+        F: Fn(Settings) + Send + 'static,
+    {
+        let path = path.into();
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let file_name = path
+            .file_name()
+            .context("Settings path has no file name")?
+            .to_os_string();
+        let last = Mutex::new(current);
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                || !event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name() == Some(file_name.as_os_str()))
+            {
+                return;
+            }
+
+            match Settings::load_file(&path) {
+                Ok(settings) => {
+                    let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+                    if *last != settings {
+                        *last = settings.clone();
+                        on_change(settings);
+                    }
+                }
+                Err(e) => warn!("Ignoring settings change: {:#}", e),
+            }
+        })
+        .context("Failed to create settings watcher")?;
+        watcher
+            .watch(Path::new(&dir), RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {:?}", dir))?;
+
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    const SETTINGS: &str = r#"
+[user]
+name = "Test User"
+dob = "1990-01-01"
+gender = "male"
+
+[llmSettings]
+host = "http://localhost:11434"
+
+[runtime]
+permissions = ["image"]
+iteration_delay_secs = 5.0
+
+[runtime.popups.image]
+[runtime.popups.video]
+[runtime.popups.audio]
+
+[runtime.pack]
+current = "Test Pack"
+mood = "default"
+"#;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_change_to_settings_file_triggers_reload() {
+        let dir = std::env::temp_dir().join(format!("goon-ai-settings-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("settings.toml");
+        std::fs::write(&path, SETTINGS).unwrap();
+
+        let (tx, rx) = channel();
+        let current = Settings::load_file(&path).unwrap();
+        let _watcher = SettingsWatcher::watch(&path, current, move |settings| {
+            let _ = tx.send(settings);
+        })
+        .unwrap();
+
+        // A broken save is skipped, the next good one comes through
+        std::fs::write(&path, "[user").unwrap();
+        std::fs::write(
+            &path,
+            SETTINGS.replace("iteration_delay_secs = 5.0", "iteration_delay_secs = 1.5"),
+        )
+        .unwrap();
+
+        let reloaded = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(reloaded.runtime.iteration_delay_secs, 1.5);
+    }
+}
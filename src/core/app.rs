@@ -15,10 +15,12 @@ pub struct App {
     #[allow(dead_code)]
     pub permissions: Arc<PermissionChecker>,
     original_wallpaper: Option<PathBuf>,
+    /// Safe mode turned on by the caller, on top of the settings file
+    forced_safe_mode: bool,
 }
 
 impl App {
-    pub fn new() -> Result<Self> {
+    pub fn new(forced_safe_mode: bool) -> Result<Self> {
         // 1. Load Settings
         let settings = Settings::load()?;
         println!("Loaded settings for user: {}", settings.user.name);
@@ -35,7 +37,11 @@ impl App {
         let user_perms: PermissionSet = settings.runtime.permissions.clone().into();
         let pack_perms: PermissionSet = pack_config.meta.permissions.clone().into();
 
-        let active_perms = PermissionResolver::resolve(&pack_perms, &user_perms);
+        let active_perms = PermissionResolver::resolve(
+            &pack_perms,
+            &user_perms,
+            settings.runtime.safe_mode || forced_safe_mode,
+        );
         let permissions = PermissionChecker::new(active_perms);
 
         // Check for missing permissions (optional logging)
@@ -71,6 +77,7 @@ impl App {
             pack_config: Arc::new(pack_config),
             permissions: Arc::new(permissions),
             original_wallpaper,
+            forced_safe_mode,
         })
     }
 
@@ -90,7 +97,8 @@ impl App {
             self.permissions.clone(),
             window_handle.clone(),
             Arc::new(AtomicBool::new(true)),
-        );
+        )
+        .with_forced_safe_mode(self.forced_safe_mode);
 
         // Schedule the orchestrator to run within the Slint event loop context
         slint::spawn_local(async move {
@@ -127,7 +135,8 @@ impl App {
             self.permissions.clone(),
             window_handle.clone(),
             Arc::new(AtomicBool::new(true)),
-        );
+        )
+        .with_forced_safe_mode(self.forced_safe_mode);
 
         // Clone script for the closure
         let script = script.to_string();
@@ -156,8 +165,8 @@ impl Drop for App {
         if let Some(path) = &self.original_wallpaper {
             println!("Restoring wallpaper: {:?}", path);
             let setter = crate::media::wallpaper::PlatformWallpaperSetter;
-            use crate::media::wallpaper::WallpaperSetter;
-            if let Err(e) = setter.set_wallpaper(path) {
+            use crate::media::wallpaper::{WallpaperMode, WallpaperSetter};
+            if let Err(e) = setter.set_wallpaper(path, WallpaperMode::default()) {
                 eprintln!("Failed to restore wallpaper: {}", e);
             }
         }
@@ -10,5 +10,6 @@ pub mod windows;
 
 pub use tray::{SystemTray, TrayCommand};
 pub use windows::{
-    WindowCommand, WindowHandle, WindowSpawner, WindowSpawnerHandle, run_event_loop,
+    PopupKind, SpawnQueueFull, WindowCommand, WindowHandle, WindowLimitReached, WindowSpawner,
+    WindowSpawnerHandle, run_event_loop,
 };
@@ -3,7 +3,17 @@
 //! Uses channels to communicate between the LLM/eval thread and the Slint UI thread.
 //! Each window handles its own lifecycle and cleanup.
 
+pub mod events;
+pub mod fade;
 pub mod image;
+pub mod layout;
+pub mod limits;
+pub mod mitosis;
+pub mod overlay;
+pub mod prompts;
+pub mod replay;
+pub mod schedule;
+pub mod shake;
 pub mod spawner;
 pub mod types;
 pub mod video;
@@ -12,5 +22,9 @@ pub mod write_lines;
 // Import the generated Slint modules
 slint::include_modules!();
 
+pub use events::{WindowEvent, WindowEvents};
+pub use prompts::{PromptOutcome, PromptResult, PromptResults};
 pub use spawner::{WindowSpawner, WindowSpawnerHandle, run_event_loop};
-pub use types::{WindowCommand, WindowHandle, WindowResponse};
+pub use types::{
+    PopupKind, SpawnQueueFull, WindowCommand, WindowHandle, WindowLimitReached, WindowResponse,
+};
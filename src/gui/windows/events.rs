@@ -0,0 +1,84 @@
+//! Input events from spawned windows
+//!
+//! Slint callbacks fire on the UI thread while scripts wait on the runtime
+//! thread, so events are handed over through oneshot channels registered per
+//! window handle. Events nobody is waiting for are dropped.
+
+use super::types::WindowHandle;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use ts_rs::TS;
+
+/// Input event received by a window
+#[derive(Serialize, Debug, Clone, PartialEq, TS)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum WindowEvent {
+    /// The window was clicked, coordinates are relative to the window in logical pixels
+    Click { x: f32, y: f32 },
+    /// A key was pressed while the window had focus
+    Key { text: String },
+    /// The window was closed before any other event arrived
+    Closed,
+}
+
+/// Scripts waiting for the next event of each window
+#[derive(Clone, Default)]
+pub struct WindowEvents {
+    waiters: Arc<Mutex<HashMap<WindowHandle, Vec<oneshot::Sender<WindowEvent>>>>>,
+}
+
+impl WindowEvents {
+    /// Wait for the next event on a window
+    pub fn subscribe(&self, handle: WindowHandle) -> oneshot::Receiver<WindowEvent> {
+        let (tx, rx) = oneshot::channel();
+        self.waiters
+            .lock()
+            .unwrap()
+            .entry(handle)
+            .or_default()
+            .push(tx);
+        rx
+    }
+
+    /// Deliver an event to everyone waiting on the window.
+    ///
+    /// Returns whether anyone was waiting.
+    pub fn dispatch(&self, handle: WindowHandle, event: WindowEvent) -> bool {
+        let Some(waiters) = self.waiters.lock().unwrap().remove(&handle) else {
+            return false;
+        };
+        let mut delivered = false;
+        for tx in waiters {
+            delivered |= tx.send(event.clone()).is_ok();
+        }
+        delivered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_dispatch_resolves_waiters_once() {
+        let events = WindowEvents::default();
+        let handle = WindowHandle(Uuid::new_v4());
+        let other = WindowHandle(Uuid::new_v4());
+
+        let mut first = events.subscribe(handle);
+        let mut second = events.subscribe(handle);
+        let mut unrelated = events.subscribe(other);
+
+        let click = WindowEvent::Click { x: 4.0, y: 2.0 };
+        assert!(events.dispatch(handle, click.clone()));
+        assert_eq!(first.try_recv().unwrap(), click);
+        assert_eq!(second.try_recv().unwrap(), click);
+        assert!(unrelated.try_recv().is_err());
+
+        // Waiters are consumed, the next event has nobody to go to
+        assert!(!events.dispatch(handle, WindowEvent::Closed));
+    }
+}
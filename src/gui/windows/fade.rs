@@ -0,0 +1,110 @@
+//! Window fade-in and fade-out
+//!
+//! Fades animate the opacity the window content is rendered with on every
+//! spawner tick, rather than the native window alpha, so they also work on
+//! Wayland where clients can't change a window's opacity.
+
+use std::time::{Duration, Instant};
+
+/// Longest a fade can run
+pub const MAX_FADE_DURATION: Duration = Duration::from_secs(10);
+
+/// Fades requested for a window when it opens and closes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WindowFade {
+    pub fade_in: Option<Duration>,
+    pub fade_out: Option<Duration>,
+}
+
+impl WindowFade {
+    pub fn from_millis(fade_in_ms: Option<u64>, fade_out_ms: Option<u64>) -> Self {
+        Self {
+            fade_in: fade_in_ms.map(Duration::from_millis),
+            fade_out: fade_out_ms.map(Duration::from_millis),
+        }
+    }
+
+    /// Opacity a window should be shown with before its fade-in starts
+    pub fn initial_opacity(&self, opacity: f32) -> f32 {
+        match self.fade_in {
+            Some(fade) if !fade.is_zero() => 0.0,
+            _ => opacity,
+        }
+    }
+}
+
+/// A running opacity animation for one window
+#[derive(Debug, Clone, Copy)]
+pub struct Fade {
+    from: f32,
+    to: f32,
+    started: Instant,
+    duration: Duration,
+}
+
+impl Fade {
+    /// Start a fade, clamping its duration to the limit
+    pub fn new(from: f32, to: f32, now: Instant, duration: Duration) -> Self {
+        Self {
+            from,
+            to,
+            started: now,
+            duration: duration.min(MAX_FADE_DURATION),
+        }
+    }
+
+    /// Opacity at `now`, or `None` once the fade is over
+    pub fn opacity_at(&self, now: Instant) -> Option<f32> {
+        let elapsed = now.saturating_duration_since(self.started);
+        if elapsed >= self.duration {
+            return None;
+        }
+        let progress = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        Some(self.from + (self.to - self.from) * progress)
+    }
+
+    /// Opacity the fade ends at
+    pub fn target(&self) -> f32 {
+        self.to
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fade_opacity_over_time() {
+        let now = Instant::now();
+        let fade_in = Fade::new(0.0, 0.8, now, Duration::from_millis(500));
+        assert_eq!(fade_in.opacity_at(now), Some(0.0));
+        let halfway = fade_in
+            .opacity_at(now + Duration::from_millis(250))
+            .unwrap();
+        assert!((halfway - 0.4).abs() < 1e-6);
+        assert_eq!(fade_in.opacity_at(now + Duration::from_millis(500)), None);
+        assert_eq!(fade_in.target(), 0.8);
+
+        let fade_out = Fade::new(0.8, 0.0, now, Duration::from_millis(500));
+        let halfway = fade_out
+            .opacity_at(now + Duration::from_millis(250))
+            .unwrap();
+        assert!((halfway - 0.4).abs() < 1e-6);
+
+        let long = Fade::new(0.0, 1.0, now, Duration::from_secs(60));
+        assert_eq!(long.opacity_at(now + MAX_FADE_DURATION), None);
+    }
+
+    #[test]
+    fn test_initial_opacity() {
+        assert_eq!(WindowFade::default().initial_opacity(0.7), 0.7);
+        assert_eq!(
+            WindowFade::from_millis(Some(500), None).initial_opacity(0.7),
+            0.0
+        );
+        assert_eq!(
+            WindowFade::from_millis(Some(0), Some(500)).initial_opacity(0.7),
+            0.7
+        );
+    }
+}
@@ -1,23 +1,33 @@
 use super::ImageWindow;
+use super::layout::{AutoLayout, layout_monitor};
 use super::types::WindowHandle;
+use crate::media::image::color_key::ColorKey;
 use anyhow::Result;
 use i_slint_backend_winit::WinitWindowAccessor;
 use slint::ComponentHandle;
 use std::rc::Rc;
 use tracing::debug;
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn(
     handle: WindowHandle,
     path: &std::path::Path,
     width: Option<u32>,
     height: Option<u32>,
     opacity: f32,
+    always_on_top: bool,
+    layout: AutoLayout,
+    color_key: Option<ColorKey>,
 ) -> Result<Rc<ImageWindow>> {
     // Load the image
-    let image_data = image::open(path)
+    let mut image_data = image::open(path)
         .map_err(|e| anyhow::anyhow!("Failed to load image: {}", e))?
         .into_rgba8();
 
+    if let Some(key) = color_key {
+        key.apply(&mut image_data);
+    }
+
     let img_width = image_data.width();
     let img_height = image_data.height();
 
@@ -50,11 +60,32 @@ pub fn spawn(
         if let Some(window) = window_weak.upgrade()
             && let Ok(winit_window) = window.window().winit_window().await
         {
-            winit_window
-                .set_window_level(i_slint_backend_winit::winit::window::WindowLevel::AlwaysOnTop);
+            if always_on_top {
+                winit_window.set_window_level(
+                    i_slint_backend_winit::winit::window::WindowLevel::AlwaysOnTop,
+                );
+            }
             winit_window.set_resizable(false);
             winit_window.set_decorations(false);
             winit_window.set_window_icon(None);
+
+            if layout.is_active()
+                && let Some((rect, scale)) = layout_monitor(&winit_window, &layout)
+            {
+                let physical_size = (
+                    (window_width as f64 * scale) as u32,
+                    (window_height as f64 * scale) as u32,
+                );
+                let placement = layout.place(&rect, physical_size);
+
+                window.set_image_width((placement.width as f64 / scale) as i32);
+                window.set_image_height((placement.height as f64 / scale) as i32);
+                if let Some((x, y)) = placement.position {
+                    winit_window.set_outer_position(
+                        i_slint_backend_winit::winit::dpi::PhysicalPosition::new(x, y),
+                    );
+                }
+            }
         }
     });
 
@@ -0,0 +1,272 @@
+//! Screen-relative window sizing and placement
+
+use anyhow::{Result, anyhow};
+use i_slint_backend_winit::winit::monitor::MonitorHandle;
+use i_slint_backend_winit::winit::window::Window;
+use rand::Rng;
+use rand::prelude::IndexedRandom;
+
+/// A monitor's area in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonitorRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Requested automatic layout for a window
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AutoLayout {
+    /// Fraction of the monitor (0.0-1.0) the window may fill, keeping the content's aspect ratio
+    pub size_percent: Option<f32>,
+    /// Whether to center the window on the monitor
+    pub center: bool,
+    /// Monitor to show the window on, instead of the one the window manager picks
+    pub monitor: Option<MonitorRect>,
+    /// Top-left corner relative to `monitor`, the window is centered on it when unset
+    pub offset: Option<(i32, i32)>,
+}
+
+/// Computed window size and position in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub width: u32,
+    pub height: u32,
+    /// Top-left corner, or `None` to leave the position to the window manager
+    pub position: Option<(i32, i32)>,
+}
+
+impl AutoLayout {
+    pub fn is_active(&self) -> bool {
+        self.size_percent.is_some() || self.center || self.monitor.is_some()
+    }
+
+    /// Size and position a window of `size` on the chosen monitor, or on `current` if none was
+    pub fn place(&self, current: &MonitorRect, size: (u32, u32)) -> Placement {
+        let monitor = self.monitor.as_ref().unwrap_or(current);
+        let (mut width, mut height) = size;
+
+        if let Some(percent) = self.size_percent {
+            let percent = percent.clamp(0.01, 1.0) as f64;
+            let max_width = monitor.width as f64 * percent;
+            let max_height = monitor.height as f64 * percent;
+            let scale = (max_width / width.max(1) as f64).min(max_height / height.max(1) as f64);
+            width = ((width as f64 * scale).round() as u32).max(1);
+            height = ((height as f64 * scale).round() as u32).max(1);
+        }
+
+        let centered = (
+            monitor.x + (monitor.width as i32 - width as i32) / 2,
+            monitor.y + (monitor.height as i32 - height as i32) / 2,
+        );
+        let position = match (self.center, self.monitor, self.offset) {
+            (true, _, _) => Some(centered),
+            (false, Some(_), Some((dx, dy))) => Some((monitor.x + dx, monitor.y + dy)),
+            (false, Some(_), None) => Some(centered),
+            (false, None, _) => None,
+        };
+
+        Placement {
+            width,
+            height,
+            position,
+        }
+    }
+}
+
+impl From<&MonitorHandle> for MonitorRect {
+    fn from(monitor: &MonitorHandle) -> Self {
+        Self {
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+        }
+    }
+}
+
+/// Monitor to lay `window` out on and its scale factor: the chosen one if any,
+/// otherwise the one the window is on
+pub fn layout_monitor(window: &Window, layout: &AutoLayout) -> Option<(MonitorRect, f64)> {
+    let current = window
+        .current_monitor()
+        .or_else(|| window.primary_monitor());
+    match layout.monitor {
+        Some(rect) => {
+            let scale = window
+                .available_monitors()
+                .find(|monitor| monitor.position().x == rect.x && monitor.position().y == rect.y)
+                .or(current)
+                .map_or(1.0, |monitor| monitor.scale_factor());
+            Some((rect, scale))
+        }
+        None => current.map(|monitor| (MonitorRect::from(&monitor), monitor.scale_factor())),
+    }
+}
+
+/// The monitor a window asked for by `index`, or a random one.
+///
+/// `None` leaves the choice to the window manager.
+pub fn pick_monitor(
+    monitors: &[MonitorRect],
+    index: Option<usize>,
+    random: bool,
+    rng: &mut impl Rng,
+) -> Result<Option<MonitorRect>> {
+    if let Some(index) = index {
+        return monitors
+            .get(index)
+            .copied()
+            .map(Some)
+            .ok_or_else(|| anyhow!("Monitor {} not found, {} connected", index, monitors.len()));
+    }
+    if random {
+        return Ok(monitors.choose(rng).copied());
+    }
+    Ok(None)
+}
+
+/// Random top-left corner for a window of `size` that keeps it on `monitor` where it fits
+pub fn random_position(monitor: &MonitorRect, size: (u32, u32), rng: &mut impl Rng) -> (i32, i32) {
+    let max_x = monitor.width.saturating_sub(size.0);
+    let max_y = monitor.height.saturating_sub(size.1);
+    (
+        monitor.x + rng.random_range(0..=max_x) as i32,
+        monitor.y + rng.random_range(0..=max_y) as i32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MONITOR: MonitorRect = MonitorRect {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+
+    #[test]
+    fn test_half_screen_centered() {
+        let layout = AutoLayout {
+            size_percent: Some(0.5),
+            center: true,
+            ..Default::default()
+        };
+        let placement = layout.place(&MONITOR, (1000, 1000));
+        assert_eq!(placement.width, 540);
+        assert_eq!(placement.height, 540);
+        assert_eq!(placement.position, Some((690, 270)));
+    }
+
+    #[test]
+    fn test_center_on_offset_monitor() {
+        let monitor = MonitorRect {
+            x: 1920,
+            y: 0,
+            width: 1280,
+            height: 1024,
+        };
+        let layout = AutoLayout {
+            size_percent: Some(0.5),
+            center: true,
+            ..Default::default()
+        };
+        let placement = layout.place(&monitor, (1600, 900));
+        assert_eq!((placement.width, placement.height), (640, 360));
+        assert_eq!(placement.position, Some((1920 + 320, 332)));
+    }
+
+    #[test]
+    fn test_size_without_center() {
+        let layout = AutoLayout {
+            size_percent: Some(0.25),
+            center: false,
+            ..Default::default()
+        };
+        let placement = layout.place(&MONITOR, (1920, 1080));
+        assert_eq!((placement.width, placement.height), (480, 270));
+        assert_eq!(placement.position, None);
+    }
+
+    #[test]
+    fn test_random_position_stays_on_monitor() {
+        use rand::SeedableRng;
+
+        let monitor = MonitorRect {
+            x: 1920,
+            y: 0,
+            width: 1280,
+            height: 1024,
+        };
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        for _ in 0..100 {
+            let (x, y) = random_position(&monitor, (400, 300), &mut rng);
+            assert!((1920..=1920 + 880).contains(&x));
+            assert!((0..=724).contains(&y));
+        }
+        // Windows larger than the monitor go to its corner
+        assert_eq!(random_position(&monitor, (2000, 2000), &mut rng), (1920, 0));
+    }
+
+    #[test]
+    fn test_place_on_chosen_monitor() {
+        let second = MonitorRect {
+            x: 1920,
+            y: 0,
+            width: 1280,
+            height: 1024,
+        };
+        let layout = AutoLayout {
+            monitor: Some(second),
+            offset: Some((100, 50)),
+            ..Default::default()
+        };
+        assert!(layout.is_active());
+        let placement = layout.place(&MONITOR, (400, 300));
+        assert_eq!(placement.position, Some((2020, 50)));
+
+        // Without an offset the window is centered on the chosen monitor
+        let layout = AutoLayout {
+            monitor: Some(second),
+            ..Default::default()
+        };
+        let placement = layout.place(&MONITOR, (400, 300));
+        assert_eq!(placement.position, Some((1920 + 440, 362)));
+    }
+
+    #[test]
+    fn test_pick_monitor() {
+        use rand::SeedableRng;
+
+        let second = MonitorRect {
+            x: 1920,
+            y: 0,
+            width: 1280,
+            height: 1024,
+        };
+        let monitors = [MONITOR, second];
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+
+        assert_eq!(
+            pick_monitor(&monitors, Some(1), false, &mut rng).unwrap(),
+            Some(second)
+        );
+        assert!(
+            pick_monitor(&monitors, Some(2), false, &mut rng)
+                .unwrap_err()
+                .to_string()
+                .contains("2 connected")
+        );
+        assert_eq!(
+            pick_monitor(&monitors, None, false, &mut rng).unwrap(),
+            None
+        );
+        let random = pick_monitor(&monitors, None, true, &mut rng)
+            .unwrap()
+            .unwrap();
+        assert!(monitors.contains(&random));
+    }
+}
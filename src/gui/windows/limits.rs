@@ -0,0 +1,85 @@
+//! Caps on how many image and video popups are open at once
+//!
+//! A popup takes a slot when its spawn is sent and gives it back when the
+//! window closes or fails to open, so a burst of spawns can't overshoot the cap
+//! while the UI thread catches up.
+
+use super::types::{PopupKind, WindowLimitReached};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Open popups of each kind and how many may be open, shared with the spawner
+#[derive(Debug)]
+pub struct PopupSlots {
+    open: [AtomicUsize; 2],
+    max: [AtomicUsize; 2],
+}
+
+impl Default for PopupSlots {
+    fn default() -> Self {
+        Self {
+            open: Default::default(),
+            max: [AtomicUsize::new(usize::MAX), AtomicUsize::new(usize::MAX)],
+        }
+    }
+}
+
+impl PopupSlots {
+    /// Allow at most `max` popups of `kind` at once
+    pub fn set_max(&self, kind: PopupKind, max: usize) {
+        self.max[kind.index()].store(max, Ordering::Relaxed);
+    }
+
+    /// Take a slot for a new popup, failing if all are in use
+    pub fn acquire(&self, kind: PopupKind) -> Result<(), WindowLimitReached> {
+        let max = self.max[kind.index()].load(Ordering::Relaxed);
+        self.open[kind.index()]
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| {
+                (open < max).then_some(open + 1)
+            })
+            .map(|_| ())
+            .map_err(|_| WindowLimitReached { kind, max })
+    }
+
+    /// Give back the slot of a popup that closed or never opened
+    pub fn release(&self, kind: PopupKind) {
+        let _ = self.open[kind.index()].fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| {
+            open.checked_sub(1)
+        });
+    }
+
+    /// Popups of `kind` open or about to open
+    pub fn open(&self, kind: PopupKind) -> usize {
+        self.open[kind.index()].load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slots_are_capped_per_kind() {
+        let slots = PopupSlots::default();
+        slots.set_max(PopupKind::Image, 2);
+
+        assert!(slots.acquire(PopupKind::Image).is_ok());
+        assert!(slots.acquire(PopupKind::Image).is_ok());
+        assert_eq!(
+            slots.acquire(PopupKind::Image),
+            Err(WindowLimitReached {
+                kind: PopupKind::Image,
+                max: 2
+            })
+        );
+        // Videos have their own cap
+        assert!(slots.acquire(PopupKind::Video).is_ok());
+
+        slots.release(PopupKind::Image);
+        assert_eq!(slots.open(PopupKind::Image), 1);
+        assert!(slots.acquire(PopupKind::Image).is_ok());
+
+        slots.release(PopupKind::Video);
+        slots.release(PopupKind::Video);
+        assert_eq!(slots.open(PopupKind::Video), 0);
+    }
+}
@@ -0,0 +1,88 @@
+//! Popup mitosis
+//!
+//! An image or video popup that the user closes, or that times out, comes back
+//! as several copies at random spots on the screen. Copies count towards the
+//! popup caps like any other window, and only a few generations and a limited
+//! number of live copies are allowed so a session can't grow without bound.
+
+use super::layout::{MonitorRect, random_position};
+use super::types::PopupKind;
+use i_slint_backend_winit::WinitWindowAccessor;
+use i_slint_backend_winit::winit::dpi::PhysicalPosition;
+use slint::ComponentHandle;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Most copies a single popup splits into
+pub const MAX_MITOSIS_FACTOR: u32 = 4;
+/// Copies of copies stop splitting after this many generations
+pub const MAX_GENERATIONS: u32 = 3;
+/// Most copies open at once, across all generations
+pub const MAX_LIVE_COPIES: usize = 32;
+
+/// Copies made of each popup kind when one closes, shared with the spawner
+#[derive(Debug, Default)]
+pub struct MitosisFactors {
+    factors: [AtomicU32; 2],
+}
+
+impl MitosisFactors {
+    /// Split closing popups of `kind` into `factor` copies, 0 turns mitosis off
+    pub fn set(&self, kind: PopupKind, factor: u32) {
+        self.factors[kind.index()].store(factor.min(MAX_MITOSIS_FACTOR), Ordering::Relaxed);
+    }
+
+    pub fn get(&self, kind: PopupKind) -> u32 {
+        self.factors[kind.index()].load(Ordering::Relaxed)
+    }
+}
+
+/// Copies to spawn for a closing popup of `generation` (originals are 0)
+/// while `live_copies` copies are already open
+pub fn copies_to_spawn(factor: u32, generation: u32, live_copies: usize) -> usize {
+    if generation >= MAX_GENERATIONS {
+        return 0;
+    }
+    (factor as usize).min(MAX_LIVE_COPIES.saturating_sub(live_copies))
+}
+
+/// Move a window to a random spot on its monitor once it has been created
+pub fn scatter<C: ComponentHandle + 'static>(window: &C) {
+    let window_weak = window.as_weak();
+    let _ = slint::spawn_local(async move {
+        if let Some(window) = window_weak.upgrade()
+            && let Ok(winit_window) = window.window().winit_window().await
+            && let Some(monitor) = winit_window
+                .current_monitor()
+                .or_else(|| winit_window.primary_monitor())
+        {
+            let rect = MonitorRect::from(&monitor);
+            let size = winit_window.outer_size();
+            let (x, y) = random_position(&rect, (size.width, size.height), &mut rand::rng());
+            winit_window.set_outer_position(PhysicalPosition::new(x, y));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copies_to_spawn() {
+        assert_eq!(copies_to_spawn(2, 0, 0), 2);
+        assert_eq!(copies_to_spawn(0, 0, 0), 0);
+        // Only room for one more copy
+        assert_eq!(copies_to_spawn(2, 1, MAX_LIVE_COPIES - 1), 1);
+        assert_eq!(copies_to_spawn(2, 1, MAX_LIVE_COPIES), 0);
+        assert_eq!(copies_to_spawn(2, MAX_GENERATIONS, 0), 0);
+    }
+
+    #[test]
+    fn test_factor_is_capped() {
+        let factors = MitosisFactors::default();
+        assert_eq!(factors.get(PopupKind::Image), 0);
+        factors.set(PopupKind::Image, 100);
+        assert_eq!(factors.get(PopupKind::Image), MAX_MITOSIS_FACTOR);
+        assert_eq!(factors.get(PopupKind::Video), 0);
+    }
+}
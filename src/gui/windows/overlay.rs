@@ -0,0 +1,102 @@
+use super::OverlayWindow;
+use super::types::WindowHandle;
+use anyhow::Result;
+use i_slint_backend_winit::WinitWindowAccessor;
+use i_slint_backend_winit::winit::window::{Fullscreen, WindowLevel};
+use slint::ComponentHandle;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// State for an overlay window
+pub struct OverlayState {
+    pub window: Rc<OverlayWindow>,
+    /// Drives the fade-in, stopped when the overlay is dropped
+    _fade_timer: Option<slint::Timer>,
+}
+
+/// Opacity of an overlay `elapsed` into a fade towards `target`
+pub fn fade_opacity(target: f32, elapsed: Duration, fade: Duration) -> f32 {
+    if fade.is_zero() {
+        return target;
+    }
+    target * (elapsed.as_secs_f32() / fade.as_secs_f32()).min(1.0)
+}
+
+pub fn spawn(
+    handle: WindowHandle,
+    color: [f32; 4],
+    fade_in: Option<Duration>,
+    click_through: bool,
+) -> Result<OverlayState> {
+    let window = Rc::new(OverlayWindow::new()?);
+
+    let target_opacity = color[3];
+    window.set_fill_color(slint::Color::from_rgb_u8(
+        (color[0] * 255.0) as u8,
+        (color[1] * 255.0) as u8,
+        (color[2] * 255.0) as u8,
+    ));
+
+    let fade_timer = fade_in.filter(|fade| !fade.is_zero()).map(|fade| {
+        window.set_fill_opacity(0.0);
+        let started = Instant::now();
+        let window_weak = window.as_weak();
+        let timer = slint::Timer::default();
+        timer.start(
+            slint::TimerMode::Repeated,
+            Duration::from_millis(16),
+            move || {
+                if let Some(window) = window_weak.upgrade() {
+                    window.set_fill_opacity(fade_opacity(target_opacity, started.elapsed(), fade));
+                }
+            },
+        );
+        timer
+    });
+    if fade_timer.is_none() {
+        window.set_fill_opacity(target_opacity);
+    }
+
+    window.show()?;
+
+    // Cover the whole monitor and stay above everything else
+    let window_weak = window.as_weak();
+    let _ = slint::spawn_local(async move {
+        if let Some(window) = window_weak.upgrade()
+            && let Ok(winit_window) = window.window().winit_window().await
+        {
+            winit_window.set_window_level(WindowLevel::AlwaysOnTop);
+            winit_window.set_decorations(false);
+            winit_window.set_window_icon(None);
+            winit_window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+
+            if click_through && let Err(e) = winit_window.set_cursor_hittest(false) {
+                warn!("Overlay click-through is not supported: {}", e);
+            }
+        }
+    });
+
+    debug!("Spawned overlay window: {:?}", handle);
+    Ok(OverlayState {
+        window,
+        _fade_timer: fade_timer,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fade_opacity() {
+        let fade = Duration::from_millis(1000);
+        assert_eq!(fade_opacity(0.8, Duration::ZERO, fade), 0.0);
+        assert!((fade_opacity(0.8, Duration::from_millis(500), fade) - 0.4).abs() < 1e-6);
+        assert_eq!(fade_opacity(0.8, Duration::from_millis(2000), fade), 0.8);
+        assert_eq!(
+            fade_opacity(0.8, Duration::from_millis(10), Duration::ZERO),
+            0.8
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Results of write_lines prompts
+//!
+//! Unlike input events, an outcome is kept once it is known so a script that
+//! starts waiting after the prompt finished still learns how it ended.
+
+use super::types::WindowHandle;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use ts_rs::TS;
+
+/// How a prompt ended
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "camelCase")]
+pub enum PromptOutcome {
+    /// The user typed the text correctly
+    Submitted,
+    /// The prompt timed out before the text was typed
+    TimedOut,
+    /// The prompt was closed by a script or on shutdown
+    Closed,
+}
+
+/// How a prompt ended and what the user typed
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptResult {
+    /// Whether the user submitted the prompt before it timed out or was closed
+    pub submitted: bool,
+    /// The submitted text, only set when `submitted` is true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub input: Option<String>,
+    /// How the prompt ended
+    pub outcome: PromptOutcome,
+}
+
+impl From<PromptOutcome> for PromptResult {
+    fn from(outcome: PromptOutcome) -> Self {
+        Self {
+            submitted: outcome == PromptOutcome::Submitted,
+            input: None,
+            outcome,
+        }
+    }
+}
+
+enum PromptState {
+    Pending(Vec<oneshot::Sender<PromptResult>>),
+    Finished(PromptResult),
+}
+
+/// Prompts that have been spawned and how they ended
+#[derive(Clone, Default)]
+pub struct PromptResults {
+    prompts: Arc<Mutex<HashMap<WindowHandle, PromptState>>>,
+}
+
+impl PromptResults {
+    /// Start tracking a prompt, before its window is spawned
+    pub fn track(&self, handle: WindowHandle) {
+        self.prompts
+            .lock()
+            .unwrap()
+            .insert(handle, PromptState::Pending(Vec::new()));
+    }
+
+    /// Stop tracking a prompt whose window was never spawned
+    pub fn forget(&self, handle: WindowHandle) {
+        self.prompts.lock().unwrap().remove(&handle);
+    }
+
+    /// Wait for the result of a prompt, `None` if the handle isn't a prompt
+    pub fn wait(&self, handle: WindowHandle) -> Option<oneshot::Receiver<PromptResult>> {
+        let (tx, rx) = oneshot::channel();
+        match self.prompts.lock().unwrap().get_mut(&handle)? {
+            PromptState::Pending(waiters) => waiters.push(tx),
+            PromptState::Finished(result) => {
+                let _ = tx.send(result.clone());
+            }
+        }
+        Some(rx)
+    }
+
+    /// Record that the user submitted `input`, see [`PromptResults::finish`]
+    pub fn submit(&self, handle: WindowHandle, input: String) -> bool {
+        self.finish_with(
+            handle,
+            PromptResult {
+                submitted: true,
+                input: Some(input),
+                outcome: PromptOutcome::Submitted,
+            },
+        )
+    }
+
+    /// Record how a prompt ended, the first outcome wins.
+    ///
+    /// Returns whether this was a tracked, still pending prompt.
+    pub fn finish(&self, handle: WindowHandle, outcome: PromptOutcome) -> bool {
+        self.finish_with(handle, outcome.into())
+    }
+
+    fn finish_with(&self, handle: WindowHandle, result: PromptResult) -> bool {
+        let mut prompts = self.prompts.lock().unwrap();
+        let Some(state) = prompts.get_mut(&handle) else {
+            return false;
+        };
+        if matches!(state, PromptState::Finished(_)) {
+            return false;
+        }
+        if let PromptState::Pending(waiters) =
+            std::mem::replace(state, PromptState::Finished(result.clone()))
+        {
+            for tx in waiters {
+                let _ = tx.send(result.clone());
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_first_outcome_is_kept() {
+        let prompts = PromptResults::default();
+        let handle = WindowHandle(Uuid::new_v4());
+        assert!(prompts.wait(handle).is_none());
+
+        prompts.track(handle);
+        let mut early = prompts.wait(handle).unwrap();
+        assert!(prompts.submit(handle, "I will obey".to_string()));
+        // Closing the window afterwards doesn't change the outcome
+        assert!(!prompts.finish(handle, PromptOutcome::Closed));
+
+        let submitted = PromptResult {
+            submitted: true,
+            input: Some("I will obey".to_string()),
+            outcome: PromptOutcome::Submitted,
+        };
+        assert_eq!(early.try_recv().unwrap(), submitted);
+        let mut late = prompts.wait(handle).unwrap();
+        assert_eq!(late.try_recv().unwrap(), submitted);
+    }
+
+    #[test]
+    fn test_timed_out_prompt_has_no_input() {
+        let prompts = PromptResults::default();
+        let handle = WindowHandle(Uuid::new_v4());
+        prompts.track(handle);
+        assert!(prompts.finish(handle, PromptOutcome::TimedOut));
+        assert!(!prompts.submit(handle, "too late".to_string()));
+
+        let result = prompts.wait(handle).unwrap().try_recv().unwrap();
+        assert!(!result.submitted);
+        assert_eq!(result.input, None);
+        assert_eq!(result.outcome, PromptOutcome::TimedOut);
+        assert_eq!(
+            serde_json::to_string(&result).unwrap(),
+            r#"{"submitted":false,"outcome":"timedOut"}"#
+        );
+    }
+}
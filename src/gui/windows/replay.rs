@@ -0,0 +1,71 @@
+//! Spawn parameters of recent windows, so they can be shown again
+
+use super::types::{WindowCommand, WindowHandle};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Most windows remembered for replay, older ones are forgotten first
+pub const MAX_REPLAYABLE: usize = 256;
+
+#[derive(Default)]
+struct History {
+    commands: HashMap<WindowHandle, WindowCommand>,
+    order: VecDeque<WindowHandle>,
+}
+
+/// The spawn command of each recently spawned window
+#[derive(Clone, Default)]
+pub struct SpawnHistory {
+    inner: Arc<Mutex<History>>,
+}
+
+impl SpawnHistory {
+    /// Remember a spawn command under its handle
+    pub fn record(&self, command: &WindowCommand) {
+        let Some(handle) = command.spawn_handle() else {
+            return;
+        };
+        let mut history = self.inner.lock().unwrap();
+        if history.commands.insert(handle, command.clone()).is_none() {
+            history.order.push_back(handle);
+        }
+        while history.order.len() > MAX_REPLAYABLE {
+            if let Some(oldest) = history.order.pop_front() {
+                history.commands.remove(&oldest);
+            }
+        }
+    }
+
+    /// The command that spawned a window, if it is still remembered
+    pub fn get(&self, handle: WindowHandle) -> Option<WindowCommand> {
+        self.inner.lock().unwrap().commands.get(&handle).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_oldest_commands_are_forgotten() {
+        let history = SpawnHistory::default();
+        let overlay = |handle| WindowCommand::SpawnOverlay {
+            handle,
+            color: [0.0; 4],
+            fade_in: None,
+            click_through: false,
+            timeout: None,
+        };
+
+        let first = WindowHandle(Uuid::new_v4());
+        history.record(&overlay(first));
+        history.record(&WindowCommand::CloseAll);
+        assert!(history.get(first).is_some());
+
+        for _ in 0..MAX_REPLAYABLE {
+            history.record(&overlay(WindowHandle(Uuid::new_v4())));
+        }
+        assert!(history.get(first).is_none());
+    }
+}
@@ -0,0 +1,198 @@
+//! Auto-close scheduling for timed windows
+//!
+//! Deadlines are tracked separately from the Slint windows themselves so the
+//! spawner can expire windows from its polling timer and answer remaining-time
+//! queries without touching the UI components. Windows that require a click to
+//! close are gated the same way, as are windows that must stay up for a minimum
+//! time before closing.
+
+use super::types::WindowHandle;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Scheduled close deadlines keyed by window handle
+#[derive(Debug, Default)]
+pub struct CloseSchedule {
+    deadlines: HashMap<WindowHandle, Instant>,
+}
+
+impl CloseSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedule a window to close after `timeout` from `now`
+    pub fn schedule(&mut self, handle: WindowHandle, now: Instant, timeout: Duration) {
+        self.deadlines.insert(handle, now + timeout);
+    }
+
+    /// Remove any scheduled close for a window
+    pub fn cancel(&mut self, handle: WindowHandle) {
+        self.deadlines.remove(&handle);
+    }
+
+    /// Time left before the window closes, or `None` if it is untimed
+    pub fn remaining(&self, handle: WindowHandle, now: Instant) -> Option<Duration> {
+        self.deadlines
+            .get(&handle)
+            .map(|deadline| deadline.saturating_duration_since(now))
+    }
+
+    /// Remove and return every window whose deadline has passed
+    pub fn take_expired(&mut self, now: Instant) -> Vec<WindowHandle> {
+        let expired: Vec<WindowHandle> = self
+            .deadlines
+            .iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(handle, _)| *handle)
+            .collect();
+
+        for handle in &expired {
+            self.deadlines.remove(handle);
+        }
+        expired
+    }
+}
+
+/// Decides whether a click closes a click-to-close window
+#[derive(Debug, Clone, Copy)]
+pub struct ClickGate {
+    clickable_from: Instant,
+}
+
+impl ClickGate {
+    /// Gate for a window shown at `shown` that must stay up for `min_visible`
+    pub fn new(shown: Instant, min_visible: Duration) -> Self {
+        Self {
+            clickable_from: shown + min_visible,
+        }
+    }
+
+    /// Whether a click at `now` should close the window
+    pub fn accepts(&self, now: Instant) -> bool {
+        now >= self.clickable_from
+    }
+}
+
+/// When windows were shown, to hold back closes that come too early
+#[derive(Debug, Default)]
+pub struct MinVisible {
+    shown: HashMap<WindowHandle, Instant>,
+}
+
+impl MinVisible {
+    /// Remember that a window was shown at `now`
+    pub fn shown(&mut self, handle: WindowHandle, now: Instant) {
+        self.shown.insert(handle, now);
+    }
+
+    pub fn forget(&mut self, handle: WindowHandle) {
+        self.shown.remove(&handle);
+    }
+
+    pub fn clear(&mut self) {
+        self.shown.clear();
+    }
+
+    /// How much longer a window must stay up, `None` if it may close at `now`
+    pub fn hold_for(
+        &self,
+        handle: WindowHandle,
+        now: Instant,
+        min_visible: Duration,
+    ) -> Option<Duration> {
+        let shown = self.shown.get(&handle)?;
+        let remaining = (*shown + min_visible).saturating_duration_since(now);
+        (!remaining.is_zero()).then_some(remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_remaining_time_decreases() {
+        let mut schedule = CloseSchedule::new();
+        let handle = WindowHandle(Uuid::new_v4());
+        let start = Instant::now();
+        schedule.schedule(handle, start, Duration::from_millis(1000));
+
+        let first = schedule.remaining(handle, start).unwrap();
+        let second = schedule
+            .remaining(handle, start + Duration::from_millis(400))
+            .unwrap();
+
+        assert_eq!(first, Duration::from_millis(1000));
+        assert_eq!(second, Duration::from_millis(600));
+        assert!(second < first);
+    }
+
+    #[test]
+    fn test_untimed_window_has_no_remaining_time() {
+        let schedule = CloseSchedule::new();
+        let handle = WindowHandle(Uuid::new_v4());
+        assert!(schedule.remaining(handle, Instant::now()).is_none());
+    }
+
+    #[test]
+    fn test_take_expired() {
+        let mut schedule = CloseSchedule::new();
+        let short = WindowHandle(Uuid::new_v4());
+        let long = WindowHandle(Uuid::new_v4());
+        let start = Instant::now();
+        schedule.schedule(short, start, Duration::from_millis(50));
+        schedule.schedule(long, start, Duration::from_secs(10));
+
+        let expired = schedule.take_expired(start + Duration::from_millis(100));
+        assert_eq!(expired, vec![short]);
+        assert!(schedule.remaining(short, start).is_none());
+        assert!(schedule.remaining(long, start).is_some());
+    }
+
+    #[test]
+    fn test_click_gate_ignores_early_clicks() {
+        let shown = Instant::now();
+        let gate = ClickGate::new(shown, Duration::from_millis(500));
+
+        assert!(!gate.accepts(shown));
+        assert!(!gate.accepts(shown + Duration::from_millis(499)));
+        assert!(gate.accepts(shown + Duration::from_millis(500)));
+
+        let immediate = ClickGate::new(shown, Duration::ZERO);
+        assert!(immediate.accepts(shown));
+    }
+
+    #[test]
+    fn test_immediate_close_held_until_min_visible() {
+        let mut min_visible = MinVisible::default();
+        let mut schedule = CloseSchedule::new();
+        let handle = WindowHandle(Uuid::new_v4());
+        let shown = Instant::now();
+        let min = Duration::from_millis(300);
+        min_visible.shown(handle, shown);
+
+        // Asked to close right away: the close is pushed back instead
+        let hold = min_visible.hold_for(handle, shown, min).unwrap();
+        assert_eq!(hold, min);
+        schedule.schedule(handle, shown, hold);
+
+        let almost = shown + Duration::from_millis(299);
+        assert!(schedule.take_expired(almost).is_empty());
+        assert_eq!(schedule.take_expired(shown + min), vec![handle]);
+        assert!(min_visible.hold_for(handle, shown + min, min).is_none());
+
+        // Untracked windows and a zero minimum never hold
+        assert!(
+            min_visible
+                .hold_for(WindowHandle(Uuid::new_v4()), shown, min)
+                .is_none()
+        );
+        assert!(
+            min_visible
+                .hold_for(handle, shown, Duration::ZERO)
+                .is_none()
+        );
+    }
+}
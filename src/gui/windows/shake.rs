@@ -0,0 +1,107 @@
+//! Window shake effect
+//!
+//! The window is moved around its original position with a decaying oscillation
+//! on every spawner tick, then put back where it started.
+
+use std::f64::consts::TAU;
+use std::time::{Duration, Instant};
+
+/// Largest shake offset in pixels
+pub const MAX_SHAKE_AMPLITUDE: u32 = 50;
+/// Longest a shake can run
+pub const MAX_SHAKE_DURATION: Duration = Duration::from_secs(5);
+/// Oscillations per second along the x axis
+const SHAKE_FREQUENCY: f64 = 20.0;
+
+/// Offset from the original position `elapsed` into a shake.
+///
+/// The window swings horizontally with a smaller vertical wobble at twice the
+/// frequency, and the amplitude decays linearly to zero at the end.
+pub fn shake_offset(elapsed: Duration, duration: Duration, amplitude: u32) -> (i32, i32) {
+    if duration.is_zero() || elapsed >= duration {
+        return (0, 0);
+    }
+
+    let t = elapsed.as_secs_f64();
+    let decay = 1.0 - t / duration.as_secs_f64();
+    let amplitude = amplitude as f64 * decay;
+    let phase = TAU * SHAKE_FREQUENCY * t;
+    (
+        (amplitude * phase.sin()).round() as i32,
+        (amplitude / 2.0 * (2.0 * phase).sin()).round() as i32,
+    )
+}
+
+/// A running shake for one window
+#[derive(Debug, Clone, Copy)]
+pub struct Shake {
+    /// Position to restore when the shake ends
+    pub origin: (i32, i32),
+    started: Instant,
+    duration: Duration,
+    amplitude: u32,
+}
+
+impl Shake {
+    /// Start a shake, clamping duration and amplitude to their limits
+    pub fn new(origin: (i32, i32), now: Instant, duration: Duration, amplitude: u32) -> Self {
+        Self {
+            origin,
+            started: now,
+            duration: duration.min(MAX_SHAKE_DURATION),
+            amplitude: amplitude.min(MAX_SHAKE_AMPLITUDE),
+        }
+    }
+
+    /// Window position at `now`, or `None` once the shake is over
+    pub fn position_at(&self, now: Instant) -> Option<(i32, i32)> {
+        let elapsed = now.saturating_duration_since(self.started);
+        if elapsed >= self.duration {
+            return None;
+        }
+        let (dx, dy) = shake_offset(elapsed, self.duration, self.amplitude);
+        Some((self.origin.0 + dx, self.origin.1 + dy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shake_offset() {
+        let duration = Duration::from_millis(1000);
+
+        assert_eq!(shake_offset(Duration::ZERO, duration, 20), (0, 0));
+        // Quarter period at 20 Hz: full swing right, vertical wobble back at zero
+        assert_eq!(
+            shake_offset(Duration::from_micros(12_500), duration, 20),
+            (20, 0)
+        );
+        // Three quarters: swing left, decayed by 3.75%
+        assert_eq!(
+            shake_offset(Duration::from_micros(37_500), duration, 20),
+            (-19, 0)
+        );
+        // Halfway through the shake the amplitude has halved
+        assert_eq!(
+            shake_offset(Duration::from_micros(512_500), duration, 20),
+            (10, 0)
+        );
+        assert_eq!(shake_offset(duration, duration, 20), (0, 0));
+    }
+
+    #[test]
+    fn test_shake_is_bounded_and_ends() {
+        let now = Instant::now();
+        let shake = Shake::new((100, 100), now, Duration::from_secs(60), 1000);
+
+        let (x, y) = shake
+            .position_at(now + Duration::from_micros(12_500))
+            .unwrap();
+        assert!((x - 100).unsigned_abs() <= MAX_SHAKE_AMPLITUDE);
+        assert!((y - 100).unsigned_abs() <= MAX_SHAKE_AMPLITUDE);
+
+        assert!(shake.position_at(now + MAX_SHAKE_DURATION).is_none());
+    }
+}
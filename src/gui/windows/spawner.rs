@@ -1,19 +1,55 @@
+use super::events::{WindowEvent, WindowEvents};
+use super::fade::{Fade, WindowFade};
 use super::image;
-use super::types::{WindowCommand, WindowHandle, WindowInfo, WindowOptions, WindowResponse};
+use super::layout::AutoLayout;
+use super::limits::PopupSlots;
+use super::mitosis::{self, MitosisFactors};
+use super::overlay::{self, OverlayState};
+use super::prompts::{PromptOutcome, PromptResults};
+use super::replay::SpawnHistory;
+use super::schedule::{ClickGate, CloseSchedule, MinVisible};
+use super::shake::Shake;
+use super::types::{
+    PopupDefaults, PopupKind, SpawnQueueFull, WindowCommand, WindowHandle, WindowInfo,
+    WindowOptions, WindowResponse,
+};
 use super::video::{self, VideoState};
 use super::write_lines;
 use super::{ImageWindow, WriteLinesWindow};
+use crate::media::image::color_key::ColorKey;
 use anyhow::Result;
+use i_slint_backend_winit::WinitWindowAccessor;
 use slint::ComponentHandle;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::mpsc::{Receiver, Sender, channel};
-use tracing::{error, info};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+/// Spawns that may be queued for the UI thread before new ones are rejected
+pub const MAX_PENDING_SPAWNS: usize = 64;
+/// Spawns handled per tick, so a burst doesn't freeze the UI
+const MAX_SPAWNS_PER_TICK: usize = 8;
 
 // Thread-local storage for active windows
 thread_local! {
     static WINDOWS: RefCell<HashMap<WindowHandle, WindowType>> = RefCell::new(HashMap::new());
+    static CLOSE_SCHEDULE: RefCell<CloseSchedule> = RefCell::new(CloseSchedule::new());
+    static SHAKES: RefCell<HashMap<WindowHandle, Shake>> = RefCell::new(HashMap::new());
+    static CLICK_GATES: RefCell<HashMap<WindowHandle, ClickGate>> = RefCell::new(HashMap::new());
+    static CLICKED_TO_CLOSE: RefCell<Vec<WindowHandle>> = const { RefCell::new(Vec::new()) };
+    static MIN_VISIBLE: RefCell<MinVisible> = RefCell::new(MinVisible::default());
+    static FADES: RefCell<HashMap<WindowHandle, Fade>> = RefCell::new(HashMap::new());
+    /// Fade-out and full opacity of windows that asked to fade out when closed
+    static FADE_OUTS: RefCell<HashMap<WindowHandle, (Duration, f32)>> =
+        RefCell::new(HashMap::new());
+    /// Windows already closed that are still fading out
+    static FADING_OUT: RefCell<Vec<(WindowType, Fade)>> = const { RefCell::new(Vec::new()) };
+    /// Generation of each open mitosis copy
+    static GENERATIONS: RefCell<HashMap<WindowHandle, u32>> = RefCell::new(HashMap::new());
 }
 
 /// Enum to hold different window types
@@ -21,6 +57,7 @@ enum WindowType {
     WriteLines(Rc<WriteLinesWindow>),
     Image(Rc<ImageWindow>),
     Video(VideoState),
+    Overlay(OverlayState),
 }
 
 impl WindowType {
@@ -29,23 +66,203 @@ impl WindowType {
             WindowType::WriteLines(w) => w.hide(),
             WindowType::Image(w) => w.hide(),
             WindowType::Video(state) => state.window.hide(),
+            WindowType::Overlay(state) => state.window.hide(),
+        }
+    }
+
+    /// Forward clicks and key presses to scripts waiting on this window
+    fn connect_events(&self, handle: WindowHandle, events: &WindowEvents) {
+        macro_rules! connect {
+            ($window:expr) => {{
+                let click_events = events.clone();
+                $window.on_clicked(move |x, y| {
+                    click_events.dispatch(handle, WindowEvent::Click { x, y });
+                    // Closed on the next tick rather than from inside the window's own callback
+                    let accepted = CLICK_GATES.with(|gates| {
+                        gates
+                            .borrow()
+                            .get(&handle)
+                            .is_some_and(|gate| gate.accepts(Instant::now()))
+                    });
+                    if accepted {
+                        CLICKED_TO_CLOSE.with(|clicked| clicked.borrow_mut().push(handle));
+                    }
+                });
+                let key_events = events.clone();
+                $window.on_key_pressed(move |text| {
+                    key_events.dispatch(
+                        handle,
+                        WindowEvent::Key {
+                            text: text.to_string(),
+                        },
+                    );
+                });
+            }};
+        }
+
+        match self {
+            WindowType::WriteLines(_) => {}
+            WindowType::Image(w) => connect!(w),
+            WindowType::Video(state) => connect!(state.window),
+            WindowType::Overlay(state) => connect!(state.window),
+        }
+    }
+
+    /// Set the opacity the window content is rendered with
+    fn set_opacity(&self, opacity: f32) {
+        match self {
+            // Text prompts are always drawn opaque
+            WindowType::WriteLines(_) => {}
+            WindowType::Image(w) => w.set_image_opacity(opacity),
+            WindowType::Video(state) => state.window.set_video_opacity(opacity),
+            WindowType::Overlay(state) => state.window.set_fill_opacity(opacity),
+        }
+    }
+
+    /// Let clicks pass through the window, prompts always stay clickable since they take input
+    fn set_click_through(&self, click_through: bool) {
+        match self {
+            WindowType::WriteLines(_) => {}
+            WindowType::Image(w) => apply_click_through(&**w, click_through),
+            WindowType::Video(state) => apply_click_through(&*state.window, click_through),
+            WindowType::Overlay(state) => apply_click_through(&*state.window, click_through),
         }
     }
+
+    /// Move the window to a random spot on its monitor
+    fn scatter(&self) {
+        match self {
+            WindowType::Image(w) => mitosis::scatter(&**w),
+            WindowType::Video(state) => mitosis::scatter(&*state.window),
+            WindowType::WriteLines(_) | WindowType::Overlay(_) => {}
+        }
+    }
+
+    fn window(&self) -> &slint::Window {
+        match self {
+            WindowType::WriteLines(w) => w.window(),
+            WindowType::Image(w) => w.window(),
+            WindowType::Video(state) => state.window.window(),
+            WindowType::Overlay(state) => state.window.window(),
+        }
+    }
+}
+
+/// Set whether the native window ignores the cursor, once it has been created
+fn apply_click_through<C: ComponentHandle + 'static>(window: &C, click_through: bool) {
+    let window_weak = window.as_weak();
+    let _ = slint::spawn_local(async move {
+        if let Some(window) = window_weak.upgrade()
+            && let Ok(winit_window) = window.window().winit_window().await
+            && let Err(e) = winit_window.set_cursor_hittest(!click_through)
+        {
+            warn!("Click-through is not supported: {}", e);
+        }
+    });
 }
 
 /// Handle to send commands to the window spawner
 #[derive(Clone)]
 pub struct WindowSpawnerHandle {
     pub command_tx: Sender<WindowCommand>,
+    /// Input events from spawned windows
+    pub events: WindowEvents,
+    /// Outcomes of write_lines prompts
+    pub prompts: PromptResults,
+    /// Spawn commands of recent windows, for replaying them
+    history: SpawnHistory,
+    /// Minimum time in milliseconds windows stay up, shared with the spawner
+    min_visible_ms: Arc<AtomicU64>,
+    /// Spawn commands sent but not yet handled, shared with the spawner
+    pending_spawns: Arc<AtomicUsize>,
+    /// Open image and video popups and their caps, shared with the spawner
+    popup_slots: Arc<PopupSlots>,
+    /// Copies made of closing popups, shared with the spawner
+    mitosis: Arc<MitosisFactors>,
+    popup_defaults: PopupDefaults,
 }
 
 impl WindowSpawnerHandle {
+    /// Handle sending to `command_tx`, for driving the spawner from elsewhere (e.g. tests)
+    pub fn new(command_tx: Sender<WindowCommand>) -> Self {
+        Self {
+            command_tx,
+            events: WindowEvents::default(),
+            prompts: PromptResults::default(),
+            history: SpawnHistory::default(),
+            min_visible_ms: Arc::default(),
+            pending_spawns: Arc::default(),
+            popup_slots: Arc::default(),
+            mitosis: Arc::default(),
+            popup_defaults: PopupDefaults::default(),
+        }
+    }
+
+    /// Use `defaults` for image and video popups that don't set opacity, always-on-top or a timeout
+    pub fn with_popup_defaults(mut self, defaults: PopupDefaults) -> Self {
+        self.popup_defaults = defaults;
+        self
+    }
+
+    /// Keep windows up for at least `min_visible` before a close request or timeout closes them
+    pub fn set_min_visible(&self, min_visible: Duration) {
+        self.min_visible_ms
+            .store(min_visible.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Allow at most `max` image or video popups open at once
+    pub fn set_max_popups(&self, kind: PopupKind, max: usize) {
+        self.popup_slots.set_max(kind, max);
+    }
+
+    /// Split image or video popups into `factor` copies when the user closes them or
+    /// they time out, 0 turns it off
+    pub fn set_mitosis(&self, kind: PopupKind, factor: u32) {
+        self.mitosis.set(kind, factor);
+    }
+
+    /// Send a command to the UI thread.
+    ///
+    /// Spawns fail with [`SpawnQueueFull`] while [`MAX_PENDING_SPAWNS`] are still queued,
+    /// and image or video spawns with [`WindowLimitReached`](super::types::WindowLimitReached)
+    /// while the most of their kind are open. Other commands are always sent.
     pub fn send(&self, command: WindowCommand) -> Result<()> {
-        self.command_tx
-            .send(command)
-            .map_err(|e| anyhow::anyhow!("Failed to send window command: {}", e))
+        let is_spawn = command.is_spawn();
+        let popup_kind = command.popup_kind();
+        if let Some(kind) = popup_kind {
+            self.popup_slots.acquire(kind)?;
+        }
+        let release = || {
+            if let Some(kind) = popup_kind {
+                self.popup_slots.release(kind);
+            }
+        };
+
+        if is_spawn
+            && self
+                .pending_spawns
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |pending| {
+                    (pending < MAX_PENDING_SPAWNS).then_some(pending + 1)
+                })
+                .is_err()
+        {
+            release();
+            return Err(SpawnQueueFull.into());
+        }
+
+        if is_spawn {
+            self.history.record(&command);
+        }
+        self.command_tx.send(command).map_err(|e| {
+            if is_spawn {
+                self.pending_spawns.fetch_sub(1, Ordering::SeqCst);
+            }
+            release();
+            anyhow::anyhow!("Failed to send window command: {}", e)
+        })
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn_write_lines(
         &self,
         text: String,
@@ -54,6 +271,39 @@ impl WindowSpawnerHandle {
         background_color: [f32; 4],
         alignment: String,
         window_options: Option<WindowOptions>,
+        exact_match: bool,
+        timeout: Option<Duration>,
+    ) -> Result<WindowHandle> {
+        let handle = WindowHandle(uuid::Uuid::new_v4());
+        // Track before sending so the outcome can't be missed
+        self.prompts.track(handle);
+        self.send(WindowCommand::SpawnWriteLines {
+            handle,
+            text,
+            font_size,
+            text_color,
+            background_color,
+            alignment,
+            window_options,
+            interactive: true,
+            exact_match,
+            timeout,
+        })
+        .inspect_err(|_| self.prompts.forget(handle))?;
+        Ok(handle)
+    }
+
+    /// Show text in the write_lines window without the input field, closing after `timeout`
+    #[allow(clippy::too_many_arguments)]
+    pub fn spawn_banner(
+        &self,
+        text: String,
+        font_size: f32,
+        text_color: [f32; 4],
+        background_color: [f32; 4],
+        alignment: String,
+        window_options: Option<WindowOptions>,
+        timeout: Duration,
     ) -> Result<WindowHandle> {
         let handle = WindowHandle(uuid::Uuid::new_v4());
         self.send(WindowCommand::SpawnWriteLines {
@@ -64,16 +314,27 @@ impl WindowSpawnerHandle {
             background_color,
             alignment,
             window_options,
+            interactive: false,
+            exact_match: true,
+            timeout: Some(timeout),
         })?;
         Ok(handle)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn_image(
         &self,
         path: std::path::PathBuf,
         width: Option<u32>,
         height: Option<u32>,
-        opacity: f32,
+        opacity: Option<f32>,
+        always_on_top: Option<bool>,
+        timeout: Option<Duration>,
+        click_to_close: Option<Duration>,
+        layout: AutoLayout,
+        color_key: Option<ColorKey>,
+        fade: WindowFade,
+        click_through: bool,
     ) -> Result<WindowHandle> {
         let handle = WindowHandle(uuid::Uuid::new_v4());
         self.send(WindowCommand::SpawnImage {
@@ -81,19 +342,33 @@ impl WindowSpawnerHandle {
             path,
             width,
             height,
-            opacity,
+            opacity: opacity.unwrap_or(self.popup_defaults.opacity),
+            always_on_top: always_on_top.unwrap_or(self.popup_defaults.always_on_top),
+            timeout: timeout.or(self.popup_defaults.image_timeout),
+            click_to_close,
+            layout,
+            color_key,
+            fade,
+            click_through,
         })?;
         Ok(handle)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn_video(
         &self,
         path: std::path::PathBuf,
         width: Option<u32>,
         height: Option<u32>,
-        opacity: f32,
+        opacity: Option<f32>,
+        always_on_top: Option<bool>,
         loop_playback: bool,
         volume: f32,
+        layout: AutoLayout,
+        timeout: Option<Duration>,
+        click_to_close: Option<Duration>,
+        fade: WindowFade,
+        click_through: bool,
     ) -> Result<WindowHandle> {
         let handle = WindowHandle(uuid::Uuid::new_v4());
         self.send(WindowCommand::SpawnVideo {
@@ -101,13 +376,68 @@ impl WindowSpawnerHandle {
             path,
             width,
             height,
-            opacity,
+            opacity: opacity.unwrap_or(self.popup_defaults.opacity),
+            always_on_top: always_on_top.unwrap_or(self.popup_defaults.always_on_top),
             loop_playback,
             volume,
+            layout,
+            timeout: timeout.or(self.popup_defaults.video_timeout),
+            click_to_close,
+            fade,
+            click_through,
+        })?;
+        Ok(handle)
+    }
+
+    pub fn spawn_overlay(
+        &self,
+        color: [f32; 4],
+        fade_in: Option<Duration>,
+        click_through: bool,
+        timeout: Option<Duration>,
+    ) -> Result<WindowHandle> {
+        let handle = WindowHandle(uuid::Uuid::new_v4());
+        self.send(WindowCommand::SpawnOverlay {
+            handle,
+            color,
+            fade_in,
+            click_through,
+            timeout,
         })?;
         Ok(handle)
     }
 
+    /// Let clicks pass through a window to the ones below, or catch them again
+    pub fn set_click_through(&self, handle: WindowHandle, click_through: bool) -> Result<()> {
+        self.send(WindowCommand::SetClickThrough(handle, click_through))
+    }
+
+    /// Change the opacity a window is rendered with, clamped to 0.0-1.0
+    pub fn set_opacity(&self, handle: WindowHandle, opacity: f32) -> Result<()> {
+        let (tx, rx) = channel();
+        self.send(WindowCommand::SetOpacity(
+            handle,
+            opacity.clamp(0.0, 1.0),
+            tx,
+        ))?;
+        rx.recv()
+            .map_err(|e| anyhow::anyhow!("Failed to receive opacity result: {}", e))?
+            .map_err(|e| anyhow::anyhow!(e))
+    }
+
+    pub fn shake_window(
+        &self,
+        handle: WindowHandle,
+        duration: Duration,
+        amplitude: u32,
+    ) -> Result<()> {
+        self.send(WindowCommand::ShakeWindow {
+            handle,
+            duration,
+            amplitude,
+        })
+    }
+
     pub fn pause_video(&self, handle: WindowHandle) -> Result<()> {
         self.send(WindowCommand::PauseVideo(handle))
     }
@@ -116,6 +446,59 @@ impl WindowSpawnerHandle {
         self.send(WindowCommand::ResumeVideo(handle))
     }
 
+    pub fn set_video_volume(&self, handle: WindowHandle, volume: f32) -> Result<()> {
+        self.send(WindowCommand::SetVideoVolume(handle, volume))
+    }
+
+    pub fn seek_video(&self, handle: WindowHandle, position: Duration) -> Result<()> {
+        self.send(WindowCommand::SeekVideo(handle, position))
+    }
+
+    /// The command that spawned a window, if it is recent enough to be remembered
+    pub fn spawn_command(&self, handle: WindowHandle) -> Option<WindowCommand> {
+        self.history.get(handle)
+    }
+
+    /// Spawn a window again with the parameters it was originally spawned with
+    pub fn replay(&self, handle: WindowHandle) -> Result<WindowHandle> {
+        let command = self
+            .spawn_command(handle)
+            .ok_or_else(|| anyhow::anyhow!("Unknown window handle: {}", handle.0))?;
+        let new_handle = WindowHandle(uuid::Uuid::new_v4());
+        let command = command.with_spawn_handle(new_handle);
+        let is_prompt = matches!(
+            command,
+            WindowCommand::SpawnWriteLines {
+                interactive: true,
+                ..
+            }
+        );
+        if is_prompt {
+            self.prompts.track(new_handle);
+        }
+        self.send(command).inspect_err(|_| {
+            if is_prompt {
+                self.prompts.forget(new_handle);
+            }
+        })?;
+        Ok(new_handle)
+    }
+
+    /// Spawn up to `count` copies of a window, stopping at the first one refused
+    pub fn divide(&self, handle: WindowHandle, count: usize) -> Vec<WindowHandle> {
+        let mut copies = Vec::with_capacity(count);
+        for _ in 0..count {
+            match self.replay(handle) {
+                Ok(copy) => copies.push(copy),
+                Err(e) => {
+                    info!("Stopped mitosis of window {}: {}", handle.0, e);
+                    break;
+                }
+            }
+        }
+        copies
+    }
+
     pub fn close_window(&self, handle: WindowHandle) -> Result<()> {
         self.send(WindowCommand::CloseWindow(handle))
     }
@@ -126,25 +509,51 @@ impl WindowSpawnerHandle {
         rx.recv()
             .map_err(|e| anyhow::anyhow!("Failed to receive active windows: {}", e))
     }
+
+    /// Time left before a window auto-closes, or `None` if it has no timeout
+    pub fn get_remaining_time(&self, handle: WindowHandle) -> Result<Option<Duration>> {
+        let (tx, rx) = channel();
+        self.send(WindowCommand::GetRemainingTime(handle, tx))?;
+        rx.recv()
+            .map_err(|e| anyhow::anyhow!("Failed to receive remaining time: {}", e))?
+            .map_err(|e| anyhow::anyhow!(e))
+    }
 }
 
 /// Window spawner that processes commands on the Slint UI thread
 pub struct WindowSpawner {
     command_rx: Receiver<WindowCommand>,
     response_tx: Sender<WindowResponse>,
+    response_rx: Receiver<WindowResponse>,
+    events: WindowEvents,
+    prompts: PromptResults,
+    pending_spawns: Arc<AtomicUsize>,
+    popup_slots: Arc<PopupSlots>,
+    mitosis: Arc<MitosisFactors>,
+    min_visible_ms: Arc<AtomicU64>,
+    /// Sends the copies made by mitosis
+    handle: WindowSpawnerHandle,
 }
 
 impl WindowSpawner {
     /// Create a new window spawner channel pair
     pub fn create() -> (WindowSpawnerHandle, Self) {
         let (command_tx, command_rx) = channel();
-        let (response_tx, _response_rx) = channel();
+        let (response_tx, response_rx) = channel();
 
-        let handle = WindowSpawnerHandle { command_tx };
+        let handle = WindowSpawnerHandle::new(command_tx);
 
         let spawner = Self {
             command_rx,
             response_tx,
+            response_rx,
+            events: handle.events.clone(),
+            prompts: handle.prompts.clone(),
+            pending_spawns: handle.pending_spawns.clone(),
+            popup_slots: handle.popup_slots.clone(),
+            mitosis: handle.mitosis.clone(),
+            min_visible_ms: handle.min_visible_ms.clone(),
+            handle: handle.clone(),
         };
 
         (handle, spawner)
@@ -152,7 +561,21 @@ impl WindowSpawner {
 
     /// Process pending commands (call this from the Slint event loop)
     pub fn process_commands(&self) {
-        while let Ok(cmd) = self.command_rx.try_recv() {
+        self.handle_responses();
+        self.close_expired_windows();
+        self.close_clicked_windows();
+        self.update_shakes();
+        self.update_fades();
+
+        let mut spawned = 0;
+        while spawned < MAX_SPAWNS_PER_TICK
+            && let Ok(cmd) = self.command_rx.try_recv()
+        {
+            if cmd.is_spawn() {
+                spawned += 1;
+                self.pending_spawns.fetch_sub(1, Ordering::SeqCst);
+            }
+
             match cmd {
                 WindowCommand::GetActiveWindows(tx) => {
                     WINDOWS.with(|windows| {
@@ -165,17 +588,31 @@ impl WindowSpawner {
                                     WindowType::WriteLines(_) => "WriteLines".to_string(),
                                     WindowType::Image(_) => "Image".to_string(),
                                     WindowType::Video(_) => "Video".to_string(),
+                                    WindowType::Overlay(_) => "Overlay".to_string(),
                                 },
                                 description: match window_type {
                                     WindowType::WriteLines(_) => "Text prompt window".to_string(),
                                     WindowType::Image(_) => "Image display window".to_string(),
                                     WindowType::Video(_) => "Video player window".to_string(),
+                                    WindowType::Overlay(_) => {
+                                        "Full-screen color overlay".to_string()
+                                    }
                                 },
                             })
                             .collect();
                         let _ = tx.send(info);
                     });
                 }
+                WindowCommand::GetRemainingTime(handle, tx) => {
+                    let known = WINDOWS.with(|windows| windows.borrow().contains_key(&handle));
+                    let result = if known {
+                        Ok(CLOSE_SCHEDULE
+                            .with(|schedule| schedule.borrow().remaining(handle, Instant::now())))
+                    } else {
+                        Err(format!("Unknown window handle: {}", handle.0))
+                    };
+                    let _ = tx.send(result);
+                }
                 WindowCommand::SpawnWriteLines {
                     handle,
                     text,
@@ -184,6 +621,9 @@ impl WindowSpawner {
                     background_color,
                     alignment,
                     window_options,
+                    interactive,
+                    exact_match,
+                    timeout,
                 } => {
                     match write_lines::spawn(
                         handle,
@@ -193,6 +633,8 @@ impl WindowSpawner {
                         background_color,
                         &alignment,
                         window_options,
+                        interactive,
+                        exact_match,
                         self.response_tx.clone(),
                     ) {
                         Ok(window) => {
@@ -201,10 +643,14 @@ impl WindowSpawner {
                                     .borrow_mut()
                                     .insert(handle, WindowType::WriteLines(window));
                             });
+                            MIN_VISIBLE
+                                .with(|shown| shown.borrow_mut().shown(handle, Instant::now()));
+                            self.schedule_close(handle, timeout);
                             let _ = self.response_tx.send(WindowResponse::Spawned(handle));
                         }
                         Err(e) => {
                             error!("Failed to spawn write_lines window: {}", e);
+                            self.prompts.finish(handle, PromptOutcome::Closed);
                             let _ = self.response_tx.send(WindowResponse::Error(e.to_string()));
                         }
                     }
@@ -215,17 +661,36 @@ impl WindowSpawner {
                     width,
                     height,
                     opacity,
-                } => match image::spawn(handle, &path, width, height, opacity) {
+                    always_on_top,
+                    timeout,
+                    click_to_close,
+                    layout,
+                    color_key,
+                    fade,
+                    click_through,
+                } => match image::spawn(
+                    handle,
+                    &path,
+                    width,
+                    height,
+                    fade.initial_opacity(opacity),
+                    always_on_top,
+                    layout,
+                    color_key,
+                ) {
                     Ok(window) => {
-                        WINDOWS.with(|windows| {
-                            windows
-                                .borrow_mut()
-                                .insert(handle, WindowType::Image(window));
-                        });
+                        self.insert_window(handle, WindowType::Image(window));
+                        self.start_fades(handle, fade, opacity);
+                        if click_through {
+                            self.set_click_through(handle, true);
+                        }
+                        self.schedule_close_or_click(handle, timeout, click_to_close);
                         let _ = self.response_tx.send(WindowResponse::Spawned(handle));
                     }
                     Err(e) => {
                         error!("Failed to spawn image window: {}", e);
+                        self.popup_slots.release(PopupKind::Image);
+                        GENERATIONS.with(|generations| generations.borrow_mut().remove(&handle));
                         let _ = self.response_tx.send(WindowResponse::Error(e.to_string()));
                     }
                 },
@@ -235,31 +700,90 @@ impl WindowSpawner {
                     width,
                     height,
                     opacity,
-                    loop_playback: _,
-                    volume: _,
-                } => match video::spawn(handle, &path, width, height, opacity) {
+                    always_on_top,
+                    loop_playback,
+                    volume,
+                    layout,
+                    timeout,
+                    click_to_close,
+                    fade,
+                    click_through,
+                } => match video::spawn(
+                    handle,
+                    &path,
+                    width,
+                    height,
+                    fade.initial_opacity(opacity),
+                    always_on_top,
+                    loop_playback,
+                    volume,
+                    layout,
+                ) {
                     Ok(state) => {
-                        WINDOWS.with(|windows| {
-                            windows
-                                .borrow_mut()
-                                .insert(handle, WindowType::Video(state));
-                        });
+                        self.insert_window(handle, WindowType::Video(state));
+                        self.start_fades(handle, fade, opacity);
+                        if click_through {
+                            self.set_click_through(handle, true);
+                        }
+                        self.schedule_close_or_click(handle, timeout, click_to_close);
                         let _ = self.response_tx.send(WindowResponse::Spawned(handle));
                     }
                     Err(e) => {
                         error!("Failed to spawn video window: {}", e);
+                        self.popup_slots.release(PopupKind::Video);
+                        GENERATIONS.with(|generations| generations.borrow_mut().remove(&handle));
+                        let _ = self.response_tx.send(WindowResponse::Error(e.to_string()));
+                    }
+                },
+                WindowCommand::SpawnOverlay {
+                    handle,
+                    color,
+                    fade_in,
+                    click_through,
+                    timeout,
+                } => match overlay::spawn(handle, color, fade_in, click_through) {
+                    Ok(state) => {
+                        self.insert_window(handle, WindowType::Overlay(state));
+                        self.schedule_close(handle, timeout);
+                        let _ = self.response_tx.send(WindowResponse::Spawned(handle));
+                    }
+                    Err(e) => {
+                        error!("Failed to spawn overlay window: {}", e);
                         let _ = self.response_tx.send(WindowResponse::Error(e.to_string()));
                     }
                 },
+                WindowCommand::ShakeWindow {
+                    handle,
+                    duration,
+                    amplitude,
+                } => {
+                    self.shake_window(handle, duration, amplitude);
+                }
+                WindowCommand::SetClickThrough(handle, click_through) => {
+                    self.set_click_through(handle, click_through);
+                }
+                WindowCommand::SetOpacity(handle, opacity, tx) => {
+                    let _ = tx.send(self.set_opacity(handle, opacity));
+                }
                 WindowCommand::PauseVideo(handle) => {
                     self.pause_video(handle);
                 }
                 WindowCommand::ResumeVideo(handle) => {
                     self.resume_video(handle);
                 }
+                WindowCommand::SetVideoVolume(handle, volume) => {
+                    self.set_video_volume(handle, volume);
+                }
+                WindowCommand::SeekVideo(handle, position) => {
+                    self.seek_video(handle, position);
+                }
                 WindowCommand::CloseWindow(handle) => {
-                    self.close_window(handle);
-                    let _ = self.response_tx.send(WindowResponse::Closed(handle));
+                    // Closed by the script, even if it stays up a little longer
+                    self.prompts.finish(handle, PromptOutcome::Closed);
+                    if !self.hold_if_too_early(handle) {
+                        self.close_window(handle);
+                        let _ = self.response_tx.send(WindowResponse::Closed(handle));
+                    }
                 }
                 WindowCommand::CloseAll => {
                     self.close_all_windows();
@@ -268,6 +792,36 @@ impl WindowSpawner {
         }
     }
 
+    fn insert_window(&self, handle: WindowHandle, window_type: WindowType) {
+        window_type.connect_events(handle, &self.events);
+        WINDOWS.with(|windows| {
+            windows.borrow_mut().insert(handle, window_type);
+        });
+        MIN_VISIBLE.with(|shown| shown.borrow_mut().shown(handle, Instant::now()));
+        if GENERATIONS.with(|generations| generations.borrow().contains_key(&handle)) {
+            WINDOWS.with(|windows| {
+                if let Some(window_type) = windows.borrow().get(&handle) {
+                    window_type.scatter();
+                }
+            });
+        }
+    }
+
+    /// Push back the close of a window shown less than the minimum time ago.
+    ///
+    /// Returns whether the close was held back.
+    fn hold_if_too_early(&self, handle: WindowHandle) -> bool {
+        let min_visible = Duration::from_millis(self.min_visible_ms.load(Ordering::Relaxed));
+        let now = Instant::now();
+        let Some(hold) =
+            MIN_VISIBLE.with(|shown| shown.borrow().hold_for(handle, now, min_visible))
+        else {
+            return false;
+        };
+        CLOSE_SCHEDULE.with(|schedule| schedule.borrow_mut().schedule(handle, now, hold));
+        true
+    }
+
     fn pause_video(&self, handle: WindowHandle) {
         WINDOWS.with(|windows| {
             if let Some(WindowType::Video(state)) = windows.borrow().get(&handle)
@@ -288,19 +842,284 @@ impl WindowSpawner {
         });
     }
 
+    fn set_video_volume(&self, handle: WindowHandle, volume: f32) {
+        WINDOWS.with(|windows| {
+            if let Some(WindowType::Video(state)) = windows.borrow().get(&handle)
+                && let Ok(player) = state.player.lock()
+            {
+                player.set_volume(volume);
+            }
+        });
+    }
+
+    fn seek_video(&self, handle: WindowHandle, position: Duration) {
+        WINDOWS.with(|windows| {
+            if let Some(WindowType::Video(state)) = windows.borrow().get(&handle)
+                && let Ok(player) = state.player.lock()
+            {
+                player.seek(position);
+            }
+        });
+    }
+
+    fn set_click_through(&self, handle: WindowHandle, click_through: bool) {
+        WINDOWS.with(|windows| {
+            if let Some(window_type) = windows.borrow().get(&handle) {
+                window_type.set_click_through(click_through);
+            }
+        });
+    }
+
+    fn set_opacity(&self, handle: WindowHandle, opacity: f32) -> Result<(), String> {
+        let found = WINDOWS.with(|windows| {
+            windows
+                .borrow()
+                .get(&handle)
+                .map(|window_type| window_type.set_opacity(opacity))
+                .is_some()
+        });
+        if !found {
+            return Err(format!("Unknown window handle: {}", handle.0));
+        }
+
+        // Replaces a running fade-in, and a fade-out starts from the new opacity
+        FADES.with(|fades| fades.borrow_mut().remove(&handle));
+        FADE_OUTS.with(|fade_outs| {
+            if let Some((_, full)) = fade_outs.borrow_mut().get_mut(&handle) {
+                *full = opacity;
+            }
+        });
+        Ok(())
+    }
+
+    fn shake_window(&self, handle: WindowHandle, duration: Duration, amplitude: u32) {
+        let Some(position) = WINDOWS.with(|windows| {
+            windows
+                .borrow()
+                .get(&handle)
+                .map(|window_type| window_type.window().position())
+        }) else {
+            return;
+        };
+
+        SHAKES.with(|shakes| {
+            let mut shakes = shakes.borrow_mut();
+            // Restarting a shake keeps the position from before the first one
+            let origin = shakes
+                .get(&handle)
+                .map(|shake| shake.origin)
+                .unwrap_or((position.x, position.y));
+            shakes.insert(
+                handle,
+                Shake::new(origin, Instant::now(), duration, amplitude),
+            );
+        });
+    }
+
+    fn update_shakes(&self) {
+        let now = Instant::now();
+        SHAKES.with(|shakes| {
+            shakes.borrow_mut().retain(|handle, shake| {
+                WINDOWS.with(|windows| {
+                    let windows = windows.borrow();
+                    let Some(window_type) = windows.get(handle) else {
+                        return false;
+                    };
+                    let position = shake.position_at(now);
+                    let (x, y) = position.unwrap_or(shake.origin);
+                    window_type
+                        .window()
+                        .set_position(slint::PhysicalPosition::new(x, y));
+                    position.is_some()
+                })
+            });
+        });
+    }
+
+    /// Start a window's fade-in and remember its fade-out for when it closes
+    fn start_fades(&self, handle: WindowHandle, fade: WindowFade, opacity: f32) {
+        if let Some(fade_in) = fade.fade_in.filter(|fade_in| !fade_in.is_zero()) {
+            FADES.with(|fades| {
+                fades
+                    .borrow_mut()
+                    .insert(handle, Fade::new(0.0, opacity, Instant::now(), fade_in));
+            });
+        }
+        if let Some(fade_out) = fade.fade_out.filter(|fade_out| !fade_out.is_zero()) {
+            FADE_OUTS.with(|fade_outs| fade_outs.borrow_mut().insert(handle, (fade_out, opacity)));
+        }
+    }
+
+    fn update_fades(&self) {
+        let now = Instant::now();
+        FADES.with(|fades| {
+            fades.borrow_mut().retain(|handle, fade| {
+                WINDOWS.with(|windows| {
+                    let windows = windows.borrow();
+                    let Some(window_type) = windows.get(handle) else {
+                        return false;
+                    };
+                    let opacity = fade.opacity_at(now);
+                    window_type.set_opacity(opacity.unwrap_or(fade.target()));
+                    opacity.is_some()
+                })
+            });
+        });
+        FADING_OUT.with(|fading| {
+            fading
+                .borrow_mut()
+                .retain(|(window_type, fade)| match fade.opacity_at(now) {
+                    Some(opacity) => {
+                        window_type.set_opacity(opacity);
+                        true
+                    }
+                    None => {
+                        let _ = window_type.hide();
+                        false
+                    }
+                });
+        });
+    }
+
+    fn schedule_close(&self, handle: WindowHandle, timeout: Option<Duration>) {
+        if let Some(timeout) = timeout {
+            CLOSE_SCHEDULE.with(|schedule| {
+                schedule
+                    .borrow_mut()
+                    .schedule(handle, Instant::now(), timeout)
+            });
+        }
+    }
+
+    /// Gate a window behind a click if requested, otherwise schedule its timeout
+    fn schedule_close_or_click(
+        &self,
+        handle: WindowHandle,
+        timeout: Option<Duration>,
+        click_to_close: Option<Duration>,
+    ) {
+        match click_to_close {
+            Some(min_visible) => CLICK_GATES.with(|gates| {
+                gates
+                    .borrow_mut()
+                    .insert(handle, ClickGate::new(Instant::now(), min_visible));
+            }),
+            None => self.schedule_close(handle, timeout),
+        }
+    }
+
+    fn close_clicked_windows(&self) {
+        let clicked = CLICKED_TO_CLOSE.with(|clicked| std::mem::take(&mut *clicked.borrow_mut()));
+        for handle in clicked {
+            self.close_and_divide(handle);
+            let _ = self.response_tx.send(WindowResponse::Closed(handle));
+        }
+    }
+
+    fn close_expired_windows(&self) {
+        let expired =
+            CLOSE_SCHEDULE.with(|schedule| schedule.borrow_mut().take_expired(Instant::now()));
+        for handle in expired {
+            if self.hold_if_too_early(handle) {
+                continue;
+            }
+            self.prompts.finish(handle, PromptOutcome::TimedOut);
+            self.close_and_divide(handle);
+            let _ = self.response_tx.send(WindowResponse::Closed(handle));
+        }
+    }
+
+    /// Close a window the user dismissed or that timed out, replacing it with
+    /// copies if mitosis is on for its kind
+    fn close_and_divide(&self, handle: WindowHandle) {
+        let kind = WINDOWS.with(|windows| match windows.borrow().get(&handle) {
+            Some(WindowType::Image(_)) => Some(PopupKind::Image),
+            Some(WindowType::Video(_)) => Some(PopupKind::Video),
+            _ => None,
+        });
+        let generation = GENERATIONS
+            .with(|generations| generations.borrow().get(&handle).copied())
+            .unwrap_or(0);
+        self.close_window(handle);
+
+        let Some(kind) = kind else {
+            return;
+        };
+        let live_copies = GENERATIONS.with(|generations| generations.borrow().len());
+        let count = mitosis::copies_to_spawn(self.mitosis.get(kind), generation, live_copies);
+        for copy in self.handle.divide(handle, count) {
+            GENERATIONS.with(|generations| generations.borrow_mut().insert(copy, generation + 1));
+        }
+    }
+
     fn close_window(&self, handle: WindowHandle) {
+        GENERATIONS.with(|generations| generations.borrow_mut().remove(&handle));
+        CLOSE_SCHEDULE.with(|schedule| schedule.borrow_mut().cancel(handle));
+        CLICK_GATES.with(|gates| gates.borrow_mut().remove(&handle));
+        MIN_VISIBLE.with(|shown| shown.borrow_mut().forget(handle));
+        // A window still fading in fades out from where it got to
+        let fade_in = FADES.with(|fades| fades.borrow_mut().remove(&handle));
+        let fade_out = FADE_OUTS.with(|fade_outs| fade_outs.borrow_mut().remove(&handle));
         WINDOWS.with(|windows| {
             if let Some(window_type) = windows.borrow_mut().remove(&handle) {
-                let _ = window_type.hide();
+                self.release_slot(&window_type);
+                match fade_out {
+                    Some((duration, opacity)) => {
+                        let now = Instant::now();
+                        let from = fade_in
+                            .and_then(|fade| fade.opacity_at(now))
+                            .unwrap_or(opacity);
+                        let fade = Fade::new(from, 0.0, now, duration);
+                        FADING_OUT.with(|fading| fading.borrow_mut().push((window_type, fade)));
+                    }
+                    None => {
+                        let _ = window_type.hide();
+                    }
+                }
             }
         });
+        self.events.dispatch(handle, WindowEvent::Closed);
+        self.prompts.finish(handle, PromptOutcome::Closed);
+    }
+
+    /// Act on responses sent back by windows
+    fn handle_responses(&self) {
+        while let Ok(response) = self.response_rx.try_recv() {
+            if let WindowResponse::PromptSubmitted { handle, input } = response {
+                self.prompts.submit(handle, input);
+                self.close_window(handle);
+            }
+        }
+    }
+
+    /// Free the popup slot a closed window was holding
+    fn release_slot(&self, window_type: &WindowType) {
+        match window_type {
+            WindowType::Image(_) => self.popup_slots.release(PopupKind::Image),
+            WindowType::Video(_) => self.popup_slots.release(PopupKind::Video),
+            WindowType::WriteLines(_) | WindowType::Overlay(_) => {}
+        }
     }
 
     fn close_all_windows(&self) {
+        CLOSE_SCHEDULE.with(|schedule| *schedule.borrow_mut() = CloseSchedule::new());
+        CLICK_GATES.with(|gates| gates.borrow_mut().clear());
+        MIN_VISIBLE.with(|shown| shown.borrow_mut().clear());
+        // Closing everything is immediate, including windows mid fade-out
+        FADES.with(|fades| fades.borrow_mut().clear());
+        FADE_OUTS.with(|fade_outs| fade_outs.borrow_mut().clear());
+        GENERATIONS.with(|generations| generations.borrow_mut().clear());
+        for (window_type, _) in FADING_OUT.with(|fading| std::mem::take(&mut *fading.borrow_mut()))
+        {
+            let _ = window_type.hide();
+        }
         WINDOWS.with(|windows| {
             let mut windows = windows.borrow_mut();
-            for (_, window_type) in windows.drain() {
+            for (handle, window_type) in windows.drain() {
+                self.release_slot(&window_type);
                 let _ = window_type.hide();
+                self.events.dispatch(handle, WindowEvent::Closed);
+                self.prompts.finish(handle, PromptOutcome::Closed);
             }
         });
     }
@@ -325,3 +1144,347 @@ pub fn run_event_loop(spawner: WindowSpawner) -> Result<()> {
     slint::run_event_loop_until_quit()?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gui::windows::types::WindowLimitReached;
+
+    #[test]
+    fn test_spawns_rejected_when_queue_full() {
+        let (command_tx, command_rx) = channel();
+        let handle = WindowSpawnerHandle::new(command_tx);
+
+        for _ in 0..MAX_PENDING_SPAWNS {
+            handle
+                .spawn_overlay([0.0, 0.0, 0.0, 1.0], None, false, None)
+                .unwrap();
+        }
+        let err = handle
+            .spawn_overlay([0.0, 0.0, 0.0, 1.0], None, false, None)
+            .unwrap_err();
+        assert!(err.is::<SpawnQueueFull>());
+
+        // Closing is never throttled
+        handle.send(WindowCommand::CloseAll).unwrap();
+
+        let queued: Vec<WindowCommand> = command_rx.try_iter().collect();
+        assert_eq!(queued.len(), MAX_PENDING_SPAWNS + 1);
+        assert!(matches!(queued.last(), Some(WindowCommand::CloseAll)));
+    }
+
+    #[test]
+    fn test_prompt_submission_resolves_outcome() {
+        let (handle, spawner) = WindowSpawner::create();
+        let prompt = handle
+            .spawn_write_lines(
+                "I will obey".to_string(),
+                32.0,
+                [1.0; 4],
+                [0.0; 4],
+                "left".to_string(),
+                None,
+                true,
+                None,
+            )
+            .unwrap();
+        let mut outcome = handle.prompts.wait(prompt).unwrap();
+
+        // What the write_lines window sends once the text matches
+        spawner
+            .response_tx
+            .send(WindowResponse::PromptSubmitted {
+                handle: prompt,
+                input: "I will obey".to_string(),
+            })
+            .unwrap();
+        spawner.handle_responses();
+
+        let result = outcome.try_recv().unwrap();
+        assert_eq!(result.outcome, PromptOutcome::Submitted);
+        assert_eq!(result.input.as_deref(), Some("I will obey"));
+    }
+
+    #[test]
+    fn test_set_opacity_is_clamped_and_reports_unknown_windows() {
+        let (command_tx, command_rx) = channel();
+        let handle = WindowSpawnerHandle::new(command_tx);
+        // Stand in for the UI thread, which has no windows open
+        let ui = std::thread::spawn(move || match command_rx.recv() {
+            Ok(WindowCommand::SetOpacity(window, opacity, tx)) => {
+                let _ = tx.send(Err(format!("Unknown window handle: {}", window.0)));
+                opacity
+            }
+            other => panic!("expected SetOpacity, got {:?}", other),
+        });
+
+        let err = handle
+            .set_opacity(WindowHandle(uuid::Uuid::new_v4()), 1.5)
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Unknown window handle"));
+        assert_eq!(ui.join().unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_popups_beyond_max_are_refused() {
+        let (command_tx, command_rx) = channel();
+        let handle = WindowSpawnerHandle::new(command_tx);
+        handle.set_max_popups(PopupKind::Image, 2);
+
+        let spawn_image = || {
+            handle.spawn_image(
+                "image.png".into(),
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                AutoLayout::default(),
+                None,
+                WindowFade::default(),
+                false,
+            )
+        };
+        spawn_image().unwrap();
+        spawn_image().unwrap();
+        let err = spawn_image().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<WindowLimitReached>(),
+            Some(&WindowLimitReached {
+                kind: PopupKind::Image,
+                max: 2
+            })
+        );
+
+        // Other window types aren't held back by the image cap
+        handle
+            .spawn_video(
+                "video.mp4".into(),
+                None,
+                None,
+                None,
+                None,
+                false,
+                1.0,
+                AutoLayout::default(),
+                None,
+                None,
+                WindowFade::default(),
+                false,
+            )
+            .unwrap();
+        assert_eq!(command_rx.try_iter().count(), 3);
+    }
+
+    #[test]
+    fn test_mitosis_spawns_copies_of_closed_window() {
+        let (command_tx, command_rx) = channel();
+        let handle = WindowSpawnerHandle::new(command_tx);
+        handle.set_mitosis(PopupKind::Image, 2);
+        handle.set_max_popups(PopupKind::Image, 4);
+
+        let original = handle
+            .spawn_image(
+                "image.png".into(),
+                None,
+                None,
+                None,
+                None,
+                Some(Duration::from_secs(3)),
+                None,
+                AutoLayout::default(),
+                None,
+                WindowFade::default(),
+                false,
+            )
+            .unwrap();
+        let count = mitosis::copies_to_spawn(handle.mitosis.get(PopupKind::Image), 0, 0);
+        let copies = handle.divide(original, count);
+        assert_eq!(copies.len(), 2);
+
+        let commands: Vec<WindowCommand> = command_rx.try_iter().collect();
+        assert_eq!(commands.len(), 3);
+        for (command, copy) in commands[1..].iter().zip(&copies) {
+            match command {
+                WindowCommand::SpawnImage { handle, path, .. } => {
+                    assert_eq!(handle, copy);
+                    assert_eq!(path, &std::path::PathBuf::from("image.png"));
+                }
+                other => panic!("expected SpawnImage, got {:?}", other),
+            }
+        }
+
+        // Copies stop at the image cap, the original still holds its slot here
+        assert_eq!(handle.divide(original, 5).len(), 1);
+    }
+
+    #[test]
+    fn test_replay_closed_image() {
+        let (command_tx, command_rx) = channel();
+        let handle = WindowSpawnerHandle::new(command_tx);
+
+        let original = handle
+            .spawn_image(
+                "image.png".into(),
+                None,
+                None,
+                None,
+                None,
+                Some(Duration::from_secs(3)),
+                None,
+                AutoLayout::default(),
+                None,
+                WindowFade::default(),
+                false,
+            )
+            .unwrap();
+        handle.close_window(original).unwrap();
+
+        let replayed = handle.replay(original).unwrap();
+        assert_ne!(replayed, original);
+        assert!(handle.replay(WindowHandle(uuid::Uuid::new_v4())).is_err());
+
+        let commands: Vec<WindowCommand> = command_rx.try_iter().collect();
+        assert_eq!(commands.len(), 3);
+        match &commands[2] {
+            WindowCommand::SpawnImage {
+                handle,
+                path,
+                timeout,
+                ..
+            } => {
+                assert_eq!(*handle, replayed);
+                assert_eq!(path, &std::path::PathBuf::from("image.png"));
+                assert_eq!(*timeout, Some(Duration::from_secs(3)));
+            }
+            other => panic!("expected SpawnImage, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_popup_timeouts_from_settings() {
+        let (command_tx, command_rx) = channel();
+        let handle = WindowSpawnerHandle::new(command_tx).with_popup_defaults(PopupDefaults {
+            image_timeout: Some(Duration::from_millis(50)),
+            video_timeout: Some(Duration::from_secs(15)),
+            ..Default::default()
+        });
+
+        let spawn_image = |timeout| {
+            handle
+                .spawn_image(
+                    "image.png".into(),
+                    None,
+                    None,
+                    None,
+                    None,
+                    timeout,
+                    None,
+                    AutoLayout::default(),
+                    None,
+                    WindowFade::default(),
+                    false,
+                )
+                .unwrap();
+            match command_rx.try_recv().unwrap() {
+                WindowCommand::SpawnImage { timeout, .. } => timeout,
+                other => panic!("expected SpawnImage, got {:?}", other),
+            }
+        };
+        assert_eq!(spawn_image(None), Some(Duration::from_millis(50)));
+        // A duration passed by the script wins
+        assert_eq!(
+            spawn_image(Some(Duration::from_secs(3))),
+            Some(Duration::from_secs(3))
+        );
+
+        handle
+            .spawn_video(
+                "video.mp4".into(),
+                None,
+                None,
+                None,
+                None,
+                false,
+                1.0,
+                AutoLayout::default(),
+                None,
+                None,
+                WindowFade::default(),
+                false,
+            )
+            .unwrap();
+        match command_rx.try_recv().unwrap() {
+            WindowCommand::SpawnVideo { timeout, .. } => {
+                assert_eq!(timeout, Some(Duration::from_secs(15)));
+            }
+            other => panic!("expected SpawnVideo, got {:?}", other),
+        }
+
+        // The scheduled close of a 50ms window comes due shortly after it opens
+        let mut schedule = CloseSchedule::new();
+        let opened = Instant::now();
+        let image = WindowHandle(uuid::Uuid::new_v4());
+        schedule.schedule(image, opened, Duration::from_millis(50));
+        assert!(schedule.take_expired(opened).is_empty());
+        assert_eq!(
+            schedule.take_expired(opened + Duration::from_millis(60)),
+            vec![image]
+        );
+    }
+
+    #[test]
+    fn test_popup_defaults_fill_missing_options() {
+        let (command_tx, command_rx) = channel();
+        let handle = WindowSpawnerHandle::new(command_tx).with_popup_defaults(PopupDefaults {
+            opacity: 0.6,
+            always_on_top: false,
+            ..Default::default()
+        });
+
+        let spawn = |opacity, always_on_top| {
+            handle
+                .spawn_image(
+                    "image.png".into(),
+                    None,
+                    None,
+                    opacity,
+                    always_on_top,
+                    None,
+                    None,
+                    AutoLayout::default(),
+                    None,
+                    WindowFade::default(),
+                    false,
+                )
+                .unwrap();
+            command_rx.try_recv().unwrap()
+        };
+
+        match spawn(None, None) {
+            WindowCommand::SpawnImage {
+                opacity,
+                always_on_top,
+                ..
+            } => {
+                assert_eq!(opacity, 0.6);
+                assert!(!always_on_top);
+            }
+            other => panic!("expected SpawnImage, got {:?}", other),
+        }
+
+        // Per-call options still win
+        match spawn(Some(1.0), Some(true)) {
+            WindowCommand::SpawnImage {
+                opacity,
+                always_on_top,
+                ..
+            } => {
+                assert_eq!(opacity, 1.0);
+                assert!(always_on_top);
+            }
+            other => panic!("expected SpawnImage, got {:?}", other),
+        }
+    }
+}
@@ -1,10 +1,15 @@
+use super::fade::WindowFade;
+use super::layout::AutoLayout;
+use crate::media::image::color_key::ColorKey;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::path::PathBuf;
+use std::time::Duration;
 use ts_rs::TS;
 use uuid::Uuid;
 
 /// Screen position coordinates
-#[derive(Deserialize, Debug, Clone, TS)]
+#[derive(Deserialize, Debug, Clone, TS, JsonSchema)]
 pub struct Position {
     /// X coordinate in pixels from the left edge of the screen
     pub x: i32,
@@ -13,7 +18,7 @@ pub struct Position {
 }
 
 /// Window or element dimensions
-#[derive(Deserialize, Debug, Clone, TS)]
+#[derive(Deserialize, Debug, Clone, TS, JsonSchema)]
 pub struct Size {
     /// Width in pixels
     pub width: u32,
@@ -22,12 +27,12 @@ pub struct Size {
 }
 
 /// Common window configuration options
-#[derive(Deserialize, Debug, Default, Clone, TS)]
+#[derive(Deserialize, Debug, Default, Clone, TS, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct WindowOptions {
     /// Window opacity from 0.0 (transparent) to 1.0 (opaque)
     pub opacity: Option<f32>,
-    /// Initial window position on screen
+    /// Initial window position on screen, relative to `monitor` when one is chosen
     pub position: Option<Position>,
     /// Initial window size
     pub size: Option<Size>,
@@ -35,6 +40,36 @@ pub struct WindowOptions {
     pub always_on_top: Option<bool>,
     /// Whether to show window decorations (title bar, borders)
     pub decorations: Option<bool>,
+    /// Milliseconds to fade the window in from transparent when it opens
+    pub fade_in_ms: Option<u64>,
+    /// Milliseconds to fade the window out before it closes
+    pub fade_out_ms: Option<u64>,
+    /// Index of the display to show the window on, see `system.getMonitors()`
+    pub monitor: Option<usize>,
+    /// Show the window on a random display, ignored when `monitor` is set
+    pub random_monitor: Option<bool>,
+}
+
+/// Opacity, stacking and timeouts used for image and video popups when a call doesn't set them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PopupDefaults {
+    pub opacity: f32,
+    pub always_on_top: bool,
+    /// How long image popups stay up, `None` keeps them until closed
+    pub image_timeout: Option<Duration>,
+    /// How long video popups stay up, `None` keeps them until closed
+    pub video_timeout: Option<Duration>,
+}
+
+impl Default for PopupDefaults {
+    fn default() -> Self {
+        Self {
+            opacity: 1.0,
+            always_on_top: true,
+            image_timeout: None,
+            video_timeout: None,
+        }
+    }
 }
 
 /// Unique identifier for a window.
@@ -55,6 +90,11 @@ pub struct WindowInfo {
 pub enum WindowCommand {
     /// Get list of active windows
     GetActiveWindows(std::sync::mpsc::Sender<Vec<WindowInfo>>),
+    /// Get the time left before a window auto-closes (`None` if untimed, error if unknown)
+    GetRemainingTime(
+        WindowHandle,
+        std::sync::mpsc::Sender<Result<Option<Duration>, String>>,
+    ),
     /// Spawn a new write_lines window
     SpawnWriteLines {
         handle: WindowHandle,
@@ -64,6 +104,11 @@ pub enum WindowCommand {
         background_color: [f32; 4],
         alignment: String,
         window_options: Option<WindowOptions>,
+        /// Whether the user has to type the text back, otherwise it is only shown
+        interactive: bool,
+        /// Whether input must match `text` to be accepted, otherwise any non-empty answer is
+        exact_match: bool,
+        timeout: Option<Duration>,
     },
     /// Spawn a new image window
     SpawnImage {
@@ -72,6 +117,15 @@ pub enum WindowCommand {
         width: Option<u32>,
         height: Option<u32>,
         opacity: f32,
+        always_on_top: bool,
+        timeout: Option<Duration>,
+        /// Close only on a click after this long, ignoring `timeout`
+        click_to_close: Option<Duration>,
+        layout: AutoLayout,
+        color_key: Option<ColorKey>,
+        fade: WindowFade,
+        /// Let clicks pass through to the windows below
+        click_through: bool,
     },
     /// Spawn a new video window
     SpawnVideo {
@@ -80,19 +134,154 @@ pub enum WindowCommand {
         width: Option<u32>,
         height: Option<u32>,
         opacity: f32,
+        always_on_top: bool,
         loop_playback: bool,
         volume: f32,
+        /// Where to place the window, videos keep their own size
+        layout: AutoLayout,
+        timeout: Option<Duration>,
+        /// Close only on a click after this long, ignoring `timeout`
+        click_to_close: Option<Duration>,
+        fade: WindowFade,
+        /// Let clicks pass through to the windows below
+        click_through: bool,
+    },
+    /// Spawn a full-screen color overlay
+    SpawnOverlay {
+        handle: WindowHandle,
+        /// RGBA fill color, alpha is the final opacity
+        color: [f32; 4],
+        fade_in: Option<Duration>,
+        click_through: bool,
+        timeout: Option<Duration>,
+    },
+    /// Oscillate a window around its position, then put it back
+    ShakeWindow {
+        handle: WindowHandle,
+        duration: Duration,
+        amplitude: u32,
     },
+    /// Let clicks pass through a window, or catch them again (ignored for prompts)
+    SetClickThrough(WindowHandle, bool),
+    /// Change the opacity a window is rendered with (ignored for prompts), error if unknown
+    SetOpacity(
+        WindowHandle,
+        f32,
+        std::sync::mpsc::Sender<Result<(), String>>,
+    ),
     /// Pause a video
     PauseVideo(WindowHandle),
     /// Resume a video
     ResumeVideo(WindowHandle),
+    /// Change the audio volume of a video
+    SetVideoVolume(WindowHandle, f32),
+    /// Jump to a position in a video
+    SeekVideo(WindowHandle, Duration),
     /// Close a specific window
     CloseWindow(WindowHandle),
     /// Close all windows
     CloseAll,
 }
 
+impl WindowCommand {
+    /// Whether this command opens a new window
+    pub fn is_spawn(&self) -> bool {
+        self.spawn_handle().is_some()
+    }
+
+    /// Handle of the window this command opens
+    pub fn spawn_handle(&self) -> Option<WindowHandle> {
+        match self {
+            WindowCommand::SpawnWriteLines { handle, .. }
+            | WindowCommand::SpawnImage { handle, .. }
+            | WindowCommand::SpawnVideo { handle, .. }
+            | WindowCommand::SpawnOverlay { handle, .. } => Some(*handle),
+            _ => None,
+        }
+    }
+
+    /// Capped popup type this command opens
+    pub fn popup_kind(&self) -> Option<PopupKind> {
+        match self {
+            WindowCommand::SpawnImage { .. } => Some(PopupKind::Image),
+            WindowCommand::SpawnVideo { .. } => Some(PopupKind::Video),
+            _ => None,
+        }
+    }
+
+    /// The same spawn for a new window
+    pub fn with_spawn_handle(mut self, new_handle: WindowHandle) -> Self {
+        if let WindowCommand::SpawnWriteLines { handle, .. }
+        | WindowCommand::SpawnImage { handle, .. }
+        | WindowCommand::SpawnVideo { handle, .. }
+        | WindowCommand::SpawnOverlay { handle, .. } = &mut self
+        {
+            *handle = new_handle;
+        }
+        self
+    }
+}
+
+/// A spawn was rejected because too many are still waiting for the UI thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpawnQueueFull;
+
+impl std::fmt::Display for SpawnQueueFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Too many windows are waiting to open, wait before spawning more"
+        )
+    }
+}
+
+impl std::error::Error for SpawnQueueFull {}
+
+/// Popup types with a cap on how many may be open at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PopupKind {
+    Image,
+    Video,
+}
+
+impl PopupKind {
+    /// Position of this kind in per-kind arrays
+    pub(crate) fn index(self) -> usize {
+        match self {
+            PopupKind::Image => 0,
+            PopupKind::Video => 1,
+        }
+    }
+}
+
+impl std::fmt::Display for PopupKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PopupKind::Image => write!(f, "image"),
+            PopupKind::Video => write!(f, "video"),
+        }
+    }
+}
+
+/// A spawn was rejected because the most windows of its kind are already open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowLimitReached {
+    pub kind: PopupKind,
+    pub max: usize,
+}
+
+impl std::fmt::Display for WindowLimitReached {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Too many {} windows are open (max {}), close one before spawning more",
+            self.kind, self.max
+        )
+    }
+}
+
+impl std::error::Error for WindowLimitReached {}
+
 /// Response from window operations
 #[derive(Debug, Clone)]
 pub enum WindowResponse {
@@ -1,4 +1,5 @@
 use super::VideoWindow;
+use super::layout::{AutoLayout, layout_monitor};
 use super::types::WindowHandle;
 use crate::media::video::player::{self, Player, Rescaler};
 use anyhow::Result;
@@ -14,12 +15,17 @@ pub struct VideoState {
     pub player: Arc<Mutex<Player>>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn(
     handle: WindowHandle,
     path: &std::path::Path,
     width: Option<u32>,
     height: Option<u32>,
     opacity: f32,
+    always_on_top: bool,
+    loop_playback: bool,
+    volume: f32,
+    layout: AutoLayout,
 ) -> Result<VideoState> {
     let window = VideoWindow::new()?;
     let window = Rc::new(window);
@@ -40,6 +46,8 @@ pub fn spawn(
     let window_weak = window.as_weak();
     let player = Player::start(
         path.to_path_buf(),
+        loop_playback,
+        volume,
         move |new_frame| {
             // Rebuild rescaler if format changed
             let rebuild_rescaler = to_rgb_rescaler.as_ref().is_none_or(|existing_rescaler| {
@@ -81,11 +89,27 @@ pub fn spawn(
         if let Some(window) = window_weak.upgrade()
             && let Ok(winit_window) = window.window().winit_window().await
         {
-            winit_window
-                .set_window_level(i_slint_backend_winit::winit::window::WindowLevel::AlwaysOnTop);
+            if always_on_top {
+                winit_window.set_window_level(
+                    i_slint_backend_winit::winit::window::WindowLevel::AlwaysOnTop,
+                );
+            }
             winit_window.set_resizable(false);
             winit_window.set_decorations(false);
             winit_window.set_window_icon(None);
+
+            // Videos keep their size, only the position is laid out
+            if layout.is_active()
+                && let Some((rect, _)) = layout_monitor(&winit_window, &layout)
+            {
+                let size = winit_window.outer_size();
+                let placement = layout.place(&rect, (size.width, size.height));
+                if let Some((x, y)) = placement.position {
+                    winit_window.set_outer_position(
+                        i_slint_backend_winit::winit::dpi::PhysicalPosition::new(x, y),
+                    );
+                }
+            }
         }
     });
 
@@ -7,6 +7,16 @@ use std::rc::Rc;
 use std::sync::mpsc::Sender;
 use tracing::{debug, info};
 
+/// Whether a prompt accepts `input`: the exact text for write-lines drills,
+/// or any non-empty answer to an open question
+fn accepts(input: &str, expected: &str, exact_match: bool) -> bool {
+    if exact_match {
+        input == expected
+    } else {
+        !input.trim().is_empty()
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn spawn(
     handle: WindowHandle,
@@ -16,6 +26,8 @@ pub fn spawn(
     background_color: [f32; 4],
     alignment: &str,
     window_options: Option<WindowOptions>,
+    interactive: bool,
+    exact_match: bool,
     response_tx: Sender<WindowResponse>,
 ) -> Result<Rc<WriteLinesWindow>> {
     let window = WriteLinesWindow::new()?;
@@ -37,6 +49,7 @@ pub fn spawn(
         (background_color[2] * 255.0) as u8,
     ));
     window.set_alignment(alignment.into());
+    window.set_interactive(interactive);
 
     // Set up input submission callback
     let expected_text = text.to_string();
@@ -47,7 +60,7 @@ pub fn spawn(
         info!("Prompt submitted: {}", input_str);
 
         if let Some(w) = window_weak.upgrade() {
-            if input_str == expected_text {
+            if accepts(&input_str, &expected_text, exact_match) {
                 let _ = response_tx.send(WindowResponse::PromptSubmitted {
                     handle: window_handle,
                     input: input_str,
@@ -61,10 +74,12 @@ pub fn spawn(
         }
     });
 
-    // Prevent closing via OS controls
-    window
-        .window()
-        .on_close_requested(|| slint::CloseRequestResponse::KeepWindowShown);
+    // Prevent closing via OS controls until the text has been typed
+    if interactive {
+        window
+            .window()
+            .on_close_requested(|| slint::CloseRequestResponse::KeepWindowShown);
+    }
 
     // Show window
     window.show()?;
@@ -76,8 +91,10 @@ pub fn spawn(
         if let Some(window) = window_weak.upgrade()
             && let Ok(winit_window) = window.window().winit_window().await
         {
-            winit_window.set_ime_allowed(true);
-            winit_window.focus_window();
+            if interactive {
+                winit_window.set_ime_allowed(true);
+                winit_window.focus_window();
+            }
 
             if let Some(opts) = options {
                 if let Some(always_on_top) = opts.always_on_top {
@@ -119,8 +136,23 @@ pub fn spawn(
     });
 
     // Request focus on the text input
-    window.invoke_grab_focus();
+    if interactive {
+        window.invoke_grab_focus();
+    }
 
     debug!("Spawned write_lines window: {:?}", handle);
     Ok(window)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts() {
+        assert!(accepts("I will obey", "I will obey", true));
+        assert!(!accepts("I will", "I will obey", true));
+        assert!(accepts("Blue", "What's your favourite colour?", false));
+        assert!(!accepts("  ", "What's your favourite colour?", false));
+    }
+}
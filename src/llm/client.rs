@@ -1,20 +1,87 @@
-use crate::config::settings::LLMSettings;
-use anyhow::Result;
+use crate::config::settings::{LLMProviderKind, LLMSettings};
+use crate::llm::openai::OpenAiCompatClient;
+use crate::llm::retry::Backoff;
+use anyhow::{Result, anyhow};
+use futures::StreamExt;
+use futures::stream::BoxStream;
 use ollama_rs::{
     Ollama,
     generation::chat::{ChatMessage, request::ChatMessageRequest},
+    models::ModelOptions,
 };
+use std::future::Future;
 use tracing::{debug, info};
 use url::Url;
 
-#[allow(dead_code)]
-pub struct LLMClient {
+/// Chunks of a reply as they are generated
+pub type ChunkStream = BoxStream<'static, Result<String>>;
+
+/// Sampling settings sent with every request, the server's defaults are used
+/// for any left unset
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GenerationOptions {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<u32>,
+    pub seed: Option<i32>,
+}
+
+impl From<&LLMSettings> for GenerationOptions {
+    fn from(settings: &LLMSettings) -> Self {
+        Self {
+            temperature: settings.temperature,
+            top_p: settings.top_p,
+            max_tokens: settings.max_tokens,
+            seed: settings.seed,
+        }
+    }
+}
+
+impl GenerationOptions {
+    /// Ollama model options, `None` when nothing is set so requests stay unchanged
+    fn model_options(&self) -> Option<ModelOptions> {
+        if *self == Self::default() {
+            return None;
+        }
+        let mut options = ModelOptions::default();
+        if let Some(temperature) = self.temperature {
+            options = options.temperature(temperature);
+        }
+        if let Some(top_p) = self.top_p {
+            options = options.top_p(top_p);
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            options = options.num_predict(i32::try_from(max_tokens).unwrap_or(i32::MAX));
+        }
+        if let Some(seed) = self.seed {
+            options = options.seed(seed);
+        }
+        Some(options)
+    }
+}
+
+/// A chat backend the loop can send prompts to
+pub trait LLMProvider {
+    /// Send the conversation and return the assistant's reply
+    fn chat(&self, messages: Vec<ChatMessage>) -> impl Future<Output = Result<String>> + Send;
+
+    /// Send the conversation and stream the reply as it is generated.
+    ///
+    /// Dropping the stream abandons the rest of the reply.
+    fn chat_stream(
+        &self,
+        messages: Vec<ChatMessage>,
+    ) -> impl Future<Output = Result<ChunkStream>> + Send;
+}
+
+/// Talks to an Ollama server through `ollama-rs`
+pub struct OllamaClient {
     client: Ollama,
     model: String,
+    options: GenerationOptions,
 }
 
-impl LLMClient {
-    #[allow(dead_code)]
+impl OllamaClient {
     pub fn new(settings: &LLMSettings, model: &str) -> Self {
         let url = Url::parse(&settings.host).unwrap_or_else(|_| {
             eprintln!(
@@ -31,16 +98,110 @@ impl LLMClient {
         );
         let port = url.port().unwrap_or(11434);
 
-        let client = Ollama::new(host, port);
+        Self {
+            client: Ollama::new(host, port),
+            model: model.to_string(),
+            options: GenerationOptions::from(settings),
+        }
+    }
+
+    fn request(&self, messages: Vec<ChatMessage>) -> ChatMessageRequest {
+        let request = ChatMessageRequest::new(self.model.clone(), messages);
+        match self.options.model_options() {
+            Some(options) => request.options(options),
+            None => request,
+        }
+    }
+
+    pub async fn health_check(&self) -> Result<bool> {
+        // Simple check, maybe list models
+        let _models = self.client.list_local_models().await?;
+        Ok(true)
+    }
+}
+
+impl LLMProvider for OllamaClient {
+    async fn chat(&self, messages: Vec<ChatMessage>) -> Result<String> {
+        let request = self.request(messages);
+        let response = self.client.send_chat_messages(request).await?;
+        Ok(response.message.content)
+    }
+
+    async fn chat_stream(&self, messages: Vec<ChatMessage>) -> Result<ChunkStream> {
+        let request = self.request(messages);
+        let stream = self.client.send_chat_messages_stream(request).await?;
+        Ok(stream
+            .map(|response| {
+                response
+                    .map(|response| response.message.content)
+                    .map_err(|_| anyhow!("Ollama response stream failed"))
+            })
+            .boxed())
+    }
+}
+
+enum Backend {
+    Ollama(OllamaClient),
+    OpenAi(OpenAiCompatClient),
+}
+
+/// The client the loop uses, backed by the provider chosen in the settings
+pub struct LLMClient {
+    backend: Backend,
+    host: String,
+    model: String,
+    /// Spaces out requests while the server can't be reached
+    backoff: Backoff,
+}
+
+impl LLMClient {
+    pub fn new(settings: &LLMSettings, model: &str) -> Self {
+        let (backend, host) = match settings.provider {
+            LLMProviderKind::Ollama => {
+                let client = OllamaClient::new(settings, model);
+                let host = client.client.url_str().to_string();
+                (Backend::Ollama(client), host)
+            }
+            LLMProviderKind::OpenAi => {
+                let client = OpenAiCompatClient::new(
+                    &settings.host,
+                    settings.api_key.as_deref(),
+                    model,
+                    GenerationOptions::from(settings),
+                );
+                let host = client.base_url().to_string();
+                (Backend::OpenAi(client), host)
+            }
+        };
 
         Self {
-            client,
+            backend,
+            host,
             model: model.to_string(),
+            backoff: Backoff::default(),
         }
     }
 
+    /// The base URL of the LLM server this client talks to
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
     #[allow(dead_code)]
-    pub async fn chat(&self, messages: Vec<ChatMessage>) -> Result<String> {
+    pub async fn health_check(&self) -> Result<bool> {
+        match &self.backend {
+            Backend::Ollama(client) => client.health_check().await,
+            Backend::OpenAi(client) => client.health_check().await,
+        }
+    }
+}
+
+impl LLMProvider for LLMClient {
+    async fn chat(&self, messages: Vec<ChatMessage>) -> Result<String> {
         info!(
             "Sending chat request to model: {} with {} messages",
             self.model,
@@ -48,22 +209,81 @@ impl LLMClient {
         );
         debug!("Messages: {:?}", messages);
 
-        let request = ChatMessageRequest::new(self.model.clone(), messages);
-        let response = self.client.send_chat_messages(request).await?;
+        let content = self
+            .backoff
+            .retry(|| {
+                let messages = messages.clone();
+                async move {
+                    match &self.backend {
+                        Backend::Ollama(client) => client.chat(messages).await,
+                        Backend::OpenAi(client) => client.chat(messages).await,
+                    }
+                }
+            })
+            .await?;
+
+        info!("Received response from LLM ({} chars)", content.len());
+        debug!("Response content: {}", content);
+
+        Ok(content)
+    }
 
+    async fn chat_stream(&self, messages: Vec<ChatMessage>) -> Result<ChunkStream> {
         info!(
-            "Received response from LLM ({} chars)",
-            response.message.content.len()
+            "Streaming chat request to model: {} with {} messages",
+            self.model,
+            messages.len()
         );
-        debug!("Response content: {}", response.message.content);
+        debug!("Messages: {:?}", messages);
 
-        Ok(response.message.content)
+        // Only starting the reply is retried, a stream that breaks off is not
+        self.backoff
+            .retry(|| {
+                let messages = messages.clone();
+                async move {
+                    match &self.backend {
+                        Backend::Ollama(client) => client.chat_stream(messages).await,
+                        Backend::OpenAi(client) => client.chat_stream(messages).await,
+                    }
+                }
+            })
+            .await
     }
+}
 
-    #[allow(dead_code)]
-    pub async fn health_check(&self) -> Result<bool> {
-        // Simple check, maybe list models
-        let _models = self.client.list_local_models().await?;
-        Ok(true)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generation_options_from_settings() {
+        let settings: LLMSettings = toml::from_str(
+            r#"
+host = "http://localhost:11434"
+temperature = 0.2
+top_p = 0.9
+max_tokens = 512
+seed = 7
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            GenerationOptions::from(&settings),
+            GenerationOptions {
+                temperature: Some(0.2),
+                top_p: Some(0.9),
+                max_tokens: Some(512),
+                seed: Some(7),
+            }
+        );
+        assert!(GenerationOptions::from(&settings).model_options().is_some());
+
+        // Without any set, requests go out as before
+        let settings: LLMSettings = toml::from_str(r#"host = "http://localhost:11434""#).unwrap();
+        assert_eq!(
+            GenerationOptions::from(&settings),
+            GenerationOptions::default()
+        );
+        assert!(GenerationOptions::default().model_options().is_none());
     }
 }
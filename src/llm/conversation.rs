@@ -22,15 +22,20 @@ impl ConversationManager {
         }
     }
 
-    #[allow(dead_code)]
+    /// Most messages kept, user and assistant turns alike
+    pub fn capacity(&self) -> usize {
+        self.max_history
+    }
+
+    /// Append a message, dropping the oldest ones past the capacity
     pub fn add_message(&mut self, role: &str, content: &str) {
-        if self.history.len() >= self.max_history {
-            self.history.pop_front();
-        }
         self.history.push_back(Message {
             role: role.to_string(),
             content: content.to_string(),
         });
+        while self.history.len() > self.max_history {
+            self.history.pop_front();
+        }
     }
 
     #[allow(dead_code)]
@@ -63,4 +68,25 @@ mod tests {
         assert_eq!(mgr.get_history()[0].content, "2");
         assert_eq!(mgr.get_history()[2].content, "4");
     }
+
+    #[test]
+    fn test_history_never_exceeds_capacity() {
+        let mut mgr = ConversationManager::new(4);
+        assert_eq!(mgr.capacity(), 4);
+        for i in 0..10 {
+            let role = if i % 2 == 0 { "user" } else { "assistant" };
+            mgr.add_message(role, &i.to_string());
+            assert!(mgr.get_history().len() <= mgr.capacity());
+        }
+        let kept: Vec<&str> = mgr
+            .get_history()
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(kept, ["6", "7", "8", "9"]);
+
+        let mut empty = ConversationManager::new(0);
+        empty.add_message("user", "dropped");
+        assert!(empty.get_history().is_empty());
+    }
 }
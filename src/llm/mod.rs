@@ -1,3 +1,5 @@
 pub mod client;
 pub mod conversation;
+pub mod openai;
 pub mod prompt;
+pub mod retry;
@@ -0,0 +1,325 @@
+//! Client for OpenAI-compatible chat servers
+//!
+//! vLLM, LM Studio, llama.cpp server and OpenRouter all accept the
+//! `/v1/chat/completions` request format, so one client covers them.
+
+use crate::llm::client::{ChunkStream, GenerationOptions, LLMProvider};
+use anyhow::{Context, Result};
+use futures::{StreamExt, TryStreamExt, stream};
+use ollama_rs::generation::chat::{ChatMessage, MessageRole};
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+
+pub struct OpenAiCompatClient {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    options: GenerationOptions,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<RequestMessage<'a>>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i32>,
+}
+
+#[derive(Serialize)]
+struct RequestMessage<'a> {
+    role: &'static str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct ResponseMessage {
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StreamChunk {
+    choices: Vec<StreamChoice>,
+}
+
+#[derive(Deserialize)]
+struct StreamChoice {
+    delta: ResponseMessage,
+}
+
+/// Splits a server-sent event stream into the content deltas of its chunks
+#[derive(Default)]
+struct SseDecoder {
+    buffer: Vec<u8>,
+}
+
+impl SseDecoder {
+    /// Feed bytes from the response, returning the text of every complete event.
+    ///
+    /// Lines are only decoded once complete, so characters split across
+    /// network chunks come out whole.
+    fn push(&mut self, bytes: &[u8]) -> Result<Vec<String>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut deltas = Vec::new();
+        while let Some(newline) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else {
+                continue;
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                continue;
+            }
+            let chunk: StreamChunk =
+                serde_json::from_str(data).context("Unexpected chat completion chunk")?;
+            if let Some(content) = chunk
+                .choices
+                .into_iter()
+                .next()
+                .and_then(|choice| choice.delta.content)
+                && !content.is_empty()
+            {
+                deltas.push(content);
+            }
+        }
+        Ok(deltas)
+    }
+}
+
+fn role_name(role: &MessageRole) -> &'static str {
+    match role {
+        MessageRole::User => "user",
+        MessageRole::Assistant => "assistant",
+        MessageRole::System => "system",
+        MessageRole::Tool => "tool",
+    }
+}
+
+impl OpenAiCompatClient {
+    /// `base_url` may include the `/v1` suffix or not, e.g. `http://localhost:8000`
+    /// or `https://openrouter.ai/api/v1`
+    pub fn new(
+        base_url: &str,
+        api_key: Option<&str>,
+        model: &str,
+        options: GenerationOptions,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.filter(|key| !key.is_empty()).map(str::to_string),
+            model: model.to_string(),
+            options,
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Full URL of an API path such as `chat/completions`
+    fn endpoint(&self, path: &str) -> String {
+        if self.base_url.ends_with("/v1") {
+            format!("{}/{}", self.base_url, path)
+        } else {
+            format!("{}/v1/{}", self.base_url, path)
+        }
+    }
+
+    fn request_body(&self, messages: &[ChatMessage], stream: bool) -> Result<Vec<u8>> {
+        let request = ChatRequest {
+            model: &self.model,
+            messages: messages
+                .iter()
+                .map(|m| RequestMessage {
+                    role: role_name(&m.role),
+                    content: &m.content,
+                })
+                .collect(),
+            stream,
+            temperature: self.options.temperature,
+            top_p: self.options.top_p,
+            max_tokens: self.options.max_tokens,
+            seed: self.options.seed,
+        };
+        Ok(serde_json::to_vec(&request)?)
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    async fn send(&self, messages: &[ChatMessage], stream: bool) -> Result<reqwest::Response> {
+        let body = self.request_body(messages, stream)?;
+        Ok(self
+            .authorize(self.client.post(self.endpoint("chat/completions")))
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await?
+            .error_for_status()?)
+    }
+
+    pub async fn health_check(&self) -> Result<bool> {
+        let response = self
+            .authorize(self.client.get(self.endpoint("models")))
+            .send()
+            .await?;
+        Ok(response.status().is_success())
+    }
+}
+
+fn parse_response(body: &[u8]) -> Result<String> {
+    let response: ChatResponse =
+        serde_json::from_slice(body).context("Unexpected chat completion response")?;
+    response
+        .choices
+        .into_iter()
+        .next()
+        .context("Chat completion response has no choices")
+        .map(|choice| choice.message.content.unwrap_or_default())
+}
+
+impl LLMProvider for OpenAiCompatClient {
+    async fn chat(&self, messages: Vec<ChatMessage>) -> Result<String> {
+        let response = self.send(&messages, false).await?;
+        parse_response(&response.bytes().await?)
+    }
+
+    async fn chat_stream(&self, messages: Vec<ChatMessage>) -> Result<ChunkStream> {
+        let response = self.send(&messages, true).await?;
+        let mut decoder = SseDecoder::default();
+        Ok(response
+            .bytes_stream()
+            .map(move |bytes| decoder.push(&bytes?))
+            .map_ok(|deltas| stream::iter(deltas.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_with_and_without_version() {
+        let local = OpenAiCompatClient::new(
+            "http://localhost:8000/",
+            None,
+            "qwen",
+            GenerationOptions::default(),
+        );
+        assert_eq!(
+            local.endpoint("chat/completions"),
+            "http://localhost:8000/v1/chat/completions"
+        );
+
+        let router = OpenAiCompatClient::new(
+            "https://openrouter.ai/api/v1",
+            Some("sk"),
+            "qwen",
+            GenerationOptions::default(),
+        );
+        assert_eq!(
+            router.endpoint("chat/completions"),
+            "https://openrouter.ai/api/v1/chat/completions"
+        );
+    }
+
+    #[test]
+    fn test_request_and_response_format() {
+        let client = OpenAiCompatClient::new(
+            "http://localhost:8000",
+            Some(""),
+            "qwen",
+            GenerationOptions::default(),
+        );
+        assert!(client.api_key.is_none());
+
+        let body = client
+            .request_body(
+                &[
+                    ChatMessage::new(MessageRole::System, "Be brief".to_string()),
+                    ChatMessage::new(MessageRole::User, "Hi".to_string()),
+                ],
+                false,
+            )
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "model": "qwen",
+                "messages": [
+                    { "role": "system", "content": "Be brief" },
+                    { "role": "user", "content": "Hi" }
+                ],
+                "stream": false
+            })
+        );
+
+        let reply = br#"{"id":"1","choices":[{"index":0,"message":{"role":"assistant","content":"Hello"}}]}"#;
+        assert_eq!(parse_response(reply).unwrap(), "Hello");
+        assert!(parse_response(br#"{"choices":[]}"#).is_err());
+    }
+
+    #[test]
+    fn test_request_includes_sampling_options() {
+        let options = GenerationOptions {
+            temperature: Some(0.5),
+            max_tokens: Some(256),
+            ..Default::default()
+        };
+        let client = OpenAiCompatClient::new("http://localhost:8000", None, "qwen", options);
+
+        let body = client
+            .request_body(
+                &[ChatMessage::new(MessageRole::User, "Hi".to_string())],
+                true,
+            )
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["temperature"], 0.5);
+        assert_eq!(json["max_tokens"], 256);
+        // Unset options are left to the server
+        assert!(json.get("top_p").is_none());
+        assert!(json.get("seed").is_none());
+    }
+
+    #[test]
+    fn test_sse_decoder_joins_split_chunks() {
+        let mut decoder = SseDecoder::default();
+        let first = "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\ndata: {\"choices\":[{\"delta\":{\"content\":\"caf";
+        let mut bytes = first.as_bytes().to_vec();
+        // Split in the middle of the two byte "é"
+        bytes.push(0xc3);
+        assert!(decoder.push(&bytes).unwrap().is_empty());
+
+        let rest = [&[0xa9][..], b"\"}}]}\n\ndata: [DONE]\n\n"].concat();
+        assert_eq!(decoder.push(&rest).unwrap(), vec!["caf\u{e9}".to_string()]);
+
+        assert!(decoder.push(b"data: not json\n").is_err());
+    }
+}
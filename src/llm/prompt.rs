@@ -1,25 +1,22 @@
-use crate::config::pack::PackConfig;
+use crate::config::pack::{Mood, PackConfig};
 use crate::config::settings::User;
 use crate::gui::windows::types::WindowInfo;
 use crate::llm::conversation::ConversationManager;
 use chrono::Datelike;
 use ollama_rs::generation::chat::{ChatMessage, MessageRole};
 
-#[allow(dead_code)]
-pub struct PromptBuilder;
+/// Builds the chat messages sent to the LLM each iteration.
+///
+/// The system message starts with a prefix that only depends on the pack, the
+/// mood's prompt, the SDK definitions and the user, so it is built once and
+/// stays byte-identical between iterations. Backends can then reuse it from
+/// their prompt cache. The mood, open windows and history follow it.
+pub struct PromptBuilder {
+    static_prefix: String,
+}
 
 impl PromptBuilder {
-    #[allow(dead_code)]
-    pub fn build(
-        pack_config: &PackConfig,
-        mood: &str,
-        user: &User,
-        history: &ConversationManager,
-        sdk_defs: &str,
-        active_windows: &[WindowInfo],
-        execution_failed: bool,
-    ) -> Vec<ChatMessage> {
-        let mut messages = Vec::new();
+    pub fn new(pack_config: &PackConfig, mood: &Mood, user: &User, sdk_defs: &str) -> Self {
         let mut system_content = String::new();
 
         // 1. System Prompt
@@ -31,48 +28,25 @@ impl PromptBuilder {
             DO NOT use 'import' statements. The code is executed in a global context where SDK is pre-loaded.\n\n";
 
         // Priority: 1. Mood prompt, 2. Pack prompt, 3. Default system prompt
-        let mut system_used = false;
-
-        // Check for mood-specific prompt
-        if let Some(m) = pack_config.moods.iter().find(|m| m.name == mood)
-            && let Some(prompt) = &m.prompt
-        {
+        if let Some(prompt) = &mood.prompt {
             system_content.push_str(prompt);
             system_content.push_str("\n\n");
-            system_used = true;
-        }
-
-        // If no mood prompt, check for pack-level prompt
-        if !system_used
-            && let Some(prompts) = &pack_config.prompts
+        } else if let Some(prompts) = &pack_config.prompts
             && let Some(sys) = &prompts.system
         {
             system_content.push_str(sys);
             system_content.push_str("\n\n");
-            system_used = true;
-        }
-
-        // If no mood or pack prompt, use default
-        if !system_used {
+        } else {
             system_content.push_str(default_system);
         }
 
-        // 2. Mood
+        // 2. Moods
         system_content.push_str("# Moods\n");
         system_content.push_str("Moods are used to change the available media. You can change moods if you want to change up the current session.\n\n");
 
-        system_content.push_str("## Current Mood\n");
-        if let Some(m) = pack_config.moods.iter().find(|m| m.name == mood) {
-            system_content.push_str(&format!("**{}**: {}\n\n", m.name, m.description));
-        } else {
-            system_content.push_str(&format!("**{}** (unknown mood)\n\n", mood));
-        }
-
-        system_content.push_str("## Other Moods Available\n");
+        system_content.push_str("## Moods Available\n");
         for m in &pack_config.moods {
-            if m.name != mood {
-                system_content.push_str(&format!("- **{}**: {}\n", m.name, m.description));
-            }
+            system_content.push_str(&format!("- **{}**: {}\n", m.name, m.description));
         }
         system_content.push('\n');
 
@@ -82,20 +56,7 @@ impl PromptBuilder {
         system_content.push_str(sdk_defs);
         system_content.push_str("\n```\n\n");
 
-        // 4. Active Windows
-        if !active_windows.is_empty() {
-            system_content.push_str("# Active Windows\n");
-            system_content.push_str("The following windows are currently open. You can close them using their handle ID.\n");
-            for window in active_windows {
-                system_content.push_str(&format!(
-                    "- Type: {}, Handle: {}, Description: {}\n",
-                    window.window_type, window.handle.0, window.description
-                ));
-            }
-            system_content.push('\n');
-        }
-
-        // 5. User Profile
+        // 4. User Profile
         system_content.push_str("# User Profile\n");
         system_content.push_str(&format!("Name: {}\n", user.name));
         system_content.push_str(&format!("Gender: {}\n\n", user.gender));
@@ -120,11 +81,53 @@ impl PromptBuilder {
         );
         system_content
             .push_str("Output ONLY a single TypeScript code wrapped in a ```typescript``` block, previous defintions will not be evaluated.\n");
-        system_content.push_str("Do not include any other text, explanations.\n");
+        system_content.push_str("Do not include any other text, explanations.\n\n");
+
+        Self {
+            static_prefix: system_content,
+        }
+    }
+
+    /// The part of the system message that doesn't change between iterations
+    pub fn static_prefix(&self) -> &str {
+        &self.static_prefix
+    }
+
+    /// Messages for one iteration, the static prefix followed by the current state
+    pub fn messages(
+        &self,
+        mood: &Mood,
+        history: &ConversationManager,
+        active_windows: &[WindowInfo],
+        execution_failed: bool,
+    ) -> Vec<ChatMessage> {
+        let mut messages = Vec::new();
+        let mut system_content = self.static_prefix.clone();
+
+        // 6. Current Mood
+        system_content.push_str("# Current Mood\n");
+        if mood.description.is_empty() {
+            system_content.push_str(&format!("**{}**\n\n", mood.name));
+        } else {
+            system_content.push_str(&format!("**{}**: {}\n\n", mood.name, mood.description));
+        }
+
+        // 7. Active Windows
+        if !active_windows.is_empty() {
+            system_content.push_str("# Active Windows\n");
+            system_content.push_str("The following windows are currently open. You can close them using their handle ID.\n");
+            for window in active_windows {
+                system_content.push_str(&format!(
+                    "- Type: {}, Handle: {}, Description: {}\n",
+                    window.window_type, window.handle.0, window.description
+                ));
+            }
+            system_content.push('\n');
+        }
 
         messages.push(ChatMessage::new(MessageRole::System, system_content));
 
-        // 6. History - Only include if execution failed
+        // 8. History - Only include if execution failed
         if execution_failed {
             for msg in history.get_history() {
                 let role = match msg.role.as_str() {
@@ -139,12 +142,41 @@ impl PromptBuilder {
 
         messages
     }
+
+    /// Build the messages for a single call, looking the mood up by name
+    pub fn build(
+        pack_config: &PackConfig,
+        mood: &str,
+        user: &User,
+        history: &ConversationManager,
+        sdk_defs: &str,
+        active_windows: &[WindowInfo],
+        execution_failed: bool,
+    ) -> Vec<ChatMessage> {
+        let mood = pack_config
+            .moods
+            .iter()
+            .find(|m| m.name == mood)
+            .cloned()
+            .unwrap_or_else(|| Mood {
+                name: mood.to_string(),
+                description: "(unknown mood)".to_string(),
+                tags: vec![],
+                prompt: None,
+            });
+        Self::new(pack_config, &mood, user, sdk_defs).messages(
+            &mood,
+            history,
+            active_windows,
+            execution_failed,
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::pack::{Assets, Mood, PackMeta};
+    use crate::config::pack::{Assets, PackMeta};
     use crate::config::settings::User;
 
     fn create_dummy_pack_config() -> PackConfig {
@@ -169,6 +201,7 @@ mod tests {
             },
             websites: None,
             prompts: None,
+            popups: None,
         }
     }
 
@@ -241,4 +274,31 @@ mod tests {
         assert_eq!(messages.len(), 1); // System only
         assert_eq!(messages[0].role, MessageRole::System);
     }
+
+    #[test]
+    fn test_static_prefix_is_stable() {
+        let pack_config = create_dummy_pack_config();
+        let user = create_dummy_user();
+        let mood = &pack_config.moods[0];
+
+        let first = PromptBuilder::new(&pack_config, mood, &user, "class image {}");
+        let second = PromptBuilder::new(&pack_config, mood, &user, "class image {}");
+        assert_eq!(
+            first.static_prefix().as_bytes(),
+            second.static_prefix().as_bytes()
+        );
+
+        // Changing state only affects what follows the prefix
+        let mut history = ConversationManager::new(10);
+        history.add_message("user", "Hello");
+        let window = WindowInfo {
+            handle: crate::gui::WindowHandle(uuid::Uuid::new_v4()),
+            window_type: "Image".to_string(),
+            description: "Image display window".to_string(),
+        };
+        let messages = first.messages(mood, &history, &[window], true);
+        assert!(messages[0].content.starts_with(second.static_prefix()));
+        assert!(messages[0].content.contains("# Active Windows"));
+        assert!(!first.static_prefix().contains("# Active Windows"));
+    }
 }
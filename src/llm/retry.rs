@@ -0,0 +1,206 @@
+//! Retry LLM requests that fail to reach the server
+//!
+//! Only transport failures are retried: refused connections, timeouts and
+//! overloaded servers. A missing model or a malformed reply fails straight
+//! away, since asking again would only hammer the endpoint.
+
+use anyhow::Result;
+use ollama_rs::error::OllamaError;
+use rand::Rng;
+use reqwest::StatusCode;
+use std::future::Future;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tracing::warn;
+
+/// Tries per request before the error is given back
+pub const MAX_ATTEMPTS: u32 = 4;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Exponential backoff with jitter, kept across requests so a server that
+/// stays down is asked less and less often until it answers again
+#[derive(Debug)]
+pub struct Backoff {
+    base: Duration,
+    max: Duration,
+    /// Transport failures since the last success
+    failures: AtomicU32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(BASE_DELAY, MAX_DELAY)
+    }
+}
+
+impl Backoff {
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max,
+            failures: AtomicU32::new(0),
+        }
+    }
+
+    /// Transport failures since the last success
+    pub fn failures(&self) -> u32 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// Record a failure and return how long to wait before trying again.
+    ///
+    /// The delay doubles with each failure up to the maximum, and is picked
+    /// from the upper half of that so clients don't retry in lockstep.
+    pub fn next_delay(&self) -> Duration {
+        let failures = self.failures.fetch_add(1, Ordering::Relaxed);
+        let ceiling = self
+            .base
+            .saturating_mul(2u32.saturating_pow(failures))
+            .min(self.max);
+        let half = ceiling / 2;
+        half + rand::rng().random_range(Duration::ZERO..=half)
+    }
+
+    pub fn reset(&self) {
+        self.failures.store(0, Ordering::Relaxed);
+    }
+
+    /// Run `request` until it succeeds, fails with something other than a
+    /// transport error, or has been tried [`MAX_ATTEMPTS`] times
+    pub async fn retry<T, F, Fut>(&self, mut request: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match request().await {
+                Ok(value) => {
+                    self.reset();
+                    return Ok(value);
+                }
+                Err(e) if attempt < MAX_ATTEMPTS && is_transport_error(&e) => {
+                    let delay = self.next_delay();
+                    warn!("LLM request failed: {:#}, retrying in {:?}", e, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Whether `error` came from failing to reach the server rather than from the
+/// server rejecting the request
+pub fn is_transport_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            return is_transient(e);
+        }
+        if let Some(OllamaError::ReqwestError(e)) = cause.downcast_ref::<OllamaError>() {
+            return is_transient(e);
+        }
+        cause.is::<std::io::Error>()
+    })
+}
+
+fn is_transient(error: &reqwest::Error) -> bool {
+    error.is_connect()
+        || error.is_timeout()
+        || error.status().is_some_and(|status| {
+            matches!(
+                status,
+                StatusCode::TOO_MANY_REQUESTS
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::io::ErrorKind;
+
+    fn backoff() -> Backoff {
+        Backoff::new(Duration::from_millis(1), Duration::from_millis(4))
+    }
+
+    fn refused() -> anyhow::Error {
+        std::io::Error::from(ErrorKind::ConnectionRefused).into()
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_transport_errors_are_retried_until_success() {
+        let backoff = backoff();
+        let mut calls = 0;
+        let reply = backoff
+            .retry(|| {
+                calls += 1;
+                let attempt = calls;
+                async move {
+                    if attempt <= 2 {
+                        Err(refused())
+                    } else {
+                        Ok("hello")
+                    }
+                }
+            })
+            .await;
+
+        assert_eq!(reply.unwrap(), "hello");
+        assert_eq!(calls, 3);
+        // Success starts the next failure from the shortest delay again
+        assert_eq!(backoff.failures(), 0);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_model_errors_are_not_retried() {
+        let backoff = backoff();
+        let mut calls = 0;
+        let reply: Result<()> = backoff
+            .retry(|| {
+                calls += 1;
+                async { Err(anyhow!("model 'llama9' not found")) }
+            })
+            .await;
+
+        assert!(reply.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_attempts_are_bounded() {
+        let backoff = backoff();
+        let mut calls = 0;
+        let reply: Result<()> = backoff
+            .retry(|| {
+                calls += 1;
+                async { Err(refused()) }
+            })
+            .await;
+
+        assert!(is_transport_error(&reply.unwrap_err()));
+        assert_eq!(calls, MAX_ATTEMPTS);
+        assert_eq!(backoff.failures(), MAX_ATTEMPTS - 1);
+    }
+
+    #[test]
+    fn test_delay_doubles_up_to_max() {
+        let backoff = Backoff::new(Duration::from_millis(100), Duration::from_millis(300));
+        for ceiling in [100, 200, 300, 300] {
+            let delay = backoff.next_delay();
+            assert!(delay >= Duration::from_millis(ceiling / 2), "{:?}", delay);
+            assert!(delay <= Duration::from_millis(ceiling), "{:?}", delay);
+        }
+        backoff.reset();
+        assert!(backoff.next_delay() <= Duration::from_millis(100));
+    }
+}
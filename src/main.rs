@@ -1,7 +1,9 @@
 use anyhow::Result;
 use goon_ai::app_loop::orchestrator::Orchestrator;
-use goon_ai::config::pack::PackConfig;
-use goon_ai::config::settings::Settings;
+use goon_ai::app_loop::shutdown::Shutdown;
+use goon_ai::config::pack::{PackConfig, validation_failed};
+use goon_ai::config::settings::{SETTINGS_FILE, Settings};
+use goon_ai::config::watcher::SettingsWatcher;
 use goon_ai::gui::tray::{SystemTray, TrayCommand};
 use goon_ai::gui::windows::{WindowSpawner, run_event_loop};
 use goon_ai::permissions::{PermissionChecker, PermissionResolver, PermissionSet};
@@ -9,7 +11,18 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use tracing::info;
 
+/// How long to wait for the loop thread to clean up before quitting anyway
+const SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Safe mode asked for outside the settings file, with `GOON_SAFE_MODE=1` or `--safe-mode`
+fn safe_mode_forced() -> bool {
+    std::env::var("GOON_SAFE_MODE").is_ok_and(|v| v == "1" || v == "true")
+        || std::env::args().any(|arg| arg == "--safe-mode")
+}
+
 fn main() -> Result<()> {
+    let forced_safe_mode = safe_mode_forced();
+
     // Create window spawner channel pair
     let (window_handle, window_spawner) = WindowSpawner::create();
 
@@ -30,6 +43,13 @@ fn main() -> Result<()> {
 
     tracing_subscriber::fmt().with_env_filter(env_filter).init();
 
+    // Ctrl-C, SIGTERM and the tray Quit command all go through the same shutdown
+    let shutdown = Shutdown::new();
+    if let Err(e) = shutdown.install_signal_handlers() {
+        tracing::warn!("Failed to install signal handlers: {}", e);
+    }
+    let shutdown_for_llm = shutdown.clone();
+
     // Spawn LLM loop thread
     let _llm_thread = std::thread::spawn(move || {
         let rt = tokio::runtime::Builder::new_multi_thread()
@@ -57,10 +77,29 @@ fn main() -> Result<()> {
                 }
             };
 
+            // Catch authoring mistakes before the loop starts failing on them
+            let mut problems = pack_config
+                .validate(&std::path::Path::new("packs").join(&settings.runtime.pack.current))
+                .err()
+                .unwrap_or_default();
+            if let Err(e) = pack_config.validate_mood(&settings.runtime.pack.mood) {
+                problems.push(e);
+            }
+            if !problems.is_empty() {
+                let e = validation_failed(&settings.runtime.pack.current, &problems);
+                eprintln!("Invalid pack config: {}", e);
+                tracing::error!("Invalid pack config: {}", e);
+                return;
+            }
+
             // Compute permissions using resolver
             let user_perms: PermissionSet = settings.runtime.permissions.clone().into();
             let pack_perms: PermissionSet = pack_config.meta.permissions.clone().into();
-            let active_perms = PermissionResolver::resolve(&pack_perms, &user_perms);
+            let active_perms = PermissionResolver::resolve(
+                &pack_perms,
+                &user_perms,
+                settings.runtime.safe_mode || forced_safe_mode,
+            );
 
             info!("User permissions: {:?}", user_perms);
             info!("Pack permissions: {:?}", pack_perms);
@@ -75,6 +114,9 @@ fn main() -> Result<()> {
                 if is_running_for_llm.load(Ordering::Relaxed) {
                     break;
                 }
+                if shutdown_for_llm.is_requested() {
+                    return;
+                }
                 tokio::time::sleep(std::time::Duration::from_millis(100)).await;
             }
 
@@ -87,21 +129,41 @@ fn main() -> Result<()> {
                 permissions,
                 window_handle_for_llm,
                 is_running_for_llm.clone(),
-            );
+            )
+            .with_shutdown(shutdown_for_llm.clone())
+            .with_forced_safe_mode(forced_safe_mode);
+
+            // Apply edits to settings.toml without restarting
+            let settings_tx = orchestrator.settings_sender();
+            let current = (**settings_tx.borrow()).clone();
+            let _settings_watcher =
+                match SettingsWatcher::watch(SETTINGS_FILE, current, move |settings| {
+                    info!("Reloaded {}", SETTINGS_FILE);
+                    let _ = settings_tx.send(Arc::new(settings));
+                }) {
+                    Ok(watcher) => Some(watcher),
+                    Err(e) => {
+                        tracing::warn!("Settings won't be reloaded: {:#}", e);
+                        None
+                    }
+                };
 
-            // Run the orchestrator loop
-            // TODO: Add check for is_running to pause/resume
+            // Run the orchestrator loop, it idles between iterations while paused
             if let Err(e) = orchestrator.run().await {
                 eprintln!("Orchestrator error: {}", e);
                 tracing::error!("Orchestrator error: {}", e);
             }
         });
+
+        // Cleanup is done (or the loop never started), let the UI thread exit
+        shutdown_for_llm.finish();
     });
 
     // Create a timer to poll tray commands
     let timer = slint::Timer::default();
     let tray_cell = std::cell::RefCell::new(tray);
     let is_running_for_tray = is_running.clone();
+    let shutdown_started = std::cell::Cell::new(None::<std::time::Instant>);
 
     // Start the timer to poll tray commands
     timer.start(
@@ -131,10 +193,22 @@ fn main() -> Result<()> {
                     }
                     TrayCommand::Quit => {
                         info!("Quitting application...");
-                        let _ = slint::quit_event_loop();
+                        shutdown.request();
                     }
                 }
             }
+
+            // Give the loop thread a moment to clean up, but don't hang on a slow LLM call
+            if shutdown.is_requested() {
+                let started = shutdown_started.get().unwrap_or_else(|| {
+                    let now = std::time::Instant::now();
+                    shutdown_started.set(Some(now));
+                    now
+                });
+                if shutdown.is_finished() || started.elapsed() > SHUTDOWN_GRACE {
+                    let _ = slint::quit_event_loop();
+                }
+            }
         },
     );
 
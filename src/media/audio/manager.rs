@@ -4,16 +4,54 @@ use rodio::mixer::Mixer;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+/// How often the timer thread steps crossfades and stops timed tracks
+const TIMER_TICK: Duration = Duration::from_millis(20);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct AudioHandle(pub Uuid);
 
+/// A track fading out while another fades in to replace it
+#[derive(Debug, Clone, Copy)]
+struct Crossfade {
+    from: AudioHandle,
+    to: AudioHandle,
+    from_volume: f32,
+    to_volume: f32,
+    started: Instant,
+    duration: Duration,
+}
+
+impl Crossfade {
+    /// Volumes of the outgoing and incoming tracks at `now`, or `None` once done
+    fn volumes_at(&self, now: Instant) -> Option<(f32, f32)> {
+        let elapsed = now.saturating_duration_since(self.started);
+        if elapsed >= self.duration {
+            return None;
+        }
+        let progress = elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        Some((
+            self.from_volume * (1.0 - progress),
+            self.to_volume * progress,
+        ))
+    }
+}
+
 pub struct AudioManager {
     mixer: Mixer,
     players: HashMap<AudioHandle, AudioPlayer>,
     play_order: Vec<AudioHandle>,
+    volumes: HashMap<AudioHandle, f32>,
+    crossfades: Vec<Crossfade>,
+    /// When timed tracks stop, and how long paused ones have left
+    stop_at: HashMap<AudioHandle, Instant>,
+    paused_remaining: HashMap<AudioHandle, Duration>,
+    timer_running: bool,
+    master_volume: f32,
+    muted: bool,
     max_concurrent: usize,
 }
 
@@ -23,6 +61,13 @@ impl AudioManager {
             mixer,
             players: HashMap::new(),
             play_order: Vec::new(),
+            volumes: HashMap::new(),
+            crossfades: Vec::new(),
+            stop_at: HashMap::new(),
+            paused_remaining: HashMap::new(),
+            timer_running: false,
+            master_volume: 1.0,
+            muted: false,
             max_concurrent,
         }
     }
@@ -44,42 +89,232 @@ impl AudioManager {
             }
         }
 
-        let player = AudioPlayer::new(&self.mixer, file_path)?;
-        player.set_volume(volume);
-        player.play(duration)?;
+        self.start_player(file_path, volume, volume, duration)
+    }
 
-        let handle = AudioHandle(Uuid::new_v4());
-        self.players.insert(handle, player);
-        self.play_order.push(handle);
+    /// Start `file_path` silently and fade it up to `volume` over `fade` while
+    /// the track behind `from` fades out. Both tracks count towards the
+    /// concurrency cap until the fade completes and `from` is stopped.
+    ///
+    /// If `from` is not playing the new track simply starts at `volume`.
+    pub fn crossfade_to(
+        &mut self,
+        from: AudioHandle,
+        file_path: PathBuf,
+        volume: f32,
+        duration: Option<Duration>,
+        fade: Duration,
+    ) -> Result<AudioHandle> {
+        self.cleanup_finished();
+
+        if !self.players.contains_key(&from) || fade.is_zero() {
+            self.stop_audio(from);
+            return self.play_audio(file_path, volume, duration);
+        }
 
+        // Make room without cutting off the track being faded out
+        if self.players.len() >= self.max_concurrent {
+            let oldest = self.play_order.iter().copied().find(|&h| h != from);
+            if let Some(oldest) = oldest {
+                self.stop_audio(oldest);
+            }
+        }
+
+        // A track still being faded out in favour of `from` is cut off so
+        // only one fade drives the volume of `from`
+        let replaced: Vec<AudioHandle> = self
+            .crossfades
+            .iter()
+            .filter(|c| c.to == from)
+            .map(|c| c.from)
+            .collect();
+        for handle in replaced {
+            self.stop_audio(handle);
+        }
+
+        let handle = self.start_player(file_path, 0.0, volume, duration)?;
+        self.crossfades.push(Crossfade {
+            from,
+            to: handle,
+            from_volume: self.volumes.get(&from).copied().unwrap_or(1.0),
+            to_volume: volume,
+            started: Instant::now(),
+            duration: fade,
+        });
         Ok(handle)
     }
 
+    /// Step running crossfades to `now`, stopping tracks that have faded out.
+    /// Returns whether any crossfade is still running.
+    pub fn update_crossfades(&mut self, now: Instant) -> bool {
+        let mut finished = Vec::new();
+        for crossfade in &self.crossfades {
+            match crossfade.volumes_at(now) {
+                Some((from_volume, to_volume)) => {
+                    if let Some(player) = self.players.get(&crossfade.from) {
+                        player.set_volume(self.output_volume(from_volume));
+                    }
+                    if let Some(player) = self.players.get(&crossfade.to) {
+                        player.set_volume(self.output_volume(to_volume));
+                    }
+                }
+                None => finished.push(*crossfade),
+            }
+        }
+
+        for crossfade in finished {
+            self.stop_audio(crossfade.from);
+            self.set_volume(crossfade.to, crossfade.to_volume);
+        }
+
+        !self.crossfades.is_empty()
+    }
+
+    /// Stop tracks whose duration has run out by `now`
+    pub fn stop_expired(&mut self, now: Instant) {
+        let expired: Vec<AudioHandle> = self
+            .stop_at
+            .iter()
+            .filter(|&(_, &at)| at <= now)
+            .map(|(&h, _)| h)
+            .collect();
+
+        for handle in expired {
+            self.stop_audio(handle);
+        }
+    }
+
+    /// Whether the timer thread still has crossfades or timed tracks to handle
+    fn has_timers(&self) -> bool {
+        !self.crossfades.is_empty() || !self.stop_at.is_empty()
+    }
+
     pub fn stop_audio(&mut self, handle: AudioHandle) {
         if let Some(player) = self.players.remove(&handle) {
             player.stop();
         }
         self.play_order.retain(|&h| h != handle);
+        self.volumes.remove(&handle);
+        self.stop_at.remove(&handle);
+        self.paused_remaining.remove(&handle);
+        self.crossfades
+            .retain(|c| c.from != handle && c.to != handle);
+    }
+
+    pub fn stop_all(&mut self) {
+        for (_, player) in self.players.drain() {
+            player.stop();
+        }
+        self.play_order.clear();
+        self.volumes.clear();
+        self.stop_at.clear();
+        self.paused_remaining.clear();
+        self.crossfades.clear();
     }
 
-    pub fn pause_audio(&self, handle: AudioHandle) {
+    /// Number of tracks currently held by the manager
+    pub fn active_count(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Number of tracks that have not finished playing
+    pub fn playing_count(&mut self) -> usize {
+        self.cleanup_finished();
+        self.players.len()
+    }
+
+    pub fn pause_audio(&mut self, handle: AudioHandle) {
         if let Some(player) = self.players.get(&handle) {
             player.pause();
+            // A paused track's duration doesn't run down
+            if let Some(at) = self.stop_at.remove(&handle) {
+                let remaining = at.saturating_duration_since(Instant::now());
+                self.paused_remaining.insert(handle, remaining);
+            }
         }
     }
 
-    pub fn resume_audio(&self, handle: AudioHandle) {
+    pub fn resume_audio(&mut self, handle: AudioHandle) {
         if let Some(player) = self.players.get(&handle) {
             player.resume();
+            if let Some(remaining) = self.paused_remaining.remove(&handle) {
+                self.stop_at.insert(handle, Instant::now() + remaining);
+            }
         }
     }
 
-    pub fn set_volume(&self, handle: AudioHandle, volume: f32) {
+    pub fn set_volume(&mut self, handle: AudioHandle, volume: f32) {
         if let Some(player) = self.players.get(&handle) {
-            player.set_volume(volume);
+            player.set_volume(self.output_volume(volume));
+            self.volumes.insert(handle, volume);
+        }
+        // A track still fading in keeps fading, towards the new volume
+        for crossfade in self.crossfades.iter_mut().filter(|c| c.to == handle) {
+            crossfade.to_volume = volume;
         }
     }
 
+    /// Scale every track, including ones started later, by `volume`
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.clamp(0.0, 1.0);
+        self.apply_volumes();
+    }
+
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume
+    }
+
+    /// Silence every track, including ones started while muted, without
+    /// losing their volumes
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.apply_volumes();
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Volume a sink plays at for a track volume, after master volume and mute
+    fn output_volume(&self, volume: f32) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            volume * self.master_volume
+        }
+    }
+
+    /// Push master volume and mute to every sink. Tracks mid-crossfade are
+    /// corrected on the next crossfade tick.
+    fn apply_volumes(&self) {
+        for (handle, player) in &self.players {
+            let volume = self.volumes.get(handle).copied().unwrap_or(1.0);
+            player.set_volume(self.output_volume(volume));
+        }
+    }
+
+    fn start_player(
+        &mut self,
+        file_path: PathBuf,
+        initial_volume: f32,
+        volume: f32,
+        duration: Option<Duration>,
+    ) -> Result<AudioHandle> {
+        let player = AudioPlayer::new(&self.mixer, file_path)?;
+        player.set_volume(self.output_volume(initial_volume));
+        player.play(duration)?;
+
+        let handle = AudioHandle(Uuid::new_v4());
+        self.players.insert(handle, player);
+        self.play_order.push(handle);
+        self.volumes.insert(handle, volume);
+        if let Some(duration) = duration {
+            self.stop_at.insert(handle, Instant::now() + duration);
+        }
+
+        Ok(handle)
+    }
+
     fn cleanup_finished(&mut self) {
         let finished: Vec<AudioHandle> = self
             .players
@@ -93,3 +328,157 @@ impl AudioManager {
         }
     }
 }
+
+/// Step crossfades and stop timed tracks on a timer thread until none are
+/// left, so tracks stop on time even if no further ops come in. Only one
+/// timer runs at a time; tracks and crossfades started later are picked up by it.
+pub fn run_timers(manager: &Arc<Mutex<AudioManager>>) {
+    {
+        let Ok(mut guard) = manager.lock() else {
+            return;
+        };
+        if guard.timer_running || !guard.has_timers() {
+            return;
+        }
+        guard.timer_running = true;
+    }
+
+    let manager = manager.clone();
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(TIMER_TICK);
+            let Ok(mut guard) = manager.lock() else {
+                return;
+            };
+            let now = Instant::now();
+            guard.update_crossfades(now);
+            guard.stop_expired(now);
+            // Paused timed tracks are picked up again when resumed
+            if !guard.has_timers() {
+                guard.timer_running = false;
+                return;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "packs/TestPack/audio/sample-3s.mp3";
+
+    fn manager(max_concurrent: usize) -> AudioManager {
+        // A detached mixer so tests don't need an audio device
+        let (mixer, _source) = rodio::mixer::mixer(2, 44100);
+        AudioManager::new(mixer, max_concurrent)
+    }
+
+    #[test]
+    fn test_crossfade_volumes() {
+        let now = Instant::now();
+        let crossfade = Crossfade {
+            from: AudioHandle(Uuid::new_v4()),
+            to: AudioHandle(Uuid::new_v4()),
+            from_volume: 1.0,
+            to_volume: 0.5,
+            started: now,
+            duration: Duration::from_millis(1000),
+        };
+        assert_eq!(crossfade.volumes_at(now), Some((1.0, 0.0)));
+        let (from, to) = crossfade
+            .volumes_at(now + Duration::from_millis(500))
+            .unwrap();
+        assert!((from - 0.5).abs() < 1e-6);
+        assert!((to - 0.25).abs() < 1e-6);
+        assert_eq!(crossfade.volumes_at(now + Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_crossfade_frees_old_track_when_done() {
+        let mut manager = manager(2);
+        let first = manager.play_audio(SAMPLE.into(), 1.0, None).unwrap();
+        let second = manager
+            .crossfade_to(first, SAMPLE.into(), 0.8, None, Duration::from_secs(1))
+            .unwrap();
+
+        // Both tracks are held while the fade runs
+        assert_eq!(manager.active_count(), 2);
+        assert!(manager.update_crossfades(Instant::now()));
+        assert_eq!(manager.active_count(), 2);
+
+        assert!(!manager.update_crossfades(Instant::now() + Duration::from_secs(2)));
+        assert_eq!(manager.active_count(), 1);
+        assert!(manager.players.contains_key(&second));
+        assert_eq!(manager.volumes.get(&second), Some(&0.8));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_timed_track_stops_without_further_calls() {
+        let manager = Arc::new(Mutex::new(manager(4)));
+        let handle = manager
+            .lock()
+            .unwrap()
+            .play_audio(SAMPLE.into(), 1.0, Some(Duration::from_millis(100)))
+            .unwrap();
+        let untimed = manager
+            .lock()
+            .unwrap()
+            .play_audio(SAMPLE.into(), 1.0, None)
+            .unwrap();
+        run_timers(&manager);
+
+        std::thread::sleep(Duration::from_millis(150));
+        let manager = manager.lock().unwrap();
+        assert!(!manager.players.contains_key(&handle));
+        assert!(manager.players.contains_key(&untimed));
+        assert_eq!(manager.active_count(), 1);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_stopping_early_cancels_timer() {
+        let mut manager = manager(4);
+        let handle = manager
+            .play_audio(SAMPLE.into(), 1.0, Some(Duration::from_millis(100)))
+            .unwrap();
+        manager.stop_audio(handle);
+        assert!(!manager.has_timers());
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_new_tracks_respect_mute() {
+        let mut manager = manager(4);
+        let playing = manager.play_audio(SAMPLE.into(), 0.8, None).unwrap();
+
+        manager.set_muted(true);
+        assert_eq!(manager.players[&playing].volume(), 0.0);
+        let started = manager.play_audio(SAMPLE.into(), 0.8, None).unwrap();
+        assert_eq!(manager.players[&started].volume(), 0.0);
+
+        // Unmuting restores each track's own volume, scaled by the master volume
+        manager.set_master_volume(0.5);
+        manager.set_muted(false);
+        assert!((manager.players[&playing].volume() - 0.4).abs() < 1e-6);
+        assert!((manager.players[&started].volume() - 0.4).abs() < 1e-6);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_crossfade_keeps_fading_track_at_cap() {
+        let mut manager = manager(2);
+        let first = manager.play_audio(SAMPLE.into(), 1.0, None).unwrap();
+        let second = manager.play_audio(SAMPLE.into(), 1.0, None).unwrap();
+
+        // At the cap, the oldest track other than the one fading out makes room
+        let third = manager
+            .crossfade_to(second, SAMPLE.into(), 1.0, None, Duration::from_secs(1))
+            .unwrap();
+        assert!(!manager.players.contains_key(&first));
+        assert!(manager.players.contains_key(&second));
+        assert!(manager.players.contains_key(&third));
+    }
+}
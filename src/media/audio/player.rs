@@ -43,6 +43,10 @@ impl AudioPlayer {
         self.sink.set_volume(volume);
     }
 
+    pub fn volume(&self) -> f32 {
+        self.sink.volume()
+    }
+
     pub fn is_finished(&self) -> bool {
         self.sink.empty()
     }
@@ -0,0 +1,225 @@
+//! Screen region capture
+//!
+//! Captures are written to a temp directory and tracked, so every file a runtime
+//! produced is deleted again when the runtime shuts down.
+
+use crate::gui::windows::layout::MonitorRect;
+use anyhow::{Result, anyhow};
+use image::RgbaImage;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+use xcap::Monitor;
+
+/// A region of the desktop in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Find the monitor that fully contains `region`.
+///
+/// Returns the monitor index and the region's offset within that monitor.
+pub fn locate_region(monitors: &[MonitorRect], region: &Region) -> Result<(usize, u32, u32)> {
+    if region.width == 0 || region.height == 0 {
+        return Err(anyhow!("Capture region must not be empty"));
+    }
+
+    monitors
+        .iter()
+        .enumerate()
+        .find_map(|(index, monitor)| {
+            let right = monitor.x as i64 + monitor.width as i64;
+            let bottom = monitor.y as i64 + monitor.height as i64;
+            let inside = region.x >= monitor.x
+                && region.y >= monitor.y
+                && region.x as i64 + region.width as i64 <= right
+                && region.y as i64 + region.height as i64 <= bottom;
+            inside.then(|| {
+                (
+                    index,
+                    (region.x - monitor.x) as u32,
+                    (region.y - monitor.y) as u32,
+                )
+            })
+        })
+        .ok_or_else(|| {
+            anyhow!(
+                "Capture region {}x{} at ({}, {}) is not within a single display",
+                region.width,
+                region.height,
+                region.x,
+                region.y
+            )
+        })
+}
+
+fn monitor_rects(monitors: &[Monitor]) -> Result<Vec<MonitorRect>> {
+    monitors
+        .iter()
+        .map(|m| {
+            Ok(MonitorRect {
+                x: m.x()?,
+                y: m.y()?,
+                width: m.width()?,
+                height: m.height()?,
+            })
+        })
+        .collect()
+}
+
+/// Bounds of every connected display
+pub fn display_rects() -> Result<Vec<MonitorRect>> {
+    monitor_rects(&Monitor::all()?)
+}
+
+/// Bounds of every connected display and whether it is the primary one
+pub fn displays() -> Result<Vec<(MonitorRect, bool)>> {
+    let monitors = Monitor::all()?;
+    let rects = monitor_rects(&monitors)?;
+    Ok(rects
+        .into_iter()
+        .zip(monitors.iter().map(|m| m.is_primary().unwrap_or(false)))
+        .collect())
+}
+
+/// Resolution and scale factor of the primary display, or the first one if
+/// none is marked primary
+pub fn primary_display() -> Result<(u32, u32, f32)> {
+    let monitors = Monitor::all()?;
+    let monitor = monitors
+        .iter()
+        .find(|m| m.is_primary().unwrap_or(false))
+        .or_else(|| monitors.first())
+        .ok_or_else(|| anyhow!("No displays found"))?;
+    Ok((monitor.width()?, monitor.height()?, monitor.scale_factor()?))
+}
+
+/// Resolution of the primary display, or the first one if none is marked primary
+pub fn primary_display_size() -> Result<(u32, u32)> {
+    primary_display().map(|(width, height, _)| (width, height))
+}
+
+/// Capture a region of the desktop, validating it against the display bounds
+pub fn capture_region(region: &Region) -> Result<RgbaImage> {
+    let monitors = Monitor::all()?;
+    let (index, x, y) = locate_region(&monitor_rects(&monitors)?, region)?;
+    Ok(monitors[index].capture_region(x, y, region.width, region.height)?)
+}
+
+/// Temp files written by screen captures and generated images, deleted when dropped
+pub struct CaptureFiles {
+    dir: PathBuf,
+    files: Vec<PathBuf>,
+}
+
+impl Default for CaptureFiles {
+    fn default() -> Self {
+        Self::new(std::env::temp_dir().join("goon-ai-captures"))
+    }
+}
+
+impl CaptureFiles {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            files: Vec::new(),
+        }
+    }
+
+    /// Save an image as PNG and return its path
+    pub fn save(&mut self, image: &RgbaImage) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("{}.png", Uuid::new_v4()));
+        image.save(&path)?;
+        self.files.push(path.clone());
+        Ok(path)
+    }
+
+    /// Delete every file written so far
+    pub fn cleanup(&mut self) {
+        for file in self.files.drain(..) {
+            remove_quietly(&file);
+        }
+    }
+}
+
+fn remove_quietly(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        eprintln!("Failed to remove capture {}: {}", path.display(), e);
+    }
+}
+
+impl Drop for CaptureFiles {
+    fn drop(&mut self) {
+        self.cleanup();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MONITORS: [MonitorRect; 2] = [
+        MonitorRect {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        },
+        MonitorRect {
+            x: 1920,
+            y: 0,
+            width: 1280,
+            height: 1024,
+        },
+    ];
+
+    fn region(x: i32, y: i32, width: u32, height: u32) -> Region {
+        Region {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn test_region_bounds() {
+        assert_eq!(
+            locate_region(&MONITORS, &region(0, 0, 200, 100)).unwrap(),
+            (0, 0, 0)
+        );
+        assert_eq!(
+            locate_region(&MONITORS, &region(1720, 980, 200, 100)).unwrap(),
+            (0, 1720, 980)
+        );
+        assert_eq!(
+            locate_region(&MONITORS, &region(2000, 24, 100, 1000)).unwrap(),
+            (1, 80, 24)
+        );
+
+        // Off screen, overflowing, spanning two displays, or empty
+        assert!(locate_region(&MONITORS, &region(-10, 0, 100, 100)).is_err());
+        assert!(locate_region(&MONITORS, &region(1800, 1000, 200, 100)).is_err());
+        assert!(locate_region(&MONITORS, &region(1900, 0, 100, 100)).is_err());
+        assert!(locate_region(&MONITORS, &region(0, 0, 0, 100)).is_err());
+        assert!(locate_region(&[], &region(0, 0, 10, 10)).is_err());
+    }
+
+    #[test]
+    fn test_captures_removed_on_drop() {
+        let dir = std::env::temp_dir().join(format!("goon-ai-capture-test-{}", Uuid::new_v4()));
+        let mut files = CaptureFiles::new(dir.clone());
+        let path = files.save(&RgbaImage::new(4, 4)).unwrap();
+        assert!(path.exists());
+
+        drop(files);
+        assert!(!path.exists());
+        let _ = std::fs::remove_dir(dir);
+    }
+}
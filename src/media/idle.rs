@@ -0,0 +1,127 @@
+//! User idle time and screen lock detection
+//!
+//! Values are best-effort: each platform queries whatever the desktop exposes and
+//! returns an error when the information isn't available.
+
+use anyhow::{Result, anyhow};
+use std::process::Command;
+
+pub trait IdleProvider: Send + Sync {
+    /// Seconds since the last keyboard or mouse input
+    fn idle_seconds(&self) -> Result<u64>;
+    /// Whether the screen is currently locked
+    fn is_screen_locked(&self) -> Result<bool>;
+}
+
+pub struct PlatformIdleProvider;
+
+/// Run a command and return its trimmed stdout if it succeeded
+fn command_output(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(anyhow!("{} exited with {}", program, output.status));
+    }
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+#[cfg(target_os = "linux")]
+impl IdleProvider for PlatformIdleProvider {
+    fn idle_seconds(&self) -> Result<u64> {
+        // X11 via xprintidle, falling back to the GNOME (Wayland) idle monitor
+        if let Ok(ms) = command_output("xprintidle", &[]) {
+            return Ok(ms.parse::<u64>()? / 1000);
+        }
+
+        let output = command_output(
+            "gdbus",
+            &[
+                "call",
+                "--session",
+                "--dest",
+                "org.gnome.Mutter.IdleMonitor",
+                "--object-path",
+                "/org/gnome/Mutter/IdleMonitor/Core",
+                "--method",
+                "org.gnome.Mutter.IdleMonitor.GetIdletime",
+            ],
+        )
+        .map_err(|_| anyhow!("Idle time is not available on this desktop"))?;
+
+        // Output looks like "(uint64 12345,)"
+        let ms = output
+            .trim_matches(|c| c == '(' || c == ')' || c == ',')
+            .trim_start_matches("uint64")
+            .trim();
+        Ok(ms.parse::<u64>()? / 1000)
+    }
+
+    fn is_screen_locked(&self) -> Result<bool> {
+        let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".to_string());
+        let locked = command_output(
+            "loginctl",
+            &["show-session", &session, "-p", "LockedHint", "--value"],
+        )
+        .map_err(|_| anyhow!("Screen lock state is not available on this desktop"))?;
+        Ok(locked == "yes")
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl IdleProvider for PlatformIdleProvider {
+    fn idle_seconds(&self) -> Result<u64> {
+        let output = command_output("ioreg", &["-c", "IOHIDSystem"])?;
+        let nanos = output
+            .lines()
+            .find(|line| line.contains("\"HIDIdleTime\""))
+            .and_then(|line| line.split('=').nth(1))
+            .ok_or_else(|| anyhow!("HIDIdleTime not found"))?
+            .trim()
+            .parse::<u64>()?;
+        Ok(nanos / 1_000_000_000)
+    }
+
+    fn is_screen_locked(&self) -> Result<bool> {
+        Err(anyhow!("Screen lock detection is not supported on macOS"))
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl IdleProvider for PlatformIdleProvider {
+    fn idle_seconds(&self) -> Result<u64> {
+        use windows_sys::Win32::System::SystemInformation::GetTickCount;
+        use windows_sys::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+        let mut info = LASTINPUTINFO {
+            cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+        let ok = unsafe { GetLastInputInfo(&mut info) };
+        if ok == 0 {
+            return Err(anyhow!("GetLastInputInfo failed"));
+        }
+        let now = unsafe { GetTickCount() };
+        Ok(now.wrapping_sub(info.dwTime) as u64 / 1000)
+    }
+
+    fn is_screen_locked(&self) -> Result<bool> {
+        // The lock screen is hosted by LogonUI.exe while the workstation is locked
+        let output = command_output("tasklist", &["/FI", "IMAGENAME eq LogonUI.exe", "/NH"])?;
+        Ok(output.contains("LogonUI.exe"))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+impl IdleProvider for PlatformIdleProvider {
+    fn idle_seconds(&self) -> Result<u64> {
+        Err(anyhow!("Idle time is not supported on this platform"))
+    }
+
+    fn is_screen_locked(&self) -> Result<bool> {
+        Err(anyhow!(
+            "Screen lock detection is not supported on this platform"
+        ))
+    }
+}
@@ -0,0 +1,103 @@
+//! Chroma-key transparency for image windows
+//!
+//! Pixels close to the key color are made fully transparent, so rectangular
+//! assets with a solid background can be shown as non-rectangular popups.
+
+use anyhow::{Result, anyhow};
+use image::RgbaImage;
+
+/// Default maximum per-channel distance (0.0-1.0) for a pixel to match the key
+pub const DEFAULT_TOLERANCE: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorKey {
+    /// Key color as normalized RGB
+    pub color: [f32; 3],
+    /// Maximum per-channel distance (0.0-1.0) for a pixel to be keyed out
+    pub tolerance: f32,
+}
+
+/// Parse a `#rrggbb` or `#rgb` hex color into 8-bit RGB
+pub fn parse_hex_rgb(hex: &str) -> Result<[u8; 3]> {
+    let digits = hex.trim().trim_start_matches('#');
+    let expanded: String = match digits.len() {
+        3 => digits.chars().flat_map(|c| [c, c]).collect(),
+        6 => digits.to_string(),
+        _ => return Err(anyhow!("Invalid color '{}', expected #rrggbb", hex)),
+    };
+
+    let channel = |i: usize| {
+        u8::from_str_radix(&expanded[i..i + 2], 16)
+            .map_err(|_| anyhow!("Invalid color '{}', expected #rrggbb", hex))
+    };
+
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+impl ColorKey {
+    /// Parse a `#rrggbb` or `#rgb` hex color
+    pub fn from_hex(hex: &str, tolerance: Option<f32>) -> Result<Self> {
+        let rgb = parse_hex_rgb(hex)?;
+        Ok(Self {
+            color: rgb.map(|v| v as f32 / 255.0),
+            tolerance: tolerance.unwrap_or(DEFAULT_TOLERANCE).clamp(0.0, 1.0),
+        })
+    }
+
+    /// Whether an 8-bit RGB pixel matches the key
+    pub fn matches(&self, rgb: [u8; 3]) -> bool {
+        rgb.iter()
+            .zip(self.color)
+            .all(|(&c, key)| (c as f32 / 255.0 - key).abs() <= self.tolerance)
+    }
+
+    /// Make every matching pixel fully transparent
+    pub fn apply(&self, image: &mut RgbaImage) {
+        for pixel in image.pixels_mut() {
+            if self.matches([pixel[0], pixel[1], pixel[2]]) {
+                pixel[3] = 0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    fn test_parse_hex() {
+        let key = ColorKey::from_hex("#00ff00", Some(0.2)).unwrap();
+        assert_eq!(key.color, [0.0, 1.0, 0.0]);
+        assert_eq!(key.tolerance, 0.2);
+
+        let short = ColorKey::from_hex("#f0f", None).unwrap();
+        assert_eq!(short.color, [1.0, 0.0, 1.0]);
+        assert_eq!(short.tolerance, DEFAULT_TOLERANCE);
+
+        assert!(ColorKey::from_hex("#12345", None).is_err());
+        assert!(ColorKey::from_hex("#gggggg", None).is_err());
+    }
+
+    #[test]
+    fn test_tolerance_is_clamped() {
+        let key = ColorKey::from_hex("#000000", Some(5.0)).unwrap();
+        assert_eq!(key.tolerance, 1.0);
+    }
+
+    #[test]
+    fn test_apply_keys_out_matching_pixels() {
+        let key = ColorKey::from_hex("#00ff00", Some(0.1)).unwrap();
+        let mut image = RgbaImage::new(3, 1);
+        image.put_pixel(0, 0, Rgba([0, 255, 0, 255]));
+        image.put_pixel(1, 0, Rgba([10, 240, 5, 255]));
+        image.put_pixel(2, 0, Rgba([255, 0, 0, 255]));
+
+        key.apply(&mut image);
+
+        assert_eq!(image.get_pixel(0, 0)[3], 0);
+        assert_eq!(image.get_pixel(1, 0)[3], 0);
+        assert_eq!(image.get_pixel(2, 0)[3], 255);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod animation;
+pub mod color_key;
 pub mod loader;
 // pub mod renderer;
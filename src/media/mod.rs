@@ -3,7 +3,10 @@
 //! Each module handles a specific type of media display or playback.
 
 pub mod audio;
+pub mod capture;
+pub mod idle;
 pub mod image;
+pub mod mouse;
 pub mod video;
 pub mod wallpaper;
 pub mod write_lines;
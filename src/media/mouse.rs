@@ -0,0 +1,142 @@
+//! Time-limited mouse effects (jiggle, confine)
+//!
+//! Each effect runs on its own thread and polls the cursor. Nothing is grabbed at
+//! the OS level, so the cursor is free again as soon as the thread stops, whether
+//! the effect ran to completion, was released, or was dropped with the runtime.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Hard upper bound for any mouse effect
+pub const MAX_EFFECT_DURATION: Duration = Duration::from_secs(30);
+
+/// Clamp a requested effect duration to `MAX_EFFECT_DURATION`
+pub fn clamp_effect_duration(duration_ms: u64) -> Duration {
+    Duration::from_millis(duration_ms).min(MAX_EFFECT_DURATION)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MouseEffectHandle(pub Uuid);
+
+/// A running mouse effect. Dropping it stops the effect.
+pub struct MouseEffect {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MouseEffect {
+    /// Run `tick` every `interval` until `duration` elapses or the effect is stopped.
+    ///
+    /// `setup` runs on the effect thread, so platform input handles that are not
+    /// `Send` can be created there.
+    pub fn spawn<F, T>(duration: Duration, interval: Duration, setup: F) -> Self
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: FnMut(),
+    {
+        let duration = duration.min(MAX_EFFECT_DURATION);
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+
+        let thread = std::thread::spawn(move || {
+            let started = Instant::now();
+            let mut tick = setup();
+            while !thread_stop.load(Ordering::Relaxed) && started.elapsed() < duration {
+                tick();
+                std::thread::sleep(interval);
+            }
+        });
+
+        Self {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    /// Whether the effect has finished on its own
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(|t| t.is_finished())
+    }
+
+    /// Stop the effect and wait for its thread to exit
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MouseEffect {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Active mouse effects for a runtime, released when the runtime is dropped
+#[derive(Default)]
+pub struct MouseEffects {
+    effects: HashMap<MouseEffectHandle, MouseEffect>,
+}
+
+impl MouseEffects {
+    pub fn insert(&mut self, effect: MouseEffect) -> MouseEffectHandle {
+        self.effects.retain(|_, e| !e.is_finished());
+        let handle = MouseEffectHandle(Uuid::new_v4());
+        self.effects.insert(handle, effect);
+        handle
+    }
+
+    pub fn release(&mut self, handle: MouseEffectHandle) {
+        self.effects.remove(&handle);
+    }
+
+    pub fn release_all(&mut self) {
+        self.effects.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_duration_is_clamped() {
+        assert_eq!(clamp_effect_duration(500), Duration::from_millis(500));
+        assert_eq!(clamp_effect_duration(10 * 60 * 1000), MAX_EFFECT_DURATION);
+    }
+
+    #[test]
+    fn test_drop_releases_effect() {
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let counter = ticks.clone();
+        let effect = MouseEffect::spawn(MAX_EFFECT_DURATION, Duration::from_millis(1), move || {
+            move || {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+        std::thread::sleep(Duration::from_millis(20));
+        drop(effect);
+
+        let after_drop = ticks.load(Ordering::Relaxed);
+        assert!(after_drop > 0);
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(ticks.load(Ordering::Relaxed), after_drop);
+    }
+
+    #[test]
+    fn test_effect_finishes_after_duration() {
+        let effect =
+            MouseEffect::spawn(Duration::from_millis(10), Duration::from_millis(1), || {
+                || {}
+            });
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(effect.is_finished());
+    }
+}
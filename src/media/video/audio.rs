@@ -2,29 +2,67 @@
 //!
 //! Handles audio decoding and playback via cpal, synchronized with video.
 
-use cpal::SizedSample;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Sample, SizedSample};
 use futures::FutureExt;
 use futures::future::OptionFuture;
 use ringbuf::HeapRb;
 use ringbuf::traits::{Consumer, Producer, Split};
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Duration;
 
 use super::ControlCommand;
+use super::player::StreamMessage;
+
+/// Volume of a video's audio, shared with the output stream so it can change while playing
+#[derive(Clone, Debug)]
+pub struct SharedVolume(Arc<AtomicU32>);
+
+impl SharedVolume {
+    /// Start at `volume`, clamped to 0.0 (silent) to 1.0 (full)
+    pub fn new(volume: f32) -> Self {
+        let shared = Self(Arc::new(AtomicU32::new(0)));
+        shared.set(volume);
+        shared
+    }
+
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    /// Change the volume, clamped to 0.0 to 1.0
+    pub fn set(&self, volume: f32) {
+        let volume = if volume.is_nan() {
+            0.0
+        } else {
+            volume.clamp(0.0, 1.0)
+        };
+        self.0.store(volume.to_bits(), Ordering::Relaxed);
+    }
+}
 
 /// Audio playback thread that processes packets from the demuxer
 pub struct AudioPlaybackThread {
     control_sender: smol::channel::Sender<ControlCommand>,
-    packet_sender: smol::channel::Sender<ffmpeg_next::codec::packet::packet::Packet>,
+    packet_sender: smol::channel::Sender<StreamMessage>,
+    /// Set from a seek until its flush arrives, packets in between are stale
+    seeking: Arc<AtomicBool>,
     receiver_thread: Option<std::thread::JoinHandle<()>>,
 }
 
 impl AudioPlaybackThread {
-    /// Start the audio playback thread for the given audio stream
-    pub fn start(stream: &ffmpeg_next::format::stream::Stream) -> Result<Self, anyhow::Error> {
+    /// Start the audio playback thread for the given audio stream, played at `volume`
+    pub fn start(
+        stream: &ffmpeg_next::format::stream::Stream,
+        volume: SharedVolume,
+    ) -> Result<Self, anyhow::Error> {
         let (control_sender, control_receiver) = smol::channel::unbounded();
         let (packet_sender, packet_receiver) = smol::channel::bounded(128);
+        let seeking = Arc::new(AtomicBool::new(false));
+        let seeking_for_thread = seeking.clone();
 
         let decoder_context = ffmpeg_next::codec::Context::from_parameters(stream.parameters())?;
         let packet_decoder = decoder_context.decoder().audio()?;
@@ -56,6 +94,8 @@ impl AudioPlaybackThread {
                         cpal::SampleFormat::U8 => FFmpegToCpalForwarder::new::<u8>(
                             config,
                             &device,
+                            volume.clone(),
+                            seeking_for_thread.clone(),
                             packet_receiver,
                             packet_decoder,
                             ffmpeg_next::util::format::sample::Sample::U8(
@@ -66,6 +106,8 @@ impl AudioPlaybackThread {
                         cpal::SampleFormat::F32 => FFmpegToCpalForwarder::new::<f32>(
                             config,
                             &device,
+                            volume.clone(),
+                            seeking_for_thread.clone(),
                             packet_receiver,
                             packet_decoder,
                             ffmpeg_next::util::format::sample::Sample::F32(
@@ -79,24 +121,24 @@ impl AudioPlaybackThread {
                         }
                     };
 
-                    let packet_receiver_impl = async { ffmpeg_to_cpal_forwarder.stream().await }
-                        .fuse()
-                        .shared();
-
                     let mut playing = true;
+                    let mut finished = false;
 
                     loop {
-                        let packet_receiver: OptionFuture<_> = if playing {
-                            Some(packet_receiver_impl.clone())
+                        // While seeking stale packets are dropped even when paused
+                        let wants_packets =
+                            !finished && (playing || seeking_for_thread.load(Ordering::Acquire));
+                        let next_message: OptionFuture<_> = if wants_packets {
+                            Some(ffmpeg_to_cpal_forwarder.next())
                         } else {
                             None
                         }
                         .into();
 
-                        smol::pin!(packet_receiver);
+                        smol::pin!(next_message);
 
                         futures::select! {
-                            _ = packet_receiver => {},
+                            more = next_message => finished = more == Some(false),
                             received_command = control_receiver.recv().fuse() => {
                                 match received_command {
                                     Ok(ControlCommand::Pause) => {
@@ -105,6 +147,8 @@ impl AudioPlaybackThread {
                                     Ok(ControlCommand::Play) => {
                                         playing = true;
                                     }
+                                    // Wakes the thread up to drop the stale packets
+                                    Ok(ControlCommand::Seek(_)) => {}
                                     Err(_) => {
                                         // Channel closed -> quit
                                         return;
@@ -119,13 +163,14 @@ impl AudioPlaybackThread {
         Ok(Self {
             control_sender,
             packet_sender,
+            seeking,
             receiver_thread: Some(receiver_thread),
         })
     }
 
     /// Send a packet to the audio thread for decoding
     pub async fn receive_packet(&self, packet: ffmpeg_next::codec::packet::packet::Packet) -> bool {
-        match self.packet_sender.send(packet).await {
+        match self.packet_sender.send(StreamMessage::Packet(packet)).await {
             Ok(_) => true,
             Err(smol::channel::SendError(_)) => false,
         }
@@ -135,6 +180,18 @@ impl AudioPlaybackThread {
     pub async fn send_control_message(&self, message: ControlCommand) {
         let _ = self.control_sender.send(message).await;
     }
+
+    /// Drop packets until the demuxer has seeked and called [`Self::flush`]
+    pub async fn begin_seek(&self) {
+        self.seeking.store(true, Ordering::Release);
+        self.send_control_message(ControlCommand::Seek(Duration::ZERO))
+            .await;
+    }
+
+    /// Packets from now on come from the seeked position
+    pub async fn flush(&self) {
+        let _ = self.packet_sender.send(StreamMessage::Flush).await;
+    }
 }
 
 impl Drop for AudioPlaybackThread {
@@ -176,7 +233,8 @@ impl<T: Copy + 'static, P: Producer<Item = T>> FFmpegToCpalSampleForwarder for P
 struct FFmpegToCpalForwarder {
     _cpal_stream: cpal::Stream,
     ffmpeg_to_cpal_pipe: Box<dyn FFmpegToCpalSampleForwarder>,
-    packet_receiver: smol::channel::Receiver<ffmpeg_next::codec::packet::packet::Packet>,
+    seeking: Arc<AtomicBool>,
+    packet_receiver: smol::channel::Receiver<StreamMessage>,
     packet_decoder: ffmpeg_next::decoder::Audio,
     resampler: ffmpeg_next::software::resampling::Context,
 }
@@ -185,7 +243,9 @@ impl FFmpegToCpalForwarder {
     fn new<T: Send + SizedSample + 'static>(
         config: cpal::SupportedStreamConfig,
         device: &cpal::Device,
-        packet_receiver: smol::channel::Receiver<ffmpeg_next::codec::packet::packet::Packet>,
+        volume: SharedVolume,
+        seeking: Arc<AtomicBool>,
+        packet_receiver: smol::channel::Receiver<StreamMessage>,
         packet_decoder: ffmpeg_next::decoder::Audio,
         output_format: ffmpeg_next::util::format::sample::Sample,
         output_channel_layout: ffmpeg_next::util::channel_layout::ChannelLayout,
@@ -199,6 +259,14 @@ impl FFmpegToCpalForwarder {
                 move |data: &mut [T], _| {
                     let filled = sample_consumer.pop_slice(data);
                     data[filled..].fill(T::EQUILIBRIUM);
+
+                    let volume = volume.get();
+                    if volume < 1.0 {
+                        let amp = <T::Float as Sample>::from_sample(volume);
+                        for sample in &mut data[..filled] {
+                            *sample = sample.mul_amp(amp);
+                        }
+                    }
                 },
                 move |err| {
                     eprintln!("Error feeding audio stream to cpal: {}", err);
@@ -222,34 +290,45 @@ impl FFmpegToCpalForwarder {
         Self {
             _cpal_stream: cpal_stream,
             ffmpeg_to_cpal_pipe: Box::new(sample_producer),
+            seeking,
             packet_receiver,
             packet_decoder,
             resampler,
         }
     }
 
-    async fn stream(&mut self) {
-        loop {
-            let Ok(packet) = self.packet_receiver.recv().await else {
-                break;
-            };
-
-            self.packet_decoder.send_packet(&packet).unwrap();
-
-            let mut decoded_frame = ffmpeg_next::util::frame::Audio::empty();
-
-            while self
-                .packet_decoder
-                .receive_frame(&mut decoded_frame)
-                .is_ok()
-            {
-                let mut resampled_frame = ffmpeg_next::util::frame::Audio::empty();
-                self.resampler
-                    .run(&decoded_frame, &mut resampled_frame)
-                    .unwrap();
-
-                self.ffmpeg_to_cpal_pipe.forward(resampled_frame).await;
+    /// Play the next message from the demuxer, false once there are no more
+    async fn next(&mut self) -> bool {
+        let Ok(message) = self.packet_receiver.recv().await else {
+            return false;
+        };
+
+        match message {
+            // Packets from before a seek are dropped
+            StreamMessage::Packet(_) if self.seeking.load(Ordering::Acquire) => {}
+            StreamMessage::Packet(packet) => {
+                self.packet_decoder.send_packet(&packet).unwrap();
+
+                let mut decoded_frame = ffmpeg_next::util::frame::Audio::empty();
+
+                while self
+                    .packet_decoder
+                    .receive_frame(&mut decoded_frame)
+                    .is_ok()
+                {
+                    let mut resampled_frame = ffmpeg_next::util::frame::Audio::empty();
+                    self.resampler
+                        .run(&decoded_frame, &mut resampled_frame)
+                        .unwrap();
+
+                    self.ffmpeg_to_cpal_pipe.forward(resampled_frame).await;
+                }
+            }
+            StreamMessage::Flush => {
+                self.packet_decoder.flush();
+                self.seeking.store(false, Ordering::Release);
             }
         }
+        true
     }
 }
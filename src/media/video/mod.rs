@@ -6,5 +6,6 @@
 
 pub mod audio;
 pub mod player;
+pub mod probe;
 
 pub use player::{ControlCommand, Player, VideoHandle};
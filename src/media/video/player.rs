@@ -5,18 +5,41 @@
 
 use anyhow::Result;
 use futures::{FutureExt, future::OptionFuture};
+use std::cell::Cell;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 use uuid::Uuid;
 
-use super::audio::AudioPlaybackThread;
+use super::audio::{AudioPlaybackThread, SharedVolume};
 
 /// Control commands for the player
 #[derive(Clone, Copy, Debug)]
 pub enum ControlCommand {
     Play,
     Pause,
+    /// Jump to a position from the start of the video
+    Seek(Duration),
+}
+
+/// What the demuxer hands to the decoding threads
+pub enum StreamMessage {
+    Packet(ffmpeg_next::codec::packet::Packet),
+    /// The demuxer jumped to another position, the next packet starts from there
+    Flush,
+}
+
+/// Where to seek in a video lasting `duration_us`, in microseconds.
+///
+/// `None` when the position is at or past the end, where playback ends as if
+/// the video had played through.
+fn seek_target(position: Duration, duration_us: i64) -> Option<i64> {
+    let target = i64::try_from(position.as_micros()).unwrap_or(i64::MAX);
+    if duration_us > 0 && target >= duration_us {
+        return None;
+    }
+    Some(target)
 }
 
 /// Unique handle for a video instance
@@ -41,6 +64,7 @@ pub struct Player {
     demuxer_thread: Option<std::thread::JoinHandle<()>>,
     playing: bool,
     playing_changed_callback: Arc<dyn Fn(bool) + Send + Sync>,
+    volume: SharedVolume,
 }
 
 impl Player {
@@ -48,10 +72,14 @@ impl Player {
     ///
     /// # Arguments
     /// * `path` - Path to the video file (can be a URL or local path)
+    /// * `loop_playback` - Start over from the beginning when the end is reached
+    /// * `volume` - Audio volume from 0.0 (silent) to 1.0 (full)
     /// * `video_frame_callback` - Called with each decoded video frame
     /// * `playing_changed_callback` - Called when play/pause state changes
     pub fn start<P: Into<PathBuf>>(
         path: P,
+        loop_playback: bool,
+        volume: f32,
         video_frame_callback: impl FnMut(&ffmpeg_next::util::frame::Video) + Send + 'static,
         playing_changed_callback: impl Fn(bool) + Send + Sync + 'static,
     ) -> Result<Self> {
@@ -59,6 +87,8 @@ impl Player {
         let (control_sender, control_receiver) = smol::channel::unbounded();
         let playing_changed = Arc::new(playing_changed_callback);
         let playing_changed_for_thread = playing_changed.clone();
+        let volume = SharedVolume::new(volume);
+        let volume_for_thread = volume.clone();
 
         let demuxer_thread = std::thread::Builder::new()
             .name("video demuxer thread".into())
@@ -103,23 +133,61 @@ impl Player {
                         .best(ffmpeg_next::media::Type::Audio)
                         .and_then(|audio_stream| {
                             let audio_stream_index = audio_stream.index();
-                            AudioPlaybackThread::start(&audio_stream)
+                            AudioPlaybackThread::start(&audio_stream, volume_for_thread)
                                 .ok()
                                 .map(|thread| (audio_stream_index, thread))
                         });
 
+                    let duration_us = input_context.duration();
                     let mut playing = true;
+                    // Latest seek the packet forwarder hasn't made yet
+                    let pending_seek: Cell<Option<Duration>> = Cell::new(None);
 
                     // Packet forwarding future
                     let packet_forwarder_impl = async {
-                        for (stream, packet) in input_context.packets() {
+                        loop {
+                            if let Some(position) = pending_seek.take() {
+                                let Some(target) = seek_target(position, duration_us) else {
+                                    if loop_playback {
+                                        pending_seek.set(Some(Duration::ZERO));
+                                        continue;
+                                    }
+                                    break;
+                                };
+                                // Back to the keyframe at or before the target
+                                if let Err(e) = input_context.seek(target, ..target) {
+                                    eprintln!("Failed to seek video: {}", e);
+                                }
+                                video_playback_thread.flush().await;
+                                if let Some((_, ref audio_thread)) = audio_info {
+                                    audio_thread.flush().await;
+                                }
+                            }
+
+                            let mut packet = ffmpeg_next::codec::packet::Packet::empty();
+                            match packet.read(&mut input_context) {
+                                Ok(()) => {}
+                                Err(ffmpeg_next::Error::Eof) => {
+                                    // Start over, the clock follows the timestamps back to zero
+                                    if !loop_playback {
+                                        break;
+                                    }
+                                    if let Err(e) = input_context.seek(0, ..) {
+                                        eprintln!("Failed to loop video: {}", e);
+                                        break;
+                                    }
+                                    continue;
+                                }
+                                Err(_) => continue,
+                            }
+
                             if let Some((audio_idx, ref audio_thread)) = audio_info
-                                && stream.index() == audio_idx
+                                && packet.stream() == audio_idx
                             {
-                                audio_thread.receive_packet(packet.clone()).await;
+                                audio_thread.receive_packet(packet).await;
                                 continue;
                             }
-                            if stream.index() == video_stream_index {
+                            if packet.stream() == video_stream_index {
                                 video_playback_thread.receive_packet(packet).await;
                             }
                         }
@@ -128,12 +196,14 @@ impl Player {
                     .shared();
 
                     loop {
-                        let packet_forwarder: OptionFuture<_> = if playing {
-                            Some(packet_forwarder_impl.clone())
-                        } else {
-                            None
-                        }
-                        .into();
+                        // A seek while paused still goes through, to show the new frame
+                        let packet_forwarder: OptionFuture<_> =
+                            if playing || pending_seek.get().is_some() {
+                                Some(packet_forwarder_impl.clone())
+                            } else {
+                                None
+                            }
+                            .into();
 
                         smol::pin!(packet_forwarder);
 
@@ -144,19 +214,19 @@ impl Player {
                             },
                             received_command = control_receiver.recv().fuse() => {
                                 match received_command {
+                                    Ok(ControlCommand::Seek(position)) => {
+                                        pending_seek.set(Some(position));
+                                        video_playback_thread.begin_seek().await;
+                                        if let Some((_, ref audio_thread)) = audio_info {
+                                            audio_thread.begin_seek().await;
+                                        }
+                                    }
                                     Ok(command) => {
                                         video_playback_thread.send_control_message(command).await;
                                         if let Some((_, ref audio_thread)) = audio_info {
                                             audio_thread.send_control_message(command).await;
                                         }
-                                        match command {
-                                            ControlCommand::Play => {
-                                                playing = true;
-                                            },
-                                            ControlCommand::Pause => {
-                                                playing = false;
-                                            }
-                                        }
+                                        playing = matches!(command, ControlCommand::Play);
                                         playing_changed_for_thread(playing);
                                     }
                                     Err(_) => {
@@ -178,6 +248,7 @@ impl Player {
             demuxer_thread: Some(demuxer_thread),
             playing,
             playing_changed_callback: playing_changed,
+            volume,
         })
     }
 
@@ -215,6 +286,26 @@ impl Player {
     pub fn is_playing(&self) -> bool {
         self.playing
     }
+
+    /// Set the audio volume, clamped to 0.0 (silent) to 1.0 (full)
+    pub fn set_volume(&self, volume: f32) {
+        self.volume.set(volume);
+    }
+
+    /// Current audio volume
+    pub fn volume(&self) -> f32 {
+        self.volume.get()
+    }
+
+    /// Jump to `position` from the start of the video.
+    ///
+    /// A paused video shows the frame there and stays paused. Seeking past the
+    /// end stops playback, or starts over for a looping video.
+    pub fn seek(&self, position: Duration) {
+        let _ = self
+            .control_sender
+            .send_blocking(ControlCommand::Seek(position));
+    }
 }
 
 impl Drop for Player {
@@ -229,7 +320,9 @@ impl Drop for Player {
 /// Video playback thread that handles decoding and timing
 struct VideoPlaybackThread {
     control_sender: smol::channel::Sender<ControlCommand>,
-    packet_sender: smol::channel::Sender<ffmpeg_next::codec::packet::packet::Packet>,
+    packet_sender: smol::channel::Sender<StreamMessage>,
+    /// Set from a seek until its flush arrives, packets in between are stale
+    seeking: Arc<AtomicBool>,
     receiver_thread: Option<std::thread::JoinHandle<()>>,
 }
 
@@ -240,64 +333,85 @@ impl VideoPlaybackThread {
     ) -> Result<Self> {
         let (control_sender, control_receiver) = smol::channel::unbounded();
         let (packet_sender, packet_receiver) = smol::channel::bounded(128);
+        let seeking = Arc::new(AtomicBool::new(false));
+        let seeking_for_thread = seeking.clone();
 
         let decoder_context = ffmpeg_next::codec::Context::from_parameters(stream.parameters())?;
         let mut packet_decoder = decoder_context.decoder().video()?;
 
-        let clock = StreamClock::new(stream);
+        let mut clock = StreamClock::new(stream);
 
         let receiver_thread = std::thread::Builder::new()
             .name("video playback thread".into())
             .spawn(move || {
                 smol::block_on(async move {
-                    let packet_receiver_impl = async {
-                        loop {
-                            let Ok(packet) = packet_receiver.recv().await else {
-                                break;
-                            };
-
-                            smol::future::yield_now().await;
-
-                            packet_decoder.send_packet(&packet).unwrap();
-
-                            let mut decoded_frame = ffmpeg_next::util::frame::Video::empty();
-
-                            while packet_decoder.receive_frame(&mut decoded_frame).is_ok() {
-                                if let Some(delay) =
-                                    clock.convert_pts_to_instant(decoded_frame.pts())
-                                {
-                                    smol::Timer::after(delay).await;
-                                }
-
-                                video_frame_callback(&decoded_frame);
-                            }
-                        }
-                    }
-                    .fuse()
-                    .shared();
-
+                    let seeking = seeking_for_thread;
                     let mut playing = true;
+                    // Show one frame after seeking while paused
+                    let mut preview = false;
+                    let mut finished = false;
 
                     loop {
-                        let packet_receiver: OptionFuture<_> = if playing {
-                            Some(packet_receiver_impl.clone())
+                        let wants_packets =
+                            !finished && (playing || preview || seeking.load(Ordering::Acquire));
+                        let next_message: OptionFuture<_> = if wants_packets {
+                            Some(packet_receiver.recv())
                         } else {
                             None
                         }
                         .into();
 
-                        smol::pin!(packet_receiver);
+                        smol::pin!(next_message);
 
                         futures::select! {
-                            _ = packet_receiver => {},
+                            message = next_message => match message {
+                                // Packets from before a seek are dropped
+                                Some(Ok(StreamMessage::Packet(_)))
+                                    if seeking.load(Ordering::Acquire) => {}
+                                Some(Ok(StreamMessage::Packet(packet))) => {
+                                    packet_decoder.send_packet(&packet).unwrap();
+
+                                    let mut decoded_frame =
+                                        ffmpeg_next::util::frame::Video::empty();
+
+                                    while packet_decoder.receive_frame(&mut decoded_frame).is_ok()
+                                    {
+                                        if preview {
+                                            video_frame_callback(&decoded_frame);
+                                            preview = false;
+                                            break;
+                                        }
+
+                                        if let Some(delay) =
+                                            clock.convert_pts_to_instant(decoded_frame.pts())
+                                        {
+                                            smol::Timer::after(delay).await;
+                                        }
+
+                                        video_frame_callback(&decoded_frame);
+                                    }
+                                }
+                                Some(Ok(StreamMessage::Flush)) => {
+                                    packet_decoder.flush();
+                                    clock.restart();
+                                    seeking.store(false, Ordering::Release);
+                                    preview = !playing;
+                                }
+                                // The demuxer is done
+                                _ => finished = true,
+                            },
                             received_command = control_receiver.recv().fuse() => {
                                 match received_command {
                                     Ok(ControlCommand::Pause) => {
                                         playing = false;
                                     }
                                     Ok(ControlCommand::Play) => {
+                                        // Time frames from now, not from before the pause
+                                        clock.restart();
                                         playing = true;
                                     }
+                                    // Wakes the thread up to drop the stale packets
+                                    Ok(ControlCommand::Seek(_)) => {}
                                     Err(_) => {
                                         // Channel closed -> quit
                                         return;
@@ -312,12 +426,13 @@ impl VideoPlaybackThread {
         Ok(Self {
             control_sender,
             packet_sender,
+            seeking,
             receiver_thread: Some(receiver_thread),
         })
     }
 
-    async fn receive_packet(&self, packet: ffmpeg_next::codec::packet::packet::Packet) -> bool {
-        match self.packet_sender.send(packet).await {
+    async fn receive_packet(&self, packet: ffmpeg_next::codec::packet::Packet) -> bool {
+        match self.packet_sender.send(StreamMessage::Packet(packet)).await {
             Ok(_) => true,
             Err(smol::channel::SendError(_)) => false,
         }
@@ -326,6 +441,18 @@ impl VideoPlaybackThread {
     async fn send_control_message(&self, message: ControlCommand) {
         let _ = self.control_sender.send(message).await;
     }
+
+    /// Drop packets until the demuxer has seeked and called [`Self::flush`]
+    async fn begin_seek(&self) {
+        self.seeking.store(true, Ordering::Release);
+        self.send_control_message(ControlCommand::Seek(Duration::ZERO))
+            .await;
+    }
+
+    /// Packets from now on come from the seeked position
+    async fn flush(&self) {
+        let _ = self.packet_sender.send(StreamMessage::Flush).await;
+    }
 }
 
 impl Drop for VideoPlaybackThread {
@@ -341,27 +468,52 @@ impl Drop for VideoPlaybackThread {
 struct StreamClock {
     time_base_seconds: f64,
     start_time: Instant,
+    /// Timestamp of the last frame, to notice when a looping video starts over
+    last_pts: Option<i64>,
+    /// Time the next frame from now, after a seek or pause
+    restart: bool,
 }
 
 impl StreamClock {
     fn new(stream: &ffmpeg_next::format::stream::Stream) -> Self {
         let time_base = stream.time_base();
         let time_base_seconds = time_base.numerator() as f64 / time_base.denominator() as f64;
+        Self::with_time_base(time_base_seconds, Instant::now())
+    }
 
-        let start_time = Instant::now();
-
+    fn with_time_base(time_base_seconds: f64, start_time: Instant) -> Self {
         Self {
             time_base_seconds,
             start_time,
+            last_pts: None,
+            restart: false,
         }
     }
 
-    fn convert_pts_to_instant(&self, pts: Option<i64>) -> Option<Duration> {
-        pts.and_then(|pts| {
-            let pts_since_start = Duration::from_secs_f64(pts as f64 * self.time_base_seconds);
-            self.start_time.checked_add(pts_since_start)
-        })
-        .map(|absolute_pts| absolute_pts.saturating_duration_since(Instant::now()))
+    /// Show the next frame right away and time the ones after it from there
+    fn restart(&mut self) {
+        self.restart = true;
+    }
+
+    fn convert_pts_to_instant(&mut self, pts: Option<i64>) -> Option<Duration> {
+        self.delay_at(pts, Instant::now())
+    }
+
+    /// How long to wait at `now` before showing the frame with timestamp `pts`
+    fn delay_at(&mut self, pts: Option<i64>, now: Instant) -> Option<Duration> {
+        let pts = pts?;
+        let pts_since_start = Duration::from_secs_f64(pts.max(0) as f64 * self.time_base_seconds);
+
+        // Timestamps going back means the video started over, so the clock does too
+        if self.restart || self.last_pts.is_some_and(|last| pts < last) {
+            self.restart = false;
+            self.start_time = now.checked_sub(pts_since_start).unwrap_or(now);
+        }
+        self.last_pts = Some(pts);
+
+        self.start_time
+            .checked_add(pts_since_start)
+            .map(|absolute_pts| absolute_pts.saturating_duration_since(now))
     }
 }
 
@@ -406,3 +558,78 @@ pub fn video_frame_to_pixel_buffer(
 
     pixel_buffer
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_restarts_when_video_loops() {
+        let start = Instant::now();
+        // Millisecond timestamps
+        let mut clock = StreamClock::with_time_base(0.001, start);
+
+        assert_eq!(clock.delay_at(Some(0), start), Some(Duration::ZERO));
+        assert_eq!(
+            clock.delay_at(Some(500), start + Duration::from_millis(100)),
+            Some(Duration::from_millis(400))
+        );
+        // A frame that is already late is shown right away
+        assert_eq!(
+            clock.delay_at(Some(1000), start + Duration::from_millis(1200)),
+            Some(Duration::ZERO)
+        );
+
+        // Back at the start of the video, frames are timed from now again
+        let looped = start + Duration::from_secs(10);
+        assert_eq!(clock.delay_at(Some(0), looped), Some(Duration::ZERO));
+        assert_eq!(
+            clock.delay_at(Some(40), looped),
+            Some(Duration::from_millis(40))
+        );
+        assert_eq!(clock.delay_at(None, looped), None);
+    }
+
+    #[test]
+    fn test_clock_restarts_after_seek() {
+        let start = Instant::now();
+        let mut clock = StreamClock::with_time_base(0.001, start);
+        assert_eq!(clock.delay_at(Some(0), start), Some(Duration::ZERO));
+
+        // Jumping ahead shows the new frame right away instead of waiting for it
+        clock.restart();
+        let seeked = start + Duration::from_millis(10);
+        assert_eq!(clock.delay_at(Some(5000), seeked), Some(Duration::ZERO));
+        assert_eq!(
+            clock.delay_at(Some(5040), seeked),
+            Some(Duration::from_millis(40))
+        );
+    }
+
+    #[test]
+    fn test_seek_target() {
+        let ten_seconds = 10_000_000;
+        assert_eq!(
+            seek_target(Duration::from_millis(2500), ten_seconds),
+            Some(2_500_000)
+        );
+        assert_eq!(seek_target(Duration::ZERO, ten_seconds), Some(0));
+        // At or past the end playback ends
+        assert_eq!(seek_target(Duration::from_secs(10), ten_seconds), None);
+        assert_eq!(seek_target(Duration::from_secs(60), ten_seconds), None);
+        // Without a known duration the demuxer finds out
+        assert_eq!(seek_target(Duration::from_secs(60), 0), Some(60_000_000));
+    }
+
+    #[test]
+    fn test_volume_is_clamped() {
+        let volume = SharedVolume::new(1.5);
+        assert_eq!(volume.get(), 1.0);
+        volume.set(0.25);
+        assert_eq!(volume.get(), 0.25);
+        volume.set(-1.0);
+        assert_eq!(volume.get(), 0.0);
+        volume.set(f32::NAN);
+        assert_eq!(volume.get(), 0.0);
+    }
+}
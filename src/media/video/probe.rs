@@ -0,0 +1,86 @@
+//! Reading a video's length and size without decoding it
+//!
+//! Probing opens the container with FFmpeg, which adds up over a large pack,
+//! so results are cached per file until it changes.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Length and size of a video file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoInfo {
+    pub duration: Option<Duration>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Probed files, with the modification time and size they had when probed
+static CACHE: LazyLock<Mutex<HashMap<PathBuf, (SystemTime, u64, VideoInfo)>>> =
+    LazyLock::new(Default::default);
+
+/// Duration and dimensions of the best video stream in `path`
+pub fn probe(path: &Path) -> Result<VideoInfo> {
+    let metadata = std::fs::metadata(path)?;
+    let stamp = (metadata.modified()?, metadata.len());
+    if let Some(&(modified, len, info)) = CACHE.lock().unwrap().get(path)
+        && (modified, len) == stamp
+    {
+        return Ok(info);
+    }
+
+    let info = probe_uncached(path)?;
+    CACHE
+        .lock()
+        .unwrap()
+        .insert(path.to_path_buf(), (stamp.0, stamp.1, info));
+    Ok(info)
+}
+
+fn probe_uncached(path: &Path) -> Result<VideoInfo> {
+    let input = ffmpeg_next::format::input(path)?;
+    let stream = input
+        .streams()
+        .best(ffmpeg_next::media::Type::Video)
+        .context("No video stream found")?;
+    let decoder = ffmpeg_next::codec::context::Context::from_parameters(stream.parameters())?
+        .decoder()
+        .video()?;
+
+    // The container duration is in AV_TIME_BASE units (microseconds)
+    let duration = u64::try_from(input.duration())
+        .ok()
+        .filter(|&micros| micros > 0)
+        .map(Duration::from_micros);
+
+    Ok(VideoInfo {
+        duration,
+        width: decoder.width(),
+        height: decoder.height(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_probe_fixture() {
+        let path = Path::new("packs/TestPack/video/test-bunny.mp4");
+        let info = probe(path).unwrap();
+        assert_eq!((info.width, info.height), (1920, 1080));
+        let duration = info.duration.unwrap();
+        assert!(
+            duration.abs_diff(Duration::from_secs(10)) < Duration::from_millis(100),
+            "{:?}",
+            duration
+        );
+
+        // The second probe comes from the cache
+        assert_eq!(probe(path).unwrap(), info);
+        assert!(probe(Path::new("packs/TestPack/video/missing.mp4")).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+//! Solid-color wallpapers
+//!
+//! The image is generated at the primary display's resolution and written to a
+//! temp file, which is deleted again once the original wallpaper is restored.
+
+use crate::media::capture::CaptureFiles;
+use anyhow::Result;
+use image::{Rgba, RgbaImage};
+use std::path::PathBuf;
+
+/// An opaque image filled with `color`
+pub fn solid_color(color: [u8; 3], width: u32, height: u32) -> RgbaImage {
+    let [r, g, b] = color;
+    RgbaImage::from_pixel(width, height, Rgba([r, g, b, 255]))
+}
+
+/// Generated color wallpapers, deleted on restore or when dropped
+pub struct ColorWallpapers {
+    files: CaptureFiles,
+}
+
+impl Default for ColorWallpapers {
+    fn default() -> Self {
+        Self::new(std::env::temp_dir().join("goon-ai-wallpapers"))
+    }
+}
+
+impl ColorWallpapers {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            files: CaptureFiles::new(dir),
+        }
+    }
+
+    /// Write a solid-color image and return its path
+    pub fn generate(&mut self, color: [u8; 3], width: u32, height: u32) -> Result<PathBuf> {
+        self.files.save(&solid_color(color, width, height))
+    }
+
+    /// Delete every generated wallpaper
+    pub fn cleanup(&mut self) {
+        self.files.cleanup();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_generated_wallpaper_size_and_color() {
+        let dir = std::env::temp_dir().join(format!("goon-ai-wallpapers-{}", Uuid::new_v4()));
+        let mut wallpapers = ColorWallpapers::new(dir.clone());
+
+        let path = wallpapers.generate([255, 128, 0], 64, 32).unwrap();
+        let image = image::open(&path).unwrap().into_rgba8();
+        assert_eq!(image.dimensions(), (64, 32));
+        assert!(image.pixels().all(|p| *p == Rgba([255, 128, 0, 255])));
+
+        wallpapers.cleanup();
+        assert!(!path.exists());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -1,13 +1,160 @@
-use super::WallpaperSetter;
+use super::{WallpaperMode, WallpaperSetter};
 use anyhow::{Result, anyhow};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
 use url::Url;
 
 pub struct LinuxWallpaperSetter;
 
+/// Whether the session runs on Wayland, where X11 tools like feh can't set the wallpaper
+fn is_wayland() -> bool {
+    env::var_os("WAYLAND_DISPLAY").is_some()
+        || env::var("XDG_SESSION_TYPE").is_ok_and(|t| t.eq_ignore_ascii_case("wayland"))
+}
+
+/// `org.gnome.desktop.background picture-options` value for a mode
+fn gnome_picture_option(mode: WallpaperMode) -> &'static str {
+    match mode {
+        WallpaperMode::Fill => "zoom",
+        WallpaperMode::Fit => "scaled",
+        WallpaperMode::Stretch => "stretched",
+        WallpaperMode::Center => "centered",
+        WallpaperMode::Tile => "wallpaper",
+    }
+}
+
+/// `FillMode` of the Plasma `org.kde.image` wallpaper plugin
+fn kde_fill_mode(mode: WallpaperMode) -> u8 {
+    match mode {
+        WallpaperMode::Stretch => 0,
+        WallpaperMode::Fit => 1,
+        WallpaperMode::Fill => 2,
+        WallpaperMode::Tile => 3,
+        WallpaperMode::Center => 6,
+    }
+}
+
+/// `image-style` of the XFCE desktop
+fn xfce_image_style(mode: WallpaperMode) -> u8 {
+    match mode {
+        WallpaperMode::Center => 1,
+        WallpaperMode::Tile => 2,
+        WallpaperMode::Stretch => 3,
+        WallpaperMode::Fit => 4,
+        WallpaperMode::Fill => 5,
+    }
+}
+
+fn feh_flag(mode: WallpaperMode) -> &'static str {
+    match mode {
+        WallpaperMode::Fill => "--bg-fill",
+        WallpaperMode::Fit => "--bg-max",
+        WallpaperMode::Stretch => "--bg-scale",
+        WallpaperMode::Center => "--bg-center",
+        WallpaperMode::Tile => "--bg-tile",
+    }
+}
+
+fn nitrogen_flag(mode: WallpaperMode) -> &'static str {
+    match mode {
+        WallpaperMode::Fill => "--set-zoom-fill",
+        WallpaperMode::Fit => "--set-zoom",
+        WallpaperMode::Stretch => "--set-scaled",
+        WallpaperMode::Center => "--set-centered",
+        WallpaperMode::Tile => "--set-tiled",
+    }
+}
+
+/// `swww img --resize` value, swww can't tile so that fills instead
+fn swww_resize(mode: WallpaperMode) -> &'static str {
+    match mode {
+        WallpaperMode::Fill | WallpaperMode::Tile => "crop",
+        WallpaperMode::Fit => "fit",
+        WallpaperMode::Stretch => "stretch",
+        WallpaperMode::Center => "no",
+    }
+}
+
+fn swaybg_mode(mode: WallpaperMode) -> &'static str {
+    match mode {
+        WallpaperMode::Fill => "fill",
+        WallpaperMode::Fit => "fit",
+        WallpaperMode::Stretch => "stretch",
+        WallpaperMode::Center => "center",
+        WallpaperMode::Tile => "tile",
+    }
+}
+
+/// Set the wallpaper with the Wayland tools wlroots compositors (sway, Hyprland) use
+fn set_wayland_wallpaper(path_str: &str, mode: WallpaperMode) -> Result<()> {
+    let swww_ok = Command::new("swww")
+        .args(["img", path_str, "--resize", swww_resize(mode)])
+        .output()
+        .is_ok_and(|o| o.status.success());
+    if swww_ok {
+        return Ok(());
+    }
+
+    // swaybg keeps running to draw the background, so replace any running instance
+    let _ = Command::new("pkill").args(["-x", "swaybg"]).output();
+    if Command::new("swaybg")
+        .args(["-i", path_str, "-m", swaybg_mode(mode)])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .is_ok()
+    {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "Failed to set wallpaper on Wayland, tried swww and swaybg"
+    ))
+}
+
+/// Best-effort read of the wallpaper set by swww or swaybg
+fn get_wayland_wallpaper() -> Result<PathBuf> {
+    if let Ok(output) = Command::new("swww").arg("query").output()
+        && output.status.success()
+        && let Some(path) = parse_swww_query(&String::from_utf8_lossy(&output.stdout))
+    {
+        return Ok(path);
+    }
+
+    if let Ok(output) = Command::new("pgrep").args(["-a", "swaybg"]).output()
+        && let Some(path) = parse_swaybg_args(&String::from_utf8_lossy(&output.stdout))
+    {
+        return Ok(path);
+    }
+
+    Err(anyhow!(
+        "Failed to get wallpaper on Wayland, tried swww and swaybg"
+    ))
+}
+
+/// Image shown on the first output in `swww query` output, e.g.
+/// `eDP-1: 1920x1080, scale: 1, currently displaying: image: /path/to/image.png`
+fn parse_swww_query(output: &str) -> Option<PathBuf> {
+    output.lines().find_map(|line| {
+        let (_, path) = line.split_once("image: ")?;
+        let path = path.trim();
+        (!path.is_empty()).then(|| PathBuf::from(path))
+    })
+}
+
+/// Image passed to a running swaybg in `pgrep -a swaybg` output, e.g.
+/// `1234 swaybg -i /path/to/image.png -m fill`
+fn parse_swaybg_args(output: &str) -> Option<PathBuf> {
+    output.lines().find_map(|line| {
+        let mut args = line.split_whitespace();
+        args.find(|&arg| arg == "-i" || arg == "--image")?;
+        args.next().map(PathBuf::from)
+    })
+}
+
 impl WallpaperSetter for LinuxWallpaperSetter {
     fn get_wallpaper(&self) -> Result<PathBuf> {
         let desktop = env::var("XDG_CURRENT_DESKTOP")
@@ -90,6 +237,8 @@ impl WallpaperSetter for LinuxWallpaperSetter {
                 PathBuf::from(first_uri)
             };
             Ok(path)
+        } else if is_wayland() {
+            get_wayland_wallpaper()
         } else {
             // Try to read from nitrogen config
             if let Ok(config_dir) = env::var("XDG_CONFIG_HOME") {
@@ -142,7 +291,7 @@ impl WallpaperSetter for LinuxWallpaperSetter {
         }
     }
 
-    fn set_wallpaper(&self, path: &Path) -> Result<()> {
+    fn set_wallpaper(&self, path: &Path, mode: WallpaperMode) -> Result<()> {
         let path_str = path.to_str().ok_or_else(|| anyhow!("Invalid path"))?;
         let desktop = env::var("XDG_CURRENT_DESKTOP")
             .unwrap_or_default()
@@ -164,6 +313,14 @@ impl WallpaperSetter for LinuxWallpaperSetter {
                     &uri,
                 ])
                 .output();
+            let _ = Command::new("gsettings")
+                .args([
+                    "set",
+                    "org.gnome.desktop.background",
+                    "picture-options",
+                    gnome_picture_option(mode),
+                ])
+                .output();
             Ok(())
         } else if desktop.contains("kde") || desktop.contains("plasma") {
             let uri = Url::from_file_path(path)
@@ -177,9 +334,11 @@ impl WallpaperSetter for LinuxWallpaperSetter {
                     d.wallpaperPlugin = "org.kde.image";
                     d.currentConfigGroup = Array("Wallpaper", "org.kde.image", "General");
                     d.writeConfig("Image", "{}");
+                    d.writeConfig("FillMode", {});
                 }}
                 "#,
-                uri
+                uri,
+                kde_fill_mode(mode)
             );
             Command::new("qdbus")
                 .args([
@@ -194,8 +353,10 @@ impl WallpaperSetter for LinuxWallpaperSetter {
         } else if desktop.contains("xfce") {
             // Try xfconf-query loop via shell
             let cmd = format!(
-                "xfconf-query -c xfce4-desktop -l | grep last-image | while read property; do xfconf-query -c xfce4-desktop -p \"$property\" -s \"{}\"; done",
-                path_str
+                "xfconf-query -c xfce4-desktop -l | grep last-image | while read property; do xfconf-query -c xfce4-desktop -p \"$property\" -s \"{}\"; done; \
+                 xfconf-query -c xfce4-desktop -l | grep image-style | while read property; do xfconf-query -c xfce4-desktop -p \"$property\" -s {}; done",
+                path_str,
+                xfce_image_style(mode)
             );
             Command::new("sh")
                 .arg("-c")
@@ -203,10 +364,12 @@ impl WallpaperSetter for LinuxWallpaperSetter {
                 .output()
                 .map(|_| ())
                 .map_err(|e| anyhow!("Failed to set XFCE wallpaper: {}", e))
+        } else if is_wayland() {
+            set_wayland_wallpaper(path_str, mode)
         } else {
             // Fallback to feh or nitrogen
             if Command::new("feh")
-                .arg("--bg-scale")
+                .arg(feh_flag(mode))
                 .arg(path_str)
                 .output()
                 .is_ok()
@@ -214,7 +377,7 @@ impl WallpaperSetter for LinuxWallpaperSetter {
                 return Ok(());
             }
             if Command::new("nitrogen")
-                .args(["--set-scaled", path_str])
+                .args([nitrogen_flag(mode), path_str])
                 .output()
                 .is_ok()
             {
@@ -227,3 +390,47 @@ impl WallpaperSetter for LinuxWallpaperSetter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modes_map_to_backend_options() {
+        assert_eq!(gnome_picture_option(WallpaperMode::default()), "zoom");
+        assert_eq!(kde_fill_mode(WallpaperMode::Fit), 1);
+        assert_eq!(xfce_image_style(WallpaperMode::Tile), 2);
+        assert_eq!(feh_flag(WallpaperMode::Fit), "--bg-max");
+        assert_eq!(nitrogen_flag(WallpaperMode::Center), "--set-centered");
+        // swww has no tiling, it falls back to fill
+        assert_eq!(
+            swww_resize(WallpaperMode::Tile),
+            swww_resize(WallpaperMode::Fill)
+        );
+        assert_eq!(swaybg_mode(WallpaperMode::Stretch), "stretch");
+    }
+
+    #[test]
+    fn test_parse_swww_query() {
+        let output = "eDP-1: 1920x1080, scale: 1, currently displaying: image: /home/u/bg.png\n\
+                      HDMI-A-1: 2560x1440, scale: 1, currently displaying: color: 000000\n";
+        assert_eq!(
+            parse_swww_query(output),
+            Some(PathBuf::from("/home/u/bg.png"))
+        );
+        assert_eq!(
+            parse_swww_query("eDP-1: 1920x1080, currently displaying: color: 000000"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_swaybg_args() {
+        assert_eq!(
+            parse_swaybg_args("1234 swaybg -i /home/u/bg.png -m fill\n"),
+            Some(PathBuf::from("/home/u/bg.png"))
+        );
+        assert_eq!(parse_swaybg_args("1234 swaybg -c #000000\n"), None);
+        assert_eq!(parse_swaybg_args(""), None);
+    }
+}
@@ -1,10 +1,97 @@
-use super::WallpaperSetter;
+use super::{WallpaperMode, WallpaperSetter};
 use anyhow::{Result, anyhow};
+use objc::rc::autoreleasepool;
+use objc::runtime::{BOOL, NO, Object, YES};
+use objc::{class, msg_send, sel, sel_impl};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
 pub struct MacOsWallpaperSetter;
 
+#[link(name = "AppKit", kind = "framework")]
+unsafe extern "C" {
+    static NSWorkspaceDesktopImageScalingKey: *mut Object;
+    static NSWorkspaceDesktopImageAllowClippingKey: *mut Object;
+}
+
+/// `NSImageScaling` values
+const NS_IMAGE_SCALE_AXES_INDEPENDENTLY: usize = 1;
+const NS_IMAGE_SCALE_NONE: usize = 2;
+const NS_IMAGE_SCALE_PROPORTIONALLY_UP_OR_DOWN: usize = 3;
+
+/// `NSUTF8StringEncoding`
+const NS_UTF8_STRING_ENCODING: usize = 4;
+
+/// Image scaling and whether the image may be clipped, AppKit can't tile so that fills
+fn desktop_image_scaling(mode: WallpaperMode) -> (usize, BOOL) {
+    match mode {
+        WallpaperMode::Fill | WallpaperMode::Tile => {
+            (NS_IMAGE_SCALE_PROPORTIONALLY_UP_OR_DOWN, YES)
+        }
+        WallpaperMode::Fit => (NS_IMAGE_SCALE_PROPORTIONALLY_UP_OR_DOWN, NO),
+        WallpaperMode::Stretch => (NS_IMAGE_SCALE_AXES_INDEPENDENTLY, NO),
+        WallpaperMode::Center => (NS_IMAGE_SCALE_NONE, NO),
+    }
+}
+
+/// Set the desktop image of every screen, or only the one at `display` in
+/// `NSScreen.screens` (the first is the one with the menu bar)
+fn set_desktop_image(path: &Path, mode: WallpaperMode, display: Option<usize>) -> Result<()> {
+    let path_str = path.to_str().ok_or_else(|| anyhow!("Invalid path"))?;
+
+    autoreleasepool(|| unsafe {
+        let screens: *mut Object = msg_send![class!(NSScreen), screens];
+        let count: usize = msg_send![screens, count];
+        let targets = match display {
+            Some(index) if index >= count => {
+                return Err(anyhow!(
+                    "Display {} does not exist, there are {} displays",
+                    index,
+                    count
+                ));
+            }
+            Some(index) => index..index + 1,
+            None => 0..count,
+        };
+
+        let string: *mut Object = msg_send![class!(NSString), alloc];
+        let string: *mut Object = msg_send![string,
+            initWithBytes: path_str.as_ptr()
+            length: path_str.len()
+            encoding: NS_UTF8_STRING_ENCODING];
+        let _: () = msg_send![string, autorelease];
+        let url: *mut Object = msg_send![class!(NSURL), fileURLWithPath: string];
+
+        let (scaling, clipping) = desktop_image_scaling(mode);
+        let scaling: *mut Object = msg_send![class!(NSNumber), numberWithUnsignedInteger: scaling];
+        let clipping: *mut Object = msg_send![class!(NSNumber), numberWithBool: clipping];
+        let keys = [
+            NSWorkspaceDesktopImageScalingKey,
+            NSWorkspaceDesktopImageAllowClippingKey,
+        ];
+        let values = [scaling, clipping];
+        let options: *mut Object = msg_send![class!(NSDictionary),
+            dictionaryWithObjects: values.as_ptr()
+            forKeys: keys.as_ptr()
+            count: keys.len()];
+
+        let workspace: *mut Object = msg_send![class!(NSWorkspace), sharedWorkspace];
+        for index in targets {
+            let screen: *mut Object = msg_send![screens, objectAtIndex: index];
+            let mut error: *mut Object = std::ptr::null_mut();
+            let ok: BOOL = msg_send![workspace,
+                setDesktopImageURL: url
+                forScreen: screen
+                options: options
+                error: &mut error];
+            if ok == NO {
+                return Err(anyhow!("Failed to set wallpaper on display {}", index));
+            }
+        }
+        Ok(())
+    })
+}
+
 impl WallpaperSetter for MacOsWallpaperSetter {
     fn get_wallpaper(&self) -> Result<PathBuf> {
         let script = r#"tell application "System Events" to get picture of first desktop"#;
@@ -18,19 +105,17 @@ impl WallpaperSetter for MacOsWallpaperSetter {
         Ok(PathBuf::from(path_str))
     }
 
-    fn set_wallpaper(&self, path: &Path) -> Result<()> {
-        let path_str = path.to_str().ok_or_else(|| anyhow!("Invalid path"))?;
-
-        let script = format!(
-            r#"tell application "System Events" to tell every desktop to set picture to "{}""#,
-            path_str
-        );
+    fn set_wallpaper(&self, path: &Path, mode: WallpaperMode) -> Result<()> {
+        set_desktop_image(path, mode, None)
+    }
 
-        Command::new("osascript")
-            .arg("-e")
-            .arg(script)
-            .output()
-            .map(|_| ())
-            .map_err(|e| anyhow!("Failed to set macOS wallpaper: {}", e))
+    /// Fails if `display` is not an index into the connected screens
+    fn set_wallpaper_on_display(
+        &self,
+        path: &Path,
+        mode: WallpaperMode,
+        display: usize,
+    ) -> Result<()> {
+        set_desktop_image(path, mode, Some(display))
     }
 }
@@ -1,8 +1,52 @@
 use anyhow::Result;
+use schemars::JsonSchema;
+use serde::Deserialize;
 use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+pub mod color;
+
+/// How the wallpaper image is fitted to the screen.
+///
+/// Backends without an equivalent fall back to the closest mode they have:
+/// swww and macOS can't tile, so tiling fills there instead.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, TS, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum WallpaperMode {
+    /// Scale to cover the screen, cropping the overflow
+    #[default]
+    Fill,
+    /// Scale to fit inside the screen, keeping the aspect ratio
+    Fit,
+    /// Scale to the screen size, ignoring the aspect ratio
+    Stretch,
+    /// Center at the original size
+    Center,
+    /// Repeat at the original size
+    Tile,
+}
+
+/// The wallpaper that was set before the runtime started, used to restore it
+#[derive(Debug, Clone)]
+pub struct WallpaperBackup(pub PathBuf);
 
 pub trait WallpaperSetter {
-    fn set_wallpaper(&self, path: &Path) -> Result<()>;
+    fn set_wallpaper(&self, path: &Path, mode: WallpaperMode) -> Result<()>;
+
+    /// Set the wallpaper on a single display. Backends that can't target one
+    /// display set it on all of them.
+    fn set_wallpaper_on_display(
+        &self,
+        path: &Path,
+        mode: WallpaperMode,
+        display: usize,
+    ) -> Result<()> {
+        tracing::warn!(
+            "Setting the wallpaper on display {} isn't supported here, setting it on all displays",
+            display
+        );
+        self.set_wallpaper(path, mode)
+    }
     fn get_wallpaper(&self) -> Result<PathBuf>;
 }
 
@@ -26,7 +70,7 @@ pub struct PlatformWallpaperSetter;
 
 #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
 impl WallpaperSetter for PlatformWallpaperSetter {
-    fn set_wallpaper(&self, _path: &Path) -> Result<()> {
+    fn set_wallpaper(&self, _path: &Path, _mode: WallpaperMode) -> Result<()> {
         Err(anyhow::anyhow!(
             "Wallpaper setting not supported on this platform"
         ))
@@ -49,6 +93,6 @@ mod tests {
         let setter = PlatformWallpaperSetter;
         // Just check if it compiles and we can call the method (even if it fails)
         // We pass a dummy path
-        let _ = setter.set_wallpaper(Path::new("dummy"));
+        let _ = setter.set_wallpaper(Path::new("dummy"), WallpaperMode::default());
     }
 }
@@ -1,4 +1,4 @@
-use super::WallpaperSetter;
+use super::{WallpaperMode, WallpaperSetter};
 use anyhow::{Result, anyhow};
 use std::path::{Path, PathBuf};
 
@@ -14,6 +14,45 @@ use windows_sys::Win32::UI::WindowsAndMessaging::{
 
 pub struct WindowsWallpaperSetter;
 
+/// `WallpaperStyle` and `TileWallpaper` values under `HKCU\Control Panel\Desktop`
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn wallpaper_style(mode: WallpaperMode) -> (&'static str, &'static str) {
+    match mode {
+        WallpaperMode::Fill => ("10", "0"),
+        WallpaperMode::Fit => ("6", "0"),
+        WallpaperMode::Stretch => ("2", "0"),
+        WallpaperMode::Center => ("0", "0"),
+        WallpaperMode::Tile => ("0", "1"),
+    }
+}
+
+/// Write the style to the registry, it is picked up when the wallpaper is next set
+#[cfg(target_os = "windows")]
+fn set_wallpaper_style(mode: WallpaperMode) -> Result<()> {
+    let (style, tile) = wallpaper_style(mode);
+    for (name, value) in [("WallpaperStyle", style), ("TileWallpaper", tile)] {
+        let status = std::process::Command::new("reg")
+            .args([
+                "add",
+                r"HKCU\Control Panel\Desktop",
+                "/v",
+                name,
+                "/t",
+                "REG_SZ",
+                "/d",
+                value,
+                "/f",
+            ])
+            .output()
+            .map_err(|e| anyhow!("Failed to set {}: {}", name, e))?
+            .status;
+        if !status.success() {
+            return Err(anyhow!("Failed to set {}", name));
+        }
+    }
+    Ok(())
+}
+
 impl WallpaperSetter for WindowsWallpaperSetter {
     fn get_wallpaper(&self) -> Result<PathBuf> {
         #[cfg(target_os = "windows")]
@@ -45,9 +84,14 @@ impl WallpaperSetter for WindowsWallpaperSetter {
         }
     }
 
-    fn set_wallpaper(&self, path: &Path) -> Result<()> {
+    fn set_wallpaper(&self, path: &Path, mode: WallpaperMode) -> Result<()> {
         #[cfg(target_os = "windows")]
         {
+            // Keep going with the current style if the registry can't be written
+            if let Err(e) = set_wallpaper_style(mode) {
+                tracing::warn!("{}", e);
+            }
+
             let path_str: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
 
             let result = unsafe {
@@ -66,7 +110,7 @@ impl WallpaperSetter for WindowsWallpaperSetter {
         }
         #[cfg(not(target_os = "windows"))]
         {
-            let _ = path;
+            let _ = (path, mode);
             Err(anyhow!("Not supported on this platform"))
         }
     }
@@ -13,21 +13,52 @@ impl PermissionChecker {
         }
     }
 
+    /// A checker that allows everything
+    pub fn all() -> Self {
+        Self::new(PermissionSet::all())
+    }
+
+    /// Whether every permission is granted
+    pub fn has_full_access(&self) -> bool {
+        self.permissions.is_all()
+    }
+
     pub fn has_permission(&self, permission: Permission) -> bool {
         self.permissions.contains(permission)
     }
 
+    /// Whether every one of `permissions` is granted
+    pub fn has_all(&self, permissions: &[Permission]) -> bool {
+        permissions.iter().all(|perm| self.has_permission(*perm))
+    }
+
     pub fn check(&self, permission: Permission) -> Result<(), String> {
-        if self.has_permission(permission) {
-            Ok(())
+        self.check_all(&[permission])
+    }
+
+    /// Check several permissions at once. The error names the first missing
+    /// permission and lists any others that are missing too.
+    pub fn check_all(&self, permissions: &[Permission]) -> Result<(), String> {
+        let mut missing = permissions
+            .iter()
+            .copied()
+            .filter(|perm| !self.has_permission(*perm));
+        let Some(permission) = missing.next() else {
+            return Ok(());
+        };
+        let also: Vec<String> = missing.map(|perm| format!("'{}'", perm)).collect();
+        let also = if also.is_empty() {
+            String::new()
         } else {
-            Err(format!(
-                "Permission denied: '{}'. This action requires the '{}' permission. \
-                The pack has requested this permission, but it has not been granted. \
-                To grant this permission, add '{}' to runtime.permissions in settings.yaml",
-                permission, permission, permission
-            ))
-        }
+            format!(" (also needs {})", also.join(", "))
+        };
+
+        Err(format!(
+            "Permission denied: '{}'{}. This action requires the '{}' permission. \
+            The pack has requested this permission, but it has not been granted. \
+            To grant this permission, add '{}' to runtime.permissions in settings.yaml",
+            permission, also, permission, permission
+        ))
     }
 }
 
@@ -53,4 +84,28 @@ mod tests {
         assert!(!checker.has_permission(Permission::Video));
         assert!(checker.check(Permission::Video).is_err());
     }
+
+    #[test]
+    fn test_checker_has_all() {
+        let checker = PermissionChecker::new(vec![Permission::Image].into());
+        assert!(checker.has_all(&[]));
+        assert!(checker.has_all(&[Permission::Image]));
+        assert!(!checker.has_all(&[Permission::Image, Permission::Audio]));
+
+        assert!(checker.check_all(&[Permission::Image]).is_ok());
+        let err = checker
+            .check_all(&[Permission::Audio, Permission::Image, Permission::Video])
+            .unwrap_err();
+        assert!(err.starts_with("Permission denied: 'audio' (also needs 'video')."));
+    }
+
+    #[test]
+    fn test_checker_all() {
+        let checker = PermissionChecker::all();
+        assert!(checker.has_full_access());
+        for permission in Permission::ALL {
+            assert!(checker.check(permission).is_ok());
+        }
+        assert!(!PermissionChecker::new(vec![Permission::Image].into()).has_full_access());
+    }
 }
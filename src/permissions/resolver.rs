@@ -1,4 +1,7 @@
-use super::types::PermissionSet;
+use super::types::{Permission, PermissionSet};
+
+/// Permissions that remain available in safe mode
+const SAFE_MODE_ALLOWLIST: &[Permission] = &[Permission::Image, Permission::Audio];
 
 pub struct PermissionResolver;
 
@@ -6,10 +9,21 @@ impl PermissionResolver {
     pub fn resolve(
         pack_permissions: &PermissionSet,
         user_permissions: &PermissionSet,
+        safe_mode: bool,
     ) -> PermissionSet {
         // The active permissions are those that are BOTH requested by the pack AND granted by the user.
         // This ensures security (user must grant) and efficiency (only enable what pack needs).
-        pack_permissions.intersection(user_permissions)
+        let active = pack_permissions.intersection(user_permissions);
+        if safe_mode {
+            active.intersection(&Self::safe_mode_allowlist())
+        } else {
+            active
+        }
+    }
+
+    /// The benign permissions that safe mode leaves enabled
+    pub fn safe_mode_allowlist() -> PermissionSet {
+        SAFE_MODE_ALLOWLIST.to_vec().into()
     }
 
     pub fn find_missing(
@@ -36,13 +50,31 @@ mod tests {
         user.add(Permission::Image);
         user.add(Permission::Audio);
 
-        let resolved = PermissionResolver::resolve(&pack, &user);
+        let resolved = PermissionResolver::resolve(&pack, &user, false);
 
         assert!(resolved.contains(Permission::Image));
         assert!(!resolved.contains(Permission::Video)); // Not in user
         assert!(!resolved.contains(Permission::Audio)); // Not in pack
     }
 
+    #[test]
+    fn test_safe_mode_strips_intrusive_permissions() {
+        let mut pack = PermissionSet::new();
+        pack.add(Permission::Image);
+        pack.add(Permission::Wallpaper);
+        pack.add(Permission::Input);
+
+        let user = pack.clone();
+
+        let normal = PermissionResolver::resolve(&pack, &user, false);
+        assert!(normal.contains(Permission::Wallpaper));
+
+        let safe = PermissionResolver::resolve(&pack, &user, true);
+        assert!(safe.contains(Permission::Image));
+        assert!(!safe.contains(Permission::Wallpaper));
+        assert!(!safe.contains(Permission::Input));
+    }
+
     #[test]
     fn test_find_missing() {
         let mut pack = PermissionSet::new();
@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Ordered by declaration, which is the order sets serialize in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Permission {
     Image,
@@ -12,6 +13,31 @@ pub enum Permission {
     Wallpaper,
     WriteLines,
     Website,
+    /// Control over the user's mouse and keyboard
+    Input,
+    /// Reading the contents of the user's screen
+    Screen,
+    /// Full-screen color overlays
+    Overlay,
+    /// Native desktop notifications
+    Notification,
+}
+
+impl Permission {
+    /// Every permission, anything granting "everything" is built from this
+    pub const ALL: [Permission; 11] = [
+        Permission::Image,
+        Permission::Video,
+        Permission::Audio,
+        Permission::Hypno,
+        Permission::Wallpaper,
+        Permission::WriteLines,
+        Permission::Website,
+        Permission::Input,
+        Permission::Screen,
+        Permission::Overlay,
+        Permission::Notification,
+    ];
 }
 
 #[derive(Debug)]
@@ -37,6 +63,10 @@ impl FromStr for Permission {
             "wallpaper" => Ok(Permission::Wallpaper),
             "writelines" => Ok(Permission::WriteLines),
             "website" => Ok(Permission::Website),
+            "input" => Ok(Permission::Input),
+            "screen" => Ok(Permission::Screen),
+            "overlay" => Ok(Permission::Overlay),
+            "notification" => Ok(Permission::Notification),
             _ => Err(ParsePermissionError(s.to_string())),
         }
     }
@@ -52,22 +82,37 @@ impl std::fmt::Display for Permission {
             Permission::Wallpaper => write!(f, "wallpaper"),
             Permission::WriteLines => write!(f, "writeLines"),
             Permission::Website => write!(f, "website"),
+            Permission::Input => write!(f, "input"),
+            Permission::Screen => write!(f, "screen"),
+            Permission::Overlay => write!(f, "overlay"),
+            Permission::Notification => write!(f, "notification"),
         }
     }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PermissionSet {
-    pub permissions: HashSet<Permission>,
+    /// Sorted so serialized sets don't change between runs
+    pub permissions: BTreeSet<Permission>,
 }
 
 impl PermissionSet {
     pub fn new() -> Self {
         Self {
-            permissions: HashSet::new(),
+            permissions: BTreeSet::new(),
         }
     }
 
+    /// Every permission there is
+    pub fn all() -> Self {
+        Permission::ALL.to_vec().into()
+    }
+
+    /// Whether every permission is in the set
+    pub fn is_all(&self) -> bool {
+        Permission::ALL.iter().all(|perm| self.contains(*perm))
+    }
+
     pub fn add(&mut self, permission: Permission) {
         self.permissions.insert(permission);
     }
@@ -142,6 +187,12 @@ mod tests {
             Permission::from_str("website").unwrap(),
             Permission::Website
         );
+        assert_eq!(Permission::from_str("input").unwrap(), Permission::Input);
+        assert_eq!(Permission::from_str("screen").unwrap(), Permission::Screen);
+        assert_eq!(
+            Permission::from_str("overlay").unwrap(),
+            Permission::Overlay
+        );
 
         assert!(Permission::from_str("unknown").is_err());
     }
@@ -187,6 +238,67 @@ mod tests {
         assert!(set.contains(Permission::Video));
     }
 
+    #[test]
+    fn test_permission_set_serializes_in_stable_order() {
+        let forward: PermissionSet =
+            vec![Permission::Image, Permission::Audio, Permission::Overlay].into();
+        let backward: PermissionSet =
+            vec![Permission::Overlay, Permission::Audio, Permission::Image].into();
+
+        let json = serde_json::to_string(&forward).unwrap();
+        assert_eq!(json, serde_json::to_string(&forward).unwrap());
+        assert_eq!(json, serde_json::to_string(&backward).unwrap());
+        assert_eq!(json, r#"{"permissions":["image","audio","overlay"]}"#);
+
+        let parsed: PermissionSet =
+            serde_json::from_str(r#"{"permissions":["overlay","image","audio"]}"#).unwrap();
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+    }
+
+    #[test]
+    fn test_all_contains_every_permission() {
+        // No wildcard arm, so a new variant won't compile until it's handled here
+        let listed = |perm: Permission| match perm {
+            Permission::Image
+            | Permission::Video
+            | Permission::Audio
+            | Permission::Hypno
+            | Permission::Wallpaper
+            | Permission::WriteLines
+            | Permission::Website
+            | Permission::Input
+            | Permission::Screen
+            | Permission::Overlay
+            | Permission::Notification => Permission::ALL.contains(&perm),
+        };
+        for name in [
+            "image",
+            "video",
+            "audio",
+            "hypno",
+            "wallpaper",
+            "writeLines",
+            "website",
+            "input",
+            "screen",
+            "overlay",
+            "notification",
+        ] {
+            let perm = Permission::from_str(name).unwrap();
+            assert!(listed(perm), "{} is missing from Permission::ALL", perm);
+            assert!(PermissionSet::all().contains(perm));
+        }
+
+        assert_eq!(
+            PermissionSet::all().permissions.len(),
+            Permission::ALL.len()
+        );
+        assert!(PermissionSet::all().is_all());
+        let mut partial = PermissionSet::all();
+        partial.permissions.remove(&Permission::Screen);
+        assert!(!partial.is_all());
+    }
+
     #[test]
     fn test_permission_set_is_empty() {
         let set = PermissionSet::new();
@@ -0,0 +1,80 @@
+//! `console` and uncaught errors for scripts
+//!
+//! deno_core doesn't print console output anywhere, so scripts get a small
+//! `console` whose methods hand their text to a `LogCollector` in the op state.
+//! Errors a script doesn't catch are reported with their stack the same way.
+
+use deno_core::OpState;
+use deno_core::op2;
+
+/// Installs `globalThis.console`, formatting arguments the way browsers do for plain values
+pub const CONSOLE_JS: &str = r#"
+((globalThis) => {
+  const format = (args) =>
+    args
+      .map((arg) => {
+        if (typeof arg === "string") return arg;
+        if (arg instanceof Error) return arg.stack ?? String(arg);
+        try {
+          return JSON.stringify(arg) ?? String(arg);
+        } catch {
+          return String(arg);
+        }
+      })
+      .join(" ");
+  const log = (level) => (...args) => Deno.core.ops.op_console_log(level, format(args));
+  globalThis.console = {
+    log: log("log"),
+    info: log("log"),
+    debug: log("log"),
+    warn: log("warn"),
+    error: log("error"),
+  };
+})(globalThis);
+"#;
+
+/// One line written to the console by a script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// "log", "warn" or "error"
+    pub level: String,
+    pub message: String,
+}
+
+/// Console output of the scripts run since it was last taken
+#[derive(Debug, Default)]
+pub struct LogCollector {
+    entries: Vec<LogEntry>,
+}
+
+impl LogCollector {
+    pub fn push(&mut self, level: &str, message: String) {
+        self.entries.push(LogEntry {
+            level: level.to_string(),
+            message,
+        });
+    }
+
+    /// Remove and return everything collected so far
+    pub fn take(&mut self) -> Vec<LogEntry> {
+        std::mem::take(&mut self.entries)
+    }
+}
+
+/// Stack of the error that ended the last script, if it threw
+#[derive(Debug, Default)]
+pub struct ScriptError(pub Option<String>);
+
+#[op2]
+pub fn op_report_error(state: &mut OpState, #[string] stack: String) {
+    state.put(ScriptError(Some(stack)));
+}
+
+#[op2]
+pub fn op_console_log(state: &mut OpState, #[string] level: String, #[string] message: String) {
+    if let Some(logs) = state.try_borrow_mut::<LogCollector>() {
+        logs.push(&level, message);
+    }
+}
+
+deno_core::extension!(goon_console, ops = [op_console_log, op_report_error],);
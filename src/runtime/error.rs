@@ -1,19 +1,77 @@
+use crate::gui::{SpawnQueueFull, WindowLimitReached};
 use deno_core::error::AnyError;
-use deno_error::JsErrorClass;
+use deno_error::{JsErrorClass, PropertyValue};
 use std::borrow::Cow;
 
+/// Category of an op failure, exposed to scripts as `e.code`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpErrorKind {
+    /// The pack is not allowed to use the requested capability
+    PermissionDenied,
+    /// No asset matched the request
+    AssetNotFound,
+    /// A handle ID was malformed or refers to nothing
+    InvalidHandle,
+    /// Options passed to the op could not be parsed or are invalid
+    InvalidOptions,
+    /// Too many requests are queued, try again later
+    Throttled,
+    /// The underlying platform or media backend failed
+    Backend,
+}
+
+impl OpErrorKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            OpErrorKind::PermissionDenied => "PermissionDenied",
+            OpErrorKind::AssetNotFound => "AssetNotFound",
+            OpErrorKind::InvalidHandle => "InvalidHandle",
+            OpErrorKind::InvalidOptions => "InvalidOptions",
+            OpErrorKind::Throttled => "Throttled",
+            OpErrorKind::Backend => "Backend",
+        }
+    }
+}
+
 #[derive(Debug)]
-pub struct OpError(pub AnyError);
+pub struct OpError {
+    pub kind: OpErrorKind,
+    pub error: AnyError,
+}
 
 impl OpError {
+    /// Create a `Backend` error
     pub fn new(msg: &str) -> Self {
-        OpError(anyhow::anyhow!("{}", msg))
+        Self::with_kind(OpErrorKind::Backend, msg)
+    }
+
+    pub fn with_kind(kind: OpErrorKind, msg: &str) -> Self {
+        OpError {
+            kind,
+            error: anyhow::anyhow!("{}", msg),
+        }
+    }
+
+    pub fn permission_denied(msg: &str) -> Self {
+        Self::with_kind(OpErrorKind::PermissionDenied, msg)
+    }
+
+    pub fn asset_not_found(msg: &str) -> Self {
+        Self::with_kind(OpErrorKind::AssetNotFound, msg)
+    }
+
+    pub fn invalid_handle(msg: &str) -> Self {
+        Self::with_kind(OpErrorKind::InvalidHandle, msg)
+    }
+
+    pub fn invalid_options(msg: &str) -> Self {
+        Self::with_kind(OpErrorKind::InvalidOptions, msg)
     }
 }
 
 impl std::fmt::Display for OpError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
+        self.error.fmt(f)
     }
 }
 
@@ -24,12 +82,15 @@ impl JsErrorClass for OpError {
         "Error".into()
     }
     fn get_message(&self) -> Cow<'static, str> {
-        self.0.to_string().into()
+        self.error.to_string().into()
     }
     fn get_additional_properties(
         &self,
-    ) -> Box<dyn Iterator<Item = (Cow<'static, str>, deno_error::PropertyValue)> + 'static> {
-        Box::new(std::iter::empty())
+    ) -> Box<dyn Iterator<Item = (Cow<'static, str>, PropertyValue)> + 'static> {
+        Box::new(std::iter::once((
+            "code".into(),
+            PropertyValue::String(self.kind.code().into()),
+        )))
     }
     fn get_ref(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
         self
@@ -38,7 +99,12 @@ impl JsErrorClass for OpError {
 
 impl From<AnyError> for OpError {
     fn from(err: AnyError) -> Self {
-        OpError(err)
+        let kind = if err.is::<SpawnQueueFull>() || err.is::<WindowLimitReached>() {
+            OpErrorKind::Throttled
+        } else {
+            OpErrorKind::Backend
+        };
+        OpError { kind, error: err }
     }
 }
 
@@ -61,4 +127,31 @@ mod tests {
         assert_eq!(op_err.get_class(), "Error");
         assert_eq!(op_err.get_message(), "Error");
     }
+
+    #[test]
+    fn test_op_error_code_property() {
+        let op_err = OpError::invalid_handle("Invalid audio handle ID");
+        assert_eq!(op_err.kind, OpErrorKind::InvalidHandle);
+        assert_eq!(op_err.get_message(), "Invalid audio handle ID");
+
+        let props: Vec<_> = op_err.get_additional_properties().collect();
+        assert_eq!(props.len(), 1);
+        assert_eq!(props[0].0, "code");
+        assert!(matches!(&props[0].1, PropertyValue::String(code) if code == "InvalidHandle"));
+    }
+
+    #[test]
+    fn test_spawn_queue_full_is_throttled() {
+        let op_err = OpError::from(anyhow::Error::from(SpawnQueueFull));
+        assert_eq!(op_err.kind, OpErrorKind::Throttled);
+        let op_err = OpError::from(anyhow::Error::from(WindowLimitReached {
+            kind: crate::gui::PopupKind::Video,
+            max: 3,
+        }));
+        assert_eq!(op_err.kind, OpErrorKind::Throttled);
+        assert_eq!(
+            OpError::from(anyhow::anyhow!("boom")).kind,
+            OpErrorKind::Backend
+        );
+    }
 }
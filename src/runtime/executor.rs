@@ -62,6 +62,10 @@ mod tests {
             registry,
             mood,
             max_audio_concurrent: 10,
+            max_image_concurrent: usize::MAX,
+            max_video_concurrent: usize::MAX,
+            allow_fallback: false,
+            original_wallpaper: None,
         };
 
         let code = r#"
@@ -93,6 +97,10 @@ mod tests {
             registry,
             mood,
             max_audio_concurrent: 10,
+            max_image_concurrent: usize::MAX,
+            max_video_concurrent: usize::MAX,
+            allow_fallback: false,
+            original_wallpaper: None,
         };
 
         let code = "const x: number = ;"; // Invalid syntax
@@ -1,4 +1,5 @@
 #![allow(dead_code, unused_imports, unused_variables, clippy::module_inception)]
+pub mod console;
 pub mod error;
 pub mod executor;
 pub mod runtime;
@@ -1,19 +1,58 @@
+use crate::assets::library::{ActivePack, PackLibrary};
 use crate::assets::registry::AssetRegistry;
+use crate::assets::selector::{AssetFallback, RecentAssets};
 use crate::config::pack::Mood;
-use crate::gui::WindowSpawnerHandle;
+use crate::gui::{PopupKind, WindowSpawnerHandle};
 use crate::media::audio::manager::AudioManager;
-use crate::permissions::PermissionChecker;
+use crate::media::capture::CaptureFiles;
+use crate::media::idle::{IdleProvider, PlatformIdleProvider};
+use crate::media::mouse::MouseEffects;
+use crate::media::wallpaper::color::ColorWallpapers;
+use crate::media::wallpaper::{PlatformWallpaperSetter, WallpaperBackup, WallpaperSetter};
+use crate::permissions::{Permission, PermissionChecker};
+use crate::runtime::console::{CONSOLE_JS, LogCollector, LogEntry, ScriptError, goon_console};
 use crate::sdk;
+use crate::sdk::website::OpenedWebsites;
 use crate::sdk::{
-    audio::goon_audio, hypno::goon_hypno, image::goon_image, pack::goon_pack, system::goon_system,
-    video::goon_video, wallpaper::goon_wallpaper, website::goon_website,
-    write_lines::goon_write_lines,
+    audio::goon_audio, hypno::goon_hypno, image::goon_image, mouse::goon_mouse,
+    notification::goon_notification, overlay::goon_overlay, pack::goon_pack, screen::goon_screen,
+    system::goon_system, text::goon_text, video::goon_video, wallpaper::goon_wallpaper,
+    website::goon_website, write_lines::goon_write_lines,
 };
 use crate::typescript::TypeScriptCompiler;
 use anyhow::Result;
 use deno_core::{JsRuntime, RuntimeOptions};
 use rodio::{OutputStream, OutputStreamBuilder, mixer::Mixer};
-use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// The SDK bridge compiled to JS, the sources are constant so it is shared by every runtime
+static SDK_BRIDGE: OnceLock<Vec<String>> = OnceLock::new();
+
+#[cfg(test)]
+static SDK_BRIDGE_COMPILES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+fn sdk_bridge() -> &'static [String] {
+    SDK_BRIDGE.get_or_init(|| {
+        #[cfg(test)]
+        SDK_BRIDGE_COMPILES.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+
+        let compiler = TypeScriptCompiler::new();
+        sdk::get_all_typescript_sources()
+            .into_iter()
+            .filter_map(|source| match compiler.compile(&source) {
+                Ok(js_code) => Some(js_code),
+                Err(e) => {
+                    eprintln!("Failed to compile SDK bridge code: {}", e);
+                    None
+                }
+            })
+            .collect()
+    })
+}
 
 pub struct RuntimeContext {
     pub permissions: PermissionChecker,
@@ -21,11 +60,22 @@ pub struct RuntimeContext {
     pub registry: Arc<AssetRegistry>,
     pub mood: Mood,
     pub max_audio_concurrent: usize,
+    /// Most image popups open at once
+    pub max_image_concurrent: usize,
+    /// Most video popups open at once
+    pub max_video_concurrent: usize,
+    /// Fall back to assets outside the mood when nothing matches it
+    pub allow_fallback: bool,
+    /// Wallpaper from before the session, restored by `wallpaper.restore()` and
+    /// `system.stopAll()`. When unset it is read when the runtime is created.
+    pub original_wallpaper: Option<PathBuf>,
 }
 
 pub struct GoonRuntime {
     pub js_runtime: JsRuntime,
     _audio_stream: Option<OutputStream>,
+    /// Scripts run so far, gives each one its own name in stack traces
+    scripts_run: u64,
 }
 
 impl GoonRuntime {
@@ -43,6 +93,7 @@ impl GoonRuntime {
 
         let mut js_runtime = JsRuntime::new(RuntimeOptions {
             extensions: vec![
+                goon_console::init(),
                 goon_system::init(),
                 goon_pack::init(),
                 goon_image::init(),
@@ -51,19 +102,53 @@ impl GoonRuntime {
                 goon_hypno::init(),
                 goon_wallpaper::init(),
                 goon_write_lines::init(),
+                goon_text::init(),
                 goon_website::init(),
+                goon_mouse::init(),
+                goon_screen::init(),
+                goon_overlay::init(),
+                goon_notification::init(),
             ],
             ..Default::default()
         });
 
+        // Remember the wallpaper so `system.stopAll()` can restore it
+        let wallpaper_backup = if context.permissions.has_permission(Permission::Wallpaper) {
+            context
+                .original_wallpaper
+                .clone()
+                .or_else(|| PlatformWallpaperSetter.get_wallpaper().ok())
+                .map(WallpaperBackup)
+        } else {
+            None
+        };
+
         // Store permissions in OpState
         {
             let op_state = js_runtime.op_state();
             let mut op_state = op_state.borrow_mut();
+            if let Some(backup) = wallpaper_backup {
+                op_state.put(backup);
+            }
             op_state.put(context.permissions);
+            context
+                .window_spawner
+                .set_max_popups(PopupKind::Image, context.max_image_concurrent);
+            context
+                .window_spawner
+                .set_max_popups(PopupKind::Video, context.max_video_concurrent);
             op_state.put(context.window_spawner);
             op_state.put(context.registry);
             op_state.put(context.mood);
+            op_state.put(AssetFallback(context.allow_fallback));
+            op_state.put(MouseEffects::default());
+            // Dropped with the runtime, which deletes any captured screenshots
+            op_state.put(CaptureFiles::default());
+            op_state.put(ColorWallpapers::default());
+            op_state.put(OpenedWebsites::default());
+            op_state.put(LogCollector::default());
+            op_state.put(Arc::new(Mutex::new(RecentAssets::default())));
+            op_state.put::<Arc<dyn IdleProvider>>(Arc::new(PlatformIdleProvider));
 
             if let Some(m) = mixer {
                 let audio_manager = Arc::new(Mutex::new(AudioManager::new(
@@ -74,29 +159,50 @@ impl GoonRuntime {
             }
         }
 
-        // Compile and load SDK bridge code
-        let compiler = TypeScriptCompiler::new();
-        let sources = sdk::get_all_typescript_sources();
+        if let Err(e) = js_runtime.execute_script("console.js", CONSOLE_JS) {
+            eprintln!("Failed to install console: {}", e);
+        }
 
-        for source in sources {
-            match compiler.compile(&source) {
-                Ok(js_code) => {
-                    if let Err(e) = js_runtime.execute_script("sdk_bridge.js", js_code) {
-                        eprintln!("Failed to execute SDK bridge code: {}", e);
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Failed to compile SDK bridge code: {}", e);
-                }
+        // Load the SDK bridge code
+        for js_code in sdk_bridge() {
+            if let Err(e) = js_runtime.execute_script("sdk_bridge.js", js_code.as_str()) {
+                eprintln!("Failed to execute SDK bridge code: {}", e);
             }
         }
 
         Self {
             js_runtime,
             _audio_stream: audio_stream,
+            scripts_run: 0,
+        }
+    }
+
+    /// Console output of the scripts run since the last call
+    pub fn take_logs(&mut self) -> Vec<LogEntry> {
+        self.js_runtime
+            .op_state()
+            .borrow_mut()
+            .borrow_mut::<LogCollector>()
+            .take()
+    }
+
+    /// Stop every audio track started by scripts
+    pub fn stop_all_audio(&mut self) {
+        let op_state = self.js_runtime.op_state();
+        let op_state = op_state.borrow();
+        if let Some(manager) = op_state.try_borrow::<Arc<Mutex<AudioManager>>>()
+            && let Ok(mut manager) = manager.lock()
+        {
+            manager.stop_all();
         }
     }
 
+    /// Run a script in this runtime.
+    ///
+    /// The runtime is meant to be kept for a whole session. Each script runs in
+    /// its own async function, so top-level declarations never collide between
+    /// scripts, while anything put on `globalThis` is kept for later scripts.
+    /// Packs can use that to build up helpers and state across iterations.
     pub async fn execute_script(&mut self, code: &str) -> Result<()> {
         // We wrap the code in an async IIFE to support top-level await
         // and ensure we handle the promise result.
@@ -109,33 +215,152 @@ impl GoonRuntime {
             .collect();
         let clean_code = code_lines.join("\n");
 
-        let wrapped_code = format!("(async () => {{ {} }})()", clean_code);
+        // Errors are reported with their stack, whose line numbers match the script's
+        let wrapped_code = format!(
+            "(async () => {{ {} }})().catch((e) => Deno.core.ops.op_report_error(e instanceof Error ? (e.stack ?? String(e)) : String(e)))",
+            clean_code
+        );
+
+        let result = self.run_wrapped(wrapped_code).await;
+        if result.is_err() {
+            // Don't leave the cursor jiggled or confined after a failed script
+            self.release_mouse_effects();
+        }
+        result
+    }
+
+    /// Run a script, stopping it with an error if it takes longer than `timeout`.
+    ///
+    /// A watchdog thread terminates the isolate, so scripts stuck in a
+    /// synchronous loop are stopped too, not only ones waiting on an op.
+    pub async fn execute_script_with_timeout(
+        &mut self,
+        code: &str,
+        timeout: Duration,
+    ) -> Result<()> {
+        let isolate = self.js_runtime.v8_isolate().thread_safe_handle();
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<()>();
+        let watchdog = std::thread::spawn({
+            let timed_out = timed_out.clone();
+            move || {
+                if let Err(RecvTimeoutError::Timeout) = done_rx.recv_timeout(timeout) {
+                    timed_out.store(true, Ordering::SeqCst);
+                    isolate.terminate_execution();
+                }
+            }
+        });
+
+        let result = tokio::time::timeout(timeout, self.execute_script(code)).await;
+        drop(done_tx);
+        let _ = watchdog.join();
+
+        if result.is_err() || timed_out.load(Ordering::SeqCst) {
+            // Let the next script run
+            self.js_runtime.v8_isolate().cancel_terminate_execution();
+            self.release_mouse_effects();
+            anyhow::bail!("Script timed out after {:.1}s", timeout.as_secs_f64());
+        }
+        result?
+    }
+
+    async fn run_wrapped(&mut self, wrapped_code: String) -> Result<()> {
+        self.scripts_run += 1;
+        let name = format!("user_script_{}.js", self.scripts_run);
+
+        self.js_runtime
+            .op_state()
+            .borrow_mut()
+            .put(ScriptError(None));
 
         // execute_script returns the result of the expression
-        let _promise = self
-            .js_runtime
-            .execute_script("user_script.js", wrapped_code)?;
+        let _promise = self.js_runtime.execute_script(name, wrapped_code)?;
 
         // Run event loop to handle any pending ops
         self.js_runtime.run_event_loop(Default::default()).await?;
 
+        let op_state = self.js_runtime.op_state();
+        if let Some(stack) = op_state.borrow_mut().take::<ScriptError>().0 {
+            anyhow::bail!("{}", stack);
+        }
         Ok(())
     }
+
+    /// Close all windows, stop all media and effects, and restore the wallpaper
+    pub fn stop_all(&mut self) {
+        let op_state = self.js_runtime.op_state();
+        sdk::system::stop_all(&mut op_state.borrow_mut());
+    }
+
+    /// Let scripts list and switch packs from `library`, starting from `active`
+    pub fn set_pack_library(&mut self, library: PackLibrary, active: ActivePack) {
+        self.js_runtime.op_state().borrow_mut().put(library);
+        self.set_active_pack(active);
+    }
+
+    /// Set the pack whose moods scripts can switch between
+    pub fn set_active_pack(&mut self, active: ActivePack) {
+        self.js_runtime.op_state().borrow_mut().put(active);
+    }
+
+    /// The pack currently in use, if pack switching is enabled
+    pub fn active_pack(&self) -> Option<ActivePack> {
+        self.js_runtime
+            .op_state()
+            .borrow()
+            .try_borrow::<ActivePack>()
+            .cloned()
+    }
+
+    /// The permissions scripts currently run with
+    pub fn permissions(&self) -> PermissionChecker {
+        self.js_runtime
+            .op_state()
+            .borrow()
+            .borrow::<PermissionChecker>()
+            .clone()
+    }
+
+    /// Change the permissions scripts run with from the next op on
+    pub fn set_permissions(&mut self, permissions: PermissionChecker) {
+        self.js_runtime.op_state().borrow_mut().put(permissions);
+    }
+
+    /// The mood scripts currently select assets with
+    pub fn current_mood(&self) -> Mood {
+        self.js_runtime.op_state().borrow().borrow::<Mood>().clone()
+    }
+
+    /// Stop every running mouse effect
+    pub fn release_mouse_effects(&mut self) {
+        let op_state = self.js_runtime.op_state();
+        let mut op_state = op_state.borrow_mut();
+        if let Some(effects) = op_state.try_borrow_mut::<MouseEffects>() {
+            effects.release_all();
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::assets::types::{Asset, AudioAsset, ImageAsset};
     use crate::gui::WindowSpawner;
     use crate::permissions::{Permission, PermissionChecker, PermissionSet};
 
     fn create_test_context() -> (RuntimeContext, crate::gui::WindowSpawner) {
-        let mut set = PermissionSet::new();
-        set.add(Permission::Image);
-        let permissions = PermissionChecker::new(set);
+        create_test_context_with(AssetRegistry::new(), vec![Permission::Image])
+    }
+
+    /// A context over `registry` with only `permissions` granted
+    fn create_test_context_with(
+        registry: AssetRegistry,
+        permissions: Vec<Permission>,
+    ) -> (RuntimeContext, crate::gui::WindowSpawner) {
+        let permissions = PermissionChecker::new(permissions.into());
 
         let (window_handle, window_spawner) = WindowSpawner::create();
-        let registry = Arc::new(AssetRegistry::new());
+        let registry = Arc::new(registry);
         let mood = Mood {
             name: "Test".to_string(),
             description: "".to_string(),
@@ -148,10 +373,34 @@ mod tests {
             registry,
             mood,
             max_audio_concurrent: 10,
+            max_image_concurrent: usize::MAX,
+            max_video_concurrent: usize::MAX,
+            allow_fallback: false,
+            original_wallpaper: None,
         };
         (context, window_spawner)
     }
 
+    /// An image asset with no weight or known size
+    fn image_asset(path: &str, tags: &[&str]) -> ImageAsset {
+        ImageAsset {
+            path: path.into(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            weight: None,
+            width: 0,
+            height: 0,
+        }
+    }
+
+    fn audio_asset(path: &str) -> AudioAsset {
+        AudioAsset {
+            path: path.into(),
+            tags: vec![],
+            weight: None,
+            duration: None,
+        }
+    }
+
     #[tokio::test]
     #[cfg_attr(miri, ignore)]
     async fn test_runtime_execution() {
@@ -167,6 +416,109 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_sdk_bridge_compiled_once() {
+        let (context, _spawner) = create_test_context();
+        let mut first = GoonRuntime::new(context);
+        let (context, _spawner) = create_test_context();
+        let mut second = GoonRuntime::new(context);
+        assert_eq!(
+            SDK_BRIDGE_COMPILES.load(std::sync::atomic::Ordering::SeqCst),
+            1
+        );
+
+        for runtime in [&mut first, &mut second] {
+            runtime
+                .execute_script("if (!goon.pack) throw new Error('missing SDK');")
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_globals_persist_between_scripts() {
+        let (context, _spawner) = create_test_context();
+        let mut runtime = GoonRuntime::new(context);
+
+        runtime
+            .execute_script("const step = 1; globalThis.visits = step;")
+            .await
+            .unwrap();
+        // Top-level names are per script, globals carry over
+        runtime
+            .execute_script(
+                "const step = 2; globalThis.visits += step; if (visits !== 3) throw new Error(`visits: ${visits}`);",
+            )
+            .await
+            .unwrap();
+        assert_eq!(runtime.scripts_run, 2);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_script_timeout_stops_infinite_loop() {
+        let (context, _spawner) = create_test_context();
+        let mut runtime = GoonRuntime::new(context);
+
+        let err = runtime
+            .execute_script_with_timeout("while (true) {}", Duration::from_millis(200))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("timed out"));
+
+        // The runtime is usable again afterwards
+        runtime
+            .execute_script_with_timeout("globalThis.after = 1;", Duration::from_secs(5))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_console_output_is_collected() {
+        let (context, _spawner) = create_test_context();
+        let mut runtime = GoonRuntime::new(context);
+
+        runtime
+            .execute_script(r#"console.log("hi"); console.error("count", 2, { a: 1 });"#)
+            .await
+            .unwrap();
+        assert_eq!(
+            runtime.take_logs(),
+            vec![
+                LogEntry {
+                    level: "log".to_string(),
+                    message: "hi".to_string()
+                },
+                LogEntry {
+                    level: "error".to_string(),
+                    message: r#"count 2 {"a":1}"#.to_string()
+                },
+            ]
+        );
+        assert!(runtime.take_logs().is_empty());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_runtime_error_includes_stack() {
+        let (context, _spawner) = create_test_context();
+        let mut runtime = GoonRuntime::new(context);
+
+        let err = runtime
+            .execute_script("const a = 1;\nconst b = 2;\nthrow new Error(`boom ${a + b}`);")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Error: boom 3"), "{}", err);
+        assert!(err.contains("user_script_1.js:3:"), "{}", err);
+
+        let thrown = runtime.execute_script("throw 'plain';").await.unwrap_err();
+        assert_eq!(thrown.to_string(), "plain");
+    }
+
     #[tokio::test]
     #[cfg_attr(miri, ignore)]
     async fn test_bigint_duration() {
@@ -212,6 +564,10 @@ mod tests {
             registry,
             mood,
             max_audio_concurrent: 10,
+            max_image_concurrent: usize::MAX,
+            max_video_concurrent: usize::MAX,
+            allow_fallback: false,
+            original_wallpaper: None,
         };
         let mut runtime = GoonRuntime::new(context);
 
@@ -225,6 +581,751 @@ mod tests {
         assert!(err.to_string().contains("Permission denied"));
     }
 
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_capabilities_follow_permissions() {
+        let (context, _spawner) = create_test_context();
+        let mut runtime = GoonRuntime::new(context);
+
+        let code = r#"
+            const caps = await goon.system.capabilities();
+            if (!caps.modules.includes("image")) {
+                throw new Error("Missing image: " + caps.modules);
+            }
+            if (caps.modules.includes("video")) {
+                throw new Error("Unexpected video: " + caps.modules);
+            }
+            if (!caps.version) {
+                throw new Error("Missing version");
+            }
+        "#;
+
+        let result = runtime.execute_script(code).await;
+        assert!(result.is_ok(), "{:?}", result);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_stop_all_clears_media() {
+        use crate::gui::WindowCommand;
+
+        let mut registry = AssetRegistry::new();
+        registry.add(Asset::Image(image_asset(
+            "packs/TestPack/image/beach.jpg",
+            &[],
+        )));
+        registry.add(Asset::Audio(audio_asset(
+            "packs/TestPack/audio/sample-3s.mp3",
+        )));
+        let (mut context, _spawner) =
+            create_test_context_with(registry, vec![Permission::Image, Permission::Audio]);
+
+        let (command_tx, command_rx) = std::sync::mpsc::channel();
+        context.window_spawner = WindowSpawnerHandle::new(command_tx);
+        let mut runtime = GoonRuntime::new(context);
+
+        // Use a detached mixer so the test doesn't need an audio device
+        let (mixer, _source) = rodio::mixer::mixer(2, 44100);
+        let audio_manager = Arc::new(Mutex::new(AudioManager::new(mixer, 10)));
+        runtime
+            .js_runtime
+            .op_state()
+            .borrow_mut()
+            .put(audio_manager.clone());
+
+        let result = runtime
+            .execute_script(
+                r#"
+                await goon.image.show();
+                await goon.audio.play();
+                await goon.audio.play();
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(audio_manager.lock().unwrap().active_count(), 2);
+
+        let result = runtime
+            .execute_script("await goon.system.stopAll(); await goon.system.stopAll();")
+            .await;
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(audio_manager.lock().unwrap().active_count(), 0);
+
+        let commands: Vec<WindowCommand> = command_rx.try_iter().collect();
+        assert!(matches!(commands[0], WindowCommand::SpawnImage { .. }));
+        assert!(matches!(commands[1], WindowCommand::CloseAll));
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_is_media_active_counts() {
+        use crate::gui::WindowCommand;
+        use crate::gui::windows::types::WindowInfo;
+
+        let mut registry = AssetRegistry::new();
+        registry.add(Asset::Image(image_asset(
+            "packs/TestPack/image/beach.jpg",
+            &[],
+        )));
+        registry.add(Asset::Audio(audio_asset(
+            "packs/TestPack/audio/sample-3s.mp3",
+        )));
+        let (mut context, _spawner) =
+            create_test_context_with(registry, vec![Permission::Image, Permission::Audio]);
+
+        // Stand in for the UI thread, tracking spawned windows
+        let (command_tx, command_rx) = std::sync::mpsc::channel();
+        context.window_spawner = WindowSpawnerHandle::new(command_tx);
+        std::thread::spawn(move || {
+            let mut windows = Vec::new();
+            for command in command_rx {
+                match command {
+                    WindowCommand::SpawnImage { handle, .. } => windows.push(WindowInfo {
+                        handle,
+                        window_type: "Image".to_string(),
+                        description: "Image display window".to_string(),
+                    }),
+                    WindowCommand::GetActiveWindows(tx) => {
+                        let _ = tx.send(windows.clone());
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        let mut runtime = GoonRuntime::new(context);
+        let (mixer, _source) = rodio::mixer::mixer(2, 44100);
+        runtime
+            .js_runtime
+            .op_state()
+            .borrow_mut()
+            .put(Arc::new(Mutex::new(AudioManager::new(mixer, 10))));
+
+        let result = runtime
+            .execute_script(
+                r#"
+                const before = await goon.system.isMediaActive();
+                if (before.windows !== 0 || before.audio !== 0) {
+                    throw new Error("Expected nothing active: " + JSON.stringify(before));
+                }
+                await goon.image.show();
+                await goon.audio.play();
+                const after = await goon.system.isMediaActive();
+                if (after.windows !== 1 || after.videos !== 0 || after.audio !== 1) {
+                    throw new Error("Unexpected activity: " + JSON.stringify(after));
+                }
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_idle_ops_use_injected_provider() {
+        struct AwayProvider;
+
+        impl IdleProvider for AwayProvider {
+            fn idle_seconds(&self) -> Result<u64> {
+                Ok(600)
+            }
+
+            fn is_screen_locked(&self) -> Result<bool> {
+                Ok(true)
+            }
+        }
+
+        let (context, _spawner) = create_test_context();
+        let mut runtime = GoonRuntime::new(context);
+        runtime
+            .js_runtime
+            .op_state()
+            .borrow_mut()
+            .put::<Arc<dyn IdleProvider>>(Arc::new(AwayProvider));
+
+        let code = r#"
+            const idle = await goon.system.getIdleSeconds();
+            if (idle !== 600) {
+                throw new Error("Wrong idle time: " + idle);
+            }
+            if (!(await goon.system.isScreenLocked())) {
+                throw new Error("Expected locked screen");
+            }
+        "#;
+
+        let result = runtime.execute_script(code).await;
+        assert!(result.is_ok(), "{:?}", result);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_mouse_requires_input_permission() {
+        let (context, _spawner) = create_test_context();
+        let mut runtime = GoonRuntime::new(context);
+
+        let result = runtime
+            .execute_script("await goon.mouse.move(10, 10);")
+            .await;
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Permission denied")
+        );
+
+        let result = runtime
+            .execute_script("await goon.mouse.getPosition();")
+            .await;
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Permission denied")
+        );
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_capture_requires_screen_permission() {
+        let (context, _spawner) = create_test_context();
+        let mut runtime = GoonRuntime::new(context);
+
+        let result = runtime
+            .execute_script("await goon.screen.captureRegion({ x: 0, y: 0, w: 10, h: 10 });")
+            .await;
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Permission denied")
+        );
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_show_overlay_enqueues_spawn() {
+        use crate::gui::WindowCommand;
+
+        let (mut context, _spawner) =
+            create_test_context_with(AssetRegistry::new(), vec![Permission::Overlay]);
+        let (command_tx, command_rx) = std::sync::mpsc::channel();
+        context.window_spawner = WindowSpawnerHandle::new(command_tx);
+        let mut runtime = GoonRuntime::new(context);
+
+        let result = runtime
+            .execute_script(
+                r#"
+                const overlay = await goon.overlay.show({ color: [255, 0, 0], opacity: 0.5 });
+                await overlay.close();
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+
+        match command_rx.try_recv() {
+            Ok(WindowCommand::SpawnOverlay {
+                color,
+                click_through,
+                ..
+            }) => {
+                assert_eq!(color, [1.0, 0.0, 0.0, 0.5]);
+                assert!(!click_through);
+            }
+            other => panic!("expected SpawnOverlay, got {:?}", other),
+        }
+        assert!(matches!(
+            command_rx.try_recv(),
+            Ok(WindowCommand::CloseWindow(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_tint_is_click_through_by_default() {
+        use crate::gui::WindowCommand;
+
+        let (mut context, _spawner) =
+            create_test_context_with(AssetRegistry::new(), vec![Permission::Overlay]);
+        let (command_tx, command_rx) = std::sync::mpsc::channel();
+        context.window_spawner = WindowSpawnerHandle::new(command_tx);
+        let mut runtime = GoonRuntime::new(context);
+
+        let result = runtime
+            .execute_script(
+                r#"
+                const tint = await goon.overlay.tint({ color: [255, 0, 0], opacity: 0.5 });
+                await tint.close();
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+
+        match command_rx.try_recv() {
+            Ok(WindowCommand::SpawnOverlay {
+                color,
+                click_through,
+                timeout,
+                ..
+            }) => {
+                assert_eq!(color, [1.0, 0.0, 0.0, 0.5]);
+                assert!(click_through);
+                assert!(timeout.is_none());
+            }
+            other => panic!("expected SpawnOverlay, got {:?}", other),
+        }
+        assert!(matches!(
+            command_rx.try_recv(),
+            Ok(WindowCommand::CloseWindow(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_show_banner_spawns_read_only_text() {
+        use crate::gui::WindowCommand;
+
+        let (mut context, _spawner) =
+            create_test_context_with(AssetRegistry::new(), vec![Permission::WriteLines]);
+        let (command_tx, command_rx) = std::sync::mpsc::channel();
+        context.window_spawner = WindowSpawnerHandle::new(command_tx);
+        let mut runtime = GoonRuntime::new(context);
+
+        let result = runtime
+            .execute_script(
+                r#"await goon.text.showBanner({ text: "Eyes on the screen", durationMs: 2000 });"#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+
+        match command_rx.try_recv() {
+            Ok(WindowCommand::SpawnWriteLines {
+                text,
+                interactive,
+                timeout,
+                ..
+            }) => {
+                assert_eq!(text, "Eyes on the screen");
+                assert!(!interactive);
+                assert_eq!(timeout, Some(std::time::Duration::from_millis(2000)));
+            }
+            other => panic!("expected SpawnWriteLines, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_replay_banner() {
+        use crate::gui::WindowCommand;
+
+        let (mut context, _spawner) =
+            create_test_context_with(AssetRegistry::new(), vec![Permission::WriteLines]);
+        let (command_tx, command_rx) = std::sync::mpsc::channel();
+        context.window_spawner = WindowSpawnerHandle::new(command_tx);
+        let mut runtime = GoonRuntime::new(context);
+
+        let result = runtime
+            .execute_script(
+                r#"
+                const banner = await goon.text.showBanner({ text: "Again", durationMs: 2000 });
+                const copy = await goon.system.replayWindow(banner.id);
+                if (copy === banner.id) {
+                    throw new Error("expected a new handle");
+                }
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+
+        let commands: Vec<WindowCommand> = command_rx.try_iter().collect();
+        assert_eq!(commands.len(), 2);
+        for command in commands {
+            match command {
+                WindowCommand::SpawnWriteLines {
+                    text, interactive, ..
+                } => {
+                    assert_eq!(text, "Again");
+                    assert!(!interactive);
+                }
+                other => panic!("expected SpawnWriteLines, got {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_await_prompt_result_after_submission() {
+        use crate::gui::WindowCommand;
+        use crate::gui::windows::PromptOutcome;
+
+        let (mut context, _spawner) =
+            create_test_context_with(AssetRegistry::new(), vec![Permission::WriteLines]);
+        let (command_tx, command_rx) = std::sync::mpsc::channel();
+        context.window_spawner = WindowSpawnerHandle::new(command_tx);
+        let prompts = context.window_spawner.prompts.clone();
+        // Stand in for the UI thread, the user types the text right away
+        std::thread::spawn(move || {
+            for command in command_rx {
+                if let WindowCommand::SpawnWriteLines { handle, .. } = command {
+                    prompts.finish(handle, PromptOutcome::Submitted);
+                }
+            }
+        });
+        let mut runtime = GoonRuntime::new(context);
+
+        let result = runtime
+            .execute_script(
+                r#"
+                const prompt = await goon.writeLines.show({ text: "I will obey" });
+                const outcome = await goon.writeLines.awaitResult(prompt.id);
+                if (outcome !== "submitted") {
+                    throw new Error("unexpected outcome: " + outcome);
+                }
+                try {
+                    await goon.writeLines.awaitResult("00000000-0000-0000-0000-000000000000");
+                    throw new Error("expected unknown handle to fail");
+                } catch (e) {
+                    if (e.code !== "InvalidHandle") throw e;
+                }
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_await_window_event_resolves_on_click() {
+        use crate::gui::WindowHandle;
+        use crate::gui::windows::WindowEvent;
+
+        let (context, _spawner) = create_test_context();
+        let events = context.window_spawner.events.clone();
+        let mut runtime = GoonRuntime::new(context);
+
+        let handle = WindowHandle(uuid::Uuid::new_v4());
+        // Simulate the UI thread delivering a click once the script is waiting
+        let clicker = std::thread::spawn(move || {
+            for _ in 0..500 {
+                if events.dispatch(handle, WindowEvent::Click { x: 12.0, y: 34.0 }) {
+                    return true;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            false
+        });
+
+        let result = runtime
+            .execute_script(&format!(
+                r#"
+                const event = await goon.system.awaitWindowEvent("{}");
+                if (event.type !== "click" || event.x !== 12 || event.y !== 34) {{
+                    throw new Error("Unexpected event: " + JSON.stringify(event));
+                }}
+            "#,
+                handle.0
+            ))
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+        assert!(clicker.join().unwrap());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_probe_image_dimensions() {
+        let mut registry = AssetRegistry::new();
+        registry.add(Asset::Image(image_asset(
+            "packs/TestPack/image/beach.jpg",
+            &["beach"],
+        )));
+        let (context, _spawner) = create_test_context_with(registry, vec![Permission::Image]);
+        let mut runtime = GoonRuntime::new(context);
+
+        let result = runtime
+            .execute_script(
+                r#"
+                const size = await goon.image.probe(["beach"]);
+                if (size.width !== 2500 || size.height !== 1667) {
+                    throw new Error("unexpected size: " + JSON.stringify(size));
+                }
+                try {
+                    await goon.image.probe(["missing"]);
+                    throw new Error("expected no match");
+                } catch (e) {
+                    if (e.code !== "AssetNotFound") throw e;
+                }
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_click_through_rules_out_click_to_close() {
+        let mut registry = AssetRegistry::new();
+        registry.add(Asset::Image(image_asset(
+            "packs/TestPack/image/beach.jpg",
+            &[],
+        )));
+        let (context, _spawner) = create_test_context_with(registry, vec![Permission::Image]);
+        let mut runtime = GoonRuntime::new(context);
+
+        let result = runtime
+            .execute_script(
+                r#"
+                try {
+                    await goon.image.show({ clickThrough: true, requireClickToClose: true });
+                    throw new Error("expected the options to be refused");
+                } catch (e) {
+                    if (e.code !== "InvalidOptions") throw e;
+                }
+                const handle = await goon.image.show({ clickThrough: true });
+                await goon.system.setClickThrough(handle.id, false);
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_restore_wallpaper_without_original() {
+        let (context, _spawner) =
+            create_test_context_with(AssetRegistry::new(), vec![Permission::Wallpaper]);
+        let mut runtime = GoonRuntime::new(context);
+        // Whatever the test machine reports, act as if no wallpaper was detected
+        runtime
+            .js_runtime
+            .op_state()
+            .borrow_mut()
+            .try_take::<WallpaperBackup>();
+
+        let result = runtime
+            .execute_script(
+                r#"
+                try {
+                    await goon.wallpaper.restore();
+                    throw new Error("expected restore to fail");
+                } catch (e) {
+                    if (!e.message.includes("No original wallpaper")) throw e;
+                }
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_pick_asset() {
+        use crate::assets::types::VideoAsset;
+
+        let mut registry = AssetRegistry::new();
+        for (path, tag) in [("a.jpg", "beach"), ("b.jpg", "beach"), ("c.jpg", "city")] {
+            registry.add(Asset::Image(ImageAsset {
+                width: 640,
+                height: 480,
+                ..image_asset(path, &[tag])
+            }));
+        }
+        registry.add(Asset::Video(VideoAsset {
+            path: "clip.mp4".into(),
+            tags: vec![],
+            weight: None,
+            duration: None,
+            width: 0,
+            height: 0,
+        }));
+        let (context, _spawner) = create_test_context_with(registry, vec![Permission::Image]);
+        let mut runtime = GoonRuntime::new(context);
+
+        let result = runtime
+            .execute_script(
+                r#"
+                for (let i = 0; i < 10; i++) {
+                    const asset = await goon.pack.pickAsset("image", ["beach"]);
+                    if (asset.path !== "a.jpg" && asset.path !== "b.jpg") {
+                        throw new Error("unexpected asset: " + JSON.stringify(asset));
+                    }
+                    if (asset.kind !== "image" || asset.width !== 640 || asset.tags[0] !== "beach") {
+                        throw new Error("unexpected metadata: " + JSON.stringify(asset));
+                    }
+                }
+                const city = await goon.pack.pickAsset("image", [], ["beach"]);
+                if (city.path !== "c.jpg") {
+                    throw new Error("excluded tag picked: " + city.path);
+                }
+                try {
+                    await goon.pack.pickAsset("image", ["missing"]);
+                    throw new Error("expected no match");
+                } catch (e) {
+                    if (e.code !== "AssetNotFound") throw e;
+                }
+                // Only the image permission is granted
+                try {
+                    await goon.pack.pickAsset("video");
+                    throw new Error("expected permission error");
+                } catch (e) {
+                    if (e.code !== "PermissionDenied") throw e;
+                }
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_list_tags() {
+        use crate::assets::types::VideoAsset;
+
+        let mut registry = AssetRegistry::new();
+        for tags in [&["city", "beach"][..], &["beach"]] {
+            registry.add(Asset::Image(image_asset("a.jpg", tags)));
+        }
+        registry.add(Asset::Video(VideoAsset {
+            path: "clip.mp4".into(),
+            tags: vec!["motion".to_string()],
+            weight: None,
+            duration: None,
+            width: 0,
+            height: 0,
+        }));
+        let (context, _spawner) = create_test_context_with(registry, vec![Permission::Image]);
+        let mut runtime = GoonRuntime::new(context);
+
+        let result = runtime
+            .execute_script(
+                r#"
+                // Only the image permission is granted, so video tags are left out
+                const all = await goon.system.listTags();
+                if (all.join() !== "beach,city") {
+                    throw new Error("unexpected tags: " + all);
+                }
+                const images = await goon.system.listTags("image");
+                if (images.join() !== "beach,city") {
+                    throw new Error("unexpected image tags: " + images);
+                }
+                try {
+                    await goon.system.listTags("video");
+                    throw new Error("expected permission error");
+                } catch (e) {
+                    if (e.code !== "PermissionDenied") throw e;
+                }
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_switch_pack() {
+        let packs_dir =
+            std::env::temp_dir().join(format!("goon-ai-switch-{}", uuid::Uuid::new_v4()));
+        let other = packs_dir.join("Other");
+        std::fs::create_dir_all(&other).unwrap();
+        std::fs::write(
+            other.join("config.toml"),
+            r#"
+[meta]
+name = "Other"
+version = "1.0.0"
+permissions = ["image"]
+
+[[moods]]
+name = "Calm"
+description = "Calm"
+tags = ["calm"]
+
+[assets]
+image = [
+    { path = "a.png", tags = ["calm"] },
+    { path = "b.png", tags = ["calm"] },
+]
+"#,
+        )
+        .unwrap();
+        for image in ["a.png", "b.png"] {
+            std::fs::write(other.join(image), b"").unwrap();
+        }
+
+        let (context, _spawner) = create_test_context();
+        let mut runtime = GoonRuntime::new(context);
+        let user_permissions: PermissionSet = vec![Permission::Image].into();
+        runtime.set_pack_library(
+            PackLibrary::new(&packs_dir, user_permissions, false),
+            ActivePack {
+                name: "Test".to_string(),
+                config: Arc::new(crate::config::pack::PackConfig::new("Test")),
+            },
+        );
+
+        let result = runtime
+            .execute_script(
+                r#"
+                const packs = await goon.system.listPacks();
+                if (packs.length !== 1 || packs[0] !== "Other") {
+                    throw new Error("unexpected packs: " + packs);
+                }
+                await goon.system.switchPack("Other");
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+
+        assert_eq!(runtime.active_pack().unwrap().name, "Other");
+        assert_eq!(runtime.current_mood().name, "Calm");
+        {
+            let op_state = runtime.js_runtime.op_state();
+            let op_state = op_state.borrow();
+            assert_eq!(op_state.borrow::<Arc<AssetRegistry>>().images.len(), 2);
+        }
+
+        let result = runtime
+            .execute_script(
+                r#"
+                try {
+                    await goon.system.switchPack("Missing");
+                    throw new Error("expected switching to fail");
+                } catch (e) {
+                    if (e.code !== "AssetNotFound") throw e;
+                }
+            "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+        assert_eq!(runtime.active_pack().unwrap().name, "Other");
+
+        let _ = std::fs::remove_dir_all(packs_dir);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(miri, ignore)]
+    async fn test_permission_denied_error_code() {
+        let (context, _spawner) = create_test_context();
+        let mut runtime = GoonRuntime::new(context);
+
+        let result = runtime
+            .execute_script(
+                r#"
+                try {
+                    await goon.mouse.move(10, 10);
+                    throw new Error("expected a permission error");
+                } catch (e) {
+                    if (e.code !== "PermissionDenied") {
+                        throw new Error("unexpected code: " + e.code);
+                    }
+                }
+                "#,
+            )
+            .await;
+        assert!(result.is_ok(), "{:?}", result.err());
+    }
+
     #[tokio::test]
     #[cfg_attr(miri, ignore)]
     async fn test_pack_availability() {
@@ -246,8 +1347,37 @@ mod tests {
             registry,
             mood,
             max_audio_concurrent: 10,
+            max_image_concurrent: usize::MAX,
+            max_video_concurrent: usize::MAX,
+            allow_fallback: false,
+            original_wallpaper: None,
         };
         let mut runtime = GoonRuntime::new(context);
+        let config = crate::config::pack::PackConfig::parse(
+            r#"
+[meta]
+name = "Test Pack"
+version = "1.0.0"
+permissions = []
+
+[[moods]]
+name = "TestMood"
+description = "A test mood"
+tags = ["tag1"]
+
+[[moods]]
+name = "NewMood"
+description = "Another mood"
+tags = ["tag2"]
+
+[assets]
+"#,
+        )
+        .unwrap();
+        runtime.set_active_pack(ActivePack {
+            name: "Test Pack".to_string(),
+            config: Arc::new(config),
+        });
 
         let code = r#"
             const mood = await goon.pack.getCurrentMood();
@@ -258,11 +1388,45 @@ mod tests {
                 throw new Error("Wrong mood tag: " + mood.tags[0]);
             }
 
+            const moods = await goon.pack.listMoods();
+            if (moods.map((m) => m.name).join() !== "TestMood,NewMood") {
+                throw new Error("Unexpected moods: " + JSON.stringify(moods));
+            }
+            if (moods[1].description !== "Another mood" || moods[1].tags[0] !== "tag2") {
+                throw new Error("Mood details missing: " + JSON.stringify(moods[1]));
+            }
+
             await goon.pack.setMood("NewMood");
             const newMood = await goon.pack.getCurrentMood();
             if (newMood.name !== "NewMood") {
                 throw new Error("Failed to set mood: " + newMood.name);
             }
+            // The full mood from the pack is used, not just the name
+            if (newMood.tags[0] !== "tag2") {
+                throw new Error("Mood tags not loaded: " + newMood.tags);
+            }
+
+            try {
+                await goon.pack.setMood("Missing");
+                throw new Error("expected unknown mood to fail");
+            } catch (e) {
+                if (e.code !== "AssetNotFound") {
+                    throw e;
+                }
+                if (!e.message.includes("available moods: TestMood, NewMood")) {
+                    throw new Error("Valid moods not listed: " + e.message);
+                }
+            }
+            const unchanged = await goon.pack.getCurrentMood();
+            if (unchanged.name !== "NewMood") {
+                throw new Error("Mood changed by failed setMood: " + unchanged.name);
+            }
+
+            await goon.pack.setMoods(["TestMood", "NewMood"]);
+            const blend = await goon.pack.getCurrentMood();
+            if (blend.name !== "TestMood + NewMood" || blend.tags.join() !== "tag1,tag2") {
+                throw new Error("Unexpected blend: " + JSON.stringify(blend));
+            }
         "#;
 
         let result = runtime.execute_script(code).await;
@@ -1,19 +1,27 @@
 use crate::permissions::{Permission, PermissionChecker};
 use crate::runtime::error::OpError;
 use deno_core::OpState;
-use deno_core::error::AnyError;
 
 pub fn check_permission(state: &mut OpState, permission: Permission) -> Result<(), OpError> {
     let checker = state.borrow::<PermissionChecker>();
     checker
         .check(permission)
-        .map_err(|e| AnyError::msg(e).into())
+        .map_err(|e| OpError::permission_denied(&e.to_string()))
+}
+
+/// Check that every one of `permissions` is granted, for ops that need several
+pub fn check_permissions(state: &mut OpState, permissions: &[Permission]) -> Result<(), OpError> {
+    let checker = state.borrow::<PermissionChecker>();
+    checker
+        .check_all(permissions)
+        .map_err(|e| OpError::permission_denied(&e.to_string()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::permissions::{Permission, PermissionChecker, PermissionSet};
+    use crate::runtime::error::OpErrorKind;
 
     #[test]
     #[cfg_attr(miri, ignore)]
@@ -38,7 +46,14 @@ mod tests {
             assert!(check_permission(&mut state, Permission::Image).is_ok());
 
             // Should fail
-            assert!(check_permission(&mut state, Permission::Video).is_err());
+            let err = check_permission(&mut state, Permission::Video).unwrap_err();
+            assert_eq!(err.kind, OpErrorKind::PermissionDenied);
+
+            assert!(check_permissions(&mut state, &[Permission::Image]).is_ok());
+            let err =
+                check_permissions(&mut state, &[Permission::Audio, Permission::Image]).unwrap_err();
+            assert_eq!(err.kind, OpErrorKind::PermissionDenied);
+            assert!(err.to_string().contains("'audio'"));
         }
     }
 }
@@ -1,5 +1,3 @@
-use std::fs;
-use std::path::Path;
 use syn::{ItemFn, Meta, visit::Visit};
 
 pub struct OpVisitor {
@@ -148,9 +146,12 @@ impl<'ast> Visit<'ast> for OpVisitor {
     }
 }
 
-pub fn analyze_source(path: &Path) -> (Vec<OpInfo>, Vec<StructInfo>) {
-    let content = fs::read_to_string(path).unwrap_or_default();
-    let syntax = syn::parse_file(&content).expect("Unable to parse file");
+/// Analyze Rust source code for ops and documented structs.
+///
+/// The source is passed in directly (typically via `include_str!`) so that
+/// documentation is available regardless of the working directory.
+pub fn analyze_source(content: &str) -> (Vec<OpInfo>, Vec<StructInfo>) {
+    let syntax = syn::parse_file(content).expect("Unable to parse file");
 
     let mut visitor = OpVisitor {
         ops: Vec::new(),
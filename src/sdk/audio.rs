@@ -1,33 +1,72 @@
 use crate::assets::registry::AssetRegistry;
-use crate::assets::selector::AssetSelector;
+use crate::assets::selector::{AssetFallback, AssetSelector, RecentAssets};
 use crate::assets::types::Asset;
 use crate::config::pack::Mood;
-use crate::media::audio::manager::{AudioHandle, AudioManager};
+use crate::media::audio::manager::{AudioHandle, AudioManager, run_timers};
 use crate::permissions::Permission;
 use crate::runtime::error::OpError;
 use crate::runtime::utils::check_permission;
+use crate::sdk::system::pack_asset_path;
 use deno_core::OpState;
 use deno_core::op2;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json;
 use std::cell::RefCell;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use ts_rs::TS;
 use uuid::Uuid;
 
+/// Crossfade length when a script doesn't pass one
+const DEFAULT_CROSSFADE: Duration = Duration::from_secs(2);
+
 /// Parse a string handle ID into an AudioHandle
 fn parse_audio_handle(handle_id: &str) -> Result<AudioHandle, OpError> {
-    let uuid = Uuid::parse_str(handle_id).map_err(|_| OpError::new("Invalid audio handle ID"))?;
+    let uuid = Uuid::parse_str(handle_id)
+        .map_err(|_| OpError::invalid_handle("Invalid audio handle ID"))?;
     Ok(AudioHandle(uuid))
 }
 
-#[derive(Deserialize, Debug, Default, TS)]
+/// Pick an audio file for the current mood and the given tags
+fn select_audio_path(
+    registry: &AssetRegistry,
+    recent: &Mutex<RecentAssets>,
+    mood: &Mood,
+    allow_fallback: bool,
+    tags: &[String],
+    exclude_tags: &[String],
+) -> Result<PathBuf, OpError> {
+    let selector = AssetSelector::new(registry)
+        .with_recent(recent)
+        .allow_fallback(allow_fallback)
+        .excluding(exclude_tags);
+
+    let asset = selector
+        .select_audio(mood, tags)
+        .ok_or_else(|| OpError::asset_not_found("No audio found matching tags"))?;
+
+    match asset {
+        Asset::Audio(aud) => Ok(aud.path.clone()),
+        _ => Err(OpError::asset_not_found(
+            "Selected asset is not an audio file",
+        )),
+    }
+}
+
+#[derive(Deserialize, Debug, Default, TS, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 /// Options for playing audio
 pub struct AudioOptions {
     /// A list of additional tags to filter audio files by, they will be filtered by mood tags already
     tags: Option<Vec<String>>,
+    /// Tags to avoid, audio files with any of these tags are never picked
+    exclude_tags: Option<Vec<String>>,
+    /// Path of a specific audio file relative to the pack directory, e.g. "audio/intro.mp3".
+    /// Skips tag selection, `tags` and `excludeTags` are ignored.
+    path: Option<String>,
     /// Whether to loop the audio continuously
     loop_: Option<bool>,
     /// Volume level from 0.0 (muted) to 1.0 (full volume)
@@ -42,47 +81,137 @@ pub async fn op_play_audio(
     state: Rc<RefCell<OpState>>,
     #[serde] options: Option<serde_json::Value>,
 ) -> Result<String, OpError> {
-    let (registry, mood, audio_manager) = {
+    let (registry, recent, mood, allow_fallback, audio_manager) = {
         let mut state = state.borrow_mut();
         check_permission(&mut state, Permission::Audio)?;
         let registry = state.borrow::<Arc<AssetRegistry>>().clone();
+        let recent = state.borrow::<Arc<Mutex<RecentAssets>>>().clone();
         let mood = state.borrow::<Mood>().clone();
+        let allow_fallback = state.borrow::<AssetFallback>().0;
         let audio_manager = state.try_borrow::<Arc<Mutex<AudioManager>>>().cloned();
-        (registry, mood, audio_manager)
+        (registry, recent, mood, allow_fallback, audio_manager)
     };
 
     let audio_manager =
         audio_manager.ok_or_else(|| OpError::new("Audio system not initialized"))?;
 
     let opts: AudioOptions = if let Some(o) = options {
-        serde_json::from_value(o).map_err(|e| OpError::new(&e.to_string()))?
+        serde_json::from_value(o).map_err(|e| OpError::invalid_options(&e.to_string()))?
     } else {
         AudioOptions::default()
     };
 
-    let tags = opts.tags.clone().unwrap_or_default();
-    let selector = AssetSelector::new(&registry);
+    let path = match opts.path.as_deref() {
+        Some(path) => pack_asset_path(&registry, path)?,
+        None => select_audio_path(
+            &registry,
+            &recent,
+            &mood,
+            allow_fallback,
+            opts.tags.as_deref().unwrap_or_default(),
+            opts.exclude_tags.as_deref().unwrap_or_default(),
+        )?,
+    };
 
-    let asset = selector
-        .select_audio(&mood, &tags)
-        .ok_or_else(|| OpError::new("No audio found matching tags"))?;
+    let volume = opts.volume.unwrap_or(1.0);
+    let duration = opts.duration.map(Duration::from_secs_f64);
+
+    let handle = {
+        let mut manager = audio_manager
+            .lock()
+            .map_err(|_| OpError::new("Failed to lock audio manager"))?;
+        manager
+            .play_audio(path, volume, duration)
+            .map_err(|e| OpError::new(&e.to_string()))?
+    };
+    run_timers(&audio_manager);
+
+    Ok(handle.0.to_string())
+}
+
+#[derive(Deserialize, Debug, Default, TS, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+/// Options for crossfading into a new audio track
+pub struct CrossfadeOptions {
+    /// A list of additional tags to filter audio files by, they will be filtered by mood tags already
+    tags: Option<Vec<String>>,
+    /// Tags to avoid, audio files with any of these tags are never picked
+    exclude_tags: Option<Vec<String>>,
+    /// Volume the new track fades up to, from 0.0 (muted) to 1.0 (full volume)
+    volume: Option<f32>,
+    /// Duration to play the new track in seconds, after this playback will stop automatically
+    duration: Option<f64>,
+    /// How long the crossfade takes in seconds, defaults to 2
+    fade: Option<f64>,
+}
+
+/// Crossfades from a playing track into a newly picked one.
+///
+/// The old track fades out while the new one fades in, then the old track is
+/// stopped. Both count towards the audio limit until the fade completes.
+///
+/// @param handle - The handle ID of the track to fade out.
+/// @param options - How to pick the new track and how long to fade.
+/// @returns A handle for the new track.
+#[op2(async)]
+#[string]
+pub async fn op_crossfade_audio(
+    state: Rc<RefCell<OpState>>,
+    #[string] handle_id: String,
+    #[serde] options: Option<serde_json::Value>,
+) -> Result<String, OpError> {
+    let from = parse_audio_handle(&handle_id)?;
+    let (registry, recent, mood, allow_fallback, audio_manager) = {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Audio)?;
+        let registry = state.borrow::<Arc<AssetRegistry>>().clone();
+        let recent = state.borrow::<Arc<Mutex<RecentAssets>>>().clone();
+        let mood = state.borrow::<Mood>().clone();
+        let allow_fallback = state.borrow::<AssetFallback>().0;
+        let audio_manager = state.try_borrow::<Arc<Mutex<AudioManager>>>().cloned();
+        (registry, recent, mood, allow_fallback, audio_manager)
+    };
 
-    let path = match asset {
-        Asset::Audio(aud) => &aud.path,
-        _ => return Err(OpError::new("Selected asset is not an audio file")),
+    let audio_manager =
+        audio_manager.ok_or_else(|| OpError::new("Audio system not initialized"))?;
+
+    let opts: CrossfadeOptions = if let Some(o) = options {
+        serde_json::from_value(o).map_err(|e| OpError::invalid_options(&e.to_string()))?
+    } else {
+        CrossfadeOptions::default()
+    };
+
+    let fade = match opts.fade {
+        Some(secs) if !secs.is_finite() || secs < 0.0 => {
+            return Err(OpError::invalid_options(
+                "fade must be a non-negative number of seconds",
+            ));
+        }
+        Some(secs) => Duration::from_secs_f64(secs),
+        None => DEFAULT_CROSSFADE,
     };
 
+    let path = select_audio_path(
+        &registry,
+        &recent,
+        &mood,
+        allow_fallback,
+        opts.tags.as_deref().unwrap_or_default(),
+        opts.exclude_tags.as_deref().unwrap_or_default(),
+    )?;
+
     let volume = opts.volume.unwrap_or(1.0);
-    let duration = opts.duration.map(std::time::Duration::from_secs_f64);
+    let duration = opts.duration.map(Duration::from_secs_f64);
 
     let handle = {
         let mut manager = audio_manager
             .lock()
             .map_err(|_| OpError::new("Failed to lock audio manager"))?;
         manager
-            .play_audio(path.clone(), volume, duration)
+            .crossfade_to(from, path, volume, duration, fade)
             .map_err(|e| OpError::new(&e.to_string()))?
     };
+    run_timers(&audio_manager);
 
     Ok(handle.0.to_string())
 }
@@ -131,7 +260,7 @@ pub async fn op_pause_audio(
     };
 
     if let Some(manager) = audio_manager {
-        let manager = manager
+        let mut manager = manager
             .lock()
             .map_err(|_| OpError::new("Failed to lock audio manager"))?;
         manager.pause_audio(handle);
@@ -155,7 +284,7 @@ pub async fn op_resume_audio(
     };
 
     if let Some(manager) = audio_manager {
-        let manager = manager
+        let mut manager = manager
             .lock()
             .map_err(|_| OpError::new("Failed to lock audio manager"))?;
         manager.resume_audio(handle);
@@ -181,7 +310,7 @@ pub async fn op_set_audio_volume(
     };
 
     if let Some(manager) = audio_manager {
-        let manager = manager
+        let mut manager = manager
             .lock()
             .map_err(|_| OpError::new("Failed to lock audio manager"))?;
         manager.set_volume(handle, volume);
@@ -189,13 +318,59 @@ pub async fn op_set_audio_volume(
     Ok(())
 }
 
+/// Sets the master volume, scaling every track including ones started later.
+///
+/// @param volume - Master volume from 0.0 (silent) to 1.0 (full volume).
+#[op2(async)]
+pub async fn op_set_master_volume(state: Rc<RefCell<OpState>>, volume: f32) -> Result<(), OpError> {
+    if !volume.is_finite() {
+        return Err(OpError::invalid_options("volume must be a number"));
+    }
+    let audio_manager = {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Audio)?;
+        state.try_borrow::<Arc<Mutex<AudioManager>>>().cloned()
+    };
+
+    if let Some(manager) = audio_manager {
+        let mut manager = manager
+            .lock()
+            .map_err(|_| OpError::new("Failed to lock audio manager"))?;
+        manager.set_master_volume(volume);
+    }
+    Ok(())
+}
+
+/// Mutes or unmutes all audio. Tracks started while muted stay silent until unmuted.
+///
+/// @param muted - Whether audio should be muted.
+#[op2(async)]
+pub async fn op_set_muted(state: Rc<RefCell<OpState>>, muted: bool) -> Result<(), OpError> {
+    let audio_manager = {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Audio)?;
+        state.try_borrow::<Arc<Mutex<AudioManager>>>().cloned()
+    };
+
+    if let Some(manager) = audio_manager {
+        let mut manager = manager
+            .lock()
+            .map_err(|_| OpError::new("Failed to lock audio manager"))?;
+        manager.set_muted(muted);
+    }
+    Ok(())
+}
+
 deno_core::extension!(
     goon_audio,
     ops = [
         op_play_audio,
+        op_crossfade_audio,
         op_stop_audio,
         op_pause_audio,
         op_resume_audio,
-        op_set_audio_volume
+        op_set_audio_volume,
+        op_set_master_volume,
+        op_set_muted
     ],
 );
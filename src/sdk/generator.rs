@@ -1,5 +1,5 @@
-use crate::sdk::{analysis, metadata};
-use std::path::Path;
+use crate::sdk::metadata::SdkModule;
+use crate::sdk::{analysis, registry};
 
 /// Convert a Rust op function name to a TypeScript method name.
 /// e.g., "op_show_image" -> "show"
@@ -33,25 +33,33 @@ fn op_name_to_ts_method(op_name: &str) -> String {
 use tracing::info;
 
 pub fn generate_definitions(allowed_modules: &[String]) -> String {
+    generate_module_definitions(&registry::modules(), allowed_modules)
+}
+
+/// Definitions for the allowed ones of `all_modules`, plus those every script gets
+pub fn generate_module_definitions(
+    all_modules: &[SdkModule],
+    allowed_modules: &[String],
+) -> String {
     info!("Generator received allowed_modules: {:?}", allowed_modules);
-    let all_modules = metadata::get_modules();
     let mut definitions = String::new();
 
     definitions.push_str("/** GoonAI SDK */\n");
 
-    for module in &all_modules {
+    for module in all_modules.iter() {
         let include = match module.permission {
             None => true, // Always include
-            Some(perm) => allowed_modules.iter().any(|m| m == perm || m == "all"),
+            Some(_) => allowed_modules
+                .iter()
+                .any(|m| m == module.name || m == "all"),
         };
 
         if include {
-            // Analyze source file for ops if it exists
-            let source_path = format!("src/sdk/{}.rs", module.name);
+            // Analyze the embedded module source for ops
             let mut template = module.template.clone();
 
-            if Path::new(&source_path).exists() {
-                let (ops, structs) = analysis::analyze_source(Path::new(&source_path));
+            if let Some(source) = module.source {
+                let (ops, structs) = analysis::analyze_source(source);
 
                 // Auto-generate function signatures from ops
                 for op in &ops {
@@ -225,10 +233,12 @@ pub fn generate_definitions(allowed_modules: &[String]) -> String {
 
     // Generate global 'goon' namespace
     definitions.push_str("\ndeclare const goon: {\n");
-    for module in all_modules {
+    for module in all_modules.iter() {
         let include = match module.permission {
             None => true,
-            Some(perm) => allowed_modules.iter().any(|m| m == perm || m == "all"),
+            Some(_) => allowed_modules
+                .iter()
+                .any(|m| m == module.name || m == "all"),
         };
 
         if include {
@@ -285,4 +295,12 @@ mod tests {
         assert!(defs.contains("// Module: video"));
         assert!(defs.contains("// Module: audio"));
     }
+
+    #[test]
+    fn test_generate_definitions_matches_module_name() {
+        // The mouse module is unlocked by the input permission but allowed by name
+        let defs = generate_definitions(&["mouse".to_string()]);
+        assert!(defs.contains("// Module: mouse"));
+        assert!(defs.contains("mouse: typeof mouse;"));
+    }
 }
@@ -1,5 +1,5 @@
 use crate::assets::registry::AssetRegistry;
-use crate::assets::selector::AssetSelector;
+use crate::assets::selector::{AssetFallback, AssetSelector, RecentAssets};
 use crate::assets::types::Asset;
 use crate::config::pack::Mood;
 use crate::permissions::Permission;
@@ -8,19 +8,22 @@ use crate::runtime::utils::check_permission;
 use crate::sdk::types::WindowOptions;
 use deno_core::OpState;
 use deno_core::op2;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json;
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use ts_rs::TS;
 
-#[derive(Deserialize, Debug, Default, TS)]
+#[derive(Deserialize, Debug, Default, TS, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 /// Options for displaying a hypnotic pattern
 pub struct HypnoOptions {
     /// A list of additional tags to filter hypno patterns by, they will be filtered by mood tags already
     pub tags: Option<Vec<String>>,
+    /// Tags to avoid, hypno patterns with any of these tags are never picked
+    pub exclude_tags: Option<Vec<String>>,
     /// Duration to display the pattern in seconds, after this the window will be closed automatically
     pub duration: Option<u64>,
     /// Window configuration options
@@ -39,30 +42,40 @@ pub async fn op_show_hypno(
     state: Rc<RefCell<OpState>>,
     #[serde] options: Option<serde_json::Value>,
 ) -> Result<u32, OpError> {
-    let (registry, mood) = {
+    let (registry, recent, mood, allow_fallback) = {
         let mut state = state.borrow_mut();
         check_permission(&mut state, Permission::Hypno)?;
         let registry = state.borrow::<Arc<AssetRegistry>>().clone();
+        let recent = state.borrow::<Arc<Mutex<RecentAssets>>>().clone();
         let mood = state.borrow::<Mood>().clone();
-        (registry, mood)
+        let allow_fallback = state.borrow::<AssetFallback>().0;
+        (registry, recent, mood, allow_fallback)
     };
 
     let opts: HypnoOptions = if let Some(o) = options {
-        serde_json::from_value(o).map_err(|e| OpError::new(&e.to_string()))?
+        serde_json::from_value(o).map_err(|e| OpError::invalid_options(&e.to_string()))?
     } else {
         HypnoOptions::default()
     };
 
     let tags = opts.tags.clone().unwrap_or_default();
-    let selector = AssetSelector::new(&registry);
+    let exclude_tags = opts.exclude_tags.clone().unwrap_or_default();
+    let selector = AssetSelector::new(&registry)
+        .with_recent(&recent)
+        .allow_fallback(allow_fallback)
+        .excluding(&exclude_tags);
 
     let asset = selector
         .select_hypno(&mood, &tags)
-        .ok_or_else(|| OpError::new("No hypno pattern found matching tags"))?;
+        .ok_or_else(|| OpError::asset_not_found("No hypno pattern found matching tags"))?;
 
     let path = match asset {
         Asset::Hypno(h) => &h.path,
-        _ => return Err(OpError::new("Selected asset is not a hypno pattern")),
+        _ => {
+            return Err(OpError::asset_not_found(
+                "Selected asset is not a hypno pattern",
+            ));
+        }
     };
 
     println!("Showing hypno: {:?} with options: {:?}", path, opts);
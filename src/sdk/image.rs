@@ -1,33 +1,70 @@
 use crate::assets::registry::AssetRegistry;
-use crate::assets::selector::AssetSelector;
+use crate::assets::selector::{AssetFallback, AssetSelector, RecentAssets};
 use crate::assets::types::Asset;
 use crate::config::pack::Mood;
 use crate::gui::WindowSpawnerHandle;
+use crate::gui::windows::fade::WindowFade;
+use crate::gui::windows::layout::AutoLayout;
+use crate::media::image::color_key::ColorKey;
 use crate::permissions::Permission;
 use crate::runtime::error::OpError;
 use crate::runtime::utils::check_permission;
+use crate::sdk::system::{monitor_layout, pack_asset_path, popup_click_through};
 use crate::sdk::types::WindowOptions;
 use deno_core::OpState;
 use deno_core::op2;
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tracing::{error, info};
 use ts_rs::TS;
 
-#[derive(Deserialize, Debug, Default, TS)]
+#[derive(Deserialize, Debug, Default, TS, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 /// Options for displaying an image
 pub struct ImageOptions {
     /// A list of additional tags to filter images by, they will be filtered by mood tags already, most of the time you will not need to provide any tags here
     pub tags: Option<Vec<String>>,
-    /// Duration to display the image in seconds, after this the window will be closed automatically
+    /// Tags to avoid, images with any of these tags are never picked
+    pub exclude_tags: Option<Vec<String>>,
+    /// Path of a specific image relative to the pack directory, e.g. "images/intro.png".
+    /// Skips tag selection, `tags` and `excludeTags` are ignored.
+    pub path: Option<String>,
+    /// Duration to display the image in seconds, after this the window will be closed automatically.
+    /// Defaults to the popup timeout in settings.
     pub duration: Option<u64>,
+    /// Keep the window open until the user clicks it, `duration` is ignored
+    pub require_click_to_close: Option<bool>,
+    /// Milliseconds the window must be visible before a click closes it, defaults to 0
+    pub min_visible_ms: Option<u64>,
+    /// Let mouse clicks pass through the image to the windows below, defaults to false.
+    /// Can't be combined with `requireClickToClose`.
+    pub click_through: Option<bool>,
+    /// Fraction of the screen (0.0-1.0) the image should fill, keeping its aspect ratio.
+    /// Overrides `window.size`.
+    pub size_percent: Option<f32>,
+    /// Whether to center the window on the screen. Overrides `window.position`.
+    pub center: Option<bool>,
+    /// Hex color (e.g. "#00ff00") to treat as transparent, for non-rectangular popups
+    pub color_key: Option<String>,
+    /// How close (0.0-1.0 per channel) a pixel must be to `colorKey` to be made transparent
+    pub color_key_tolerance: Option<f32>,
     /// Window configuration options
     pub window: Option<WindowOptions>,
 }
 
+/// Pixel dimensions of an image asset
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
+pub struct ImageDimensions {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+}
+
 /// Displays an image in a new window.
 ///
 /// Returns a handle object that can be used to control the window.
@@ -42,27 +79,38 @@ pub async fn op_show_image(
     state: Rc<RefCell<OpState>>,
     #[serde] options: Option<ImageOptions>,
 ) -> Result<String, OpError> {
-    let (window_spawner, registry, mood) = {
+    let (window_spawner, registry, recent, mood, allow_fallback) = {
         let mut state = state.borrow_mut();
         check_permission(&mut state, Permission::Image)?;
         let spawner = state.borrow::<WindowSpawnerHandle>().clone();
         let registry = state.borrow::<Arc<AssetRegistry>>().clone();
+        let recent = state.borrow::<Arc<Mutex<RecentAssets>>>().clone();
         let mood = state.borrow::<Mood>().clone();
-        (spawner, registry, mood)
+        let allow_fallback = state.borrow::<AssetFallback>().0;
+        (spawner, registry, recent, mood, allow_fallback)
     };
 
     let opts = options.unwrap_or_default();
 
-    let tags = opts.tags.unwrap_or_default();
-    let selector = AssetSelector::new(&registry);
+    let path = match opts.path.as_deref() {
+        Some(path) => pack_asset_path(&registry, path)?,
+        None => {
+            let tags = opts.tags.unwrap_or_default();
+            let exclude_tags = opts.exclude_tags.unwrap_or_default();
+            let selector = AssetSelector::new(&registry)
+                .with_recent(&recent)
+                .allow_fallback(allow_fallback)
+                .excluding(&exclude_tags);
 
-    let asset = selector
-        .select_image(&mood, &tags)
-        .ok_or_else(|| OpError::new("No image found matching tags"))?;
+            let asset = selector
+                .select_image(&mood, &tags)
+                .ok_or_else(|| OpError::asset_not_found("No image found matching tags"))?;
 
-    let path = match asset {
-        Asset::Image(img) => img.path.clone(),
-        _ => return Err(OpError::new("Selected asset is not an image")),
+            match asset {
+                Asset::Image(img) => img.path.clone(),
+                _ => return Err(OpError::asset_not_found("Selected asset is not an image")),
+            }
+        }
     };
 
     info!("Spawning image window: {:?}", path);
@@ -71,17 +119,85 @@ pub async fn op_show_image(
     let window = opts.window.as_ref();
     let width = window.and_then(|w| w.size.as_ref()).map(|s| s.width);
     let height = window.and_then(|w| w.size.as_ref()).map(|s| s.height);
-    let opacity = window.and_then(|w| w.opacity).unwrap_or(1.0);
+    let opacity = window.and_then(|w| w.opacity);
+    let always_on_top = window.and_then(|w| w.always_on_top);
+    let click_through = popup_click_through(opts.click_through, opts.require_click_to_close)?;
+    let fade = WindowFade::from_millis(
+        window.and_then(|w| w.fade_in_ms),
+        window.and_then(|w| w.fade_out_ms),
+    );
+    let color_key = opts
+        .color_key
+        .as_deref()
+        .map(|hex| ColorKey::from_hex(hex, opts.color_key_tolerance))
+        .transpose()
+        .map_err(|e| OpError::invalid_options(&e.to_string()))?;
 
     // Spawn the image window
     let handle = window_spawner
-        .spawn_image(path, width, height, opacity)
+        .spawn_image(
+            path,
+            width,
+            height,
+            opacity,
+            always_on_top,
+            opts.duration.map(Duration::from_secs),
+            opts.require_click_to_close
+                .unwrap_or(false)
+                .then(|| Duration::from_millis(opts.min_visible_ms.unwrap_or(0))),
+            AutoLayout {
+                size_percent: opts.size_percent,
+                center: opts.center.unwrap_or(false),
+                ..monitor_layout(window)?
+            },
+            color_key,
+            fade,
+            click_through,
+        )
         .map_err(|e| {
             error!("Failed to spawn image window: {}", e);
-            OpError::new(&e.to_string())
+            OpError::from(e)
         })?;
 
     Ok(handle.0.to_string())
 }
 
-deno_core::extension!(goon_image, ops = [op_show_image],);
+/// Gets the dimensions of an image without displaying it.
+///
+/// Only the image header is read, so this is cheap to call before deciding on a layout.
+///
+/// @param tags - Optional tags to filter images by, in addition to the mood tags.
+/// @returns The width and height of the selected image in pixels.
+#[op2(async)]
+#[serde]
+pub async fn op_probe_image(
+    state: Rc<RefCell<OpState>>,
+    #[serde] tags: Option<Vec<String>>,
+) -> Result<ImageDimensions, OpError> {
+    let (registry, mood, allow_fallback) = {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Image)?;
+        let registry = state.borrow::<Arc<AssetRegistry>>().clone();
+        let mood = state.borrow::<Mood>().clone();
+        let allow_fallback = state.borrow::<AssetFallback>().0;
+        (registry, mood, allow_fallback)
+    };
+
+    let tags = tags.unwrap_or_default();
+    let asset = AssetSelector::new(&registry)
+        .allow_fallback(allow_fallback)
+        .select_image(&mood, &tags)
+        .ok_or_else(|| OpError::asset_not_found("No image found matching tags"))?;
+
+    let path = match asset {
+        Asset::Image(img) => &img.path,
+        _ => return Err(OpError::asset_not_found("Selected asset is not an image")),
+    };
+
+    let (width, height) = ::image::image_dimensions(path)
+        .map_err(|e| OpError::new(&format!("Failed to read image {:?}: {}", path, e)))?;
+
+    Ok(ImageDimensions { width, height })
+}
+
+deno_core::extension!(goon_image, ops = [op_show_image, op_probe_image],);
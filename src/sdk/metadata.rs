@@ -1,10 +1,15 @@
-use crate::sdk::templates;
+use crate::sdk::{runtime_gen, templates};
 
+#[derive(Clone)]
 pub struct SdkModule {
     pub name: &'static str,
     pub template: String,
     pub permission: Option<&'static str>,
     pub dependencies: Vec<&'static str>,
+    /// Embedded Rust source of the module's ops, used to extract documentation
+    pub source: Option<&'static str>,
+    /// Generates the module's runtime bridge code, `None` for type-only modules
+    pub runtime: Option<fn() -> String>,
 }
 
 pub fn get_modules() -> Vec<SdkModule> {
@@ -14,60 +19,120 @@ pub fn get_modules() -> Vec<SdkModule> {
             template: templates::types_ts(),
             permission: None, // Always included
             dependencies: vec![],
+            source: Some(include_str!("types.rs")),
+            runtime: None,
         },
         SdkModule {
             name: "system",
             template: templates::system_ts(),
             permission: None, // Always included
             dependencies: vec![],
+            source: Some(include_str!("system.rs")),
+            runtime: Some(runtime_gen::generate_system_runtime),
         },
         SdkModule {
             name: "pack",
             template: templates::pack_ts(),
             permission: None, // Always included
             dependencies: vec!["types"],
+            source: Some(include_str!("pack.rs")),
+            runtime: Some(runtime_gen::generate_pack_runtime),
         },
         SdkModule {
             name: "image",
             template: templates::image_ts(),
             permission: Some("image"),
             dependencies: vec!["types"],
+            source: Some(include_str!("image.rs")),
+            runtime: Some(runtime_gen::generate_image_runtime),
         },
         SdkModule {
             name: "video",
             template: templates::video_ts(),
             permission: Some("video"),
             dependencies: vec!["types"],
+            source: Some(include_str!("video.rs")),
+            runtime: Some(runtime_gen::generate_video_runtime),
         },
         SdkModule {
             name: "audio",
             template: templates::audio_ts(),
             permission: Some("audio"),
             dependencies: vec!["types"],
+            source: Some(include_str!("audio.rs")),
+            runtime: Some(runtime_gen::generate_audio_runtime),
         },
         SdkModule {
             name: "hypno",
             template: templates::hypno_ts(),
             permission: Some("hypno"),
             dependencies: vec!["types"],
+            source: Some(include_str!("hypno.rs")),
+            runtime: Some(runtime_gen::generate_hypno_runtime),
         },
         SdkModule {
             name: "writeLines",
             template: templates::write_lines_ts(),
             permission: Some("writeLines"),
             dependencies: vec!["types", "image"],
+            source: Some(include_str!("write_lines.rs")),
+            runtime: Some(runtime_gen::generate_write_lines_runtime),
+        },
+        SdkModule {
+            name: "text",
+            template: templates::text_ts(),
+            permission: Some("writeLines"),
+            dependencies: vec!["types"],
+            source: Some(include_str!("text.rs")),
+            runtime: Some(runtime_gen::generate_text_runtime),
         },
         SdkModule {
             name: "wallpaper",
             template: templates::wallpaper_ts(),
             permission: Some("wallpaper"),
             dependencies: vec![],
+            source: Some(include_str!("wallpaper.rs")),
+            runtime: Some(runtime_gen::generate_wallpaper_runtime),
         },
         SdkModule {
             name: "website",
             template: templates::website_ts(),
             permission: Some("website"),
             dependencies: vec![],
+            source: Some(include_str!("website.rs")),
+            runtime: Some(runtime_gen::generate_website_runtime),
+        },
+        SdkModule {
+            name: "mouse",
+            template: templates::mouse_ts(),
+            permission: Some("input"),
+            dependencies: vec![],
+            source: Some(include_str!("mouse.rs")),
+            runtime: Some(runtime_gen::generate_mouse_runtime),
+        },
+        SdkModule {
+            name: "screen",
+            template: templates::screen_ts(),
+            permission: Some("screen"),
+            dependencies: vec![],
+            source: Some(include_str!("screen.rs")),
+            runtime: Some(runtime_gen::generate_screen_runtime),
+        },
+        SdkModule {
+            name: "overlay",
+            template: templates::overlay_ts(),
+            permission: Some("overlay"),
+            dependencies: vec![],
+            source: Some(include_str!("overlay.rs")),
+            runtime: Some(runtime_gen::generate_overlay_runtime),
+        },
+        SdkModule {
+            name: "notification",
+            template: templates::notification_ts(),
+            permission: Some("notification"),
+            dependencies: vec![],
+            source: Some(include_str!("notification.rs")),
+            runtime: Some(runtime_gen::generate_notification_runtime),
         },
     ]
 }
@@ -0,0 +1,281 @@
+//! Static check for calls to SDK methods that don't exist
+//!
+//! Compiled scripts are scanned before they run, so a typo like
+//! `image.displayy()` is reported to the model along with the methods it could
+//! have meant, instead of surfacing later as a `TypeError` inside V8.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Methods and ops defined by the SDK runtime code
+#[derive(Debug, Clone, Default)]
+pub struct SdkMethods {
+    /// Static methods of each SDK class, e.g. `image` -> `show`
+    classes: BTreeMap<String, BTreeSet<String>>,
+    /// Ops called by the runtime code
+    ops: BTreeSet<String>,
+}
+
+/// A call in a script to a method the SDK doesn't have
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownMethod {
+    /// The call as written, e.g. `image.displayy`
+    pub call: String,
+    /// What exists in its place
+    pub available: Vec<String>,
+}
+
+impl fmt::Display for UnknownMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown method {}; available: {}",
+            self.call,
+            self.available.join(", ")
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Dot,
+    Call,
+    Other,
+}
+
+impl SdkMethods {
+    /// Methods of every registered SDK module
+    pub fn load() -> Self {
+        Self::from_sources(&super::get_all_typescript_sources())
+    }
+
+    /// Read the classes and ops out of generated runtime code
+    pub fn from_sources(sources: &[String]) -> Self {
+        let mut methods = Self::default();
+        for source in sources {
+            let mut class = None;
+            for line in source.lines() {
+                let line = line.trim();
+                if let Some(rest) = line.strip_prefix("class ") {
+                    class = rest.split([' ', '{']).next().map(str::to_string);
+                } else if let Some(rest) = line.strip_prefix("static ")
+                    && let Some(class) = &class
+                {
+                    let rest = rest.strip_prefix("async ").unwrap_or(rest);
+                    if let Some((name, _)) = rest.split_once('(') {
+                        methods
+                            .classes
+                            .entry(class.clone())
+                            .or_default()
+                            .insert(name.to_string());
+                    }
+                }
+
+                for (index, prefix) in line.match_indices("Deno.core.ops.") {
+                    let rest = &line[index + prefix.len()..];
+                    let op: String = rest.chars().take_while(|&c| is_ident(c)).collect();
+                    if !op.is_empty() {
+                        methods.ops.insert(op);
+                    }
+                }
+            }
+        }
+        methods
+    }
+
+    /// Calls in `js` to SDK methods or ops that don't exist, each reported once
+    ///
+    /// Module names the script also uses as plain values, like a local
+    /// `const text = ...`, are taken to be shadowed and not checked.
+    pub fn check(&self, js: &str) -> Vec<UnknownMethod> {
+        let tokens = tokenize(js);
+        let chains = chains(&tokens);
+
+        let shadowed: BTreeSet<&str> = chains
+            .iter()
+            .filter(|(chain, _)| chain.len() == 1)
+            .map(|(chain, _)| chain[0])
+            .collect();
+
+        let mut unknown: Vec<UnknownMethod> = Vec::new();
+        for (chain, called) in chains {
+            if !called {
+                continue;
+            }
+            let found = match chain.as_slice() {
+                ["Deno", "core", "ops", op] if !self.ops.contains(*op) => Some(UnknownMethod {
+                    call: format!("Deno.core.ops.{}", op),
+                    available: self.ops.iter().cloned().collect(),
+                }),
+                ["goon", class, method] => self.unknown_method(class, method),
+                [class, method] if !shadowed.contains(class) => self.unknown_method(class, method),
+                _ => None,
+            };
+            if let Some(found) = found
+                && !unknown.contains(&found)
+            {
+                unknown.push(found);
+            }
+        }
+        unknown
+    }
+
+    fn unknown_method(&self, class: &str, method: &str) -> Option<UnknownMethod> {
+        let methods = self.classes.get(class)?;
+        if methods.contains(method) {
+            return None;
+        }
+        Some(UnknownMethod {
+            call: format!("{}.{}", class, method),
+            available: methods.iter().map(|m| format!("{}.{}", class, m)).collect(),
+        })
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Identifiers, dots and call parentheses of `js`, with strings and comments skipped
+fn tokenize(js: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = js.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' | '`' => {
+                // Template substitutions are skipped along with the text
+                while let Some(next) = chars.next() {
+                    match next {
+                        '\\' => {
+                            chars.next();
+                        }
+                        _ if next == c => break,
+                        _ => {}
+                    }
+                }
+                tokens.push(Token::Other);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for next in chars.by_ref() {
+                    if last == '*' && next == '/' {
+                        break;
+                    }
+                    last = next;
+                }
+            }
+            '.' => tokens.push(Token::Dot),
+            '(' => tokens.push(Token::Call),
+            _ if c.is_whitespace() => {}
+            _ if is_ident(c) => {
+                let mut ident = c.to_string();
+                while let Some(&next) = chars.peek()
+                    && is_ident(next)
+                {
+                    ident.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            _ => tokens.push(Token::Other),
+        }
+    }
+    tokens
+}
+
+/// Member chains like `goon.image.show`, and whether each is called
+fn chains(tokens: &[Token]) -> Vec<(Vec<&str>, bool)> {
+    let mut chains = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let starts_chain = i == 0 || tokens[i - 1] != Token::Dot;
+        let Token::Ident(first) = &tokens[i] else {
+            i += 1;
+            continue;
+        };
+        if !starts_chain {
+            i += 1;
+            continue;
+        }
+
+        let mut chain = vec![first.as_str()];
+        i += 1;
+        while let (Some(Token::Dot), Some(Token::Ident(next))) = (tokens.get(i), tokens.get(i + 1))
+        {
+            chain.push(next.as_str());
+            i += 2;
+        }
+        // A trailing dot means the chain goes on through something else
+        let called = tokens.get(i) == Some(&Token::Call);
+        if tokens.get(i) != Some(&Token::Dot) {
+            chains.push((chain, called));
+        }
+    }
+    chains
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typo_is_reported_with_alternatives() {
+        let methods = SdkMethods::load();
+        let unknown = methods.check("const img = await image.displayy({ tags: ['beach'] });");
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].call, "image.displayy");
+        assert!(unknown[0].available.contains(&"image.show".to_string()));
+        assert!(
+            unknown[0]
+                .to_string()
+                .starts_with("unknown method image.displayy; available: ")
+        );
+
+        let unknown = methods.check("await goon.video.plya(); await goon.video.plya();");
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].call, "video.plya");
+    }
+
+    #[test]
+    fn test_known_calls_pass() {
+        let methods = SdkMethods::load();
+        let js = r#"
+            const img = await image.show({ tags: ["beach"] });
+            await goon.audio.play();
+            await img.close();
+            Deno.core.ops.op_show_image({});
+        "#;
+        assert_eq!(methods.check(js), vec![]);
+    }
+
+    #[test]
+    fn test_strings_comments_and_locals_are_ignored() {
+        let methods = SdkMethods::load();
+        let js = r#"
+            // image.nope()
+            /* video.nope() */
+            console.log("image.nope()", 'audio.nope()', `text.nope()`);
+            const text = "a,b";
+            text.split(",");
+        "#;
+        assert_eq!(methods.check(js), vec![]);
+    }
+
+    #[test]
+    fn test_unknown_op_is_reported() {
+        let methods = SdkMethods::load();
+        let unknown = methods.check("Deno.core.ops.op_launch_missiles();");
+        assert_eq!(unknown.len(), 1);
+        assert_eq!(unknown[0].call, "Deno.core.ops.op_launch_missiles");
+        assert!(unknown[0].available.contains(&"op_show_image".to_string()));
+    }
+}
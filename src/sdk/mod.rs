@@ -1,12 +1,20 @@
 #![allow(dead_code)]
 
 use crate::permissions::{Permission, PermissionChecker};
+use schemars::JsonSchema;
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
 pub mod audio;
 pub mod hypno;
 pub mod image;
+pub mod mouse;
+pub mod notification;
+pub mod overlay;
 pub mod pack;
+pub mod screen;
 pub mod system;
+pub mod text;
 pub mod video;
 pub mod wallpaper;
 pub mod website;
@@ -15,61 +23,89 @@ pub mod write_lines;
 pub mod analysis;
 pub mod generator;
 pub mod metadata;
+pub mod methods;
+pub mod registry;
 pub mod runtime_gen;
 pub mod templates;
 pub mod types;
 
 pub fn get_all_typescript_sources() -> Vec<String> {
-    vec![
-        runtime_gen::generate_init_runtime(),
-        runtime_gen::generate_image_runtime(),
-        runtime_gen::generate_pack_runtime(),
-        runtime_gen::generate_video_runtime(),
-        runtime_gen::generate_audio_runtime(),
-        runtime_gen::generate_hypno_runtime(),
-        runtime_gen::generate_wallpaper_runtime(),
-        runtime_gen::generate_write_lines_runtime(),
-        runtime_gen::generate_website_runtime(),
-        runtime_gen::generate_system_runtime(),
-    ]
+    let mut sources = vec![runtime_gen::generate_init_runtime()];
+    sources.extend(
+        registry::modules()
+            .iter()
+            .filter_map(|module| module.runtime.map(|runtime| runtime())),
+    );
+    sources
 }
 
 pub fn generate_typescript_definitions(allowed_modules: &[String]) -> String {
-    generator::generate_definitions(allowed_modules)
+    registry::definitions(allowed_modules)
+}
+
+/// Drop cached SDK definitions and pick up modules registered since startup
+pub fn reload_definitions() {
+    registry::reload();
 }
 
 use tracing::info;
 
+/// SDK modules that the given permissions unlock
+pub fn allowed_modules(permissions: &PermissionChecker) -> Vec<String> {
+    registry::modules()
+        .iter()
+        .filter(|module| {
+            module
+                .permission
+                .and_then(|perm| Permission::from_str(perm).ok())
+                .is_some_and(|perm| permissions.has_permission(perm))
+        })
+        .map(|module| module.name.to_string())
+        .collect()
+}
+
 pub fn generate_definitions_for_permissions(permissions: &PermissionChecker) -> String {
-    let mut allowed_modules = Vec::new();
-    if permissions.has_permission(Permission::Image) {
-        allowed_modules.push("image".to_string());
-    }
-    if permissions.has_permission(Permission::Video) {
-        allowed_modules.push("video".to_string());
-    }
-    if permissions.has_permission(Permission::Audio) {
-        allowed_modules.push("audio".to_string());
-    }
-    if permissions.has_permission(Permission::Hypno) {
-        allowed_modules.push("hypno".to_string());
-    }
-    if permissions.has_permission(Permission::Wallpaper) {
-        allowed_modules.push("wallpaper".to_string());
-    }
-    if permissions.has_permission(Permission::WriteLines) {
-        allowed_modules.push("writeLines".to_string());
-    }
-    if permissions.has_permission(Permission::Website) {
-        allowed_modules.push("website".to_string());
-    }
+    // Full access includes modules whose permission isn't a known one
+    let allowed_modules = if permissions.has_full_access() {
+        vec!["all".to_string()]
+    } else {
+        allowed_modules(permissions)
+    };
 
     info!(
         "Generating SDK definitions for modules: {:?}",
         allowed_modules
     );
 
-    generator::generate_definitions(&allowed_modules)
+    registry::definitions(&allowed_modules)
+}
+
+/// JSON Schema of every options object the SDK methods take, keyed by type
+/// name, for validating scripts' arguments before they run
+pub fn generate_option_schemas() -> BTreeMap<String, serde_json::Value> {
+    fn schema<T: JsonSchema>() -> (String, serde_json::Value) {
+        (
+            T::schema_name().into_owned(),
+            schemars::schema_for!(T).to_value(),
+        )
+    }
+
+    BTreeMap::from([
+        schema::<audio::AudioOptions>(),
+        schema::<audio::CrossfadeOptions>(),
+        schema::<hypno::HypnoOptions>(),
+        schema::<image::ImageOptions>(),
+        schema::<mouse::MouseRect>(),
+        schema::<overlay::OverlayOptions>(),
+        schema::<overlay::TintOptions>(),
+        schema::<screen::CaptureRegion>(),
+        schema::<system::ShakeOptions>(),
+        schema::<text::BannerOptions>(),
+        schema::<video::VideoOptions>(),
+        schema::<wallpaper::WallpaperOptions>(),
+        schema::<website::WebsiteOptions>(),
+        schema::<write_lines::WriteLinesOptions>(),
+    ])
 }
 
 #[cfg(test)]
@@ -135,4 +171,41 @@ mod tests {
         assert!(defs.contains("class image"));
         assert!(!defs.contains("class video"));
     }
+
+    #[test]
+    fn test_generate_definitions_for_full_access() {
+        let defs = generate_definitions_for_permissions(&PermissionChecker::all());
+        assert_eq!(defs, generate_typescript_definitions(&["all".to_string()]));
+        assert!(defs.contains("class overlay"));
+    }
+
+    #[test]
+    fn test_allowed_modules_follow_metadata_permissions() {
+        let mut set = PermissionSet::new();
+        set.add(Permission::Input);
+        set.add(Permission::Image);
+        let checker = PermissionChecker::new(set);
+
+        let modules = allowed_modules(&checker);
+        assert_eq!(modules, vec!["image", "mouse"]);
+    }
+
+    #[test]
+    fn test_option_schemas() {
+        let schemas = generate_option_schemas();
+        assert_eq!(schemas.len(), 14);
+
+        let image = &schemas["ImageOptions"];
+        assert_eq!(image["type"], "object");
+        assert!(image["properties"]["excludeTags"].is_object());
+        // Nested option types are pulled in as definitions
+        assert!(image["$defs"]["WindowOptions"]["properties"]["alwaysOnTop"].is_object());
+
+        let wallpaper = &schemas["WallpaperOptions"];
+        assert!(wallpaper.to_string().contains("\"stretch\""));
+
+        // Required fields are the ones without a default
+        let banner = &schemas["BannerOptions"];
+        assert_eq!(banner["required"], serde_json::json!(["text"]));
+    }
 }
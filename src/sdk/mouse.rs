@@ -0,0 +1,249 @@
+use crate::media::mouse::{MouseEffect, MouseEffectHandle, MouseEffects, clamp_effect_duration};
+use crate::permissions::Permission;
+use crate::runtime::error::OpError;
+use crate::runtime::utils::check_permission;
+use deno_core::OpState;
+use deno_core::op2;
+use enigo::{Coordinate, Enigo, Mouse, Settings};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use ts_rs::TS;
+use uuid::Uuid;
+
+/// Largest jiggle offset in pixels
+const MAX_JIGGLE_INTENSITY: u32 = 100;
+/// How often jiggle and confine effects update the cursor
+const EFFECT_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Current mouse cursor position
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
+pub struct MousePosition {
+    /// X coordinate in pixels from the left edge of the screen
+    pub x: i32,
+    /// Y coordinate in pixels from the top edge of the screen
+    pub y: i32,
+}
+
+/// Screen rectangle the cursor can be confined to
+#[derive(Deserialize, Debug, Clone, Copy, TS, JsonSchema)]
+pub struct MouseRect {
+    /// X coordinate of the left edge in pixels
+    pub x: i32,
+    /// Y coordinate of the top edge in pixels
+    pub y: i32,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+}
+
+impl MouseRect {
+    /// Clamp a point so it lies inside this rectangle
+    pub fn clamp(&self, x: i32, y: i32) -> (i32, i32) {
+        let (x, y) = clamp_to_display(
+            x - self.x,
+            y - self.y,
+            (self.width as i32, self.height as i32),
+        );
+        (x + self.x, y + self.y)
+    }
+}
+
+/// Clamp a point so it stays on a display of the given size
+pub fn clamp_to_display(x: i32, y: i32, (width, height): (i32, i32)) -> (i32, i32) {
+    (
+        x.clamp(0, (width - 1).max(0)),
+        y.clamp(0, (height - 1).max(0)),
+    )
+}
+
+fn connect() -> Result<Enigo, OpError> {
+    Enigo::new(&Settings::default())
+        .map_err(|e| OpError::new(&format!("Failed to access input device: {}", e)))
+}
+
+/// Moves the mouse cursor to a position on screen.
+///
+/// Coordinates outside the main display are clamped to its edges.
+///
+/// @param x - X coordinate in pixels from the left edge of the screen.
+/// @param y - Y coordinate in pixels from the top edge of the screen.
+#[op2(async)]
+pub async fn op_move_mouse(state: Rc<RefCell<OpState>>, x: i32, y: i32) -> Result<(), OpError> {
+    {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Input)?;
+    }
+
+    let mut enigo = connect()?;
+    let display = enigo
+        .main_display()
+        .map_err(|e| OpError::new(&format!("Failed to read display size: {}", e)))?;
+    let (x, y) = clamp_to_display(x, y, display);
+
+    enigo
+        .move_mouse(x, y, Coordinate::Abs)
+        .map_err(|e| OpError::new(&format!("Failed to move mouse: {}", e)))?;
+
+    Ok(())
+}
+
+/// Gets the current mouse cursor position.
+///
+/// @returns The cursor position in screen pixels.
+#[op2(async)]
+#[serde]
+pub async fn op_get_mouse_position(state: Rc<RefCell<OpState>>) -> Result<MousePosition, OpError> {
+    {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Input)?;
+    }
+
+    let enigo = connect()?;
+    let (x, y) = enigo
+        .location()
+        .map_err(|e| OpError::new(&format!("Failed to read mouse position: {}", e)))?;
+
+    Ok(MousePosition { x, y })
+}
+
+/// Register a running effect and return its handle ID
+fn register_effect(state: &Rc<RefCell<OpState>>, effect: MouseEffect) -> String {
+    let mut state = state.borrow_mut();
+    let handle = state.borrow_mut::<MouseEffects>().insert(effect);
+    handle.0.to_string()
+}
+
+/// Jiggles the mouse cursor around its current position for a while.
+///
+/// The duration is capped at 30 seconds. The cursor is released early with `release()`.
+///
+/// @param durationMs - How long to jiggle, in milliseconds.
+/// @param intensity - Maximum offset in pixels (1-100).
+/// @returns A handle ID that can be passed to `release()`.
+#[op2(async)]
+#[string]
+pub async fn op_mouse_jiggle(
+    state: Rc<RefCell<OpState>>,
+    duration_ms: u32,
+    intensity: u32,
+) -> Result<String, OpError> {
+    {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Input)?;
+    }
+
+    let intensity = intensity.clamp(1, MAX_JIGGLE_INTENSITY) as i32;
+    let duration = clamp_effect_duration(duration_ms as u64);
+
+    let effect = MouseEffect::spawn(duration, EFFECT_INTERVAL, move || {
+        let mut enigo = connect().ok();
+        let anchor = enigo.as_ref().and_then(|e| e.location().ok());
+        move || {
+            if let (Some(enigo), Some((x, y))) = (enigo.as_mut(), anchor) {
+                let dx = rand::random_range(-intensity..=intensity);
+                let dy = rand::random_range(-intensity..=intensity);
+                let _ = enigo.move_mouse(x + dx, y + dy, Coordinate::Abs);
+            }
+        }
+    });
+
+    Ok(register_effect(&state, effect))
+}
+
+/// Keeps the mouse cursor inside a rectangle for a while.
+///
+/// The duration is capped at 30 seconds, and the cursor is always freed once it ends.
+///
+/// @param rect - The screen area to keep the cursor in.
+/// @param durationMs - How long to confine the cursor, in milliseconds.
+/// @returns A handle ID that can be passed to `release()`.
+#[op2(async)]
+#[string]
+pub async fn op_confine_mouse(
+    state: Rc<RefCell<OpState>>,
+    #[serde] rect: MouseRect,
+    duration_ms: u32,
+) -> Result<String, OpError> {
+    {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Input)?;
+    }
+
+    let duration = clamp_effect_duration(duration_ms as u64);
+
+    let effect = MouseEffect::spawn(duration, EFFECT_INTERVAL, move || {
+        let mut enigo = connect().ok();
+        move || {
+            if let Some(enigo) = enigo.as_mut()
+                && let Ok((x, y)) = enigo.location()
+            {
+                let (cx, cy) = rect.clamp(x, y);
+                if (cx, cy) != (x, y) {
+                    let _ = enigo.move_mouse(cx, cy, Coordinate::Abs);
+                }
+            }
+        }
+    });
+
+    Ok(register_effect(&state, effect))
+}
+
+/// Stops a jiggle or confine effect early.
+///
+/// @param handle - The handle ID returned from `jiggle()` or `confine()`.
+#[op2(async)]
+pub async fn op_release_mouse(
+    state: Rc<RefCell<OpState>>,
+    #[string] handle: String,
+) -> Result<(), OpError> {
+    let uuid = Uuid::parse_str(&handle)
+        .map_err(|_| OpError::invalid_handle("Invalid mouse effect handle ID"))?;
+    let mut state = state.borrow_mut();
+    check_permission(&mut state, Permission::Input)?;
+    state
+        .borrow_mut::<MouseEffects>()
+        .release(MouseEffectHandle(uuid));
+    Ok(())
+}
+
+deno_core::extension!(
+    goon_mouse,
+    ops = [
+        op_move_mouse,
+        op_get_mouse_position,
+        op_mouse_jiggle,
+        op_confine_mouse,
+        op_release_mouse
+    ],
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamp_to_display() {
+        let display = (1920, 1080);
+        assert_eq!(clamp_to_display(100, 200, display), (100, 200));
+        assert_eq!(clamp_to_display(-50, -10, display), (0, 0));
+        assert_eq!(clamp_to_display(5000, 5000, display), (1919, 1079));
+        assert_eq!(clamp_to_display(1920, 0, display), (1919, 0));
+    }
+
+    #[test]
+    fn test_rect_clamp() {
+        let rect = MouseRect {
+            x: 100,
+            y: 100,
+            width: 200,
+            height: 100,
+        };
+        assert_eq!(rect.clamp(150, 150), (150, 150));
+        assert_eq!(rect.clamp(0, 0), (100, 100));
+        assert_eq!(rect.clamp(1000, 1000), (299, 199));
+    }
+}
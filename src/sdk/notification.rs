@@ -0,0 +1,46 @@
+use crate::permissions::Permission;
+use crate::runtime::error::OpError;
+use crate::runtime::utils::check_permission;
+use deno_core::OpState;
+use deno_core::op2;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tracing::warn;
+
+/// Shows a native desktop notification.
+///
+/// Notifications are best effort, if the platform has no notification
+/// service the call does nothing.
+///
+/// @param title - The notification title.
+/// @param body - The notification text.
+#[op2(async)]
+pub async fn op_notify(
+    state: Rc<RefCell<OpState>>,
+    #[string] title: String,
+    #[string] body: String,
+) -> Result<(), OpError> {
+    {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Notification)?;
+    }
+
+    // Talking to the notification service blocks, on Linux it's a D-Bus call
+    let shown = tokio::task::spawn_blocking(move || {
+        notify_rust::Notification::new()
+            .appname("GoonAI")
+            .summary(&title)
+            .body(&body)
+            .show()
+            .map(|_| ())
+    })
+    .await
+    .map_err(|e| OpError::new(&format!("Notification task failed: {}", e)))?;
+
+    if let Err(e) = shown {
+        warn!("Failed to show notification: {}", e);
+    }
+    Ok(())
+}
+
+deno_core::extension!(goon_notification, ops = [op_notify],);
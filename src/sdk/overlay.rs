@@ -0,0 +1,159 @@
+use crate::gui::WindowSpawnerHandle;
+use crate::permissions::Permission;
+use crate::runtime::error::OpError;
+use crate::runtime::utils::check_permission;
+use deno_core::OpState;
+use deno_core::op2;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use ts_rs::TS;
+
+#[derive(Deserialize, Debug, Default, TS, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+/// Options for a full-screen color overlay
+pub struct OverlayOptions {
+    /// Fill color as RGB array [r, g, b] with values from 0 to 255, defaults to black
+    pub color: Option<[u8; 3]>,
+    /// Overlay opacity from 0.0 (transparent) to 1.0 (opaque), defaults to 1.0
+    pub opacity: Option<f32>,
+    /// Fade in from transparent over this many milliseconds
+    pub fade_ms: Option<u64>,
+    /// Let mouse clicks pass through the overlay to the windows below
+    pub click_through: Option<bool>,
+    /// Duration to show the overlay in seconds, after this it will be closed automatically
+    pub duration: Option<u64>,
+}
+
+impl OverlayOptions {
+    /// Fill color as normalized RGBA, with the opacity as alpha
+    pub fn rgba(&self) -> [f32; 4] {
+        let [r, g, b] = self.color.unwrap_or([0, 0, 0]);
+        [
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+            self.opacity.unwrap_or(1.0).clamp(0.0, 1.0),
+        ]
+    }
+}
+
+#[derive(Deserialize, Debug, Default, TS, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+/// Options for a full-screen color tint
+pub struct TintOptions {
+    /// Tint color as RGB array [r, g, b] with values from 0 to 255, defaults to a deep red
+    pub color: Option<[u8; 3]>,
+    /// Tint strength from 0.0 (none) to 1.0 (opaque), defaults to 0.2
+    pub opacity: Option<f32>,
+    /// Fade in from transparent over this many milliseconds
+    pub fade_ms: Option<u64>,
+    /// Let mouse clicks pass through the tint to the windows below, defaults to true
+    pub click_through: Option<bool>,
+}
+
+impl TintOptions {
+    /// As overlay options, filling in the tint defaults
+    pub fn to_overlay(&self) -> OverlayOptions {
+        OverlayOptions {
+            color: Some(self.color.unwrap_or([128, 0, 32])),
+            opacity: Some(self.opacity.unwrap_or(0.2)),
+            fade_ms: self.fade_ms,
+            click_through: Some(self.click_through.unwrap_or(true)),
+            duration: None,
+        }
+    }
+}
+
+/// Covers the whole screen with a solid color, for flashes and blackouts.
+///
+/// Returns a handle object that can be used to control the overlay.
+/// The returned handle has a `.close()` method to remove the overlay.
+///
+/// @param options - Optional color, opacity, fade-in and click-through settings.
+/// @returns A unique handle object for controlling this overlay.
+#[op2(async)]
+#[string]
+pub async fn op_show_overlay(
+    state: Rc<RefCell<OpState>>,
+    #[serde] options: Option<OverlayOptions>,
+) -> Result<String, OpError> {
+    show_overlay(state, options.unwrap_or_default())
+}
+
+/// Washes the whole screen in a translucent color, for mood lighting.
+///
+/// Unlike `show()` the tint stays until it is closed and lets clicks through by
+/// default, so the desktop stays usable. `system.stopAll()` removes it too.
+///
+/// @param options - Optional color, strength, fade-in and click-through settings.
+/// @returns A unique handle object for controlling this tint.
+#[op2(async)]
+#[string]
+pub async fn op_show_tint(
+    state: Rc<RefCell<OpState>>,
+    #[serde] options: Option<TintOptions>,
+) -> Result<String, OpError> {
+    show_overlay(state, options.unwrap_or_default().to_overlay())
+}
+
+fn show_overlay(state: Rc<RefCell<OpState>>, opts: OverlayOptions) -> Result<String, OpError> {
+    let window_spawner = {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Overlay)?;
+        state.borrow::<WindowSpawnerHandle>().clone()
+    };
+
+    let handle = window_spawner
+        .spawn_overlay(
+            opts.rgba(),
+            opts.fade_ms.map(Duration::from_millis),
+            opts.click_through.unwrap_or(false),
+            opts.duration.map(Duration::from_secs),
+        )
+        .map_err(OpError::from)?;
+
+    Ok(handle.0.to_string())
+}
+
+deno_core::extension!(goon_overlay, ops = [op_show_overlay, op_show_tint],);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_overlay_options() {
+        let opts: OverlayOptions = serde_json::from_value(serde_json::json!({
+            "color": [255, 0, 51],
+            "opacity": 0.5,
+            "fadeMs": 250,
+            "clickThrough": true
+        }))
+        .unwrap();
+        assert_eq!(opts.rgba(), [1.0, 0.0, 0.2, 0.5]);
+        assert_eq!(opts.fade_ms, Some(250));
+        assert_eq!(opts.click_through, Some(true));
+
+        assert_eq!(OverlayOptions::default().rgba(), [0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_parse_tint_options() {
+        let opts: TintOptions = serde_json::from_value(serde_json::json!({
+            "color": [0, 0, 255],
+            "opacity": 0.25
+        }))
+        .unwrap();
+        let overlay = opts.to_overlay();
+        assert_eq!(overlay.rgba(), [0.0, 0.0, 1.0, 0.25]);
+        assert_eq!(overlay.click_through, Some(true));
+        assert_eq!(overlay.duration, None);
+
+        let blackout_style: TintOptions =
+            serde_json::from_value(serde_json::json!({ "clickThrough": false })).unwrap();
+        assert_eq!(blackout_style.to_overlay().click_through, Some(false));
+    }
+}
@@ -1,7 +1,132 @@
+use crate::assets::library::ActivePack;
+use crate::assets::registry::AssetRegistry;
+use crate::assets::selector::{AssetFallback, AssetSelector, RecentAssets};
+use crate::assets::types::Asset;
 use crate::config::pack::Mood;
+use crate::permissions::Permission;
 use crate::runtime::error::OpError;
+use crate::runtime::utils::check_permission;
 use deno_core::OpState;
 use deno_core::op2;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+/// Kind of asset in a pack
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
+#[serde(rename_all = "camelCase")]
+pub enum AssetKind {
+    Image,
+    Video,
+    Audio,
+    Hypno,
+    Wallpaper,
+    Website,
+}
+
+impl AssetKind {
+    pub const ALL: [AssetKind; 6] = [
+        AssetKind::Image,
+        AssetKind::Video,
+        AssetKind::Audio,
+        AssetKind::Hypno,
+        AssetKind::Wallpaper,
+        AssetKind::Website,
+    ];
+
+    /// Assets of this kind in the registry
+    pub fn assets(self, registry: &AssetRegistry) -> &[Asset] {
+        match self {
+            AssetKind::Image => &registry.images,
+            AssetKind::Video => &registry.videos,
+            AssetKind::Audio => &registry.audio,
+            AssetKind::Hypno => &registry.hypnos,
+            AssetKind::Wallpaper => &registry.wallpapers,
+            AssetKind::Website => &registry.websites,
+        }
+    }
+
+    /// Permission needed to use assets of this kind
+    pub fn permission(self) -> Permission {
+        match self {
+            AssetKind::Image => Permission::Image,
+            AssetKind::Video => Permission::Video,
+            AssetKind::Audio => Permission::Audio,
+            AssetKind::Hypno => Permission::Hypno,
+            AssetKind::Wallpaper => Permission::Wallpaper,
+            AssetKind::Website => Permission::Website,
+        }
+    }
+}
+
+/// Metadata of an asset picked from the pack
+#[derive(Serialize, Debug, Clone, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct PickedAsset {
+    /// Kind of the asset
+    pub kind: AssetKind,
+    /// Path of the asset file, `null` for websites
+    pub path: Option<String>,
+    /// URL of a website asset
+    pub url: Option<String>,
+    /// Tags of the asset
+    pub tags: Vec<String>,
+    /// Width in pixels, for images and videos
+    pub width: Option<u32>,
+    /// Height in pixels, for images and videos
+    pub height: Option<u32>,
+    /// Duration in milliseconds, for videos and audio when known
+    pub duration_ms: Option<u64>,
+}
+
+impl From<&Asset> for PickedAsset {
+    fn from(asset: &Asset) -> Self {
+        let (kind, url, width, height, duration) = match asset {
+            Asset::Image(a) => (AssetKind::Image, None, Some(a.width), Some(a.height), None),
+            Asset::Video(a) => (
+                AssetKind::Video,
+                None,
+                Some(a.width),
+                Some(a.height),
+                a.duration,
+            ),
+            Asset::Audio(a) => (AssetKind::Audio, None, None, None, a.duration),
+            Asset::Hypno(_) => (AssetKind::Hypno, None, None, None, None),
+            Asset::Wallpaper(_) => (AssetKind::Wallpaper, None, None, None, None),
+            Asset::Website(a) => (AssetKind::Website, Some(a.url.clone()), None, None, None),
+        };
+        Self {
+            kind,
+            path: asset.get_path().map(|p| p.to_string_lossy().into_owned()),
+            url,
+            tags: asset.get_tags().clone(),
+            width,
+            height,
+            duration_ms: duration.map(|d| d.as_millis() as u64),
+        }
+    }
+}
+
+/// A mood scripts can switch to
+#[derive(Serialize, Debug, Clone, PartialEq, TS)]
+pub struct MoodInfo {
+    /// Name to pass to `setMood()`
+    pub name: String,
+    /// What the mood is about
+    pub description: String,
+    /// Tags of the assets shown in this mood, empty if it allows every asset
+    pub tags: Vec<String>,
+}
+
+impl From<&Mood> for MoodInfo {
+    fn from(mood: &Mood) -> Self {
+        Self {
+            name: mood.name.clone(),
+            description: mood.description.clone(),
+            tags: mood.tags.clone(),
+        }
+    }
+}
 
 /// Gets the current mood for the session.
 ///
@@ -15,9 +140,51 @@ pub fn op_get_current_mood(state: &mut OpState) -> Result<Mood, OpError> {
     Ok(mood.clone())
 }
 
+/// Lists the moods of the current pack.
+///
+/// Use this to find valid names for `setMood()` and `setMoods()`.
+///
+/// @returns The name, description and tags of each mood.
+#[op2]
+#[serde]
+pub fn op_list_moods(state: &mut OpState) -> Result<Vec<MoodInfo>, OpError> {
+    let active = state
+        .try_borrow::<ActivePack>()
+        .ok_or_else(|| OpError::new("No pack is loaded"))?;
+    Ok(active.config.moods.iter().map(MoodInfo::from).collect())
+}
+
+/// Look up a mood of the current pack by name, the error lists the valid names
+fn find_mood(state: &OpState, mood_name: &str) -> Result<Mood, OpError> {
+    let active = state
+        .try_borrow::<ActivePack>()
+        .ok_or_else(|| OpError::new("No pack is loaded"))?;
+    active
+        .config
+        .moods
+        .iter()
+        .find(|m| m.name == mood_name)
+        .cloned()
+        .ok_or_else(|| {
+            let names: Vec<&str> = active
+                .config
+                .moods
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect();
+            OpError::asset_not_found(&format!(
+                "Mood '{}' not found in pack '{}', available moods: {}",
+                mood_name,
+                active.name,
+                names.join(", ")
+            ))
+        })
+}
+
 /// Sets the current mood for the session by name.
 ///
 /// Changing the mood affects which assets are selected in subsequent operations.
+/// The name must be one of the moods defined by the current pack.
 ///
 /// @param mood_name - The name of the mood to set.
 #[op2(fast)]
@@ -25,37 +192,80 @@ pub fn op_set_current_mood(
     state: &mut OpState,
     #[string] mood_name: String,
 ) -> Result<(), OpError> {
-    // In a real implementation, we might want to validate the mood name against available moods
-    // For now, we just update the name and clear tags/description if we don't have the full list
-    // Or better, we should store the list of available moods in OpState too.
-
-    // For this task, let's assume we just update the mood object.
-    // But wait, if we just update the name, we lose the tags.
-    // We need access to the PackConfig or list of moods to find the matching mood.
+    let mood = find_mood(state, &mood_name)?;
+    state.put(mood);
+    Ok(())
+}
 
-    // Let's check if we have PackConfig in OpState.
-    // runtime.rs doesn't seem to put PackConfig.
+/// Blends several moods of the current pack into the active mood.
+///
+/// Assets matching any of the moods can be selected, so `["Calm", "Spiral"]`
+/// draws from both pools.
+///
+/// @param mood_names - Names of the moods to blend, at least one.
+#[op2]
+pub fn op_set_moods(state: &mut OpState, #[serde] mood_names: Vec<String>) -> Result<(), OpError> {
+    if mood_names.is_empty() {
+        return Err(OpError::invalid_options("At least one mood is required"));
+    }
+    let moods = mood_names
+        .iter()
+        .map(|name| find_mood(state, name))
+        .collect::<Result<Vec<_>, _>>()?;
 
-    // For now, let's just update the name and keep tags empty if we can't find it.
-    // This is a simplification.
+    state.put(Mood::blend(&moods));
+    Ok(())
+}
 
-    // Actually, let's try to find the mood if we can.
-    // If not, we just set the name.
+/// Picks a random asset from the current mood's pool without showing it.
+///
+/// Uses the same selection as the show functions, so scripts can inspect the
+/// asset before deciding what to do with it.
+///
+/// @param kind - The kind of asset to pick, requires the matching permission.
+/// @param tags - Optional tags to filter by, in addition to the mood tags.
+/// @param excludeTags - Optional tags to avoid, assets with any of them are never picked.
+/// @returns The path, tags and dimensions of the picked asset.
+#[op2]
+#[serde]
+pub fn op_pick_asset(
+    state: &mut OpState,
+    #[serde] kind: AssetKind,
+    #[serde] tags: Option<Vec<String>>,
+    #[serde] exclude_tags: Option<Vec<String>>,
+) -> Result<PickedAsset, OpError> {
+    check_permission(state, kind.permission())?;
+    let registry = state.borrow::<Arc<AssetRegistry>>().clone();
+    let recent = state.borrow::<Arc<Mutex<RecentAssets>>>().clone();
+    let allow_fallback = state.borrow::<AssetFallback>().0;
+    let mood = state.borrow::<Mood>();
 
-    let current_mood = state.borrow::<Mood>().clone();
-    if current_mood.name == mood_name {
-        return Ok(());
+    let tags = tags.unwrap_or_default();
+    let exclude_tags = exclude_tags.unwrap_or_default();
+    let selector = AssetSelector::new(&registry)
+        .with_recent(&recent)
+        .allow_fallback(allow_fallback)
+        .excluding(&exclude_tags);
+    let asset = match kind {
+        AssetKind::Image => selector.select_image(mood, &tags),
+        AssetKind::Video => selector.select_video(mood, &tags),
+        AssetKind::Audio => selector.select_audio(mood, &tags),
+        AssetKind::Hypno => selector.select_hypno(mood, &tags),
+        AssetKind::Wallpaper => selector.select_wallpaper(mood, &tags),
+        AssetKind::Website => selector.select_website(mood, &tags),
     }
+    .ok_or_else(|| OpError::asset_not_found("No asset found matching tags"))?;
 
-    let new_mood = Mood {
-        name: mood_name,
-        description: String::new(),
-        tags: Vec::new(),
-        prompt: None,
-    };
-
-    state.put(new_mood);
-    Ok(())
+    Ok(PickedAsset::from(asset))
 }
 
-deno_core::extension!(goon_pack, ops = [op_get_current_mood, op_set_current_mood],);
+deno_core::extension!(
+    goon_pack,
+    ops = [
+        op_get_current_mood,
+        op_list_moods,
+        op_set_current_mood,
+        op_set_moods,
+        op_pick_asset
+    ],
+);
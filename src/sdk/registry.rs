@@ -0,0 +1,143 @@
+//! Process-wide registry of SDK modules
+//!
+//! Runtime sources, permission mapping and TypeScript definitions are all read
+//! from here. Modules registered after startup become visible on [`reload`],
+//! which also drops the cached definitions.
+
+use crate::sdk::{generator, metadata::SdkModule};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+use tracing::info;
+
+#[derive(Default)]
+struct Registry {
+    /// Modules visible to the SDK, rebuilt on reload
+    modules: Option<Arc<Vec<SdkModule>>>,
+    /// Modules registered since startup, added to the snapshot on reload
+    extra: Vec<SdkModule>,
+    /// Generated definitions keyed by the allowed modules they were built for
+    definitions: HashMap<Vec<String>, String>,
+    /// Bumped on every reload so stale generations are not cached
+    generation: u64,
+}
+
+impl Registry {
+    fn snapshot(&mut self) -> Arc<Vec<SdkModule>> {
+        self.modules
+            .get_or_insert_with(|| {
+                let mut modules = crate::sdk::metadata::get_modules();
+                modules.extend(self.extra.iter().cloned());
+                Arc::new(modules)
+            })
+            .clone()
+    }
+}
+
+/// SDK modules and the definitions generated from them, the built-in modules
+/// plus any registered on top
+#[derive(Default)]
+pub struct ModuleRegistry {
+    inner: RwLock<Registry>,
+}
+
+impl ModuleRegistry {
+    /// All modules, built-in ones first
+    pub fn modules(&self) -> Arc<Vec<SdkModule>> {
+        if let Some(modules) = &self.inner.read().unwrap().modules {
+            return modules.clone();
+        }
+        self.inner.write().unwrap().snapshot()
+    }
+
+    /// Add a module, it becomes visible after the next [`ModuleRegistry::reload`]
+    pub fn register(&self, module: SdkModule) {
+        self.inner.write().unwrap().extra.push(module);
+    }
+
+    /// Rebuild the module list and drop all cached definitions
+    pub fn reload(&self) {
+        let mut registry = self.inner.write().unwrap();
+        registry.modules = None;
+        registry.definitions.clear();
+        registry.generation += 1;
+        info!("Reloaded SDK module registry");
+    }
+
+    /// TypeScript definitions for the allowed modules, generated once per module set
+    pub fn definitions(&self, allowed_modules: &[String]) -> String {
+        let generation = {
+            let registry = self.inner.read().unwrap();
+            if let Some(defs) = registry.definitions.get(allowed_modules) {
+                return defs.clone();
+            }
+            registry.generation
+        };
+
+        let defs = generator::generate_module_definitions(&self.modules(), allowed_modules);
+
+        let mut registry = self.inner.write().unwrap();
+        if registry.generation == generation {
+            registry
+                .definitions
+                .insert(allowed_modules.to_vec(), defs.clone());
+        }
+        defs
+    }
+}
+
+static REGISTRY: LazyLock<ModuleRegistry> = LazyLock::new(Default::default);
+
+/// All SDK modules, built-in ones first
+pub fn modules() -> Arc<Vec<SdkModule>> {
+    REGISTRY.modules()
+}
+
+/// Add a module to the SDK, it becomes visible after the next [`reload`]
+pub fn register(module: SdkModule) {
+    REGISTRY.register(module);
+}
+
+/// Rebuild the module list and drop all cached definitions
+pub fn reload() {
+    REGISTRY.reload();
+}
+
+/// TypeScript definitions for the allowed modules, generated once per module set
+pub fn definitions(allowed_modules: &[String]) -> String {
+    REGISTRY.definitions(allowed_modules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registered_module_appears_after_reload() {
+        // A registry of its own, so tests reading the global one are unaffected
+        let registry = ModuleRegistry::default();
+        let allowed = vec!["registryTest".to_string()];
+        let before = registry.definitions(&allowed);
+        assert!(!before.contains("// Module: registryTest"));
+
+        registry.register(SdkModule {
+            name: "registryTest",
+            template: "declare class registryTest {}".to_string(),
+            permission: Some("registryTest"),
+            dependencies: vec![],
+            source: None,
+            runtime: None,
+        });
+
+        // Still served from the cache until reloaded
+        assert_eq!(registry.definitions(&allowed), before);
+        assert!(!registry.modules().iter().any(|m| m.name == "registryTest"));
+
+        registry.reload();
+
+        assert!(registry.modules().iter().any(|m| m.name == "registryTest"));
+        let after = registry.definitions(&allowed);
+        assert!(after.contains("// Module: registryTest"));
+        assert!(after.contains("declare class registryTest {}"));
+        assert!(!modules().iter().any(|m| m.name == "registryTest"));
+    }
+}
@@ -6,7 +6,6 @@
 /// - SDK classes with static async methods that call Deno.core.ops
 /// - GlobalThis registration for the goon namespace
 use crate::sdk::analysis::{self, OpInfo};
-use std::path::Path;
 
 /// Configuration for a method on a Handle class
 #[derive(Clone, Default)]
@@ -42,8 +41,8 @@ pub struct ModuleConfig {
     pub options_type: Option<&'static str>,
     /// Additional methods to generate
     pub extra_methods: Vec<MethodConfig>,
-    /// The source file path for documentation extraction
-    pub source_path: &'static str,
+    /// The embedded Rust source of the module, used for documentation extraction
+    pub source: &'static str,
 }
 
 /// Configuration for a single method
@@ -53,15 +52,17 @@ pub struct MethodConfig {
     pub op_name: &'static str,
     /// The TypeScript method name (e.g., "getAsset")
     pub method_name: &'static str,
-    /// Parameter name if the method takes an argument
+    /// Parameter name if the method takes an argument (comma-separated for several, e.g., "x, y").
+    /// A trailing `?` marks the parameter as optional.
     pub param_name: Option<&'static str>,
-    /// Parameter type (e.g., "string", "number")
+    /// Parameter type (e.g., "string", "number"), comma-separated to match `param_name`
     pub param_type: Option<&'static str>,
     /// Whether the method is synchronous
     pub is_sync: bool,
     /// Whether the method returns a value (not void)
     pub returns_value: bool,
-    /// Return type if returns_value is true (e.g., "string", "Mood")
+    /// Return type if returns_value is true (e.g., "string", "Mood").
+    /// The module's handle class wraps the returned handle ID in a handle object.
     pub return_type: Option<&'static str>,
 }
 
@@ -150,6 +151,27 @@ fn find_op_docs(ops: &[OpInfo], op_name: &str) -> Vec<String> {
         .unwrap_or_default()
 }
 
+/// Build the parameter list and call arguments for a method.
+/// Multiple parameters are given as comma-separated names and types;
+/// optional parameters end in `?`.
+fn format_params(param_name: Option<&str>, param_type: Option<&str>) -> (String, String) {
+    let Some(names) = param_name else {
+        return (String::new(), String::new());
+    };
+
+    let params = match param_type {
+        Some(types) => names
+            .split(',')
+            .map(str::trim)
+            .zip(types.split(',').map(str::trim))
+            .map(|(name, typ)| format!("{}: {}", name, typ))
+            .collect::<Vec<_>>()
+            .join(", "),
+        None => names.to_string(),
+    };
+    (params, names.replace('?', ""))
+}
+
 /// Generate a static async method that calls a Deno op
 fn generate_method(
     method_name: &str,
@@ -162,12 +184,7 @@ fn generate_method(
 ) -> String {
     let jsdoc = generate_jsdoc(docs, "    ");
 
-    let params = match (param_name, param_type) {
-        (Some(name), Some(typ)) => format!("{}: {}", name, typ),
-        (Some(name), None) => name.to_string(),
-        _ => String::new(),
-    };
-    let args = param_name.unwrap_or("").to_string();
+    let (params, args) = format_params(param_name, param_type);
 
     let (body, return_type) = match return_handle {
         Some(handle) => (
@@ -211,12 +228,7 @@ fn generate_returning_method(
 ) -> String {
     let jsdoc = generate_jsdoc(docs, "    ");
 
-    let params = match (param_name, param_type) {
-        (Some(name), Some(typ)) => format!("{}: {}", name, typ),
-        (Some(name), None) => name.to_string(),
-        _ => String::new(),
-    };
-    let args = param_name.unwrap_or("").to_string();
+    let (params, args) = format_params(param_name, param_type);
 
     let ret_type = return_type.unwrap_or("any");
 
@@ -298,11 +310,11 @@ pub fn generate_module_runtime(config: &ModuleConfig) -> String {
     output.push_str("// @ts-nocheck\n\n");
 
     // Analyze source for documentation
-    let ops = if Path::new(config.source_path).exists() {
-        let (ops, _) = analysis::analyze_source(Path::new(config.source_path));
-        ops
-    } else {
+    let ops = if config.source.is_empty() {
         Vec::new()
+    } else {
+        let (ops, _) = analysis::analyze_source(config.source);
+        ops
     };
 
     // Generate handle class if needed
@@ -348,7 +360,20 @@ pub fn generate_module_runtime(config: &ModuleConfig) -> String {
     // Generate extra methods
     for method in &config.extra_methods {
         let docs = find_op_docs(&ops, method.op_name);
-        let generated = if method.returns_value {
+        let returns_handle = config.has_handle
+            && method.return_type.is_some()
+            && method.return_type == config.handle_class_name;
+        let generated = if method.returns_value && returns_handle {
+            generate_method(
+                method.method_name,
+                method.op_name,
+                method.param_name,
+                method.param_type,
+                method.return_type,
+                &docs,
+                method.is_sync,
+            )
+        } else if method.returns_value {
             generate_returning_method(
                 method.method_name,
                 method.op_name,
@@ -402,8 +427,16 @@ pub fn generate_image_runtime() -> String {
         primary_method: "show",
         primary_returns_value: false,
         options_type: Some("ImageOptions"),
-        extra_methods: vec![],
-        source_path: "src/sdk/image.rs",
+        extra_methods: vec![MethodConfig {
+            op_name: "op_probe_image",
+            method_name: "probe",
+            param_name: Some("tags?"),
+            param_type: Some("string[]"),
+            is_sync: false,
+            returns_value: true,
+            return_type: Some("ImageDimensions"),
+        }],
+        source: include_str!("image.rs"),
     })
 }
 
@@ -435,8 +468,27 @@ pub fn generate_video_runtime() -> String {
         primary_method: "play",
         primary_returns_value: false,
         options_type: Some("VideoOptions"),
-        extra_methods: vec![],
-        source_path: "src/sdk/video.rs",
+        extra_methods: vec![
+            MethodConfig {
+                op_name: "op_set_video_volume",
+                method_name: "setVolume",
+                param_name: Some("handleId, volume"),
+                param_type: Some("string, number"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+            MethodConfig {
+                op_name: "op_seek_video",
+                method_name: "seek",
+                param_name: Some("handleId, seconds"),
+                param_type: Some("string, number"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+        ],
+        source: include_str!("video.rs"),
     })
 }
 
@@ -468,8 +520,36 @@ pub fn generate_audio_runtime() -> String {
         primary_method: "play",
         primary_returns_value: false,
         options_type: Some("AudioOptions"),
-        extra_methods: vec![],
-        source_path: "src/sdk/audio.rs",
+        extra_methods: vec![
+            MethodConfig {
+                op_name: "op_crossfade_audio",
+                method_name: "crossfade",
+                param_name: Some("handleId, options?"),
+                param_type: Some("string, CrossfadeOptions"),
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("AudioHandle"),
+            },
+            MethodConfig {
+                op_name: "op_set_master_volume",
+                method_name: "setMasterVolume",
+                param_name: Some("volume"),
+                param_type: Some("number"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+            MethodConfig {
+                op_name: "op_set_muted",
+                method_name: "setMuted",
+                param_name: Some("muted"),
+                param_type: Some("boolean"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+        ],
+        source: include_str!("audio.rs"),
     })
 }
 
@@ -485,16 +565,162 @@ pub fn generate_system_runtime() -> String {
         primary_method: "",
         primary_returns_value: false,
         options_type: None,
-        extra_methods: vec![MethodConfig {
-            op_name: "op_close_window",
-            method_name: "closeWindow",
-            param_name: Some("handleId"),
-            param_type: Some("string"),
-            is_sync: false,
-            returns_value: false,
-            return_type: None,
-        }],
-        source_path: "src/sdk/system.rs",
+        extra_methods: vec![
+            MethodConfig {
+                op_name: "op_close_window",
+                method_name: "closeWindow",
+                param_name: Some("handleId"),
+                param_type: Some("string"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+            MethodConfig {
+                op_name: "op_replay_window",
+                method_name: "replayWindow",
+                param_name: Some("handleId"),
+                param_type: Some("string"),
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("string"),
+            },
+            MethodConfig {
+                op_name: "op_get_window_remaining_ms",
+                method_name: "getWindowRemainingMs",
+                param_name: Some("handleId"),
+                param_type: Some("string"),
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("number | null"),
+            },
+            MethodConfig {
+                op_name: "op_shake_window",
+                method_name: "shakeWindow",
+                param_name: Some("handleId, options?"),
+                param_type: Some("string, ShakeOptions"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+            MethodConfig {
+                op_name: "op_set_click_through",
+                method_name: "setClickThrough",
+                param_name: Some("handleId, clickThrough"),
+                param_type: Some("string, boolean"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+            MethodConfig {
+                op_name: "op_set_window_opacity",
+                method_name: "setOpacity",
+                param_name: Some("handleId, opacity"),
+                param_type: Some("string, number"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+            MethodConfig {
+                op_name: "op_await_window_event",
+                method_name: "awaitWindowEvent",
+                param_name: Some("handleId"),
+                param_type: Some("string"),
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("WindowEvent"),
+            },
+            MethodConfig {
+                op_name: "op_is_media_active",
+                method_name: "isMediaActive",
+                param_name: None,
+                param_type: None,
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("MediaActivity"),
+            },
+            MethodConfig {
+                op_name: "op_capabilities",
+                method_name: "capabilities",
+                param_name: None,
+                param_type: None,
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("Capabilities"),
+            },
+            MethodConfig {
+                op_name: "op_stop_all",
+                method_name: "stopAll",
+                param_name: None,
+                param_type: None,
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+            MethodConfig {
+                op_name: "op_get_idle_seconds",
+                method_name: "getIdleSeconds",
+                param_name: None,
+                param_type: None,
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("number"),
+            },
+            MethodConfig {
+                op_name: "op_is_screen_locked",
+                method_name: "isScreenLocked",
+                param_name: None,
+                param_type: None,
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("boolean"),
+            },
+            MethodConfig {
+                op_name: "op_list_tags",
+                method_name: "listTags",
+                param_name: Some("kind?"),
+                param_type: Some("AssetKind"),
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("string[]"),
+            },
+            MethodConfig {
+                op_name: "op_get_monitors",
+                method_name: "getMonitors",
+                param_name: None,
+                param_type: None,
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("MonitorInfo[]"),
+            },
+            MethodConfig {
+                op_name: "op_get_screen_size",
+                method_name: "getScreenSize",
+                param_name: None,
+                param_type: None,
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("ScreenSize"),
+            },
+            MethodConfig {
+                op_name: "op_list_packs",
+                method_name: "listPacks",
+                param_name: None,
+                param_type: None,
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("string[]"),
+            },
+            MethodConfig {
+                op_name: "op_switch_pack",
+                method_name: "switchPack",
+                param_name: Some("name"),
+                param_type: Some("string"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+        ],
+        source: include_str!("system.rs"),
     })
 }
 
@@ -510,16 +736,45 @@ pub fn generate_pack_runtime() -> String {
         primary_method: "getCurrentMood",
         primary_returns_value: true,
         options_type: None,
-        extra_methods: vec![MethodConfig {
-            op_name: "op_set_current_mood",
-            method_name: "setMood",
-            param_name: Some("moodName"),
-            param_type: Some("string"),
-            is_sync: false,
-            returns_value: false,
-            return_type: None,
-        }],
-        source_path: "src/sdk/pack.rs",
+        extra_methods: vec![
+            MethodConfig {
+                op_name: "op_list_moods",
+                method_name: "listMoods",
+                param_name: None,
+                param_type: None,
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("MoodInfo[]"),
+            },
+            MethodConfig {
+                op_name: "op_set_current_mood",
+                method_name: "setMood",
+                param_name: Some("moodName"),
+                param_type: Some("string"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+            MethodConfig {
+                op_name: "op_set_moods",
+                method_name: "setMoods",
+                param_name: Some("moodNames"),
+                param_type: Some("string[]"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+            MethodConfig {
+                op_name: "op_pick_asset",
+                method_name: "pickAsset",
+                param_name: Some("kind, tags?, excludeTags?"),
+                param_type: Some("AssetKind, string[], string[]"),
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("PickedAsset"),
+            },
+        ],
+        source: include_str!("pack.rs"),
     })
 }
 
@@ -539,8 +794,48 @@ pub fn generate_write_lines_runtime() -> String {
         primary_method: "show",
         primary_returns_value: false,
         options_type: Some("WriteLinesOptions"),
+        extra_methods: vec![
+            MethodConfig {
+                op_name: "op_await_prompt_result",
+                method_name: "awaitResult",
+                param_name: Some("handleId"),
+                param_type: Some("string"),
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("PromptOutcome"),
+            },
+            MethodConfig {
+                op_name: "op_await_prompt_input",
+                method_name: "awaitInput",
+                param_name: Some("handleId"),
+                param_type: Some("string"),
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("PromptResult"),
+            },
+        ],
+        source: include_str!("write_lines.rs"),
+    })
+}
+
+/// Generate the text module runtime
+pub fn generate_text_runtime() -> String {
+    generate_module_runtime(&ModuleConfig {
+        name: "text",
+        class_name: "text",
+        has_handle: true,
+        handle_class_name: Some("BannerHandle"),
+        handle_methods: vec![HandleMethodConfig {
+            method_name: "close",
+            op_name: "op_close_window",
+            docs: "Closes the banner.",
+        }],
+        primary_op: "op_show_banner",
+        primary_method: "showBanner",
+        primary_returns_value: false,
+        options_type: Some("BannerOptions"),
         extra_methods: vec![],
-        source_path: "src/sdk/write_lines.rs",
+        source: include_str!("text.rs"),
     })
 }
 
@@ -556,8 +851,27 @@ pub fn generate_wallpaper_runtime() -> String {
         primary_method: "set",
         primary_returns_value: false,
         options_type: Some("WallpaperOptions"),
-        extra_methods: vec![],
-        source_path: "src/sdk/wallpaper.rs",
+        extra_methods: vec![
+            MethodConfig {
+                op_name: "op_set_wallpaper_color",
+                method_name: "setColor",
+                param_name: Some("hex"),
+                param_type: Some("string"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+            MethodConfig {
+                op_name: "op_restore_wallpaper",
+                method_name: "restore",
+                param_name: None,
+                param_type: None,
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+        ],
+        source: include_str!("wallpaper.rs"),
     })
 }
 
@@ -566,15 +880,19 @@ pub fn generate_website_runtime() -> String {
     generate_module_runtime(&ModuleConfig {
         name: "website",
         class_name: "website",
-        has_handle: false,
-        handle_class_name: None,
-        handle_methods: vec![],
+        has_handle: true,
+        handle_class_name: Some("WebsiteHandle"),
+        handle_methods: vec![HandleMethodConfig {
+            method_name: "close",
+            op_name: "op_close_website",
+            docs: "Stops tracking the website. The browser tab itself stays open.",
+        }],
         primary_op: "op_open_website",
         primary_method: "open",
         primary_returns_value: false,
         options_type: Some("WebsiteOptions"),
         extra_methods: vec![],
-        source_path: "src/sdk/website.rs",
+        source: include_str!("website.rs"),
     })
 }
 
@@ -591,7 +909,149 @@ pub fn generate_hypno_runtime() -> String {
         primary_returns_value: false,
         options_type: Some("HypnoOptions"),
         extra_methods: vec![],
-        source_path: "src/sdk/hypno.rs",
+        source: include_str!("hypno.rs"),
+    })
+}
+
+/// Generate the mouse module runtime
+pub fn generate_mouse_runtime() -> String {
+    generate_module_runtime(&ModuleConfig {
+        name: "mouse",
+        class_name: "mouse",
+        has_handle: false,
+        handle_class_name: None,
+        handle_methods: vec![],
+        primary_op: "",
+        primary_method: "",
+        primary_returns_value: false,
+        options_type: None,
+        extra_methods: vec![
+            MethodConfig {
+                op_name: "op_move_mouse",
+                method_name: "move",
+                param_name: Some("x, y"),
+                param_type: Some("number, number"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+            MethodConfig {
+                op_name: "op_get_mouse_position",
+                method_name: "getPosition",
+                param_name: None,
+                param_type: None,
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("MousePosition"),
+            },
+            MethodConfig {
+                op_name: "op_mouse_jiggle",
+                method_name: "jiggle",
+                param_name: Some("durationMs, intensity"),
+                param_type: Some("number, number"),
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("string"),
+            },
+            MethodConfig {
+                op_name: "op_confine_mouse",
+                method_name: "confine",
+                param_name: Some("rect, durationMs"),
+                param_type: Some("MouseRect, number"),
+                is_sync: false,
+                returns_value: true,
+                return_type: Some("string"),
+            },
+            MethodConfig {
+                op_name: "op_release_mouse",
+                method_name: "release",
+                param_name: Some("handleId"),
+                param_type: Some("string"),
+                is_sync: false,
+                returns_value: false,
+                return_type: None,
+            },
+        ],
+        source: include_str!("mouse.rs"),
+    })
+}
+
+/// Generate the screen module runtime
+pub fn generate_screen_runtime() -> String {
+    generate_module_runtime(&ModuleConfig {
+        name: "screen",
+        class_name: "screen",
+        has_handle: false,
+        handle_class_name: None,
+        handle_methods: vec![],
+        primary_op: "",
+        primary_method: "",
+        primary_returns_value: false,
+        options_type: None,
+        extra_methods: vec![MethodConfig {
+            op_name: "op_capture_region",
+            method_name: "captureRegion",
+            param_name: Some("region"),
+            param_type: Some("CaptureRegion"),
+            is_sync: false,
+            returns_value: true,
+            return_type: Some("string"),
+        }],
+        source: include_str!("screen.rs"),
+    })
+}
+
+/// Generate the overlay module runtime
+pub fn generate_overlay_runtime() -> String {
+    generate_module_runtime(&ModuleConfig {
+        name: "overlay",
+        class_name: "overlay",
+        has_handle: true,
+        handle_class_name: Some("OverlayHandle"),
+        handle_methods: vec![HandleMethodConfig {
+            method_name: "close",
+            op_name: "op_close_window",
+            docs: "Removes the overlay.",
+        }],
+        primary_op: "op_show_overlay",
+        primary_method: "show",
+        primary_returns_value: false,
+        options_type: Some("OverlayOptions"),
+        extra_methods: vec![MethodConfig {
+            op_name: "op_show_tint",
+            method_name: "tint",
+            param_name: Some("options?"),
+            param_type: Some("TintOptions"),
+            is_sync: false,
+            returns_value: true,
+            return_type: Some("OverlayHandle"),
+        }],
+        source: include_str!("overlay.rs"),
+    })
+}
+
+/// Generate the notification module runtime
+pub fn generate_notification_runtime() -> String {
+    generate_module_runtime(&ModuleConfig {
+        name: "notification",
+        class_name: "notification",
+        has_handle: false,
+        handle_class_name: None,
+        handle_methods: vec![],
+        primary_op: "",
+        primary_method: "",
+        primary_returns_value: false,
+        options_type: None,
+        extra_methods: vec![MethodConfig {
+            op_name: "op_notify",
+            method_name: "show",
+            param_name: Some("title, body"),
+            param_type: Some("string, string"),
+            is_sync: false,
+            returns_value: false,
+            return_type: None,
+        }],
+        source: include_str!("notification.rs"),
     })
 }
 
@@ -639,6 +1099,40 @@ mod tests {
         assert!(output.contains("static async show"));
         assert!(output.contains("op_show_image"));
         assert!(output.contains("goon.image = image"));
+        assert!(output.contains("static async probe(tags?: string[])"));
+        assert!(output.contains("Deno.core.ops.op_probe_image(tags)"));
+    }
+
+    #[test]
+    fn test_generate_write_lines_runtime() {
+        let output = generate_write_lines_runtime();
+        assert!(output.contains("class WriteLinesHandle"));
+        assert!(
+            output.contains("static async awaitResult(handleId: string): Promise<PromptOutcome>")
+        );
+        assert!(output.contains("Deno.core.ops.op_await_prompt_result(handleId)"));
+        assert!(
+            output.contains("static async awaitInput(handleId: string): Promise<PromptResult>")
+        );
+    }
+
+    #[test]
+    fn test_generate_text_runtime() {
+        let output = generate_text_runtime();
+        assert!(output.contains("class BannerHandle"));
+        assert!(output.contains("class text"));
+        assert!(output.contains("static async showBanner"));
+        assert!(output.contains("op_show_banner"));
+    }
+
+    #[test]
+    fn test_generate_video_runtime() {
+        let output = generate_video_runtime();
+        assert!(output.contains("class VideoHandle"));
+        assert!(output.contains("class video"));
+        assert!(output.contains("static async setVolume(handleId: string, volume: number)"));
+        assert!(output.contains("Deno.core.ops.op_set_video_volume(handleId, volume)"));
+        assert!(output.contains("static async seek(handleId: string, seconds: number)"));
     }
 
     #[test]
@@ -648,6 +1142,12 @@ mod tests {
         assert!(output.contains("class audio"));
         assert!(output.contains("static async play"));
         assert!(output.contains("op_play_audio"));
+        assert!(output.contains(
+            "static async crossfade(handleId: string, options?: CrossfadeOptions): Promise<AudioHandle>"
+        ));
+        assert!(output.contains("Deno.core.ops.op_crossfade_audio(handleId, options)"));
+        assert!(output.contains("static async setMasterVolume(volume: number)"));
+        assert!(output.contains("static async setMuted(muted: boolean)"));
     }
 
     #[test]
@@ -655,6 +1155,83 @@ mod tests {
         let output = generate_system_runtime();
         assert!(output.contains("class system"));
         assert!(output.contains("static async closeWindow"));
+        assert!(output.contains("static async getWindowRemainingMs"));
+        assert!(output.contains("static async replayWindow(handleId: string): Promise<string>"));
+        assert!(output.contains("static async switchPack(name: string)"));
+        assert!(output.contains("static async listTags(kind?: AssetKind): Promise<string[]>"));
+        assert!(output.contains("static async getMonitors(): Promise<MonitorInfo[]>"));
+        assert!(output.contains("static async getScreenSize(): Promise<ScreenSize>"));
+        assert!(
+            output.contains("static async shakeWindow(handleId: string, options?: ShakeOptions)")
+        );
+        assert!(output.contains("Deno.core.ops.op_shake_window(handleId, options)"));
+        assert!(
+            output
+                .contains("static async setClickThrough(handleId: string, clickThrough: boolean)")
+        );
+        assert!(output.contains("static async setOpacity(handleId: string, opacity: number)"));
+        assert!(
+            output
+                .contains("static async awaitWindowEvent(handleId: string): Promise<WindowEvent>")
+        );
+        assert!(output.contains("static async isMediaActive(): Promise<MediaActivity>"));
+    }
+
+    #[test]
+    fn test_generate_pack_runtime() {
+        let output = generate_pack_runtime();
+        assert!(output.contains("class pack"));
+        assert!(output.contains("static async setMood(moodName: string)"));
+        assert!(output.contains("static async listMoods(): Promise<MoodInfo[]>"));
+        assert!(output.contains("static async setMoods(moodNames: string[])"));
+        assert!(output.contains(
+            "static async pickAsset(kind: AssetKind, tags?: string[], excludeTags?: string[]): Promise<PickedAsset>"
+        ));
+        assert!(output.contains("Deno.core.ops.op_pick_asset(kind, tags, excludeTags)"));
+    }
+
+    #[test]
+    fn test_generate_mouse_runtime() {
+        let output = generate_mouse_runtime();
+        assert!(output.contains("class mouse"));
+        assert!(output.contains("static async move(x: number, y: number)"));
+        assert!(output.contains("Deno.core.ops.op_move_mouse(x, y)"));
+    }
+
+    #[test]
+    fn test_generate_screen_runtime() {
+        let output = generate_screen_runtime();
+        assert!(output.contains("class screen"));
+        assert!(output.contains("static async captureRegion(region: CaptureRegion)"));
+        assert!(output.contains("Deno.core.ops.op_capture_region(region)"));
+    }
+
+    #[test]
+    fn test_generate_website_runtime() {
+        let output = generate_website_runtime();
+        assert!(output.contains("class WebsiteHandle"));
+        assert!(output.contains("await Deno.core.ops.op_close_website(this.id);"));
+        assert!(output.contains("class website"));
+    }
+
+    #[test]
+    fn test_generate_overlay_runtime() {
+        let output = generate_overlay_runtime();
+        assert!(output.contains("class OverlayHandle"));
+        assert!(output.contains("class overlay"));
+        assert!(output.contains("op_show_overlay"));
+        assert!(
+            output.contains("static async tint(options?: TintOptions): Promise<OverlayHandle>")
+        );
+        assert!(output.contains("const id = await Deno.core.ops.op_show_tint(options);"));
+    }
+
+    #[test]
+    fn test_generate_notification_runtime() {
+        let output = generate_notification_runtime();
+        assert!(output.contains("class notification"));
+        assert!(output.contains("static async show(title: string, body: string)"));
+        assert!(output.contains("Deno.core.ops.op_notify(title, body)"));
     }
 
     #[test]
@@ -0,0 +1,72 @@
+use crate::media::capture::{self, CaptureFiles, Region};
+use crate::permissions::Permission;
+use crate::runtime::error::OpError;
+use crate::runtime::utils::check_permission;
+use deno_core::OpState;
+use deno_core::op2;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use ts_rs::TS;
+
+/// Screen area to capture
+#[derive(Deserialize, Debug, Clone, Copy, TS, JsonSchema)]
+pub struct CaptureRegion {
+    /// X coordinate of the left edge in pixels
+    pub x: i32,
+    /// Y coordinate of the top edge in pixels
+    pub y: i32,
+    /// Width in pixels
+    pub w: u32,
+    /// Height in pixels
+    pub h: u32,
+}
+
+impl From<CaptureRegion> for Region {
+    fn from(region: CaptureRegion) -> Self {
+        Region {
+            x: region.x,
+            y: region.y,
+            width: region.w,
+            height: region.h,
+        }
+    }
+}
+
+/// Captures a region of the screen to a temporary PNG file.
+///
+/// The region must lie within a single display. Captured files are deleted when the runtime shuts down.
+///
+/// @param region - The screen area to capture.
+/// @returns The path of the captured image.
+#[op2(async)]
+#[string]
+pub async fn op_capture_region(
+    state: Rc<RefCell<OpState>>,
+    #[serde] region: CaptureRegion,
+) -> Result<String, OpError> {
+    {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Screen)?;
+    }
+
+    let region: Region = region.into();
+    let displays = capture::display_rects()
+        .map_err(|e| OpError::new(&format!("Failed to read displays: {}", e)))?;
+    capture::locate_region(&displays, &region)
+        .map_err(|e| OpError::invalid_options(&e.to_string()))?;
+
+    let image = capture::capture_region(&region)
+        .map_err(|e| OpError::new(&format!("Failed to capture screen: {}", e)))?;
+
+    let mut state = state.borrow_mut();
+    let path = state
+        .borrow_mut::<CaptureFiles>()
+        .save(&image)
+        .map_err(|e| OpError::new(&format!("Failed to save capture: {}", e)))?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+deno_core::extension!(goon_screen, ops = [op_capture_region],);
@@ -1,11 +1,93 @@
-use crate::gui::{WindowHandle, WindowSpawnerHandle};
+use crate::assets::library::{ActivePack, PackLibrary};
+use crate::assets::registry::{AssetPathError, AssetRegistry};
+use crate::config::pack::Mood;
+use crate::gui::windows::WindowEvent;
+use crate::gui::windows::layout::{AutoLayout, pick_monitor};
+use crate::gui::{WindowCommand, WindowHandle, WindowSpawnerHandle};
+use crate::media::audio::manager::AudioManager;
+use crate::media::capture;
+use crate::media::idle::IdleProvider;
+use crate::media::mouse::MouseEffects;
+use crate::media::wallpaper::WallpaperBackup;
+use crate::permissions::{Permission, PermissionChecker};
 use crate::runtime::error::OpError;
+use crate::runtime::utils::check_permission;
+use crate::sdk;
+use crate::sdk::pack::AssetKind;
+use crate::sdk::types::WindowOptions;
 use deno_core::OpState;
 use deno_core::op2;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::warn;
+use ts_rs::TS;
 use uuid::Uuid;
 
+/// SDK version and the modules available to this script
+#[derive(Serialize, Debug, Clone, TS)]
+pub struct Capabilities {
+    /// Version of the goon.ai runtime
+    pub version: String,
+    /// Names of the SDK modules this script is permitted to use (e.g. "image", "video")
+    pub modules: Vec<String>,
+}
+
+/// How much media is currently on screen or playing
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
+pub struct MediaActivity {
+    /// Number of open windows of any kind
+    pub windows: usize,
+    /// Number of open video windows, also counted in `windows`
+    pub videos: usize,
+    /// Number of audio tracks still playing
+    pub audio: usize,
+}
+
+/// Options for shaking a window
+#[derive(Deserialize, Debug, Default, TS, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShakeOptions {
+    /// How long to shake in milliseconds (at most 5000), defaults to 500
+    pub duration_ms: Option<u64>,
+    /// Largest offset from the window's position in pixels (at most 50), defaults to 10
+    pub amplitude: Option<u32>,
+}
+
+/// A connected display, in physical pixels
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, TS)]
+pub struct MonitorInfo {
+    /// Index to pass as `window.monitor`
+    pub index: usize,
+    /// X coordinate of the display's left edge on the desktop
+    pub x: i32,
+    /// Y coordinate of the display's top edge on the desktop
+    pub y: i32,
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// Whether this is the primary display
+    pub primary: bool,
+}
+
+/// Size of the primary display
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, TS)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenSize {
+    /// Width in physical pixels
+    pub width: u32,
+    /// Height in physical pixels
+    pub height: u32,
+    /// Physical pixels per logical pixel, e.g. 2 on a HiDPI display
+    pub scale_factor: f32,
+}
+
 /// Closes a window by its handle ID.
 ///
 /// You can also use the `.close()` method on the handle object returned by show functions.
@@ -21,11 +103,493 @@ pub async fn op_close_window(
         state.borrow::<WindowSpawnerHandle>().clone()
     };
 
-    let uuid = Uuid::parse_str(&handle).map_err(|e| OpError::new(&e.to_string()))?;
+    let uuid = Uuid::parse_str(&handle).map_err(|e| OpError::invalid_handle(&e.to_string()))?;
     window_spawner
         .close_window(WindowHandle(uuid))
         .map_err(|e| OpError::new(&e.to_string()))?;
     Ok(())
 }
 
-deno_core::extension!(goon_system, ops = [op_close_window,],);
+/// Shows a window again with the same asset and options it was spawned with.
+///
+/// Works for windows that are still open or already closed, including banners,
+/// as long as they were spawned recently. Timeouts start over for the new window.
+///
+/// @param handle - The handle ID of the window to replay.
+/// @returns The handle ID of the new window.
+#[op2(async)]
+#[string]
+pub async fn op_replay_window(
+    state: Rc<RefCell<OpState>>,
+    #[string] handle: String,
+) -> Result<String, OpError> {
+    let uuid = Uuid::parse_str(&handle).map_err(|e| OpError::invalid_handle(&e.to_string()))?;
+    let handle = WindowHandle(uuid);
+
+    let window_spawner = {
+        let mut state = state.borrow_mut();
+        let window_spawner = state.borrow::<WindowSpawnerHandle>().clone();
+        let permission = match window_spawner.spawn_command(handle) {
+            Some(WindowCommand::SpawnImage { .. }) => Permission::Image,
+            Some(WindowCommand::SpawnVideo { .. }) => Permission::Video,
+            Some(WindowCommand::SpawnOverlay { .. }) => Permission::Overlay,
+            // Prompts and banners from `text.showBanner` alike
+            Some(WindowCommand::SpawnWriteLines { .. }) => Permission::WriteLines,
+            _ => {
+                return Err(OpError::invalid_handle(&format!(
+                    "No spawn parameters known for window: {}",
+                    uuid
+                )));
+            }
+        };
+        // The pack may have changed since the window was first shown
+        check_permission(&mut state, permission)?;
+        window_spawner
+    };
+
+    let new_handle = window_spawner.replay(handle).map_err(OpError::from)?;
+    Ok(new_handle.0.to_string())
+}
+
+/// Returns how long until a window closes automatically.
+///
+/// Windows shown with a `duration` are closed by the runtime when it elapses.
+///
+/// @param handle - The handle ID of the window to query.
+/// @returns The remaining time in milliseconds, or `null` if the window has no timeout.
+#[op2(async)]
+#[serde]
+pub async fn op_get_window_remaining_ms(
+    state: Rc<RefCell<OpState>>,
+    #[string] handle: String,
+) -> Result<Option<u64>, OpError> {
+    let window_spawner = {
+        let state = state.borrow();
+        state.borrow::<WindowSpawnerHandle>().clone()
+    };
+
+    let uuid = Uuid::parse_str(&handle).map_err(|e| OpError::invalid_handle(&e.to_string()))?;
+    let remaining = window_spawner
+        .get_remaining_time(WindowHandle(uuid))
+        .map_err(|e| OpError::new(&e.to_string()))?;
+    Ok(remaining.map(|d| d.as_millis() as u64))
+}
+
+/// Shakes a window around its current position, then puts it back.
+///
+/// Useful for emphasis, like a jolt when something important appears.
+///
+/// @param handle - The handle ID of the window to shake.
+/// @param options - Optional duration and amplitude of the shake.
+#[op2(async)]
+pub async fn op_shake_window(
+    state: Rc<RefCell<OpState>>,
+    #[string] handle: String,
+    #[serde] options: Option<ShakeOptions>,
+) -> Result<(), OpError> {
+    let window_spawner = {
+        let state = state.borrow();
+        state.borrow::<WindowSpawnerHandle>().clone()
+    };
+
+    let uuid = Uuid::parse_str(&handle).map_err(|e| OpError::invalid_handle(&e.to_string()))?;
+    let opts = options.unwrap_or_default();
+    window_spawner
+        .shake_window(
+            WindowHandle(uuid),
+            Duration::from_millis(opts.duration_ms.unwrap_or(500)),
+            opts.amplitude.unwrap_or(10),
+        )
+        .map_err(|e| OpError::new(&e.to_string()))?;
+    Ok(())
+}
+
+/// Waits for the next click or key press on a window.
+///
+/// Resolves with `{ type: "closed" }` if the window closes first, so
+/// `await` never hangs on a window that has gone away.
+///
+/// @param handle - The handle ID of the window to listen to.
+/// @returns The event, with its `type` and event-specific data.
+#[op2(async)]
+#[serde]
+pub async fn op_await_window_event(
+    state: Rc<RefCell<OpState>>,
+    #[string] handle: String,
+) -> Result<WindowEvent, OpError> {
+    let window_spawner = {
+        let state = state.borrow();
+        state.borrow::<WindowSpawnerHandle>().clone()
+    };
+
+    let uuid = Uuid::parse_str(&handle).map_err(|e| OpError::invalid_handle(&e.to_string()))?;
+    let event = window_spawner.events.subscribe(WindowHandle(uuid));
+    Ok(event.await.unwrap_or(WindowEvent::Closed))
+}
+
+/// Counts the open windows and playing audio tracks.
+///
+/// A cheap check to avoid stacking more media on top of what is already running.
+///
+/// @returns The number of open windows, open video windows and playing audio tracks.
+#[op2(async)]
+#[serde]
+pub async fn op_is_media_active(state: Rc<RefCell<OpState>>) -> Result<MediaActivity, OpError> {
+    let (window_spawner, audio_manager) = {
+        let state = state.borrow();
+        (
+            state.borrow::<WindowSpawnerHandle>().clone(),
+            state.try_borrow::<Arc<Mutex<AudioManager>>>().cloned(),
+        )
+    };
+
+    let windows = window_spawner.get_active_windows()?;
+    let audio = audio_manager
+        .and_then(|manager| {
+            manager
+                .lock()
+                .ok()
+                .map(|mut manager| manager.playing_count())
+        })
+        .unwrap_or(0);
+
+    Ok(MediaActivity {
+        windows: windows.len(),
+        videos: windows.iter().filter(|w| w.window_type == "Video").count(),
+        audio,
+    })
+}
+
+/// Gets the runtime version and the SDK modules available to this script.
+///
+/// Use this to check whether a module can be used before calling it.
+///
+/// @returns The runtime version and the list of permitted modules.
+#[op2]
+#[serde]
+pub fn op_capabilities(state: &mut OpState) -> Result<Capabilities, OpError> {
+    let permissions = state.borrow::<PermissionChecker>();
+    Ok(Capabilities {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        modules: sdk::allowed_modules(permissions),
+    })
+}
+
+fn idle_provider(state: &OpState) -> Result<Arc<dyn IdleProvider>, OpError> {
+    state
+        .try_borrow::<Arc<dyn IdleProvider>>()
+        .cloned()
+        .ok_or_else(|| OpError::new("Idle detection not initialized"))
+}
+
+/// Gets how long the user has been idle.
+///
+/// @returns Seconds since the last keyboard or mouse input.
+#[op2]
+#[serde]
+pub fn op_get_idle_seconds(state: &mut OpState) -> Result<u64, OpError> {
+    idle_provider(state)?
+        .idle_seconds()
+        .map_err(|e| OpError::new(&e.to_string()))
+}
+
+/// Checks whether the screen is locked.
+///
+/// Useful for pausing or changing behavior while the user is away.
+///
+/// @returns True if the screen is locked.
+#[op2(fast)]
+pub fn op_is_screen_locked(state: &mut OpState) -> Result<bool, OpError> {
+    idle_provider(state)?
+        .is_screen_locked()
+        .map_err(|e| OpError::new(&e.to_string()))
+}
+
+/// Close every window, stop all audio and mouse effects, and restore the wallpaper.
+///
+/// Safe to call when nothing is active.
+pub fn stop_all(state: &mut OpState) {
+    if let Some(spawner) = state.try_borrow::<WindowSpawnerHandle>() {
+        let _ = spawner.send(WindowCommand::CloseAll);
+    }
+    if let Some(manager) = state.try_borrow::<Arc<Mutex<AudioManager>>>()
+        && let Ok(mut manager) = manager.lock()
+    {
+        manager.stop_all();
+    }
+    if let Some(effects) = state.try_borrow_mut::<MouseEffects>() {
+        effects.release_all();
+    }
+    if state.has::<WallpaperBackup>()
+        && let Err(e) = sdk::wallpaper::restore_wallpaper(state)
+    {
+        warn!("Failed to restore wallpaper: {}", e);
+    }
+}
+
+/// Stops everything: closes all windows, stops all audio and mouse effects,
+/// and restores the original wallpaper.
+///
+/// Use this to reset between scenes. It does nothing if nothing is active.
+#[op2(fast)]
+pub fn op_stop_all(state: &mut OpState) -> Result<(), OpError> {
+    stop_all(state);
+    Ok(())
+}
+
+/// Lists the tags used by the pack's assets, to pick tags that match something.
+///
+/// Without a kind, the tags of every kind this script is permitted to use are listed.
+///
+/// @param kind - Optional kind of asset to list the tags of, requires the matching permission.
+/// @returns The tags, sorted alphabetically.
+#[op2]
+#[serde]
+pub fn op_list_tags(
+    state: &mut OpState,
+    #[serde] kind: Option<AssetKind>,
+) -> Result<Vec<String>, OpError> {
+    let kinds: Vec<AssetKind> = match kind {
+        Some(kind) => {
+            check_permission(state, kind.permission())?;
+            vec![kind]
+        }
+        None => {
+            let permissions = state.borrow::<PermissionChecker>();
+            AssetKind::ALL
+                .into_iter()
+                .filter(|kind| permissions.has_permission(kind.permission()))
+                .collect()
+        }
+    };
+
+    let registry = state.borrow::<Arc<AssetRegistry>>();
+    let tags: BTreeSet<&String> = kinds
+        .iter()
+        .flat_map(|kind| kind.assets(registry))
+        .flat_map(|asset| asset.get_tags())
+        .collect();
+    Ok(tags.into_iter().cloned().collect())
+}
+
+/// Lists the connected displays, to show windows on a specific one with `window.monitor`.
+///
+/// @returns The index, position and size of each display.
+#[op2]
+#[serde]
+pub fn op_get_monitors() -> Result<Vec<MonitorInfo>, OpError> {
+    let displays = capture::displays()
+        .map_err(|e| OpError::new(&format!("Failed to read displays: {}", e)))?;
+    Ok(displays
+        .into_iter()
+        .enumerate()
+        .map(|(index, (rect, primary))| MonitorInfo {
+            index,
+            x: rect.x,
+            y: rect.y,
+            width: rect.width,
+            height: rect.height,
+            primary,
+        })
+        .collect())
+}
+
+/// Gets the size of the primary display, to place windows in corners or tile them.
+///
+/// Works before any window is open. Use `getMonitors()` for the other displays.
+///
+/// @returns The width and height in physical pixels and the display's scale factor.
+#[op2]
+#[serde]
+pub fn op_get_screen_size() -> Result<ScreenSize, OpError> {
+    let (width, height, scale_factor) = capture::primary_display()
+        .map_err(|e| OpError::new(&format!("Failed to read displays: {}", e)))?;
+    Ok(ScreenSize {
+        width,
+        height,
+        scale_factor,
+    })
+}
+
+/// Layout placing a window on the display its options ask for, if any
+pub fn monitor_layout(window: Option<&WindowOptions>) -> Result<AutoLayout, OpError> {
+    let index = window.and_then(|w| w.monitor);
+    let random = window.and_then(|w| w.random_monitor).unwrap_or(false);
+    if index.is_none() && !random {
+        return Ok(AutoLayout::default());
+    }
+
+    let displays = capture::display_rects()
+        .map_err(|e| OpError::new(&format!("Failed to read displays: {}", e)))?;
+    let monitor = pick_monitor(&displays, index, random, &mut rand::rng())
+        .map_err(|e| OpError::invalid_options(&e.to_string()))?;
+    Ok(AutoLayout {
+        monitor,
+        offset: window
+            .and_then(|w| w.position.as_ref())
+            .map(|position| (position.x, position.y)),
+        ..Default::default()
+    })
+}
+
+/// A file in the pack given by its path, for options that skip tag selection
+pub fn pack_asset_path(registry: &AssetRegistry, path: &str) -> Result<PathBuf, OpError> {
+    registry.resolve_path(path).map_err(|e| match e {
+        AssetPathError::NotFound(_) | AssetPathError::NoPackRoot => {
+            OpError::asset_not_found(&e.to_string())
+        }
+        AssetPathError::OutsidePack(_) => OpError::invalid_options(&e.to_string()),
+    })
+}
+
+/// Whether an image or video popup lets clicks through, which rules out closing it on a click
+pub fn popup_click_through(
+    click_through: Option<bool>,
+    require_click_to_close: Option<bool>,
+) -> Result<bool, OpError> {
+    let click_through = click_through.unwrap_or(false);
+    if click_through && require_click_to_close.unwrap_or(false) {
+        return Err(OpError::invalid_options(
+            "clickThrough can't be combined with requireClickToClose",
+        ));
+    }
+    Ok(click_through)
+}
+
+/// Lets mouse clicks pass through a window to the windows below, or catches them again.
+///
+/// Prompts from `writeLines` always stay clickable since they take input.
+///
+/// @param handle - The handle ID of the window.
+/// @param clickThrough - Whether clicks should pass through.
+#[op2(async)]
+pub async fn op_set_click_through(
+    state: Rc<RefCell<OpState>>,
+    #[string] handle: String,
+    click_through: bool,
+) -> Result<(), OpError> {
+    let window_spawner = {
+        let state = state.borrow();
+        state.borrow::<WindowSpawnerHandle>().clone()
+    };
+
+    let uuid = Uuid::parse_str(&handle).map_err(|e| OpError::invalid_handle(&e.to_string()))?;
+    window_spawner
+        .set_click_through(WindowHandle(uuid), click_through)
+        .map_err(|e| OpError::new(&e.to_string()))
+}
+
+/// Changes how opaque a window is, e.g. to make an image pulse or breathe.
+///
+/// Replaces any fade-in still running. Prompts from `writeLines` always stay opaque.
+///
+/// @param handle - The handle ID of the window.
+/// @param opacity - Opacity from 0.0 (invisible) to 1.0 (opaque), clamped to that range.
+#[op2(async)]
+pub async fn op_set_window_opacity(
+    state: Rc<RefCell<OpState>>,
+    #[string] handle: String,
+    opacity: f32,
+) -> Result<(), OpError> {
+    let window_spawner = {
+        let state = state.borrow();
+        state.borrow::<WindowSpawnerHandle>().clone()
+    };
+
+    if !opacity.is_finite() {
+        return Err(OpError::invalid_options("Opacity must be a number"));
+    }
+    let uuid = Uuid::parse_str(&handle).map_err(|e| OpError::invalid_handle(&e.to_string()))?;
+    window_spawner
+        .set_opacity(WindowHandle(uuid), opacity)
+        .map_err(|e| OpError::invalid_handle(&e.to_string()))
+}
+
+fn pack_library(state: &OpState) -> Result<&PackLibrary, OpError> {
+    state
+        .try_borrow::<PackLibrary>()
+        .ok_or_else(|| OpError::new("Pack switching is not available"))
+}
+
+/// Lists the installed packs.
+///
+/// @returns The pack names, sorted alphabetically.
+#[op2]
+#[serde]
+pub fn op_list_packs(state: &mut OpState) -> Result<Vec<String>, OpError> {
+    pack_library(state)?
+        .list()
+        .map_err(|e| OpError::new(&format!("Failed to list packs: {}", e)))
+}
+
+/// Switch the runtime to another installed pack, reloading its assets and permissions
+pub fn switch_pack(state: &mut OpState, name: &str) -> Result<(), OpError> {
+    let library = pack_library(state)?;
+    let packs = library
+        .list()
+        .map_err(|e| OpError::new(&format!("Failed to list packs: {}", e)))?;
+    if !packs.iter().any(|p| p == name) {
+        return Err(OpError::asset_not_found(&format!(
+            "Pack '{}' not found",
+            name
+        )));
+    }
+
+    let pack = library
+        .load(name)
+        .map_err(|e| OpError::new(&format!("Failed to load pack '{}': {}", name, e)))?;
+
+    // Keep the current mood if the new pack has one with the same name
+    let current = state.borrow::<Mood>().clone();
+    let mood = pack
+        .config
+        .moods
+        .iter()
+        .find(|m| m.name == current.name)
+        .or_else(|| pack.config.moods.first())
+        .cloned()
+        .unwrap_or(current);
+
+    state.put(pack.registry);
+    state.put(pack.permissions);
+    state.put(mood);
+    state.put(ActivePack {
+        name: pack.name,
+        config: pack.config,
+    });
+    Ok(())
+}
+
+/// Switches to another installed pack.
+///
+/// Assets, permissions and moods are reloaded from the new pack. The current mood is kept
+/// if the new pack defines it, otherwise the pack's first mood is used.
+///
+/// @param name - The pack name, as returned by `listPacks()`.
+#[op2(fast)]
+pub fn op_switch_pack(state: &mut OpState, #[string] name: String) -> Result<(), OpError> {
+    switch_pack(state, &name)
+}
+
+deno_core::extension!(
+    goon_system,
+    ops = [
+        op_close_window,
+        op_replay_window,
+        op_get_window_remaining_ms,
+        op_shake_window,
+        op_set_click_through,
+        op_set_window_opacity,
+        op_await_window_event,
+        op_is_media_active,
+        op_capabilities,
+        op_stop_all,
+        op_get_idle_seconds,
+        op_is_screen_locked,
+        op_list_tags,
+        op_get_monitors,
+        op_get_screen_size,
+        op_list_packs,
+        op_switch_pack
+    ],
+);
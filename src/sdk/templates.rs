@@ -1,4 +1,9 @@
-use crate::sdk::{audio, hypno, image, runtime_gen, types, video, wallpaper, website, write_lines};
+use crate::gui::windows::{PromptOutcome, PromptResult, WindowEvent};
+use crate::media::wallpaper::WallpaperMode;
+use crate::sdk::{
+    audio, hypno, image, mouse, overlay, pack, runtime_gen, screen, system, text, types, video,
+    wallpaper, website, write_lines,
+};
 use ts_rs::TS;
 
 fn extract_definitions(source: &str) -> String {
@@ -37,17 +42,44 @@ interface WindowHandle {{
 }
 
 pub fn system_ts() -> String {
-    extract_definitions(&runtime_gen::generate_system_runtime())
+    let capabilities_interface = system::Capabilities::decl();
+    let shake_interface = system::ShakeOptions::decl();
+    let event_type = WindowEvent::decl();
+    let activity_interface = system::MediaActivity::decl();
+    let monitor_interface = system::MonitorInfo::decl();
+    let screen_size_interface = system::ScreenSize::decl();
+    let source = extract_definitions(&runtime_gen::generate_system_runtime());
+    format!(
+        "{}\n{}\n{}\n{}\n{}\n{}\n{}",
+        capabilities_interface,
+        shake_interface,
+        event_type,
+        activity_interface,
+        monitor_interface,
+        screen_size_interface,
+        source
+    )
 }
 
 pub fn pack_ts() -> String {
-    extract_definitions(&runtime_gen::generate_pack_runtime())
+    let kind_type = pack::AssetKind::decl();
+    let picked_interface = pack::PickedAsset::decl();
+    let mood_interface = pack::MoodInfo::decl();
+    let source = extract_definitions(&runtime_gen::generate_pack_runtime());
+    format!(
+        "{}\n{}\n{}\n{}",
+        kind_type, picked_interface, mood_interface, source
+    )
 }
 
 pub fn image_ts() -> String {
     let options_interface = image::ImageOptions::decl();
+    let dimensions_interface = image::ImageDimensions::decl();
     let source = extract_definitions(&runtime_gen::generate_image_runtime());
-    format!("{}\n{}", options_interface, source)
+    format!(
+        "{}\n{}\n{}",
+        options_interface, dimensions_interface, source
+    )
 }
 
 pub fn video_ts() -> String {
@@ -58,20 +90,33 @@ pub fn video_ts() -> String {
 
 pub fn audio_ts() -> String {
     let options_interface = audio::AudioOptions::decl();
+    let crossfade_interface = audio::CrossfadeOptions::decl();
     let source = extract_definitions(&runtime_gen::generate_audio_runtime());
-    format!("{}\n{}", options_interface, source)
+    format!("{}\n{}\n{}", options_interface, crossfade_interface, source)
 }
 
 pub fn write_lines_ts() -> String {
     let options_interface = write_lines::WriteLinesOptions::decl();
+    let outcome_type = PromptOutcome::decl();
+    let result_interface = PromptResult::decl();
     let source = extract_definitions(&runtime_gen::generate_write_lines_runtime());
+    format!(
+        "{}\n{}\n{}\n{}",
+        options_interface, outcome_type, result_interface, source
+    )
+}
+
+pub fn text_ts() -> String {
+    let options_interface = text::BannerOptions::decl();
+    let source = extract_definitions(&runtime_gen::generate_text_runtime());
     format!("{}\n{}", options_interface, source)
 }
 
 pub fn wallpaper_ts() -> String {
+    let mode_type = WallpaperMode::decl();
     let options_interface = wallpaper::WallpaperOptions::decl();
     let source = extract_definitions(&runtime_gen::generate_wallpaper_runtime());
-    format!("{}\n{}", options_interface, source)
+    format!("{}\n{}\n{}", mode_type, options_interface, source)
 }
 
 pub fn website_ts() -> String {
@@ -85,3 +130,27 @@ pub fn hypno_ts() -> String {
     let source = extract_definitions(&runtime_gen::generate_hypno_runtime());
     format!("{}\n{}", options_interface, source)
 }
+
+pub fn mouse_ts() -> String {
+    let position_interface = mouse::MousePosition::decl();
+    let rect_interface = mouse::MouseRect::decl();
+    let source = extract_definitions(&runtime_gen::generate_mouse_runtime());
+    format!("{}\n{}\n{}", position_interface, rect_interface, source)
+}
+
+pub fn screen_ts() -> String {
+    let region_interface = screen::CaptureRegion::decl();
+    let source = extract_definitions(&runtime_gen::generate_screen_runtime());
+    format!("{}\n{}", region_interface, source)
+}
+
+pub fn notification_ts() -> String {
+    extract_definitions(&runtime_gen::generate_notification_runtime())
+}
+
+pub fn overlay_ts() -> String {
+    let options_interface = overlay::OverlayOptions::decl();
+    let tint_interface = overlay::TintOptions::decl();
+    let source = extract_definitions(&runtime_gen::generate_overlay_runtime());
+    format!("{}\n{}\n{}", options_interface, tint_interface, source)
+}
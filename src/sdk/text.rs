@@ -0,0 +1,107 @@
+use crate::gui::WindowSpawnerHandle;
+use crate::permissions::Permission;
+use crate::runtime::error::OpError;
+use crate::runtime::utils::check_permission;
+use crate::sdk::types::WindowOptions;
+use deno_core::OpState;
+use deno_core::op2;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use ts_rs::TS;
+
+/// How long a banner stays up when no duration is given
+const DEFAULT_BANNER_DURATION: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize, Debug, Default, TS, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+/// Options for displaying a text banner
+pub struct BannerOptions {
+    /// The text to display
+    pub text: String,
+    /// Milliseconds to show the banner before it closes, defaults to 5000
+    pub duration_ms: Option<u64>,
+    /// Font size in pixels
+    pub font_size: Option<f32>,
+    /// Text color as RGBA array [r, g, b, a] with values from 0 to 255
+    pub color: Option<[u8; 4]>,
+    /// Background color as RGBA array [r, g, b, a] with values from 0 to 255
+    pub background: Option<[u8; 4]>,
+    /// Text alignment: "left", "center", or "right"
+    pub alignment: Option<String>,
+    /// Window configuration options
+    pub window: Option<WindowOptions>,
+}
+
+fn normalize_rgba(c: [u8; 4]) -> [f32; 4] {
+    c.map(|v| v as f32 / 255.0)
+}
+
+/// Displays text for the user to read, closing automatically after a while.
+///
+/// Unlike `writeLines.show()` the user does not have to type anything.
+/// The returned handle has a `.close()` method to close the banner early.
+///
+/// @param options - The text to display, how long to show it, and font,
+///                  color and window settings.
+/// @returns A unique handle object for controlling this banner.
+#[op2(async)]
+#[string]
+pub async fn op_show_banner(
+    state: Rc<RefCell<OpState>>,
+    #[serde] options: Option<BannerOptions>,
+) -> Result<String, OpError> {
+    let window_spawner = {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::WriteLines)?;
+        state.borrow::<WindowSpawnerHandle>().clone()
+    };
+
+    let opts = options.ok_or_else(|| OpError::invalid_options("Banner options required"))?;
+
+    let handle = window_spawner
+        .spawn_banner(
+            opts.text,
+            opts.font_size.unwrap_or(32.0),
+            opts.color
+                .map(normalize_rgba)
+                .unwrap_or([1.0, 1.0, 1.0, 1.0]),
+            opts.background
+                .map(normalize_rgba)
+                .unwrap_or([0.1, 0.1, 0.1, 0.95]),
+            opts.alignment.unwrap_or_else(|| "center".to_string()),
+            opts.window,
+            opts.duration_ms
+                .map(Duration::from_millis)
+                .unwrap_or(DEFAULT_BANNER_DURATION),
+        )
+        .map_err(OpError::from)?;
+
+    Ok(handle.0.to_string())
+}
+
+deno_core::extension!(goon_text, ops = [op_show_banner],);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_banner_options() {
+        let opts: BannerOptions = serde_json::from_value(serde_json::json!({
+            "text": "Keep watching",
+            "durationMs": 1500,
+            "color": [255, 0, 0, 255],
+            "window": { "opacity": 0.5 }
+        }))
+        .unwrap();
+        assert_eq!(opts.text, "Keep watching");
+        assert_eq!(opts.duration_ms, Some(1500));
+        assert_eq!(opts.color.map(normalize_rgba), Some([1.0, 0.0, 0.0, 1.0]));
+        assert_eq!(opts.window.unwrap().opacity, Some(0.5));
+
+        assert!(serde_json::from_value::<BannerOptions>(serde_json::json!({})).is_err());
+    }
+}
@@ -1,41 +1,58 @@
 use crate::assets::registry::AssetRegistry;
-use crate::assets::selector::AssetSelector;
+use crate::assets::selector::{AssetFallback, AssetSelector, RecentAssets};
 use crate::assets::types::Asset;
 use crate::config::pack::Mood;
 use crate::gui::WindowSpawnerHandle;
+use crate::gui::windows::fade::WindowFade;
 use crate::permissions::Permission;
 use crate::runtime::error::OpError;
 use crate::runtime::utils::check_permission;
+use crate::sdk::system::{monitor_layout, pack_asset_path, popup_click_through};
 use crate::sdk::types::WindowOptions;
 use deno_core::OpState;
 use deno_core::op2;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json;
 use std::cell::RefCell;
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use ts_rs::TS;
 use uuid::Uuid;
 
 /// Parse a string handle ID into a window handle UUID
 fn parse_video_handle(handle_id: &str) -> Result<Uuid, OpError> {
-    Uuid::parse_str(handle_id).map_err(|_| OpError::new("Invalid video handle ID"))
+    Uuid::parse_str(handle_id).map_err(|_| OpError::invalid_handle("Invalid video handle ID"))
 }
 
-#[derive(Deserialize, Debug, Default, TS)]
+#[derive(Deserialize, Debug, Default, TS, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 /// Options for playing a video
 pub struct VideoOptions {
     /// A list of additional tags to filter videos by, they will be filtered by mood tags already
     pub tags: Option<Vec<String>>,
+    /// Tags to avoid, videos with any of these tags are never picked
+    pub exclude_tags: Option<Vec<String>>,
+    /// Path of a specific video relative to the pack directory, e.g. "videos/intro.mp4".
+    /// Skips tag selection, `tags` and `excludeTags` are ignored.
+    pub path: Option<String>,
     /// Whether to loop the video continuously
     pub loop_: Option<bool>,
     /// Volume level from 0.0 (muted) to 1.0 (full volume)
     pub volume: Option<f32>,
     /// Whether to start playing automatically
     pub autoplay: Option<bool>,
-    /// Duration to play the video in seconds, after this the window will be closed automatically
+    /// Duration to play the video in seconds, after this the window will be closed automatically.
+    /// Defaults to the popup timeout in settings.
     pub duration: Option<u64>,
+    /// Keep the window open until the user clicks it, `duration` is ignored
+    pub require_click_to_close: Option<bool>,
+    /// Milliseconds the window must be visible before a click closes it, defaults to 0
+    pub min_visible_ms: Option<u64>,
+    /// Let mouse clicks pass through the video to the windows below, defaults to false.
+    /// Can't be combined with `requireClickToClose`.
+    pub click_through: Option<bool>,
     /// Window configuration options
     pub window: Option<WindowOptions>,
 }
@@ -54,31 +71,42 @@ pub async fn op_show_video(
     state: Rc<RefCell<OpState>>,
     #[serde] options: Option<serde_json::Value>,
 ) -> Result<String, OpError> {
-    let (registry, mood, window_spawner) = {
+    let (registry, recent, mood, allow_fallback, window_spawner) = {
         let mut state = state.borrow_mut();
         check_permission(&mut state, Permission::Video)?;
         let registry = state.borrow::<Arc<AssetRegistry>>().clone();
+        let recent = state.borrow::<Arc<Mutex<RecentAssets>>>().clone();
         let mood = state.borrow::<Mood>().clone();
+        let allow_fallback = state.borrow::<AssetFallback>().0;
         let window_spawner = state.borrow::<WindowSpawnerHandle>().clone();
-        (registry, mood, window_spawner)
+        (registry, recent, mood, allow_fallback, window_spawner)
     };
 
     let opts: VideoOptions = if let Some(o) = options {
-        serde_json::from_value(o).map_err(|e| OpError::new(&e.to_string()))?
+        serde_json::from_value(o).map_err(|e| OpError::invalid_options(&e.to_string()))?
     } else {
         VideoOptions::default()
     };
 
-    let tags = opts.tags.clone().unwrap_or_default();
-    let selector = AssetSelector::new(&registry);
+    let path = match opts.path.as_deref() {
+        Some(path) => pack_asset_path(&registry, path)?,
+        None => {
+            let tags = opts.tags.clone().unwrap_or_default();
+            let exclude_tags = opts.exclude_tags.clone().unwrap_or_default();
+            let selector = AssetSelector::new(&registry)
+                .with_recent(&recent)
+                .allow_fallback(allow_fallback)
+                .excluding(&exclude_tags);
 
-    let asset = selector
-        .select_video(&mood, &tags)
-        .ok_or_else(|| OpError::new("No video found matching tags"))?;
+            let asset = selector
+                .select_video(&mood, &tags)
+                .ok_or_else(|| OpError::asset_not_found("No video found matching tags"))?;
 
-    let path = match asset {
-        Asset::Video(vid) => vid.path.clone(),
-        _ => return Err(OpError::new("Selected asset is not a video")),
+            match asset {
+                Asset::Video(vid) => vid.path.clone(),
+                _ => return Err(OpError::asset_not_found("Selected asset is not a video")),
+            }
+        }
     };
 
     tracing::info!("Showing video: {:?} with options: {:?}", path, opts);
@@ -86,13 +114,34 @@ pub async fn op_show_video(
     let window = opts.window.as_ref();
     let width = window.and_then(|w| w.size.as_ref()).map(|s| s.width);
     let height = window.and_then(|w| w.size.as_ref()).map(|s| s.height);
-    let opacity = window.and_then(|w| w.opacity).unwrap_or(1.0);
+    let opacity = window.and_then(|w| w.opacity);
+    let always_on_top = window.and_then(|w| w.always_on_top);
+    let click_through = popup_click_through(opts.click_through, opts.require_click_to_close)?;
+    let fade = WindowFade::from_millis(
+        window.and_then(|w| w.fade_in_ms),
+        window.and_then(|w| w.fade_out_ms),
+    );
     let loop_playback = opts.loop_.unwrap_or(false);
     let volume = opts.volume.unwrap_or(1.0);
 
     let handle = window_spawner
-        .spawn_video(path, width, height, opacity, loop_playback, volume)
-        .map_err(|e| OpError::new(&e.to_string()))?;
+        .spawn_video(
+            path,
+            width,
+            height,
+            opacity,
+            always_on_top,
+            loop_playback,
+            volume,
+            monitor_layout(window)?,
+            opts.duration.map(Duration::from_secs),
+            opts.require_click_to_close
+                .unwrap_or(false)
+                .then(|| Duration::from_millis(opts.min_visible_ms.unwrap_or(0))),
+            fade,
+            click_through,
+        )
+        .map_err(OpError::from)?;
 
     Ok(handle.0.to_string())
 }
@@ -141,7 +190,74 @@ pub async fn op_resume_video(
     Ok(())
 }
 
+/// Sets the audio volume of a playing video.
+///
+/// @param handle - The handle ID returned from play().
+/// @param volume - Volume level from 0.0 (silent) to 1.0 (full volume).
+#[op2(async)]
+pub async fn op_set_video_volume(
+    state: Rc<RefCell<OpState>>,
+    #[string] handle_id: String,
+    volume: f32,
+) -> Result<(), OpError> {
+    if !volume.is_finite() {
+        return Err(OpError::invalid_options("volume must be a number"));
+    }
+    let handle = parse_video_handle(&handle_id)?;
+    let window_spawner = {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Video)?;
+        state.borrow::<WindowSpawnerHandle>().clone()
+    };
+
+    window_spawner
+        .set_video_volume(crate::gui::windows::WindowHandle(handle), volume)
+        .map_err(|e| OpError::new(&e.to_string()))?;
+
+    Ok(())
+}
+
+/// Jumps to a position in a playing or paused video.
+///
+/// A paused video shows the frame at the new position and stays paused.
+/// Seeking past the end stops the video, or starts it over if it loops.
+///
+/// @param handle - The handle ID returned from play().
+/// @param seconds - Position from the start of the video in seconds.
+#[op2(async)]
+pub async fn op_seek_video(
+    state: Rc<RefCell<OpState>>,
+    #[string] handle_id: String,
+    seconds: f64,
+) -> Result<(), OpError> {
+    if !seconds.is_finite() {
+        return Err(OpError::invalid_options(
+            "Seek position must be a number of seconds",
+        ));
+    }
+    let handle = parse_video_handle(&handle_id)?;
+    let window_spawner = {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Video)?;
+        state.borrow::<WindowSpawnerHandle>().clone()
+    };
+
+    let position = Duration::try_from_secs_f64(seconds.max(0.0))
+        .map_err(|_| OpError::invalid_options("Seek position must be a number of seconds"))?;
+    window_spawner
+        .seek_video(crate::gui::windows::WindowHandle(handle), position)
+        .map_err(|e| OpError::new(&e.to_string()))?;
+
+    Ok(())
+}
+
 deno_core::extension!(
     goon_video,
-    ops = [op_show_video, op_pause_video, op_resume_video],
+    ops = [
+        op_show_video,
+        op_pause_video,
+        op_resume_video,
+        op_set_video_volume,
+        op_seek_video
+    ],
 );
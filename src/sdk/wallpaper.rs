@@ -1,27 +1,41 @@
 use crate::assets::registry::AssetRegistry;
-use crate::assets::selector::AssetSelector;
+use crate::assets::selector::{AssetFallback, AssetSelector, RecentAssets};
 use crate::assets::types::Asset;
 use crate::config::pack::Mood;
-use crate::media::wallpaper::{PlatformWallpaperSetter, WallpaperSetter};
+use crate::media::capture;
+use crate::media::image::color_key::parse_hex_rgb;
+use crate::media::wallpaper::color::ColorWallpapers;
+use crate::media::wallpaper::{
+    PlatformWallpaperSetter, WallpaperBackup, WallpaperMode, WallpaperSetter,
+};
 use crate::permissions::Permission;
 use crate::runtime::error::OpError;
 use crate::runtime::utils::check_permission;
 use deno_core::OpState;
 use deno_core::op2;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json;
 use std::cell::RefCell;
 use std::fs;
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use ts_rs::TS;
 
-#[derive(Deserialize, Debug, Default, TS)]
+#[derive(Deserialize, Debug, Default, TS, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 /// Options for setting the desktop wallpaper
 pub struct WallpaperOptions {
     /// A list of additional tags to filter wallpaper images by, they will be filtered by mood tags already
     tags: Option<Vec<String>>,
+    /// Tags to avoid, wallpaper images with any of these tags are never picked
+    exclude_tags: Option<Vec<String>>,
+    /// How the image is fitted to the screen, defaults to fill
+    mode: Option<WallpaperMode>,
+    /// Index of the display to set the wallpaper on, all displays when unset.
+    /// Only macOS can target a single display, elsewhere every display is set.
+    /// An index past the last display is an error.
+    display: Option<usize>,
 }
 
 /// Sets the desktop wallpaper to an image from the pack.
@@ -32,30 +46,40 @@ pub async fn op_set_wallpaper(
     state: Rc<RefCell<OpState>>,
     #[serde] options: Option<serde_json::Value>,
 ) -> Result<(), OpError> {
-    let (registry, mood) = {
+    let (registry, recent, mood, allow_fallback) = {
         let mut state = state.borrow_mut();
         check_permission(&mut state, Permission::Wallpaper)?;
         let registry = state.borrow::<Arc<AssetRegistry>>().clone();
+        let recent = state.borrow::<Arc<Mutex<RecentAssets>>>().clone();
         let mood = state.borrow::<Mood>().clone();
-        (registry, mood)
+        let allow_fallback = state.borrow::<AssetFallback>().0;
+        (registry, recent, mood, allow_fallback)
     };
 
     let opts: WallpaperOptions = if let Some(o) = options {
-        serde_json::from_value(o).map_err(|e| OpError::new(&e.to_string()))?
+        serde_json::from_value(o).map_err(|e| OpError::invalid_options(&e.to_string()))?
     } else {
         WallpaperOptions::default()
     };
 
     let tags = opts.tags.clone().unwrap_or_default();
-    let selector = AssetSelector::new(&registry);
+    let exclude_tags = opts.exclude_tags.clone().unwrap_or_default();
+    let selector = AssetSelector::new(&registry)
+        .with_recent(&recent)
+        .allow_fallback(allow_fallback)
+        .excluding(&exclude_tags);
 
     let asset = selector
         .select_wallpaper(&mood, &tags)
-        .ok_or_else(|| OpError::new("No wallpaper found matching tags"))?;
+        .ok_or_else(|| OpError::asset_not_found("No wallpaper found matching tags"))?;
 
     let path_to_set = match asset {
         Asset::Wallpaper(w) => w.path.clone(),
-        _ => return Err(OpError::new("Selected asset is not a wallpaper")),
+        _ => {
+            return Err(OpError::asset_not_found(
+                "Selected asset is not a wallpaper",
+            ));
+        }
     };
 
     // Create persistent directory
@@ -74,11 +98,86 @@ pub async fn op_set_wallpaper(
         .map_err(|e| OpError::new(&format!("Failed to copy wallpaper: {}", e)))?;
 
     let setter = PlatformWallpaperSetter;
-    setter
-        .set_wallpaper(&target_path)
+    let mode = opts.mode.unwrap_or_default();
+    match opts.display {
+        Some(display) => setter.set_wallpaper_on_display(&target_path, mode, display),
+        None => setter.set_wallpaper(&target_path, mode),
+    }
+    .map_err(|e| OpError::new(&format!("Failed to set wallpaper: {}", e)))?;
+
+    Ok(())
+}
+
+/// Sets the desktop wallpaper to a solid color.
+///
+/// The image is generated at the primary display's resolution. It is removed again
+/// when the original wallpaper is restored.
+///
+/// @param hex - The color as a hex string, e.g. "#ff0000" or "#f00".
+#[op2(async)]
+pub async fn op_set_wallpaper_color(
+    state: Rc<RefCell<OpState>>,
+    #[string] hex: String,
+) -> Result<(), OpError> {
+    {
+        let mut state = state.borrow_mut();
+        check_permission(&mut state, Permission::Wallpaper)?;
+    }
+
+    let color = parse_hex_rgb(&hex).map_err(|e| OpError::invalid_options(&e.to_string()))?;
+    let (width, height) = capture::primary_display_size()
+        .map_err(|e| OpError::new(&format!("Failed to read display size: {}", e)))?;
+
+    let path = {
+        let mut state = state.borrow_mut();
+        state
+            .borrow_mut::<ColorWallpapers>()
+            .generate(color, width, height)
+            .map_err(|e| OpError::new(&format!("Failed to generate wallpaper: {}", e)))?
+    };
+
+    PlatformWallpaperSetter
+        .set_wallpaper(&path, WallpaperMode::Fill)
         .map_err(|e| OpError::new(&format!("Failed to set wallpaper: {}", e)))?;
 
     Ok(())
 }
 
-deno_core::extension!(goon_wallpaper, ops = [op_set_wallpaper],);
+/// Set the wallpaper from before the session back
+pub fn restore_wallpaper(state: &mut OpState) -> Result<(), OpError> {
+    let backup = state
+        .try_borrow::<WallpaperBackup>()
+        .cloned()
+        .ok_or_else(|| {
+            OpError::new("No original wallpaper was detected at session start, nothing to restore")
+        })?;
+
+    PlatformWallpaperSetter
+        .set_wallpaper(&backup.0, WallpaperMode::default())
+        .map_err(|e| OpError::new(&format!("Failed to restore wallpaper: {}", e)))?;
+
+    // Generated color wallpapers are no longer shown once the original is back
+    if let Some(wallpapers) = state.try_borrow_mut::<ColorWallpapers>() {
+        wallpapers.cleanup();
+    }
+    Ok(())
+}
+
+/// Restores the wallpaper that was set before the session started.
+///
+/// Use this to revert after temporarily swapping the wallpaper for a scene.
+/// Fails if the original wallpaper couldn't be detected.
+#[op2(fast)]
+pub fn op_restore_wallpaper(state: &mut OpState) -> Result<(), OpError> {
+    check_permission(state, Permission::Wallpaper)?;
+    restore_wallpaper(state)
+}
+
+deno_core::extension!(
+    goon_wallpaper,
+    ops = [
+        op_set_wallpaper,
+        op_set_wallpaper_color,
+        op_restore_wallpaper
+    ],
+);
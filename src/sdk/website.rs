@@ -1,5 +1,5 @@
 use crate::assets::registry::AssetRegistry;
-use crate::assets::selector::AssetSelector;
+use crate::assets::selector::{AssetFallback, AssetSelector, RecentAssets};
 use crate::assets::types::Asset;
 use crate::config::pack::Mood;
 use crate::permissions::Permission;
@@ -7,58 +7,142 @@ use crate::runtime::error::OpError;
 use crate::runtime::utils::check_permission;
 use deno_core::OpState;
 use deno_core::op2;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use ts_rs::TS;
+use uuid::Uuid;
 
-#[derive(Deserialize, Debug, Default, TS)]
+#[derive(Deserialize, Debug, Default, TS, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 /// Options for opening a website
 pub struct WebsiteOptions {
     /// A list of additional tags to filter website URLs by, they will be filtered by mood tags already
     tags: Option<Vec<String>>,
+    /// Tags to avoid, website URLs with any of these tags are never picked
+    exclude_tags: Option<Vec<String>>,
+}
+
+/// Websites scripts have opened, by handle.
+///
+/// The browser is asked to open a URL and owns the tab from then on, there is
+/// no portable way to close it again. Handles only keep track of what's open.
+#[derive(Debug, Default)]
+pub struct OpenedWebsites {
+    urls: HashMap<Uuid, String>,
+}
+
+impl OpenedWebsites {
+    /// Record an opened URL and return its handle
+    pub fn add(&mut self, url: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        self.urls.insert(id, url.to_string());
+        id
+    }
+
+    /// Forget a website, returning its URL if the handle was known
+    pub fn remove(&mut self, id: &Uuid) -> Option<String> {
+        self.urls.remove(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.urls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.urls.is_empty()
+    }
 }
 
 /// Opens a website URL in the default browser.
 ///
 /// @param options - Optional configuration including tags for URL selection.
+/// @returns A handle for the opened website.
 #[op2(async)]
+#[string]
 pub async fn op_open_website(
     state: Rc<RefCell<OpState>>,
     #[serde] options: Option<serde_json::Value>,
-) -> Result<(), OpError> {
-    let (registry, mood) = {
+) -> Result<String, OpError> {
+    let (registry, recent, mood, allow_fallback) = {
         let mut state = state.borrow_mut();
         check_permission(&mut state, Permission::Website)?;
         let registry = state.borrow::<Arc<AssetRegistry>>().clone();
+        let recent = state.borrow::<Arc<Mutex<RecentAssets>>>().clone();
         let mood = state.borrow::<Mood>().clone();
-        (registry, mood)
+        let allow_fallback = state.borrow::<AssetFallback>().0;
+        (registry, recent, mood, allow_fallback)
     };
 
     let opts: WebsiteOptions = if let Some(o) = options {
-        serde_json::from_value(o).map_err(|e| OpError::new(&e.to_string()))?
+        serde_json::from_value(o).map_err(|e| OpError::invalid_options(&e.to_string()))?
     } else {
         WebsiteOptions::default()
     };
 
     let tags = opts.tags.clone().unwrap_or_default();
-    let selector = AssetSelector::new(&registry);
+    let exclude_tags = opts.exclude_tags.clone().unwrap_or_default();
+    let selector = AssetSelector::new(&registry)
+        .with_recent(&recent)
+        .allow_fallback(allow_fallback)
+        .excluding(&exclude_tags);
 
     let asset = selector
         .select_website(&mood, &tags)
-        .ok_or_else(|| OpError::new("No website found matching tags"))?;
+        .ok_or_else(|| OpError::asset_not_found("No website found matching tags"))?;
 
     let url = match asset {
         Asset::Website(w) => &w.url,
-        _ => return Err(OpError::new("Selected asset is not a website")),
+        _ => return Err(OpError::asset_not_found("Selected asset is not a website")),
     };
 
     open::that(url).map_err(|e| OpError::new(&format!("Failed to open website: {}", e)))?;
 
-    Ok(())
+    let id = state.borrow_mut().borrow_mut::<OpenedWebsites>().add(url);
+    Ok(id.to_string())
 }
 
-deno_core::extension!(goon_website, ops = [op_open_website],);
+/// Closes a website opened with `open()`.
+///
+/// Browsers don't let other programs close their tabs, so the tab stays open.
+/// This only stops tracking the website.
+///
+/// @param handleId - The website handle ID.
+#[op2]
+pub fn op_close_website(state: &mut OpState, #[string] handle_id: String) -> Result<(), OpError> {
+    check_permission(state, Permission::Website)?;
+    let id = Uuid::parse_str(&handle_id)
+        .map_err(|_| OpError::invalid_handle("Invalid website handle ID"))?;
+    state
+        .borrow_mut::<OpenedWebsites>()
+        .remove(&id)
+        .map(|_| ())
+        .ok_or_else(|| OpError::invalid_handle(&format!("Unknown website handle: {}", handle_id)))
+}
+
+deno_core::extension!(goon_website, ops = [op_open_website, op_close_website],);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opened_websites_track_handles() {
+        let mut opened = OpenedWebsites::default();
+        let first = opened.add("https://example.com");
+        let second = opened.add("https://example.com");
+        assert_ne!(first, second);
+        assert_eq!(opened.len(), 2);
+
+        assert_eq!(
+            opened.remove(&first).as_deref(),
+            Some("https://example.com")
+        );
+        assert_eq!(opened.remove(&first), None);
+        assert_eq!(opened.len(), 1);
+    }
+}
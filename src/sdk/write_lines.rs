@@ -1,18 +1,22 @@
-use crate::gui::WindowSpawnerHandle;
+use crate::gui::windows::{PromptOutcome, PromptResult};
+use crate::gui::{WindowHandle, WindowSpawnerHandle};
 use crate::permissions::Permission;
 use crate::runtime::error::OpError;
 use crate::runtime::utils::check_permission;
 use crate::sdk::types::WindowOptions;
 use deno_core::OpState;
 use deno_core::op2;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 use tracing::{debug, error, info};
 use ts_rs::TS;
+use uuid::Uuid;
 
-#[derive(Deserialize, Debug, Default, TS)]
+#[derive(Deserialize, Debug, Default, TS, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 /// Options for displaying a text prompt
 pub struct WriteLinesOptions {
@@ -30,6 +34,12 @@ pub struct WriteLinesOptions {
     pub max_width: Option<u32>,
     /// Text alignment: "left", "center", or "right"
     pub alignment: Option<String>,
+    /// Whether the user has to type `text` exactly, defaults to true.
+    /// Set to false to ask an open question, any answer is accepted and returned by `awaitInput()`.
+    pub exact_match: Option<bool>,
+    /// Milliseconds to wait for the text before the prompt closes, waits forever by default.
+    /// A prompt that times out resolves `awaitInput()` with `submitted: false`.
+    pub timeout_ms: Option<u64>,
     /// Window configuration options
     pub window: Option<WindowOptions>,
 }
@@ -37,6 +47,8 @@ pub struct WriteLinesOptions {
 /// Displays text that the user has to repeat back to you before they can close the window.
 /// This works in a "Write lines for me" approach, where you provide the lines for the user to type back.
 /// The prompt window will stay on top until the user types the exact text you provided.
+/// With `exactMatch: false` it asks an open question instead and any answer is accepted,
+/// use `awaitInput()` to read it.
 ///
 /// Returns a handle object that can be used to control the window.
 /// The returned handle has a `.close()` method to close the window.
@@ -61,11 +73,11 @@ pub async fn op_show_write_lines(
         debug!("op_show_write_lines options: {:?}", o);
         serde_json::from_value(o).map_err(|e| {
             error!("Failed to parse write_lines options: {}", e);
-            OpError::new(&e.to_string())
+            OpError::invalid_options(&e.to_string())
         })?
     } else {
         error!("WriteLines options missing");
-        return Err(OpError::new("WriteLines options required"));
+        return Err(OpError::invalid_options("WriteLines options required"));
     };
 
     let alignment = opts.alignment.unwrap_or_else(|| "left".to_string());
@@ -102,14 +114,80 @@ pub async fn op_show_write_lines(
             background_color,
             alignment,
             opts.window,
+            opts.exact_match.unwrap_or(true),
+            opts.timeout_ms.map(Duration::from_millis),
         )
         .map_err(|e| {
             error!("Failed to spawn write_lines window: {}", e);
-            OpError::new(&e.to_string())
+            OpError::from(e)
         })?;
 
     info!("WriteLines window spawned successfully: {:?}", handle);
     Ok(handle.0.to_string())
 }
 
-deno_core::extension!(goon_write_lines, ops = [op_show_write_lines],);
+/// Waits until a prompt is finished and tells how it ended.
+///
+/// Resolves with `"submitted"` once the user typed the text, `"timedOut"` if
+/// `timeoutMs` passed first, or `"closed"` if the prompt was closed otherwise.
+/// Also resolves for prompts that already finished.
+///
+/// @param handleId - The handle ID of the prompt, as returned by `show()`.
+/// @returns How the prompt ended.
+#[op2(async)]
+#[serde]
+pub async fn op_await_prompt_result(
+    state: Rc<RefCell<OpState>>,
+    #[string] handle: String,
+) -> Result<PromptOutcome, OpError> {
+    let window_spawner = {
+        let state = state.borrow();
+        state.borrow::<WindowSpawnerHandle>().clone()
+    };
+
+    Ok(wait_for_prompt(&window_spawner, &handle).await?.outcome)
+}
+
+/// Waits until a prompt is finished and returns what the user typed.
+///
+/// Resolves with `submitted: true` and the typed `input` once the user submitted
+/// the prompt, or `submitted: false` if it timed out or was closed first.
+///
+/// @param handleId - The handle ID of the prompt, as returned by `show()`.
+/// @returns Whether the prompt was submitted, the input, and how it ended.
+#[op2(async)]
+#[serde]
+pub async fn op_await_prompt_input(
+    state: Rc<RefCell<OpState>>,
+    #[string] handle: String,
+) -> Result<PromptResult, OpError> {
+    let window_spawner = {
+        let state = state.borrow();
+        state.borrow::<WindowSpawnerHandle>().clone()
+    };
+
+    wait_for_prompt(&window_spawner, &handle).await
+}
+
+async fn wait_for_prompt(
+    window_spawner: &WindowSpawnerHandle,
+    handle: &str,
+) -> Result<PromptResult, OpError> {
+    let uuid = Uuid::parse_str(handle).map_err(|e| OpError::invalid_handle(&e.to_string()))?;
+    let result = window_spawner
+        .prompts
+        .wait(WindowHandle(uuid))
+        .ok_or_else(|| OpError::invalid_handle(&format!("Unknown prompt handle: {}", handle)))?;
+    Ok(result
+        .await
+        .unwrap_or_else(|_| PromptOutcome::Closed.into()))
+}
+
+deno_core::extension!(
+    goon_write_lines,
+    ops = [
+        op_show_write_lines,
+        op_await_prompt_result,
+        op_await_prompt_input
+    ],
+);
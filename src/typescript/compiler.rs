@@ -2,11 +2,23 @@ use crate::typescript::error::CompilationError;
 use std::sync::Arc;
 use swc::Compiler;
 use swc_common::{
-    FileName, GLOBALS, Globals, SourceMap,
+    FileName, GLOBALS, Globals, SourceFile, SourceMap, Spanned,
     errors::{ColorConfig, Handler},
 };
 use swc_ecma_ast::EsVersion;
-use swc_ecma_parser::{Syntax, TsSyntax};
+use swc_ecma_parser::{Syntax, TsSyntax, parse_file_as_module};
+
+const TARGET: EsVersion = EsVersion::Es2020;
+
+fn syntax() -> Syntax {
+    Syntax::Typescript(TsSyntax {
+        tsx: false,
+        decorators: false,
+        dts: false,
+        no_early_errors: false,
+        disallow_ambiguous_jsx_like: false,
+    })
+}
 
 pub struct TypeScriptCompiler {
     compiler: Arc<Compiler>,
@@ -37,20 +49,19 @@ impl TypeScriptCompiler {
                 source.to_string(),
             );
 
+            // Parse first so syntax errors come with a position
+            if let Some(error) = self.syntax_error(&fm, source) {
+                return Err(error);
+            }
+
             let result = self.compiler.process_js_file(
                 fm,
                 &handler,
                 &swc::config::Options {
                     config: swc::config::Config {
                         jsc: swc::config::JscConfig {
-                            syntax: Some(Syntax::Typescript(TsSyntax {
-                                tsx: false,
-                                decorators: false,
-                                dts: false,
-                                no_early_errors: false,
-                                disallow_ambiguous_jsx_like: false,
-                            })),
-                            target: Some(EsVersion::Es2020),
+                            syntax: Some(syntax()),
+                            target: Some(TARGET),
                             external_helpers: false.into(), // Inline helpers instead of importing
                             ..Default::default()
                         },
@@ -71,6 +82,27 @@ impl TypeScriptCompiler {
             }
         })
     }
+
+    /// The first syntax error in `source`, with its line, column and source line
+    fn syntax_error(&self, fm: &SourceFile, source: &str) -> Option<CompilationError> {
+        let mut recovered = Vec::new();
+        let error = match parse_file_as_module(fm, syntax(), TARGET, None, &mut recovered) {
+            Ok(_) => recovered.into_iter().next()?,
+            Err(e) => e,
+        };
+
+        let loc = self.cm.lookup_char_pos(error.span().lo);
+        Some(CompilationError {
+            message: error.kind().msg().to_string(),
+            line: loc.line,
+            column: loc.col.0 + 1,
+            source_snippet: source
+                .lines()
+                .nth(loc.line.saturating_sub(1))
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -96,4 +128,15 @@ mod tests {
         let result = compiler.compile(source);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_syntax_error_position() {
+        let compiler = TypeScriptCompiler::new();
+        let source = "const a = 1;\nconst x: number = ;\nconsole.log(a);";
+        let error = compiler.compile(source).unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.column, 19);
+        assert_eq!(error.source_snippet, "const x: number = ;");
+        assert!(error.to_string().starts_with("line 2, col 19: "));
+    }
 }
@@ -1,19 +1,57 @@
 #[derive(Debug, Clone)]
 pub struct CompilationError {
     pub message: String,
+    /// 1-based line of the error, 0 when the position is unknown
     pub line: usize,
+    /// 1-based column of the error, 0 when the position is unknown
     pub column: usize,
+    /// The source line the error is on
     pub source_snippet: String,
 }
 
 impl std::fmt::Display for CompilationError {
+    /// `line 4, col 10: <message>` followed by the source line with a caret under
+    /// the column, so a model reading it back can fix the right spot
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}", self.message);
+        }
         write!(
             f,
-            "Error at {}:{}: {}\nSnippet: {}",
-            self.line, self.column, self.message, self.source_snippet
+            "line {}, col {}: {}\n  {}\n  {}^",
+            self.line,
+            self.column,
+            self.message,
+            self.source_snippet,
+            " ".repeat(self.column.saturating_sub(1))
         )
     }
 }
 
 impl std::error::Error for CompilationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_points_at_column() {
+        let error = CompilationError {
+            message: "Expected ';', got 'x'".to_string(),
+            line: 4,
+            column: 5,
+            source_snippet: "let x".to_string(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "line 4, col 5: Expected ';', got 'x'\n  let x\n      ^"
+        );
+
+        let unplaced = CompilationError {
+            column: 0,
+            line: 0,
+            ..error
+        };
+        assert_eq!(unplaced.to_string(), "Expected ';', got 'x'");
+    }
+}
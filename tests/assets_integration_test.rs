@@ -34,19 +34,23 @@ fn test_asset_flow_from_config_to_selection() {
                 ConfigAsset {
                     path: "happy_img.jpg".to_string(),
                     tags: vec!["happy".to_string(), "bright".to_string()],
+                    weight: None,
                 },
                 ConfigAsset {
                     path: "sad_img.jpg".to_string(),
                     tags: vec!["sad".to_string(), "dark".to_string()],
+                    weight: None,
                 },
                 ConfigAsset {
                     path: "neutral_img.jpg".to_string(),
                     tags: vec!["neutral".to_string()],
+                    weight: None,
                 },
             ]),
             video: Some(vec![ConfigAsset {
                 path: "happy_vid.mp4".to_string(),
                 tags: vec!["happy".to_string(), "dance".to_string()],
+                weight: None,
             }]),
             audio: None,
             hypno: None,
@@ -54,6 +58,7 @@ fn test_asset_flow_from_config_to_selection() {
         },
         websites: None,
         prompts: None,
+        popups: None,
     };
 
     // 2. Load Assets
@@ -30,10 +30,12 @@ async fn test_asset_loading_to_permission_check() {
             image: Some(vec![ConfigAsset {
                 path: "image/beach.jpg".to_string(),
                 tags: vec!["default".to_string()],
+                weight: None,
             }]),
             video: Some(vec![ConfigAsset {
                 path: "video/test-bunny.mp4".to_string(),
                 tags: vec!["default".to_string()],
+                weight: None,
             }]),
             audio: None,
             hypno: None,
@@ -41,6 +43,7 @@ async fn test_asset_loading_to_permission_check() {
         },
         websites: None,
         prompts: None,
+        popups: None,
     };
 
     // User: Grants ONLY Image permission.
@@ -53,7 +56,7 @@ async fn test_asset_loading_to_permission_check() {
 
     // 3. Resolve Permissions
     let pack_perms: PermissionSet = pack_config.meta.permissions.clone().into();
-    let active_perms = PermissionResolver::resolve(&pack_perms, &user_perms);
+    let active_perms = PermissionResolver::resolve(&pack_perms, &user_perms, false);
 
     // Verify resolution logic
     assert!(active_perms.contains(Permission::Image));
@@ -101,6 +104,10 @@ async fn test_asset_loading_to_permission_check() {
         registry: registry_arc.clone(),
         mood: mood_clone.clone(),
         max_audio_concurrent: 10,
+        max_image_concurrent: usize::MAX,
+        max_video_concurrent: usize::MAX,
+        allow_fallback: false,
+        original_wallpaper: None,
     };
 
     let mut runtime = GoonRuntime::new(context);
@@ -132,6 +139,10 @@ async fn test_asset_loading_to_permission_check() {
         registry: registry_arc.clone(),
         mood: mood_clone.clone(),
         max_audio_concurrent: 10,
+        max_image_concurrent: usize::MAX,
+        max_video_concurrent: usize::MAX,
+        allow_fallback: false,
+        original_wallpaper: None,
     };
 
     let mut runtime2 = GoonRuntime::new(context2);
@@ -31,6 +31,7 @@ fn test_llm_prompt_construction_flow() {
         },
         websites: None,
         prompts: None,
+        popups: None,
     };
 
     let user = User {
@@ -22,7 +22,7 @@ async fn test_full_permission_flow() {
 
     // 2. Resolve Permissions
     // Intersection should be: Image
-    let active_perms = PermissionResolver::resolve(&pack_perms, &user_perms);
+    let active_perms = PermissionResolver::resolve(&pack_perms, &user_perms, false);
 
     assert!(active_perms.contains(Permission::Image));
     assert!(!active_perms.contains(Permission::Video)); // Denied by user
@@ -47,6 +47,10 @@ async fn test_full_permission_flow() {
         registry: registry.clone(),
         mood: mood.clone(),
         max_audio_concurrent: 10,
+        max_image_concurrent: usize::MAX,
+        max_video_concurrent: usize::MAX,
+        allow_fallback: false,
+        original_wallpaper: None,
     };
 
     let mut runtime = GoonRuntime::new(context);
@@ -83,6 +87,10 @@ async fn test_full_permission_flow() {
         registry: registry.clone(),
         mood: mood.clone(),
         max_audio_concurrent: 10,
+        max_image_concurrent: usize::MAX,
+        max_video_concurrent: usize::MAX,
+        allow_fallback: false,
+        original_wallpaper: None,
     };
 
     let mut runtime2 = GoonRuntime::new(context2);
@@ -0,0 +1,24 @@
+#![cfg(not(miri))]
+
+use goon_ai::sdk::generate_typescript_definitions;
+use goon_ai::sdk::runtime_gen::generate_image_runtime;
+
+#[test]
+fn test_sdk_docs_available_outside_source_tree() {
+    // Run from a directory that does not contain `src/sdk/*.rs`, like an installed binary would
+    let outside = std::env::temp_dir();
+    std::env::set_current_dir(&outside).expect("Failed to change working directory");
+    assert!(!std::path::Path::new("src/sdk/image.rs").exists());
+
+    let defs = generate_typescript_definitions(&["image".to_string()]);
+    assert!(
+        defs.contains("Displays an image in a new window."),
+        "Op docs missing from generated definitions"
+    );
+
+    let runtime = generate_image_runtime();
+    assert!(
+        runtime.contains("Displays an image in a new window."),
+        "Op docs missing from generated runtime"
+    );
+}